@@ -0,0 +1,243 @@
+use crate::core::spider::{ParseResult, ParsedData, SpiderResponse};
+use crate::storage::base::StorageError;
+use crate::storage::{
+    IntoStorageData, StorageBackend, StorageCategory, StorageItem, StorageManager,
+};
+use crate::{HttpResponse, Spider};
+use chrono::Utc;
+use futures::{Stream, StreamExt};
+use log::{info, warn};
+use serde_json::json;
+use std::pin::pin;
+
+/// Counts of a [`Backfill`] run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackfillReport {
+    pub responses: u64,
+    pub items: u64,
+    /// Responses that could not be read or parsed.
+    pub parse_errors: u64,
+    pub storage_errors: u64,
+    /// Follow-up requests returned by `parse`. They are not fetched.
+    pub ignored_requests: u64,
+}
+
+/// Re-parses archived raw responses with the current version of a spider and
+/// writes the items to a new destination, so selector fixes can be applied
+/// to past crawls without fetching the pages again.
+///
+/// The spider's `parse` is used as is; its `persist_extracted_data` is not
+/// called.
+pub struct Backfill<S: Spider> {
+    spider: S,
+    destination: StorageManager,
+    category: StorageCategory,
+}
+
+impl<S: Spider> Backfill<S> {
+    pub fn new(spider: S, destination: StorageManager) -> Self {
+        Self {
+            spider,
+            destination,
+            category: StorageCategory::Data,
+        }
+    }
+
+    /// Category of `destination` the items are written to.
+    pub fn with_category(mut self, category: StorageCategory) -> Self {
+        self.category = category;
+        self
+    }
+
+    pub async fn run<R>(&self, responses: R) -> BackfillReport
+    where
+        R: Stream<Item = Result<HttpResponse, StorageError>>,
+    {
+        let mut report = BackfillReport::default();
+        let mut responses = pin!(responses);
+        while let Some(response) = responses.next().await {
+            report.responses += 1;
+            let response = match response {
+                Ok(response) => response,
+                Err(e) => {
+                    warn!("Skipping unreadable archived response: {}", e);
+                    report.parse_errors += 1;
+                    continue;
+                }
+            };
+            self.backfill_response(response, &mut report).await;
+        }
+        info!(
+            "Backfill of {} finished: {} responses, {} items, {} parse errors, {} storage errors",
+            self.spider.name(),
+            report.responses,
+            report.items,
+            report.parse_errors,
+            report.storage_errors
+        );
+        report
+    }
+
+    async fn backfill_response(&self, response: HttpResponse, report: &mut BackfillReport) {
+        let callback = response.from_request.callback.clone();
+        let response = SpiderResponse {
+            response,
+            callback,
+            content: None,
+        };
+        let (parse_result, data) = match self.spider.parse(&response) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                warn!("Failed to re-parse {}: {}", response.response.url, e);
+                report.parse_errors += 1;
+                return;
+            }
+        };
+        if let ParseResult::Continue(requests) = parse_result {
+            report.ignored_requests += requests.len() as u64;
+        }
+
+        let items = match data {
+            ParsedData::Item(item) => vec![item.into_storage_data()],
+            ParsedData::Items(items) => items.into_iter().map(|i| i.into_storage_data()).collect(),
+            ParsedData::Typed(items) => items.into_iter().map(|i| i.into_storage_data()).collect(),
+            _ => return,
+        };
+        let (storage, config) = self.destination.get_storage(&self.category);
+        for (index, data) in items.into_iter().enumerate() {
+            let item = StorageItem {
+                url: response.response.url.clone(),
+                timestamp: Utc::now(),
+                data,
+                metadata: Some(json!({
+                    "backfill": true,
+                    "fetched_at": response.response.timestamp.to_rfc3339(),
+                })),
+                id: format!("{}-{}", response.response.url, index),
+            };
+            match storage.store_serialized(item, &**config).await {
+                Ok(()) => report.items += 1,
+                Err(e) => {
+                    warn!("Failed to store backfilled item: {}", e);
+                    report.storage_errors += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::retry::{RetryCategory, RetryState};
+    use crate::core::spider::SpiderConfig;
+    use crate::http::Headers;
+    use crate::storage::{create_storage, DiskArchive, StorageType, WriteGroup};
+    use crate::{HttpRequest, ScraperResult};
+    use async_trait::async_trait;
+    use scraper::{Html, Selector};
+
+    use url::Url;
+    use uuid::Uuid;
+
+    struct TitleSpider {
+        config: SpiderConfig,
+        storage_manager: StorageManager,
+    }
+
+    #[async_trait]
+    impl Spider for TitleSpider {
+        fn name(&self) -> String {
+            "title_spider".to_string()
+        }
+
+        fn storage_manager(&self) -> &StorageManager {
+            &self.storage_manager
+        }
+
+        fn start_requests(&self) -> Vec<HttpRequest> {
+            Vec::new()
+        }
+
+        fn config(&self) -> &SpiderConfig {
+            &self.config
+        }
+
+        fn set_config(&mut self, config: SpiderConfig) {
+            self.config = config;
+        }
+
+        fn parse(&self, response: &SpiderResponse) -> ScraperResult<(ParseResult, ParsedData)> {
+            let document = Html::parse_document(response.response.body_text()?);
+            let selector = Selector::parse("h1").unwrap();
+            let titles = document
+                .select(&selector)
+                .map(|h1| json!({ "title": h1.text().collect::<String>() }))
+                .collect();
+            Ok((ParseResult::Skip, ParsedData::Items(titles)))
+        }
+
+        async fn persist_extracted_data(
+            &self,
+            _data: ParsedData,
+            _response: &SpiderResponse,
+        ) -> ScraperResult<()> {
+            unreachable!("backfill writes items itself")
+        }
+
+        async fn handle_max_retries(
+            &self,
+            _category: RetryCategory,
+            _request: Box<HttpRequest>,
+            _history: RetryState,
+        ) -> ScraperResult<()> {
+            Ok(())
+        }
+    }
+
+    async fn disk_manager(dir: &std::path::Path, category: StorageCategory) -> StorageManager {
+        let storage = create_storage(StorageType::Disk {
+            path: dir.to_string_lossy().to_string(),
+        })
+        .await
+        .unwrap();
+        StorageManager::new().register_storage(category, storage, "out")
+    }
+
+    #[tokio::test]
+    async fn test_backfill_reparses_archived_responses() {
+        let dir = std::env::temp_dir().join(format!("turboscraper_backfill_{}", Uuid::now_v7()));
+        let archive = disk_manager(&dir.join("archive"), StorageCategory::Raw).await;
+        let url = Url::parse("https://example.com/p/1").unwrap();
+        let response = HttpResponse {
+            headers: Headers::from_iter([("content-type", "text/html")]),
+            from_request: Box::new(HttpRequest::new(
+                url.clone(),
+                crate::core::SpiderCallback::ParseItem,
+                0,
+            )),
+            ..HttpResponse::for_test(&url, b"<h1>First</h1><h1>Second</h1>")
+        };
+        archive
+            .store_group(
+                WriteGroup::new(url, StorageCategory::Raw, json!({"title": "stale"}))
+                    .with_raw_response(&response),
+            )
+            .await
+            .unwrap();
+
+        let spider = TitleSpider {
+            config: SpiderConfig::default(),
+            storage_manager: StorageManager::new(),
+        };
+        let destination = disk_manager(&dir.join("backfill"), StorageCategory::Data).await;
+        let responses = DiskArchive::new(dir.join("archive")).responses().unwrap();
+        let report = Backfill::new(spider, destination).run(responses).await;
+
+        // The stale item is not a raw response and is reported as unreadable.
+        assert_eq!(report.responses, 2);
+        assert_eq!(report.parse_errors, 1);
+        assert_eq!(report.items, 2);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
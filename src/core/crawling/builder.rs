@@ -0,0 +1,150 @@
+use super::crawler::Crawler;
+use super::dedup::{DedupFilter, HashSetFilter};
+use super::events::{CrawlerEvents, EventBus};
+use super::frontier::Frontier;
+use super::handle::{CrawlControl, ShutdownToken};
+use super::politeness::PolitenessThrottle;
+use super::robots::RobotsCache;
+use super::slots::SlotLimiter;
+use crate::core::middleware::{
+    DownloaderMiddleware, DownloaderMiddlewareChain, SpiderMiddleware, SpiderMiddlewareChain,
+};
+use crate::core::pipeline::{ItemPipeline, ItemPipelineChain};
+use crate::http::ResponseType;
+use crate::parser::{ContentDispatcher, ContentParser};
+use crate::{Scraper, StatsTracker};
+use log::info;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// Assembles a [`Crawler`]. Only the scraper is required; every other
+/// component has an in-memory default.
+pub struct CrawlerBuilder {
+    scraper: Box<dyn Scraper>,
+    stats: Option<Arc<StatsTracker>>,
+    frontier: Option<Frontier>,
+    visited: Option<Arc<dyn DedupFilter>>,
+    control: Option<Arc<CrawlControl>>,
+    downloader_middlewares: DownloaderMiddlewareChain,
+    spider_middlewares: SpiderMiddlewareChain,
+    pipelines: ItemPipelineChain,
+    events: EventBus,
+    content_parsers: ContentDispatcher,
+}
+
+impl CrawlerBuilder {
+    pub fn new(scraper: Box<dyn Scraper>) -> Self {
+        Self {
+            scraper,
+            stats: None,
+            frontier: None,
+            visited: None,
+            control: None,
+            downloader_middlewares: DownloaderMiddlewareChain::new(),
+            spider_middlewares: SpiderMiddlewareChain::new(),
+            pipelines: ItemPipelineChain::new(),
+            events: EventBus::new(),
+            content_parsers: ContentDispatcher::new(),
+        }
+    }
+
+    /// Records into an existing tracker, e.g. one shared with a metrics
+    /// exporter.
+    pub fn with_stats(mut self, stats: Arc<StatsTracker>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Starts from a frontier that may already hold queued requests.
+    pub fn with_frontier(mut self, frontier: Frontier) -> Self {
+        self.frontier = Some(frontier);
+        self
+    }
+
+    /// Replaces the exact in-memory visited set, e.g. with a `BloomFilter`
+    /// for crawls of millions of URLs.
+    pub fn with_dedup_filter<F: DedupFilter + 'static>(mut self, filter: F) -> Self {
+        self.visited = Some(Arc::new(filter));
+        self
+    }
+
+    /// Lets code outside the crawler stop it, e.g. from a signal handler.
+    pub fn with_shutdown_token(mut self, token: ShutdownToken) -> Self {
+        self.control = Some(token.control());
+        self
+    }
+
+    /// Adds a middleware around every download. Middlewares run in the order
+    /// they are added.
+    pub fn with_downloader_middleware<M: DownloaderMiddleware + 'static>(
+        mut self,
+        middleware: M,
+    ) -> Self {
+        self.downloader_middlewares.push(middleware);
+        self
+    }
+
+    /// Adds a middleware over the output of every `Spider::parse` call.
+    /// Middlewares run in the order they are added.
+    pub fn with_spider_middleware<M: SpiderMiddleware + 'static>(mut self, middleware: M) -> Self {
+        self.spider_middlewares.push(middleware);
+        self
+    }
+
+    /// Adds a pipeline every scraped item passes through before it is
+    /// persisted. Pipelines run in the order they are added.
+    pub fn with_item_pipeline<P: ItemPipeline + 'static>(mut self, pipeline: P) -> Self {
+        self.pipelines.push(pipeline);
+        self
+    }
+
+    /// Pre-parses responses of `response_type` with `parser` and hands the
+    /// result to the spider as `SpiderResponse::content`.
+    pub fn with_content_parser<P: ContentParser + 'static>(
+        mut self,
+        response_type: ResponseType,
+        parser: P,
+    ) -> Self {
+        self.content_parsers.register(response_type, parser);
+        self
+    }
+
+    /// Pre-parses HTML, JSON, XML and binary responses with the built-in
+    /// parsers, unless a parser was added for the type with
+    /// [`with_content_parser`](Self::with_content_parser).
+    pub fn with_default_content_parsers(mut self) -> Self {
+        self.content_parsers.register_defaults();
+        self
+    }
+
+    /// Subscribes an observer to crawl lifecycle events.
+    pub fn with_events<E: CrawlerEvents + 'static>(mut self, observer: E) -> Self {
+        self.events.subscribe(observer);
+        self
+    }
+
+    pub fn build(self) -> Crawler {
+        info!("Initializing crawler");
+        let stats = self.stats.unwrap_or_else(|| Arc::new(StatsTracker::new()));
+        let mut scraper = self.scraper;
+        scraper.set_stats(Arc::clone(&stats));
+
+        Crawler {
+            scraper,
+            visited: self
+                .visited
+                .unwrap_or_else(|| Arc::new(HashSetFilter::new())),
+            stats,
+            throttle: Arc::new(PolitenessThrottle::new()),
+            slots: SlotLimiter::default(),
+            control: self.control.unwrap_or_default(),
+            frontier: Arc::new(Mutex::new(self.frontier.unwrap_or_default())),
+            downloader_middlewares: self.downloader_middlewares,
+            spider_middlewares: self.spider_middlewares,
+            pipelines: self.pipelines,
+            events: self.events,
+            robots: Arc::new(RobotsCache::default()),
+            content_parsers: self.content_parsers,
+        }
+    }
+}
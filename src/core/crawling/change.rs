@@ -0,0 +1,121 @@
+use crate::HttpResponse;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use url::Url;
+
+/// What identifies the content of a scraped page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageFingerprint {
+    /// SHA-256 of the raw body, hex encoded.
+    pub content_hash: String,
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+}
+
+impl PageFingerprint {
+    pub fn of(response: &HttpResponse) -> Self {
+        Self {
+            content_hash: hex::encode(Sha256::digest(&response.raw_body)),
+            etag: response.headers.get("etag").cloned(),
+            last_modified: response.headers.get("last-modified").cloned(),
+        }
+    }
+
+    /// Matching ETags are trusted; otherwise the body hashes are compared.
+    pub fn matches(&self, other: &PageFingerprint) -> bool {
+        match (&self.etag, &other.etag) {
+            (Some(a), Some(b)) if a == b => true,
+            _ => self.content_hash == other.content_hash,
+        }
+    }
+}
+
+/// Remembers the fingerprint of every page parsed so a re-crawl can skip
+/// pages that have not changed since.
+///
+/// Clones share the same fingerprints. Trackers opened from a file are saved
+/// back to it when a crawl finishes.
+#[derive(Debug, Clone, Default)]
+pub struct ChangeTracker {
+    pages: Arc<RwLock<HashMap<String, PageFingerprint>>>,
+    path: Option<PathBuf>,
+}
+
+impl ChangeTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads fingerprints saved by a previous crawl, starting empty if the
+    /// file does not exist yet.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let pages = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            pages: Arc::new(RwLock::new(pages)),
+            path: Some(path),
+        })
+    }
+
+    /// Whether `url` was parsed before with the same content.
+    pub fn is_unchanged(&self, url: &Url, fingerprint: &PageFingerprint) -> bool {
+        self.pages
+            .read()
+            .get(url.as_str())
+            .is_some_and(|previous| previous.matches(fingerprint))
+    }
+
+    /// `If-None-Match` and `If-Modified-Since` headers revalidating the page
+    /// recorded for `url` with the validators it was served with.
+    pub fn conditional_headers(&self, url: &Url) -> Vec<(&'static str, String)> {
+        let pages = self.pages.read();
+        let Some(page) = pages.get(url.as_str()) else {
+            return Vec::new();
+        };
+        let mut headers = Vec::new();
+        if let Some(etag) = &page.etag {
+            headers.push(("if-none-match", etag.clone()));
+        }
+        if let Some(last_modified) = &page.last_modified {
+            headers.push(("if-modified-since", last_modified.clone()));
+        }
+        headers
+    }
+
+    pub fn record(&self, url: &Url, fingerprint: PageFingerprint) {
+        self.pages.write().insert(url.to_string(), fingerprint);
+    }
+
+    pub fn len(&self) -> usize {
+        self.pages.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages.read().is_empty()
+    }
+
+    /// Writes the fingerprints back to the file the tracker was opened from.
+    /// Does nothing for in-memory trackers.
+    pub fn save(&self) -> io::Result<()> {
+        match &self.path {
+            Some(path) => self.save_to(path),
+            None => Ok(()),
+        }
+    }
+
+    pub fn save_to<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let json = serde_json::to_vec(&*self.pages.read())?;
+        fs::write(path, json)
+    }
+}
@@ -0,0 +1,211 @@
+use crate::StatsTracker;
+use log::{info, warn};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Longest wait given to requests held back while a half-open circuit's
+/// trial request is in flight.
+const PROBE_WAIT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests flow normally.
+    Closed,
+    /// The host failed repeatedly; requests are held back until the
+    /// cool-down ends.
+    Open,
+    /// The cool-down ended; a single trial request is let through, and its
+    /// response decides whether the circuit closes again or re-opens.
+    HalfOpen,
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitState::Closed => write!(f, "closed"),
+            CircuitState::Open => write!(f, "open"),
+            CircuitState::HalfOpen => write!(f, "half-open"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct HostCircuit {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// Whether the trial request of a half-open circuit is still in flight.
+    probing: bool,
+}
+
+impl Default for HostCircuit {
+    fn default() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            probing: false,
+        }
+    }
+}
+
+/// Per-host circuit breaker. After `failure_threshold` consecutive 5xx
+/// responses or transport errors from a host, its requests are held back for
+/// `cool_down` instead of spending retries on it.
+///
+/// Clones share the same per-host state.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cool_down: Duration,
+    hosts: Arc<Mutex<HashMap<String, HostCircuit>>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cool_down: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cool_down,
+            hosts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn state(&self, host: &str) -> CircuitState {
+        self.hosts
+            .lock()
+            .get(host)
+            .map(|circuit| circuit.state)
+            .unwrap_or(CircuitState::Closed)
+    }
+
+    /// How long requests to `host` are held back, without taking the trial
+    /// request of a half-open circuit. The crawler checks it before
+    /// dispatching; the scraper takes the trial with
+    /// [`try_acquire`](Self::try_acquire) right before fetching.
+    pub fn held_for(&self, host: &str) -> Option<Duration> {
+        let hosts = self.hosts.lock();
+        let circuit = hosts.get(host)?;
+        match circuit.state {
+            CircuitState::Closed => None,
+            CircuitState::HalfOpen => circuit.probing.then(|| self.cool_down.min(PROBE_WAIT)),
+            CircuitState::Open => {
+                let elapsed = circuit.opened_at.map(|t| t.elapsed()).unwrap_or_default();
+                self.cool_down
+                    .checked_sub(elapsed)
+                    .filter(|wait| !wait.is_zero())
+            }
+        }
+    }
+
+    /// Checks whether a request to `host` may be sent now. Every request let
+    /// through must be [recorded](Self::record), or a half-open circuit
+    /// keeps waiting for its trial request. Returns how long
+    /// to wait if the circuit is open, or half-open with its trial request
+    /// still in flight.
+    pub fn try_acquire(&self, host: &str, stats: &StatsTracker) -> Result<(), Duration> {
+        let mut hosts = self.hosts.lock();
+        let Some(circuit) = hosts.get_mut(host) else {
+            return Ok(());
+        };
+        match circuit.state {
+            CircuitState::Closed => return Ok(()),
+            CircuitState::HalfOpen if circuit.probing => {
+                return Err(self.cool_down.min(PROBE_WAIT))
+            }
+            CircuitState::HalfOpen => {}
+            CircuitState::Open => {
+                let elapsed = circuit.opened_at.map(|t| t.elapsed()).unwrap_or_default();
+                if elapsed < self.cool_down {
+                    return Err(self.cool_down - elapsed);
+                }
+                Self::transition(host, circuit, CircuitState::HalfOpen, stats);
+            }
+        }
+        circuit.probing = true;
+        Ok(())
+    }
+
+    /// Records the outcome of a request to `host`.
+    pub fn record(&self, host: &str, success: bool, stats: &StatsTracker) {
+        let mut hosts = self.hosts.lock();
+        let circuit = hosts.entry(host.to_string()).or_default();
+        circuit.probing = false;
+        if success {
+            circuit.consecutive_failures = 0;
+            if circuit.state != CircuitState::Closed {
+                Self::transition(host, circuit, CircuitState::Closed, stats);
+            }
+            return;
+        }
+
+        circuit.consecutive_failures += 1;
+        let trips = match circuit.state {
+            CircuitState::Closed => circuit.consecutive_failures >= self.failure_threshold,
+            CircuitState::HalfOpen => true,
+            CircuitState::Open => false,
+        };
+        if trips {
+            circuit.opened_at = Some(Instant::now());
+            Self::transition(host, circuit, CircuitState::Open, stats);
+        }
+    }
+
+    fn transition(host: &str, circuit: &mut HostCircuit, to: CircuitState, stats: &StatsTracker) {
+        let from = circuit.state;
+        circuit.state = to;
+        match to {
+            CircuitState::Open => warn!(
+                "Circuit for {} {} -> {} after {} consecutive failures",
+                host, from, to, circuit.consecutive_failures
+            ),
+            _ => info!("Circuit for {} {} -> {}", host, from, to),
+        }
+        stats.record_circuit_transition(format!("{} -> {}", from, to));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_opens_after_threshold_and_recovers() {
+        let stats = StatsTracker::new();
+        let breaker = CircuitBreaker::new(3, Duration::from_millis(50));
+
+        breaker.record("example.com", false, &stats);
+        breaker.record("example.com", false, &stats);
+        assert!(breaker.try_acquire("example.com", &stats).is_ok());
+        breaker.record("example.com", false, &stats);
+        assert_eq!(breaker.state("example.com"), CircuitState::Open);
+        assert!(breaker.try_acquire("example.com", &stats).is_err());
+        assert!(breaker.held_for("example.com").is_some());
+        assert!(breaker.try_acquire("other.com", &stats).is_ok());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        // Checking doesn't take the trial request.
+        assert!(breaker.held_for("example.com").is_none());
+        assert!(breaker.try_acquire("example.com", &stats).is_ok());
+        assert_eq!(breaker.state("example.com"), CircuitState::HalfOpen);
+        // Only the trial request goes through until it resolves.
+        assert!(breaker.try_acquire("example.com", &stats).is_err());
+        assert!(breaker.held_for("example.com").is_some());
+
+        // A failed trial re-opens immediately; a successful one closes.
+        breaker.record("example.com", false, &stats);
+        assert_eq!(breaker.state("example.com"), CircuitState::Open);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(breaker.try_acquire("example.com", &stats).is_ok());
+        breaker.record("example.com", true, &stats);
+        assert_eq!(breaker.state("example.com"), CircuitState::Closed);
+
+        let transitions = stats.get_stats().circuit_transitions;
+        assert_eq!(transitions["closed -> open"], 1);
+        assert_eq!(transitions["half-open -> open"], 1);
+        assert_eq!(transitions["half-open -> closed"], 1);
+    }
+}
@@ -1,37 +1,292 @@
-use crate::core::spider::{ParseResult, SpiderResponse};
-use crate::stats::{ErrorType, StatsTracker};
+use super::builder::CrawlerBuilder;
+use super::change::PageFingerprint;
+use super::dedup::{DedupFilter, DedupMethods};
+use super::events::{CrawlerEvents, EventBus};
+use super::frontier::{Frontier, FrontierSnapshot};
+use super::handle::{CrawlControl, CrawlerHandle, ShutdownToken};
+use super::politeness::PolitenessThrottle;
+use super::revisit::RevisitPolicy;
+use super::robots::{robots_path, user_agent, RobotsCache};
+use super::slots::{SlotLimiter, SlotPermit};
+use crate::core::middleware::{DownloaderMiddlewareChain, SpiderMiddlewareChain};
+use crate::core::pipeline::{ItemContext, ItemPipelineChain};
+use crate::core::retry::RetryCategory;
+use crate::core::spider::{ParseResult, ParsedData, SpiderConfig, SpiderResponse};
+use crate::core::SpiderCallback;
+use crate::parser::ContentDispatcher;
+use crate::stats::{CloseReason, ErrorType, StatsTracker};
 use crate::storage::{StorageCategory, StorageItem};
 use crate::{HttpRequest, HttpResponse, Scraper, ScraperError};
 use chrono::Utc;
 use futures::stream::{FuturesUnordered, StreamExt};
 use log::{debug, error, info, trace, warn};
-use parking_lot::RwLock;
+use parking_lot::Mutex;
 use serde_json::json;
 use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::spawn;
 use tokio::task::JoinHandle;
 use tokio::time::sleep;
+use url::Url;
 
 use crate::{ScraperResult, Spider};
 
+/// How long requests of a full slot are held back before trying again.
+const SLOT_WAIT: std::time::Duration = std::time::Duration::from_millis(100);
+
 pub struct Crawler {
-    scraper: Box<dyn Scraper>,
-    visited_urls: Arc<RwLock<HashSet<String>>>,
-    stats: Arc<StatsTracker>,
+    pub(super) scraper: Box<dyn Scraper>,
+    pub(super) visited: Arc<dyn DedupFilter>,
+    pub(super) stats: Arc<StatsTracker>,
+    pub(super) throttle: Arc<PolitenessThrottle>,
+    pub(super) slots: SlotLimiter,
+    pub(super) control: Arc<CrawlControl>,
+    pub(super) frontier: Arc<Mutex<Frontier>>,
+    pub(super) downloader_middlewares: DownloaderMiddlewareChain,
+    pub(super) spider_middlewares: SpiderMiddlewareChain,
+    pub(super) pipelines: ItemPipelineChain,
+    pub(super) events: EventBus,
+    pub(super) robots: Arc<RobotsCache>,
+    pub(super) content_parsers: ContentDispatcher,
 }
 
 impl Crawler {
+    /// A crawler with default components. Use [`Crawler::builder`] to
+    /// configure middlewares, dedup, stats and the other components.
     pub fn new(scraper: Box<dyn Scraper>) -> Self {
-        info!("Initializing crawler");
-        let stats = Arc::new(StatsTracker::new());
-        let mut scraper = scraper;
-        scraper.set_stats(Arc::clone(&stats));
-
-        Self {
-            scraper,
-            visited_urls: Arc::new(RwLock::new(HashSet::new())),
+        Self::builder(scraper).build()
+    }
+
+    pub fn builder(scraper: Box<dyn Scraper>) -> CrawlerBuilder {
+        CrawlerBuilder::new(scraper)
+    }
+
+    /// A token that stops this crawler when triggered.
+    pub fn shutdown_token(&self) -> ShutdownToken {
+        ShutdownToken::new(Arc::clone(&self.control))
+    }
+
+    /// Prepares the crawler for another run: an empty frontier and visited
+    /// set, and cleared pause/stop flags and blocked patterns. The stats
+    /// tracker is kept, so whoever shares it keeps seeing updates.
+    pub(crate) fn reset(&self) {
+        self.visited.clear();
+        self.frontier.lock().drain();
+        self.control.reset();
+    }
+
+    /// See [`CrawlerHandle::block_pattern`].
+    pub fn block_pattern(&self, pattern: &str) -> Result<usize, regex::Error> {
+        self.control.block_pattern(pattern, &self.frontier)
+    }
+
+    pub fn frontier_snapshot(&self) -> FrontierSnapshot {
+        self.frontier.lock().snapshot()
+    }
+
+    pub fn stats(&self) -> Arc<StatsTracker> {
+        Arc::clone(&self.stats)
+    }
+
+    /// Runs the spider on a background task and returns a handle to pause,
+    /// resume or stop it.
+    pub fn run_detached<S: Spider + Send + Sync + 'static>(self, spider: S) -> CrawlerHandle {
+        let control = Arc::clone(&self.control);
+        let frontier = Arc::clone(&self.frontier);
+        let task = spawn(async move { self.run(spider).await });
+        CrawlerHandle::new(control, frontier, task)
+    }
+
+    /// Pre-parses the response body and runs the spider callback.
+    fn parse_page<S: Spider>(
+        spider: &S,
+        parsers: &ContentDispatcher,
+        response: HttpResponse,
+    ) -> ScraperResult<(SpiderResponse, (ParseResult, ParsedData))> {
+        let content = parsers.dispatch(&response)?;
+        let callback = response.from_request.callback.clone();
+        let response = SpiderResponse {
+            response,
+            callback,
+            content,
+        };
+        let parsed = spider.parse(&response)?;
+        Ok((response, parsed))
+    }
+
+    /// Parses the response, in the spider's parse pool if it has one, runs
+    /// the item pipelines, records the extracted items and hands them to
+    /// the spider for persistence.
+    async fn process_spider_response<S: Spider + Send + Sync + 'static>(
+        spider: &Arc<S>,
+        stats: &StatsTracker,
+        parsers: &ContentDispatcher,
+        middlewares: &SpiderMiddlewareChain,
+        pipelines: &ItemPipelineChain,
+        events: &EventBus,
+        response: HttpResponse,
+    ) -> ScraperResult<ParseResult> {
+        if response.is_not_modified() {
+            debug!("Page {} not modified", response.url);
+            stats.record_unchanged_page();
+            let callback = response.from_request.callback.clone();
+            return spider.not_modified(&SpiderResponse {
+                response,
+                callback,
+                content: None,
+            });
+        }
+        let change = spider
+            .config()
+            .change_tracker
+            .as_ref()
+            .map(|tracker| (tracker, PageFingerprint::of(&response)));
+        if let Some((tracker, fingerprint)) = &change {
+            if tracker.is_unchanged(&response.url, fingerprint) {
+                debug!("Skipping unchanged page {}", response.url);
+                stats.record_unchanged_page();
+                return Ok(ParseResult::Skip);
+            }
+        }
+
+        let started = Instant::now();
+        let (response, parsed) = match &spider.config().parse_pool {
+            Some(pool) => {
+                let (worker, parsers) = (Arc::clone(spider), parsers.clone());
+                let parsed = pool
+                    .run(move || Self::parse_page(&*worker, &parsers, response))
+                    .await;
+                pool.adapt(spider.config().max_concurrency);
+                stats.set_parse_pool(pool.stats());
+                parsed?
+            }
+            None => Self::parse_page(&**spider, parsers, response)?,
+        };
+        stats.record_parse(started.elapsed());
+        let spider: &S = spider;
+        let response = &response;
+        let (parse_result, parsed_data) = middlewares.apply(response, parsed);
+        let visit = spider
+            .config()
+            .visited_store
+            .as_ref()
+            .filter(|store| store.tracks(&response.callback))
+            .map(|store| store.begin_page());
+        match parsed_data {
+            ParsedData::Stream(mut items) => loop {
+                let chunk = items.next_chunk(spider.config().stream_chunk_size).await?;
+                if chunk.is_empty() {
+                    break;
+                }
+                let data = ParsedData::Items(chunk);
+                Self::persist_items(spider, stats, pipelines, events, response, data).await?;
+            },
+            data => Self::persist_items(spider, stats, pipelines, events, response, data).await?,
+        }
+        // Recorded only once the page was fully processed, so a failed page
+        // is parsed again on the next crawl.
+        if let Some((tracker, fingerprint)) = change {
+            tracker.record(&response.response.url, fingerprint);
+        }
+        if let Some(visit) = visit {
+            visit.record(&response.response.url);
+        }
+        Ok(parse_result)
+    }
+
+    /// Runs `parsed_data` through the item pipelines, records it in stats
+    /// and hands it to the spider for storage.
+    async fn persist_items<S: Spider + Send + Sync + 'static>(
+        spider: &S,
+        stats: &StatsTracker,
+        pipelines: &ItemPipelineChain,
+        events: &EventBus,
+        response: &SpiderResponse,
+        parsed_data: ParsedData,
+    ) -> ScraperResult<()> {
+        let spider_name = spider.name();
+        let context = ItemContext {
+            spider: &spider_name,
+            response,
             stats,
+        };
+        let (parsed_data, dropped) = pipelines.process(parsed_data, &context).await;
+        if dropped > 0 {
+            stats.record_dropped_items(dropped);
+        }
+        let item_count = parsed_data.item_count() as u64;
+        stats.record_items(item_count);
+        if let Some(source) = &response.response.from_request.source {
+            stats.record_source_items(source, item_count);
+        }
+        if item_count > 0 {
+            stats.record_callback_items(&response.callback.name(), item_count);
+            if let Some(host) = response.response.url.host_str() {
+                stats.record_domain_items(host, item_count);
+            }
+        }
+        if item_count > 0 {
+            events.on_item_scraped(&parsed_data, response);
+        }
+        if let Some(preview) = &spider.config().item_preview {
+            preview.observe_data(&parsed_data, response);
+        }
+        let Some(format) = spider.config().dry_run else {
+            stats.start_persisting(item_count);
+            let result = spider.persist_extracted_data(parsed_data, response).await;
+            stats.finish_persisting(item_count);
+            return result;
+        };
+        for (category, item) in parsed_data.into_storage_items(&spider.collection()) {
+            format.print(&json!({
+                "category": category,
+                "url": response.response.url,
+                "callback": response.callback.name(),
+                "item": item,
+            }))?;
+        }
+        Ok(())
+    }
+
+    fn exceeded_stop_condition(
+        &self,
+        config: &SpiderConfig,
+        in_flight: usize,
+    ) -> Option<CloseReason> {
+        if let Some(max) = config.max_requests {
+            if self.stats.total_requests() + in_flight as u64 >= max {
+                return Some(CloseReason::MaxRequests(max));
+            }
+        }
+        if let Some(max) = config.max_items {
+            if self.stats.items_scraped() >= max {
+                return Some(CloseReason::MaxItems(max));
+            }
+        }
+        if let Some(max) = config.max_duration {
+            if self.stats.elapsed() >= max {
+                return Some(CloseReason::MaxDuration(max));
+            }
+        }
+        if let Some(max) = config.max_errors {
+            if self.stats.total_errors() >= max {
+                return Some(CloseReason::MaxErrors(max));
+            }
+        }
+        None
+    }
+
+    /// Stops scheduling new requests if any configured limit was reached.
+    /// In-flight requests are still drained.
+    fn enforce_stop_conditions(&self, config: &SpiderConfig, in_flight: usize) {
+        if self.control.is_stopped() {
+            return;
+        }
+        if let Some(reason) = self.exceeded_stop_condition(config, in_flight) {
+            info!("Closing spider: {}", reason);
+            self.stats.set_close_reason(reason);
+            self.control.stop();
         }
     }
 
@@ -40,42 +295,70 @@ impl Crawler {
         response: HttpResponse,
         spider: Arc<S>,
         futures: &mut FuturesUnordered<JoinHandle<ScraperResult<ParseResult>>>,
-    ) {
+    ) -> ScraperResult<()> {
         let spider_clone = Arc::clone(&spider);
-        let config = spider.config().clone();
+        let stats = Arc::clone(&self.stats);
+        let parsers = self.content_parsers.clone();
+        let middlewares = self.spider_middlewares.clone();
+        let pipelines = self.pipelines.clone();
+        let events = self.events.clone();
 
         let retry_error = ScraperError::ParsingError("Content retry requested".to_string());
 
-        if let Some((category, delay)) = config
-            .retry_config
-            .should_retry_parse(&response.url, &retry_error)
+        if let Some((category, delay)) = self
+            .take_parse_retry(&*spider, &response.from_request, &retry_error)
+            .await?
         {
             warn!(
                 "Retrying parse with same content for URL: {} (category: {:?})",
                 response.url, category
             );
+            self.emit_retry(spider.config(), &response.from_request, &category);
             sleep(delay).await;
 
-            let spider_response = SpiderResponse {
-                response: response.clone(),
-                callback: response.from_request.callback.clone(),
-            };
-
             futures.push(spawn(async move {
-                spider_clone.process_response(&spider_response).await
+                Self::process_spider_response(
+                    &spider_clone,
+                    &stats,
+                    &parsers,
+                    &middlewares,
+                    &pipelines,
+                    &events,
+                    response,
+                )
+                .await
             }));
         }
+        Ok(())
+    }
+
+    /// Puts `request` back in the frontier to be fetched after `wait`.
+    fn defer(&self, request: HttpRequest, wait: std::time::Duration) {
+        let until = Utc::now() + chrono::Duration::from_std(wait).unwrap_or_default();
+        self.frontier.lock().defer(request, until);
+    }
+
+    fn deny_retry(&self, config: &SpiderConfig, url: &Url) {
+        self.stats.record_denied_retry();
+        config.log_throttle.warn("Retry budget spent", || {
+            format!("Retry budget spent, giving up on URL: {}", url)
+        });
+    }
+
+    fn emit_retry(&self, config: &SpiderConfig, request: &HttpRequest, category: &RetryCategory) {
+        let state = config
+            .retry_config_for(request)
+            .get_retry_state(&request.url);
+        let attempt = state.counts.get(category).copied().unwrap_or(1);
+        self.events.on_retry(request, category, attempt);
     }
 
     async fn check_and_process_retry<S: Spider + Send + Sync + 'static>(
         &self,
-        request: HttpRequest,
+        mut request: HttpRequest,
         error: &ScraperError,
         spider: Arc<S>,
-        futures: &mut FuturesUnordered<JoinHandle<ScraperResult<ParseResult>>>,
-    ) {
-        let config = spider.config();
-
+    ) -> ScraperResult<()> {
         let error_item = StorageItem {
             url: request.url.clone(),
             timestamp: Utc::now(),
@@ -89,6 +372,7 @@ impl Crawler {
                 "error_type": match error {
                     ScraperError::ParsingError(_) => "parsing_error",
                     ScraperError::StorageError(_) => "storage_error",
+                    ScraperError::Spider(_) => "spider_error",
                     _ => "other_error",
                 },
                 "depth": request.depth,
@@ -107,44 +391,135 @@ impl Crawler {
             error!("Failed to store error: {:?}", e);
         }
 
-        if let Some((category, delay)) = config.retry_config.should_retry_parse(&request.url, error)
-        {
+        if let Some((category, delay)) = self.take_parse_retry(&*spider, &request, error).await? {
             warn!(
                 "Retrying request for URL: {} (category: {:?}, delay: {:?})",
                 request.url, category, delay
             );
+            self.emit_retry(spider.config(), &request, &category);
             sleep(delay).await;
-            self.process_requests(vec![request], spider, futures, true)
-                .await;
-        } else {
+            request.bypass_cache = true;
+            self.process_requests(vec![request], spider, true);
+        }
+        Ok(())
+    }
+
+    /// The category and delay of a retry of `request` after `error`, counted
+    /// only if its retry deadline and the retry budget allow it. Otherwise
+    /// the request is given up on as the scraper does once a category runs
+    /// out of retries, and handed to `Spider::handle_max_retries`.
+    async fn take_parse_retry<S: Spider + Send + Sync + 'static>(
+        &self,
+        spider: &S,
+        request: &HttpRequest,
+        error: &ScraperError,
+    ) -> ScraperResult<Option<(RetryCategory, std::time::Duration)>> {
+        let config = spider.config();
+        let retry_config = config.retry_config_for(request);
+        let Some((category, delay)) = retry_config.parse_retry(request, error) else {
             info!("No retry configuration matches error: {:?}", error);
+            return Ok(None);
+        };
+        if retry_config.deadline_exceeded(&request.url, delay) {
+            warn!("Retry deadline exceeded for URL: {}", request.url);
+        } else if !retry_config.spend_retry_budget() {
+            self.deny_retry(config, &request.url);
+        } else if retry_config.record_parse_retry(request, &category, delay) {
+            return Ok(Some((category, delay)));
         }
+        warn!(
+            "Maximum retries reached for URL: {} (category: {:?})",
+            request.url, category
+        );
+        let history = retry_config.get_retry_state(&request.url);
+        spider
+            .handle_max_retries(category, Box::new(request.clone()), history)
+            .await?;
+        Ok(None)
     }
 
     pub async fn run<S: Spider + Send + Sync + 'static>(&self, spider: S) -> ScraperResult<()> {
         let spider = Arc::new(spider);
         let mut futures = FuturesUnordered::new();
 
-        info!("Starting spider: {}", spider.name());
+        info!("Starting spider: {} {}", spider.name(), spider.version());
         debug!("Max depth: {}", spider.config().max_depth);
+        if let Some(store) = &spider.config().visited_store {
+            store.resume_as(&spider.version())?;
+        }
+        {
+            let mut frontier = self.frontier.lock();
+            frontier.set_order(spider.config().crawl_order);
+            frontier.set_windows(spider.config().crawl_windows.clone());
+            frontier.set_source_weights(spider.config().source_weights.clone());
+            frontier.set_visited_store(spider.config().visited_store.clone());
+        }
 
         let initial_requests = spider.start_requests();
-        if !initial_requests.is_empty() {
-            self.process_requests(initial_requests, Arc::clone(&spider), &mut futures, false)
-                .await;
+        let sitemaps = if spider.config().follow_robots_sitemaps {
+            self.robots_sitemaps(&initial_requests, spider.config())
+                .await
+        } else {
+            Vec::new()
+        };
+        self.process_requests(initial_requests, Arc::clone(&spider), false);
+        self.process_requests(sitemaps, Arc::clone(&spider), false);
+
+        // Tasks still running are drained even if the crawl fails.
+        let crawled = self.crawl(&spider, &mut futures).await;
+        self.finish_in_flight(&mut futures, spider.config().shutdown_timeout)
+            .await;
+        crawled?;
+        self.stats.set_close_reason(if self.control.is_stopped() {
+            CloseReason::Cancelled
+        } else {
+            CloseReason::Finished
+        });
+        let final_stats = self.stats.get_stats();
+        if let Some(reason) = &final_stats.close_reason {
+            self.events.on_spider_closed(reason, &final_stats);
         }
+        info!(
+            "Spider {} completed. Total URLs processed: {}",
+            spider.name(),
+            self.visited.len()
+        );
+        if let Some(tracker) = &spider.config().change_tracker {
+            if let Err(e) = tracker.save() {
+                warn!("Failed to save page fingerprints: {}", e);
+            }
+        }
+        if let Some(store) = &spider.config().visited_store {
+            if let Err(e) = store.save() {
+                warn!("Failed to save visited URLs: {}", e);
+            }
+        }
+        self.pipelines.close().await;
+        spider.config().log_throttle.flush();
+        self.stats.print_summary();
+        Ok(())
+    }
 
-        while let Some(result) = futures.next().await {
+    /// Dispatches requests and handles their results until the frontier is
+    /// exhausted or the crawl is stopped.
+    async fn crawl<S: Spider + Send + Sync + 'static>(
+        &self,
+        spider: &Arc<S>,
+        futures: &mut FuturesUnordered<JoinHandle<ScraperResult<ParseResult>>>,
+    ) -> ScraperResult<()> {
+        loop {
+            self.schedule(Arc::clone(spider), futures).await;
+            let Some(result) = futures.next().await else {
+                break;
+            };
+            self.enforce_stop_conditions(spider.config(), futures.len());
             match result {
                 Ok(Ok(parse_result)) => match parse_result {
-                    ParseResult::Continue(new_requests) => {
-                        self.process_requests(
-                            new_requests,
-                            Arc::clone(&spider),
-                            &mut futures,
-                            false,
-                        )
-                        .await;
+                    ParseResult::Continue(mut new_requests) => {
+                        if let Some(routes) = spider.routes() {
+                            new_requests.iter_mut().for_each(|r| routes.apply(r));
+                        }
+                        self.process_requests(new_requests, Arc::clone(spider), false);
                     }
                     ParseResult::Skip => {
                         debug!("Skipping current URL");
@@ -152,15 +527,12 @@ impl Crawler {
                     }
                     ParseResult::Stop => {
                         info!("Spider requested stop");
+                        self.stats.set_close_reason(CloseReason::SpiderStopped);
                         break;
                     }
                     ParseResult::RetryWithSameContent(response) => {
-                        self.handle_same_content_retry(
-                            *response,
-                            Arc::clone(&spider),
-                            &mut futures,
-                        )
-                        .await;
+                        self.handle_same_content_retry(*response, Arc::clone(spider), futures)
+                            .await?;
                     }
                     ParseResult::RetryWithNewContent(request) => {
                         self.check_and_process_retry(
@@ -168,142 +540,525 @@ impl Crawler {
                             &ScraperError::ParsingError(
                                 "Retry with new content requested".to_string(),
                             ),
-                            Arc::clone(&spider),
-                            &mut futures,
+                            Arc::clone(spider),
                         )
-                        .await;
+                        .await?;
                     }
                 },
-                Ok(Err((error, request))) => match error {
-                    ScraperError::MaxRetriesReached { category, url, .. } => {
-                        warn!(
-                            "Maximum retries reached for URL: {} (category: {:?})",
-                            url.to_string(),
-                            category
-                        );
-                        spider.handle_max_retries(category, request).await?;
-                    }
-                    ScraperError::StorageError(msg) => {
-                        warn!("Storage error processing request: {}", msg);
-                        self.stats.record_error(ErrorType::Storage);
-                        self.check_and_process_retry(
-                            *request,
-                            &ScraperError::StorageError(msg),
-                            Arc::clone(&spider),
-                            &mut futures,
-                        )
-                        .await;
-                    }
-                    ScraperError::ParsingError(msg) => {
-                        warn!("Parsing error processing request: {}", msg);
-                        self.check_and_process_retry(
-                            *request,
-                            &ScraperError::ParsingError(msg),
-                            Arc::clone(&spider),
-                            &mut futures,
-                        )
-                        .await;
-                    }
-                    _ => {
-                        warn!("Unhandled error type: {:?}", error);
+                Ok(Err(error)) => {
+                    // Tasks attach the request they dispatched to their errors.
+                    let (error, request) = error.into_parts();
+                    let Some(request) = request else {
+                        warn!("Task error without request: {:?}", error);
                         self.stats.record_error(ErrorType::Unhandled);
+                        continue;
+                    };
+                    match error {
+                        ScraperError::MaxRetriesReached {
+                            category,
+                            url,
+                            history,
+                            ..
+                        } => {
+                            warn!(
+                                "Maximum retries reached for URL: {} (category: {:?})",
+                                url, category
+                            );
+                            spider
+                                .handle_max_retries(category, request, *history)
+                                .await?;
+                        }
+                        ScraperError::SessionExpired { url, generation } => {
+                            let max_replays = spider
+                                .config()
+                                .session
+                                .as_ref()
+                                .map_or(0, |session| session.max_replays());
+                            if request.session_replays >= max_replays {
+                                warn!(
+                                    "Still logged out after {} replays, dropping {}",
+                                    request.session_replays, url
+                                );
+                                continue;
+                            }
+                            // Nothing new is dispatched while logging in again.
+                            let renewed = match &spider.config().session {
+                                Some(session) => {
+                                    session
+                                        .reauthenticate(self.scraper.as_ref(), generation)
+                                        .await
+                                }
+                                None => false,
+                            };
+                            if renewed {
+                                debug!("Replaying {} with the renewed session", url);
+                                let mut request = *request;
+                                request.session_replays += 1;
+                                self.frontier.lock().push(request);
+                            } else {
+                                error!("Could not log in again, stopping crawl");
+                                self.stats.set_close_reason(CloseReason::LoginFailed);
+                                self.control.stop();
+                            }
+                        }
+                        ScraperError::CircuitOpen { host, retry_after } => {
+                            debug!("Circuit open for {}, requeueing {}", host, request.url);
+                            self.defer(*request, retry_after);
+                        }
+                        ScraperError::StorageError(msg) => {
+                            warn!("Storage error processing request: {}", msg);
+                            self.stats.record_error(ErrorType::Storage);
+                            self.check_and_process_retry(
+                                *request,
+                                &ScraperError::StorageError(msg),
+                                Arc::clone(spider),
+                            )
+                            .await?;
+                        }
+                        ScraperError::ParsingError(msg) => {
+                            warn!("Parsing error processing request: {}", msg);
+                            self.check_and_process_retry(
+                                *request,
+                                &ScraperError::ParsingError(msg),
+                                Arc::clone(spider),
+                            )
+                            .await?;
+                        }
+                        ScraperError::Spider(e) => {
+                            warn!("Spider error processing request: {}", e);
+                            self.check_and_process_retry(
+                                *request,
+                                &ScraperError::Spider(e),
+                                Arc::clone(spider),
+                            )
+                            .await?;
+                        }
+                        _ => {
+                            warn!("Unhandled error type: {:?}", error);
+                            self.stats.record_error(ErrorType::Unhandled);
+                        }
                     }
-                },
+                }
                 Err(e) => {
                     warn!("Task error: {}", e);
                     self.stats.record_error(ErrorType::Unhandled);
                 }
             }
         }
-
-        info!(
-            "Spider {} completed. Total URLs processed: {}",
-            spider.name(),
-            self.visited_urls.read().len()
-        );
-        self.stats.print_summary();
         Ok(())
     }
 
-    async fn process_requests<S: Spider + Send + Sync + 'static>(
+    /// Lets the tasks still running when the crawl loop ends store their
+    /// items, for at most `timeout`. The ones left are aborted and their
+    /// items counted as unflushed.
+    async fn finish_in_flight(
+        &self,
+        futures: &mut FuturesUnordered<JoinHandle<ScraperResult<ParseResult>>>,
+        timeout: std::time::Duration,
+    ) {
+        if futures.is_empty() {
+            return;
+        }
+        debug!("Waiting for {} in-flight requests", futures.len());
+        let drained = tokio::time::timeout(timeout, async {
+            while let Some(result) = futures.next().await {
+                match result {
+                    Ok(Err(error)) => debug!("In-flight request failed: {}", error),
+                    Err(e) => warn!("Task error: {}", e),
+                    Ok(Ok(_)) => {}
+                }
+            }
+        })
+        .await;
+        if drained.is_err() {
+            warn!(
+                "{} requests still running after {:?}, aborting them",
+                futures.len(),
+                timeout
+            );
+            futures.iter().for_each(JoinHandle::abort);
+            futures.clear();
+        }
+        self.stats.record_unflushed_items();
+        let unflushed = self.stats.get_stats().unflushed_items;
+        if unflushed > 0 {
+            warn!("{} items were not stored before exit", unflushed);
+        }
+    }
+
+    /// Requests for the sitemaps listed in the `robots.txt` of every origin
+    /// of `requests`.
+    async fn robots_sitemaps(
+        &self,
+        requests: &[HttpRequest],
+        config: &SpiderConfig,
+    ) -> Vec<HttpRequest> {
+        let mut origins = HashSet::new();
+        let mut sitemaps = Vec::new();
+        for request in requests {
+            if !origins.insert(request.url.origin()) {
+                continue;
+            }
+            let rules = self
+                .robots
+                .rules(self.scraper.as_ref(), &request.url, config)
+                .await;
+            for url in rules.sitemaps() {
+                debug!("Found sitemap {} in robots.txt", url);
+                sitemaps.push(HttpRequest::new(
+                    url.clone(),
+                    SpiderCallback::ParseSitemap,
+                    0,
+                ));
+            }
+        }
+        sitemaps
+    }
+
+    /// Filters discovered requests and queues the remaining ones on the
+    /// frontier.
+    fn process_requests<S: Spider + Send + Sync + 'static>(
         &self,
         requests: Vec<HttpRequest>,
         spider: Arc<S>,
-        futures: &mut FuturesUnordered<JoinHandle<ScraperResult<ParseResult>>>,
         is_retry: bool,
     ) {
         for request in requests {
+            if self.control.is_stopped() {
+                debug!("Crawl stopped, dropping {}", request.url);
+                continue;
+            }
+
             if request.depth >= spider.config().max_depth {
                 debug!("Skipping URL {} - max depth reached", request.url);
                 continue;
             }
 
-            let url_str = request.url.to_string();
+            if self.control.is_blocked(request.url.as_str()) {
+                debug!("Skipping URL {} - blocked at runtime", request.url);
+                self.stats.record_filtered_url();
+                continue;
+            }
 
-            if !is_retry
-                && !spider.config().allow_url_revisit
-                && self.visited_urls.read().contains(&url_str)
-            {
-                debug!("Skipping URL {} - already visited", url_str);
+            if spider.config().url_filters.exceeds_limits(&request.url) {
+                debug!("Skipping URL {} - over the url size limits", request.url);
+                self.stats.record_oversized_url();
                 continue;
             }
 
-            info!("Processing URL: {} at depth {}", url_str, request.depth);
+            if !spider.config().url_filters.is_allowed(&request.url) {
+                debug!("Skipping URL {} - rejected by url filters", request.url);
+                self.stats.record_filtered_url();
+                continue;
+            }
+
+            let revisit = spider.config().revisit.for_url(&request.url);
+            let dedup = spider.config().dedup_methods.applies_to(&request);
+            if !is_retry && dedup {
+                let last_visit = spider
+                    .config()
+                    .visited_store
+                    .as_ref()
+                    .filter(|store| store.tracks(&request.callback))
+                    .and_then(|store| store.last_visit(&request.url));
+                if last_visit.is_some_and(|last| !revisit.is_due(last, Utc::now())) {
+                    debug!("Skipping URL {} - visited by a previous crawl", request.url);
+                    continue;
+                }
+            }
+
+            let first_visit = !dedup || self.visited.insert(&DedupMethods::key(&request));
+            if !first_visit && !is_retry && revisit != RevisitPolicy::Always {
+                debug!("Skipping URL {} - already visited", request.url);
+                continue;
+            }
+
+            if first_visit {
+                let trap_detector = spider.config().trap_detector.as_ref();
+                if trap_detector.is_some_and(|traps| !traps.admit(&request.url, &self.stats)) {
+                    debug!("Skipping URL {} - suspected crawl trap", request.url);
+                    continue;
+                }
+            }
+
+            if !is_retry {
+                let limit = spider.config().max_requests_per_depth.get(&request.depth);
+                if limit.is_some_and(|&max| self.stats.depth_requests(request.depth) >= max) {
+                    debug!(
+                        "Skipping URL {} - request limit for depth {} reached",
+                        request.url, request.depth
+                    );
+                    continue;
+                }
+                self.stats.record_depth_request(request.depth);
+            }
+
             if let Some(meta) = &request.meta {
                 trace!("Request metadata: {:?}", meta);
             }
 
-            self.visited_urls.write().insert(url_str);
+            self.frontier.lock().push(request);
+        }
+    }
+
+    /// Dispatches queued requests until the concurrency limit is reached or
+    /// the frontier is empty.
+    async fn schedule<S: Spider + Send + Sync + 'static>(
+        &self,
+        spider: Arc<S>,
+        futures: &mut FuturesUnordered<JoinHandle<ScraperResult<ParseResult>>>,
+    ) {
+        let config = spider.config();
+        let concurrency = match &config.warm_up {
+            Some(warm_up) => warm_up.concurrency(config.max_concurrency, &self.stats),
+            None => config.max_concurrency,
+        };
+        while futures.len() < concurrency {
+            if self.control.is_paused() {
+                // Keep draining in-flight results; only block once idle.
+                if !futures.is_empty() {
+                    return;
+                }
+                self.control.wait_while_paused().await;
+            }
 
-            if futures.len() >= spider.config().max_concurrency {
-                debug!(
-                    "Reached concurrent request limit {}, waiting for slot",
-                    spider.config().max_concurrency
-                );
-                futures.next().await;
+            self.enforce_stop_conditions(spider.config(), futures.len());
+            if self.control.is_stopped() {
+                let dropped = self.frontier.lock().drain();
+                if !dropped.is_empty() {
+                    debug!("Crawl stopped, dropping {} queued requests", dropped.len());
+                }
+                return;
             }
 
-            self.process_request(request.clone(), Arc::clone(&spider), futures)
+            let next = self.frontier.lock().pop();
+            let Some(request) = next else {
+                if !futures.is_empty() {
+                    return;
+                }
+                let opening = self.frontier.lock().next_opening(Utc::now());
+                match opening {
+                    Some(wait) => {
+                        info!(
+                            "All queued requests are held back (crawl window or open circuit), waiting {:?}",
+                            wait
+                        );
+                        self.control.sleep(wait).await;
+                        continue;
+                    }
+                    None => return,
+                }
+            };
+            if self.control.is_blocked(request.url.as_str()) {
+                debug!("Dropping {} - blocked at runtime", request.url);
+                continue;
+            }
+            if let Some(breaker) = &spider.config().circuit_breaker {
+                let host = request.url.host_str().unwrap_or_default();
+                if let Some(wait) = breaker.held_for(host) {
+                    self.defer(request, wait);
+                    continue;
+                }
+            }
+            let slot = config.slot_for(&request);
+            let max_concurrency = config.slots.get(&slot).and_then(|p| p.max_concurrency);
+            let permit = match max_concurrency {
+                Some(max) => match self.slots.try_acquire(&slot, max) {
+                    Some(permit) => Some(permit),
+                    None => {
+                        trace!("Slot {} is full, holding back {}", slot, request.url);
+                        self.defer(request, SLOT_WAIT);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            info!("Processing URL: {} at depth {}", request.url, request.depth);
+            self.events.on_request_scheduled(&request);
+            self.process_request(request, slot, permit, Arc::clone(&spider), futures)
                 .await;
         }
+        debug!(
+            "Reached concurrent request limit {}, waiting for slot",
+            concurrency
+        );
     }
 
     async fn process_request<S: Spider + Send + Sync + 'static>(
         &self,
-        request: HttpRequest,
+        mut request: HttpRequest,
+        slot: String,
+        permit: Option<SlotPermit>,
         spider: Arc<S>,
         futures: &mut FuturesUnordered<JoinHandle<ScraperResult<ParseResult>>>,
     ) {
         let spider_clone = Arc::clone(&spider);
         let scraper = self.scraper.box_clone();
-        let config = spider.config().clone();
+        let config = spider.config().resolve_for(&mut request);
+        if let Some(tracker) = config
+            .change_tracker
+            .as_ref()
+            .filter(|_| config.conditional_requests)
+        {
+            for (name, value) in tracker.conditional_headers(&request.url) {
+                if !request.headers.contains_key(name) {
+                    request.headers.insert(name, value);
+                }
+            }
+        }
+        if let Some(header) = &config.idempotency_header {
+            if !request.method.is_idempotent() {
+                request.ensure_idempotency_key(header);
+            }
+        }
         let stats = Arc::clone(&self.stats);
-        let start_time = Utc::now();
+        let throttle = Arc::clone(&self.throttle);
+        let downloader = self.downloader_middlewares.clone();
+        let parsers = self.content_parsers.clone();
+        let middlewares = self.spider_middlewares.clone();
+        let pipelines = self.pipelines.clone();
+        let robots = Arc::clone(&self.robots);
+        let events = self.events.clone();
 
         futures.push(spawn(async move {
-            let response = scraper.fetch(request.clone(), &config).await?;
-            let spider_response = SpiderResponse {
-                response: response.clone(),
-                callback: request.callback.clone(),
+            // Holds the request's place in its slot until it is processed.
+            let _permit = permit;
+            let mut delay = config.download_delay;
+            if config.respect_robots_txt {
+                let rules = robots.rules(scraper.as_ref(), &request.url, &config).await;
+                let user_agent = user_agent(&config);
+                if !rules.is_allowed(user_agent, &robots_path(&request.url)) {
+                    info!("Skipping URL {} - disallowed by robots.txt", request.url);
+                    return Ok(ParseResult::Skip);
+                }
+                if let Some(crawl_delay) = rules.crawl_delay(user_agent) {
+                    delay = delay.max(crawl_delay);
+                }
+            }
+
+            let host = request.url.host_str().unwrap_or_default();
+            let slot_delay = config.slots.get(&slot).and_then(|p| p.delay);
+            let mut wait = std::time::Duration::ZERO;
+            if !host.is_empty() {
+                let delay = match slot_delay {
+                    Some(slot_delay) if slot == host => delay.max(slot_delay),
+                    _ => delay,
+                };
+                wait = throttle.reserve(host, delay);
+            }
+            if let Some(slot_delay) = slot_delay.filter(|_| slot != host) {
+                wait = wait.max(throttle.reserve(&slot, slot_delay));
+            }
+            if !wait.is_zero() {
+                trace!(
+                    "Delaying request to {} in slot {} by {:?}",
+                    host,
+                    slot,
+                    wait
+                );
+                sleep(wait).await;
+            }
+
+            let generation = match &config.session {
+                Some(session) => session.generation().await,
+                None => 0,
             };
-            let parse_result = spider_clone.process_response(&spider_response).await;
+            let start_time = Utc::now();
+            let fetch_started = Instant::now();
+            let Some(response) = downloader
+                .fetch(scraper.as_ref(), request.clone(), &config)
+                .await?
+            else {
+                return Ok(ParseResult::Skip);
+            };
+            if let Some(pool) = &config.parse_pool {
+                pool.record_fetch(fetch_started.elapsed());
+            }
+            if config
+                .session
+                .as_ref()
+                .is_some_and(|session| session.is_logged_out(&response))
+            {
+                return Err(ScraperError::SessionExpired {
+                    url: Box::new(response.url),
+                    generation,
+                }
+                .with_request(request));
+            }
+            events.on_response_received(&response);
+            if let Some(traps) = &config.trap_detector {
+                traps.record_page(&response, &stats);
+            }
+            let parse_result = Self::process_spider_response(
+                &spider_clone,
+                &stats,
+                &parsers,
+                &middlewares,
+                &pipelines,
+                &events,
+                response.clone(),
+            )
+            .await;
             let duration = Utc::now().signed_duration_since(start_time);
 
+            if let Some(source) = &request.source {
+                stats.record_source_request(source);
+            }
+            let parse_result = parse_result.map(|result| {
+                let Some(policy) = &config.subresources else {
+                    return result;
+                };
+                let subresources = policy.requests(&response);
+                match result {
+                    ParseResult::Continue(mut requests) => {
+                        requests.extend(subresources);
+                        ParseResult::Continue(requests)
+                    }
+                    ParseResult::Skip if !subresources.is_empty() => {
+                        ParseResult::Continue(subresources)
+                    }
+                    other => other,
+                }
+            });
+            let parse_result = parse_result.map(|result| match result {
+                ParseResult::Continue(mut requests) => {
+                    config
+                        .link_filter
+                        .apply(&mut requests, &response.url, &stats);
+                    ParseResult::Continue(requests)
+                }
+                other => other,
+            });
+            let parse_result = parse_result.map(|result| match result {
+                ParseResult::Continue(mut requests) if request.source.is_some() => {
+                    for child in requests.iter_mut().filter(|r| r.source.is_none()) {
+                        child.source = request.source.clone();
+                    }
+                    ParseResult::Continue(requests)
+                }
+                other => other,
+            });
+
             // Record retry stats if any (moved outside match to avoid duplication)
             if response.retry_count > 0 {
                 for (category, count) in response.retry_history.iter() {
-                    for _ in 0..*count {
+                    for attempt in 1..=*count {
                         stats.record_retry(format!("{:?}", category));
+                        events.on_retry(&response.from_request, category, attempt);
                     }
                 }
             }
 
+            if let Some(host) = response.url.host_str() {
+                stats.record_domain_request(host, response.status >= 400 || parse_result.is_err());
+            }
+
             // Update stats based on parsing result and response
             match &parse_result {
                 Ok(_) => {
                     stats.record_request(
                         response.status,
-                        response.decoded_body.len(),
+                        response.raw_body.len(),
                         duration,
                         true, // Parsing succeeded
                     );
@@ -312,14 +1067,14 @@ impl Crawler {
                     stats.record_error(ErrorType::Parsing);
                     stats.record_request(
                         response.status,
-                        response.decoded_body.len(),
+                        response.raw_body.len(),
                         duration,
                         false, // Parsing failed
                     );
                 }
             }
 
-            parse_result
+            parse_result.map_err(|error| error.with_request(request))
         }));
     }
 }
@@ -0,0 +1,257 @@
+use crate::HttpRequest;
+use parking_lot::RwLock;
+use reqwest::Method;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+/// Request methods that go through the visited set. By default only
+/// idempotent methods are deduplicated, so POST and PATCH requests, such as
+/// form submissions, are always fetched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DedupMethods {
+    methods: HashSet<Method>,
+}
+
+impl Default for DedupMethods {
+    fn default() -> Self {
+        Self {
+            methods: [
+                Method::GET,
+                Method::HEAD,
+                Method::OPTIONS,
+                Method::PUT,
+                Method::DELETE,
+                Method::TRACE,
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+}
+
+impl DedupMethods {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deduplicates requests of no method.
+    pub fn none() -> Self {
+        Self {
+            methods: HashSet::new(),
+        }
+    }
+
+    pub fn with_method(mut self, method: Method) -> Self {
+        self.methods.insert(method);
+        self
+    }
+
+    pub fn without_method(mut self, method: &Method) -> Self {
+        self.methods.remove(method);
+        self
+    }
+
+    pub fn applies_to(&self, request: &HttpRequest) -> bool {
+        self.methods.contains(&request.method)
+    }
+
+    /// Key of `request` in the visited set: its URL, prefixed with the
+    /// method for other methods than GET so a `HEAD` does not hide the
+    /// `GET` of the same URL.
+    pub fn key(request: &HttpRequest) -> String {
+        if request.method == Method::GET {
+            request.url.to_string()
+        } else {
+            format!("{} {}", request.method, request.url)
+        }
+    }
+}
+
+/// Remembers which request keys (URLs) were already scheduled.
+pub trait DedupFilter: Send + Sync {
+    /// Records `key`, returning `true` if it had not been seen before.
+    fn insert(&self, key: &str) -> bool;
+
+    fn contains(&self, key: &str) -> bool;
+
+    /// Number of distinct keys recorded (approximate for probabilistic filters).
+    fn len(&self) -> usize;
+
+    /// Forgets every key, e.g. before a scheduled re-crawl.
+    fn clear(&self);
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Exact filter keeping every key in memory. The default.
+#[derive(Debug, Default)]
+pub struct HashSetFilter {
+    keys: RwLock<HashSet<String>>,
+}
+
+impl HashSetFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl DedupFilter for HashSetFilter {
+    fn insert(&self, key: &str) -> bool {
+        self.keys.write().insert(key.to_string())
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.keys.read().contains(key)
+    }
+
+    fn len(&self) -> usize {
+        self.keys.read().len()
+    }
+
+    fn clear(&self) {
+        self.keys.write().clear();
+    }
+}
+
+#[derive(Debug)]
+struct BloomLayer {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+    capacity: usize,
+    len: usize,
+}
+
+impl BloomLayer {
+    fn new(capacity: usize, false_positive_rate: f64) -> Self {
+        let ln2 = std::f64::consts::LN_2;
+        let num_bits = ((-(capacity as f64) * false_positive_rate.ln()) / (ln2 * ln2))
+            .ceil()
+            .max(64.0) as u64;
+        let num_hashes = ((num_bits as f64 / capacity as f64) * ln2).round().max(1.0) as u32;
+        Self {
+            bits: vec![0; num_bits.div_ceil(64) as usize],
+            num_bits,
+            num_hashes,
+            capacity,
+            len: 0,
+        }
+    }
+
+    /// Bit positions by double hashing: `h1 + i * h2`.
+    fn positions(&self, hashes: (u64, u64)) -> impl Iterator<Item = u64> + '_ {
+        let (h1, h2) = hashes;
+        (0..self.num_hashes as u64)
+            .map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits)
+    }
+
+    fn contains(&self, hashes: (u64, u64)) -> bool {
+        self.positions(hashes)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    fn insert(&mut self, hashes: (u64, u64)) {
+        let positions: Vec<u64> = self.positions(hashes).collect();
+        for bit in positions {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        self.len += 1;
+    }
+}
+
+/// Scalable bloom filter: a chain of bloom filters, each twice as large and
+/// with a tighter false-positive rate than the last, so memory grows with the
+/// crawl instead of being sized up front.
+///
+/// False positives mean a small fraction of never-seen URLs are skipped;
+/// there are no false negatives.
+#[derive(Debug)]
+pub struct BloomFilter {
+    layers: RwLock<Vec<BloomLayer>>,
+    false_positive_rate: f64,
+}
+
+/// Each new layer's false-positive rate is the previous one times this,
+/// keeping the compound rate below the configured one.
+const TIGHTENING_RATIO: f64 = 0.5;
+
+impl BloomFilter {
+    /// `initial_capacity` keys fit in the first layer while staying within
+    /// `false_positive_rate`.
+    pub fn new(initial_capacity: usize, false_positive_rate: f64) -> Self {
+        let capacity = initial_capacity.max(1);
+        let rate = false_positive_rate.clamp(f64::MIN_POSITIVE, 0.5) * (1.0 - TIGHTENING_RATIO);
+        Self {
+            layers: RwLock::new(vec![BloomLayer::new(capacity, rate)]),
+            false_positive_rate: rate,
+        }
+    }
+
+    fn hashes(key: &str) -> (u64, u64) {
+        let mut first = DefaultHasher::new();
+        key.hash(&mut first);
+        let mut second = DefaultHasher::new();
+        (key, 0x9e37_79b9_7f4a_7c15_u64).hash(&mut second);
+        // An even step could cycle through only part of the bit array.
+        (first.finish(), second.finish() | 1)
+    }
+
+    /// Approximate memory used by the bit arrays, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.layers
+            .read()
+            .iter()
+            .map(|layer| layer.bits.len() * 8)
+            .sum()
+    }
+}
+
+impl Default for BloomFilter {
+    fn default() -> Self {
+        Self::new(1_000_000, 0.001)
+    }
+}
+
+impl DedupFilter for BloomFilter {
+    fn insert(&self, key: &str) -> bool {
+        let hashes = Self::hashes(key);
+        let mut layers = self.layers.write();
+        if layers.iter().any(|layer| layer.contains(hashes)) {
+            return false;
+        }
+        let full = layers
+            .last()
+            .is_some_and(|layer| layer.len >= layer.capacity);
+        if full {
+            let depth = layers.len() as i32;
+            let capacity = layers.last().map_or(1, |layer| layer.capacity * 2);
+            let rate = self.false_positive_rate * TIGHTENING_RATIO.powi(depth);
+            layers.push(BloomLayer::new(capacity, rate));
+        }
+        if let Some(layer) = layers.last_mut() {
+            layer.insert(hashes);
+        }
+        true
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        let hashes = Self::hashes(key);
+        self.layers
+            .read()
+            .iter()
+            .any(|layer| layer.contains(hashes))
+    }
+
+    fn len(&self) -> usize {
+        self.layers.read().iter().map(|layer| layer.len).sum()
+    }
+
+    fn clear(&self) {
+        let mut layers = self.layers.write();
+        let capacity = layers.first().map_or(1, |layer| layer.capacity);
+        *layers = vec![BloomLayer::new(capacity, self.false_positive_rate)];
+    }
+}
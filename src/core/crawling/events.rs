@@ -0,0 +1,77 @@
+use crate::core::retry::RetryCategory;
+use crate::core::spider::{ParsedData, SpiderResponse};
+use crate::stats::{CloseReason, ScrapingStats};
+use crate::{HttpRequest, HttpResponse};
+use std::sync::Arc;
+
+/// Observer of crawl lifecycle events, for logging, metrics or custom
+/// bookkeeping. Every hook defaults to a no-op.
+///
+/// Hooks run inline on the crawler's tasks, so keep them cheap.
+pub trait CrawlerEvents: Send + Sync {
+    /// A request left the frontier and is about to be downloaded.
+    fn on_request_scheduled(&self, _request: &HttpRequest) {}
+
+    fn on_response_received(&self, _response: &HttpResponse) {}
+
+    /// The spider extracted at least one item from `response`.
+    fn on_item_scraped(&self, _data: &ParsedData, _response: &SpiderResponse) {}
+
+    /// A retry of `request` was triggered; `attempt` starts at 1. Retries made
+    /// by the scraper itself are reported once the request completes.
+    fn on_retry(&self, _request: &HttpRequest, _category: &RetryCategory, _attempt: usize) {}
+
+    fn on_spider_closed(&self, _reason: &CloseReason, _stats: &ScrapingStats) {}
+}
+
+/// Fans events out to every registered observer, in registration order.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    observers: Vec<Arc<dyn CrawlerEvents>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe<E: CrawlerEvents + 'static>(&mut self, observer: E) {
+        self.observers.push(Arc::new(observer));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+}
+
+impl CrawlerEvents for EventBus {
+    fn on_request_scheduled(&self, request: &HttpRequest) {
+        for observer in &self.observers {
+            observer.on_request_scheduled(request);
+        }
+    }
+
+    fn on_response_received(&self, response: &HttpResponse) {
+        for observer in &self.observers {
+            observer.on_response_received(response);
+        }
+    }
+
+    fn on_item_scraped(&self, data: &ParsedData, response: &SpiderResponse) {
+        for observer in &self.observers {
+            observer.on_item_scraped(data, response);
+        }
+    }
+
+    fn on_retry(&self, request: &HttpRequest, category: &RetryCategory, attempt: usize) {
+        for observer in &self.observers {
+            observer.on_retry(request, category, attempt);
+        }
+    }
+
+    fn on_spider_closed(&self, reason: &CloseReason, stats: &ScrapingStats) {
+        for observer in &self.observers {
+            observer.on_spider_closed(reason, stats);
+        }
+    }
+}
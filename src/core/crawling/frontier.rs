@@ -0,0 +1,383 @@
+use super::profile::for_host;
+use super::visited::VisitedStore;
+use super::window::CrawlWindow;
+use crate::HttpRequest;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// Order in which discovered requests are scheduled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrawlOrder {
+    /// Shallow pages first; requests at the same depth in discovery order.
+    #[default]
+    BreadthFirst,
+    /// Deepest pages first; the most recently discovered request wins ties.
+    DepthFirst,
+}
+
+/// Why a pending request has not been fetched yet.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingState {
+    Queued,
+    /// Held back until the crawl window of its domain opens.
+    Held,
+    /// Deferred until the given time, e.g. by an open circuit breaker.
+    Deferred(DateTime<Utc>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingRequest {
+    pub url: String,
+    pub callback: String,
+    pub depth: usize,
+    pub source: Option<String>,
+    pub state: PendingState,
+}
+
+/// Pending requests of a [`Frontier`] at one point in time, counted by
+/// domain, callback, depth and source. Depth and source decide the order
+/// in which queued requests are fetched.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FrontierSnapshot {
+    pub taken_at: DateTime<Utc>,
+    pub total: usize,
+    pub queued: usize,
+    pub held: usize,
+    pub deferred: usize,
+    pub by_domain: BTreeMap<String, usize>,
+    pub by_callback: BTreeMap<String, usize>,
+    pub by_depth: BTreeMap<usize, usize>,
+    pub by_source: BTreeMap<String, usize>,
+    pub requests: Vec<PendingRequest>,
+    /// Crawl checkpoint in progress, which items stored now are stamped
+    /// with, when the crawl resumes from a `VisitedStore`.
+    pub checkpoint: Option<u64>,
+}
+
+impl FrontierSnapshot {
+    fn add(&mut self, request: &HttpRequest, state: PendingState) {
+        let host = request.url.host_str().unwrap_or_default().to_string();
+        let callback = request.callback.name();
+        let source = request.source.clone();
+        match state {
+            PendingState::Queued => self.queued += 1,
+            PendingState::Held => self.held += 1,
+            PendingState::Deferred(_) => self.deferred += 1,
+        }
+        self.total += 1;
+        *self.by_domain.entry(host).or_default() += 1;
+        *self.by_callback.entry(callback.clone()).or_default() += 1;
+        *self.by_depth.entry(request.depth).or_default() += 1;
+        *self
+            .by_source
+            .entry(source.clone().unwrap_or_default())
+            .or_default() += 1;
+        self.requests.push(PendingRequest {
+            url: request.url.to_string(),
+            callback,
+            depth: request.depth,
+            source,
+            state,
+        });
+    }
+
+    /// The `n` domains with the most pending requests, largest first.
+    pub fn top_domains(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut domains: Vec<(&str, usize)> = self
+            .by_domain
+            .iter()
+            .map(|(domain, count)| (domain.as_str(), *count))
+            .collect();
+        domains.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        domains.truncate(n);
+        domains
+    }
+
+    /// Writes the snapshot, pending requests included, as pretty JSON.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+}
+
+#[derive(Debug)]
+struct FrontierEntry {
+    priority: (i64, i64),
+    request: HttpRequest,
+}
+
+impl PartialEq for FrontierEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+    }
+}
+
+impl Eq for FrontierEntry {}
+
+impl PartialOrd for FrontierEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FrontierEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority.cmp(&other.priority)
+    }
+}
+
+#[derive(Debug)]
+struct SourceQueue {
+    queue: BinaryHeap<FrontierEntry>,
+    weight: f64,
+    /// Weighted-fair-queueing clock; the non-empty source with the lowest
+    /// value is served next and advances by `1 / weight`.
+    virtual_time: f64,
+}
+
+/// Requests waiting to be fetched, popped according to the [`CrawlOrder`].
+///
+/// Requests are queued per seed source (`HttpRequest::source`) and sources
+/// are served by weighted fair queueing, so a large seed list cannot starve
+/// smaller ones. Requests for a domain whose [`CrawlWindow`] is closed are
+/// held back and released once the window opens.
+#[derive(Debug, Default)]
+pub struct Frontier {
+    order: CrawlOrder,
+    sources: BTreeMap<String, SourceQueue>,
+    weights: HashMap<String, u32>,
+    sequence: i64,
+    windows: Vec<(String, CrawlWindow)>,
+    held: HashMap<String, Vec<FrontierEntry>>,
+    deferred: Vec<(DateTime<Utc>, FrontierEntry)>,
+    visited_store: Option<VisitedStore>,
+}
+
+impl Frontier {
+    pub fn new(order: CrawlOrder) -> Self {
+        Self {
+            order,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_order(&mut self, order: CrawlOrder) {
+        if self.order != order {
+            let pending = self.drain();
+            self.order = order;
+            for request in pending {
+                self.push(request);
+            }
+        }
+    }
+
+    /// The store whose checkpoint in progress snapshots report.
+    pub fn set_visited_store(&mut self, store: Option<VisitedStore>) {
+        self.visited_store = store;
+    }
+
+    pub fn set_windows(&mut self, windows: Vec<(String, CrawlWindow)>) {
+        self.windows = windows;
+    }
+
+    /// Relative share of fetches for each source; unlisted sources weigh 1.
+    pub fn set_source_weights(&mut self, weights: HashMap<String, u32>) {
+        for (name, source) in self.sources.iter_mut() {
+            source.weight = weights.get(name).copied().unwrap_or(1).max(1) as f64;
+        }
+        self.weights = weights;
+    }
+
+    pub fn push(&mut self, request: HttpRequest) {
+        let entry = self.entry(request);
+        self.enqueue(entry);
+    }
+
+    /// Queues `request` but keeps it from being popped before `until`.
+    pub fn defer(&mut self, request: HttpRequest, until: DateTime<Utc>) {
+        let entry = self.entry(request);
+        self.deferred.push((until, entry));
+    }
+
+    fn entry(&mut self, request: HttpRequest) -> FrontierEntry {
+        self.sequence += 1;
+        let depth = request.depth as i64;
+        let priority = match self.order {
+            CrawlOrder::BreadthFirst => (-depth, -self.sequence),
+            CrawlOrder::DepthFirst => (depth, self.sequence),
+        };
+        FrontierEntry { priority, request }
+    }
+
+    fn enqueue(&mut self, entry: FrontierEntry) {
+        let name = entry.request.source.clone().unwrap_or_default();
+        // A source that becomes active starts at the current clock rather
+        // than cashing in the time it spent idle.
+        let now = self
+            .sources
+            .values()
+            .filter(|source| !source.queue.is_empty())
+            .map(|source| source.virtual_time)
+            .fold(None, |min: Option<f64>, t| {
+                Some(min.map_or(t, |m| m.min(t)))
+            });
+        let weight = self.weights.get(&name).copied().unwrap_or(1).max(1) as f64;
+        let source = self.sources.entry(name).or_insert(SourceQueue {
+            queue: BinaryHeap::new(),
+            weight,
+            virtual_time: 0.0,
+        });
+        if source.queue.is_empty() {
+            if let Some(now) = now {
+                source.virtual_time = source.virtual_time.max(now);
+            }
+        }
+        source.queue.push(entry);
+    }
+
+    fn pop_entry(&mut self) -> Option<FrontierEntry> {
+        let source = self
+            .sources
+            .values_mut()
+            .filter(|source| !source.queue.is_empty())
+            .min_by(|a, b| a.virtual_time.total_cmp(&b.virtual_time))?;
+        source.queue.pop()
+    }
+
+    /// Charges the source of a dispatched entry for its fetch. Entries held
+    /// by a crawl window cost their source nothing.
+    fn charge(&mut self, entry: &FrontierEntry) {
+        let name = entry.request.source.as_deref().unwrap_or_default();
+        if let Some(source) = self.sources.get_mut(name) {
+            source.virtual_time += 1.0 / source.weight;
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<HttpRequest> {
+        self.pop_at(Utc::now())
+    }
+
+    /// Pops the next request whose domain may be crawled at `now`.
+    pub fn pop_at(&mut self, now: DateTime<Utc>) -> Option<HttpRequest> {
+        self.release_held(now);
+        while let Some(entry) = self.pop_entry() {
+            let host = entry.request.url.host_str().unwrap_or_default();
+            match for_host(&self.windows, host) {
+                Some(window) if !window.is_open(now) => {
+                    self.held.entry(host.to_string()).or_default().push(entry);
+                }
+                _ => {
+                    self.charge(&entry);
+                    return Some(entry.request);
+                }
+            }
+        }
+        None
+    }
+
+    fn release_held(&mut self, now: DateTime<Utc>) {
+        let windows = &self.windows;
+        let mut released = Vec::new();
+        self.held.retain(|host, entries| {
+            let open = for_host(windows, host).is_none_or(|window| window.is_open(now));
+            if open {
+                released.append(entries);
+            }
+            !open
+        });
+        let (due, deferred): (Vec<_>, Vec<_>) = std::mem::take(&mut self.deferred)
+            .into_iter()
+            .partition(|(until, _)| *until <= now);
+        self.deferred = deferred;
+        released.extend(due.into_iter().map(|(_, entry)| entry));
+        for entry in released {
+            self.enqueue(entry);
+        }
+    }
+
+    /// Time until the earliest held or deferred request may be crawled, if
+    /// any requests are being held back.
+    pub fn next_opening(&self, now: DateTime<Utc>) -> Option<Duration> {
+        let windows = self
+            .held
+            .keys()
+            .filter_map(|host| for_host(&self.windows, host))
+            .map(|window| window.until_open(now));
+        let deferred = self
+            .deferred
+            .iter()
+            .map(|(until, _)| (*until - now).to_std().unwrap_or_default());
+        windows.chain(deferred).min()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sources
+            .values()
+            .map(|source| source.queue.len())
+            .sum::<usize>()
+            + self.held.values().map(Vec::len).sum::<usize>()
+            + self.deferred.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Counts of the pending requests, without removing them.
+    pub fn snapshot(&self) -> FrontierSnapshot {
+        let mut snapshot = FrontierSnapshot {
+            taken_at: Utc::now(),
+            checkpoint: self.visited_store.as_ref().map(|store| store.checkpoint()),
+            ..Default::default()
+        };
+        for source in self.sources.values() {
+            for entry in source.queue.iter() {
+                snapshot.add(&entry.request, PendingState::Queued);
+            }
+        }
+        for entry in self.held.values().flatten() {
+            snapshot.add(&entry.request, PendingState::Held);
+        }
+        for (until, entry) in &self.deferred {
+            snapshot.add(&entry.request, PendingState::Deferred(*until));
+        }
+        snapshot
+    }
+
+    /// Keeps only the pending requests accepted by `keep`, returning how
+    /// many were removed.
+    pub fn retain<F: Fn(&HttpRequest) -> bool>(&mut self, keep: F) -> usize {
+        let before = self.len();
+        for source in self.sources.values_mut() {
+            source.queue.retain(|entry| keep(&entry.request));
+        }
+        for entries in self.held.values_mut() {
+            entries.retain(|entry| keep(&entry.request));
+        }
+        self.deferred.retain(|(_, entry)| keep(&entry.request));
+        before - self.len()
+    }
+
+    /// Removes every pending request, held and deferred ones included.
+    pub fn drain(&mut self) -> Vec<HttpRequest> {
+        let held = std::mem::take(&mut self.held);
+        for entry in held.into_values().flatten() {
+            self.enqueue(entry);
+        }
+        for (_, entry) in std::mem::take(&mut self.deferred) {
+            self.enqueue(entry);
+        }
+        let mut pending = Vec::with_capacity(self.len());
+        while let Some(entry) = self.pop_entry() {
+            pending.push(entry.request);
+        }
+        pending
+    }
+}
@@ -0,0 +1,214 @@
+use super::frontier::{Frontier, FrontierSnapshot};
+use crate::ScraperResult;
+use log::info;
+use parking_lot::{Mutex, RwLock};
+use regex::Regex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::{JoinError, JoinHandle};
+
+/// Shared run-state flags consulted by the crawler before scheduling work.
+#[derive(Debug, Default)]
+pub(crate) struct CrawlControl {
+    paused: AtomicBool,
+    stopped: AtomicBool,
+    /// Set by a shutdown from outside; unlike the stop conditions of a run,
+    /// it outlasts [`reset`](Self::reset).
+    shut_down: AtomicBool,
+    notify: Notify,
+    /// URL patterns blocked while the crawl runs.
+    blocked: RwLock<Vec<Regex>>,
+}
+
+impl CrawlControl {
+    pub(crate) fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub(crate) fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.resume();
+    }
+
+    /// Stops the crawl and every later run of a
+    /// [`CrawlScheduler`](super::scheduler::CrawlScheduler).
+    pub(crate) fn shut_down(&self) {
+        self.shut_down.store(true, Ordering::SeqCst);
+        self.stop();
+    }
+
+    /// Clears the pause and stop flags and the blocked patterns so the
+    /// crawler can run again. A shut down crawler stays stopped.
+    pub(crate) fn reset(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+        self.stopped.store(self.is_shut_down(), Ordering::SeqCst);
+        self.blocked.write().clear();
+    }
+
+    pub(crate) fn block(&self, pattern: Regex) {
+        self.blocked.write().push(pattern);
+    }
+
+    pub(crate) fn is_blocked(&self, url: &str) -> bool {
+        self.blocked.read().iter().any(|re| re.is_match(url))
+    }
+
+    /// Blocks `pattern` and drops the matching requests from `frontier`,
+    /// returning how many were dropped.
+    pub(crate) fn block_pattern(
+        &self,
+        pattern: &str,
+        frontier: &Mutex<Frontier>,
+    ) -> Result<usize, regex::Error> {
+        let pattern = Regex::new(pattern)?;
+        let dropped = frontier
+            .lock()
+            .retain(|request| !pattern.is_match(request.url.as_str()));
+        info!(
+            "Blocking URLs matching {}, dropped {} queued requests",
+            pattern, dropped
+        );
+        self.block(pattern);
+        Ok(dropped)
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn is_shut_down(&self) -> bool {
+        self.shut_down.load(Ordering::SeqCst)
+    }
+
+    /// Blocks while the crawl is paused. Returns immediately once resumed or
+    /// stopped.
+    pub(crate) async fn wait_while_paused(&self) {
+        loop {
+            let notified = self.notify.notified();
+            if !self.is_paused() {
+                return;
+            }
+            info!("Crawl paused, waiting for resume");
+            notified.await;
+        }
+    }
+
+    /// Sleeps for `duration`, waking early if the crawl is stopped.
+    pub(crate) async fn sleep(&self, duration: Duration) {
+        let notified = self.notify.notified();
+        if self.is_stopped() {
+            return;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {}
+            _ = notified => {}
+        }
+    }
+}
+
+/// Controls a crawl started with [`Crawler::run_detached`](super::crawler::Crawler::run_detached).
+///
+/// Pausing stops new requests from being scheduled while in-flight requests
+/// finish normally. Stopping drains in-flight requests and ends the crawl.
+pub struct CrawlerHandle {
+    control: Arc<CrawlControl>,
+    frontier: Arc<Mutex<Frontier>>,
+    task: JoinHandle<ScraperResult<()>>,
+}
+
+impl CrawlerHandle {
+    pub(crate) fn new(
+        control: Arc<CrawlControl>,
+        frontier: Arc<Mutex<Frontier>>,
+        task: JoinHandle<ScraperResult<()>>,
+    ) -> Self {
+        Self {
+            control,
+            frontier,
+            task,
+        }
+    }
+
+    pub fn pause(&self) {
+        info!("Pausing crawl");
+        self.control.pause();
+    }
+
+    pub fn resume(&self) {
+        info!("Resuming crawl");
+        self.control.resume();
+    }
+
+    pub fn stop(&self) {
+        info!("Stopping crawl");
+        self.control.shut_down();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.control.is_paused()
+    }
+
+    /// Stops scheduling URLs matching `pattern` for the rest of the crawl,
+    /// e.g. an endless calendar, and drops the matching queued requests.
+    /// Returns the number of dropped requests.
+    pub fn block_pattern(&self, pattern: &str) -> Result<usize, regex::Error> {
+        self.control.block_pattern(pattern, &self.frontier)
+    }
+
+    /// Pending requests by domain, callback, depth and source, e.g. to see
+    /// which hosts a slow crawl is waiting on.
+    pub fn frontier_snapshot(&self) -> FrontierSnapshot {
+        self.frontier.lock().snapshot()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Waits for the crawl to complete and returns its result.
+    pub async fn join(self) -> Result<ScraperResult<()>, JoinError> {
+        self.task.await
+    }
+}
+
+/// Stops a crawler from outside, e.g. from a signal handler, without owning
+/// a [`CrawlerHandle`]. Pass it to
+/// [`CrawlerBuilder::with_shutdown_token`](super::builder::CrawlerBuilder::with_shutdown_token)
+/// or get one from [`Crawler::shutdown_token`](super::crawler::Crawler::shutdown_token).
+///
+/// Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownToken {
+    control: Arc<CrawlControl>,
+}
+
+impl ShutdownToken {
+    pub(crate) fn new(control: Arc<CrawlControl>) -> Self {
+        Self { control }
+    }
+
+    pub(crate) fn control(&self) -> Arc<CrawlControl> {
+        Arc::clone(&self.control)
+    }
+
+    /// Drains in-flight requests and ends the crawl.
+    pub fn shutdown(&self) {
+        info!("Shutdown requested");
+        self.control.shut_down();
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.control.is_stopped()
+    }
+}
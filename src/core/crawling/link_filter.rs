@@ -0,0 +1,130 @@
+use crate::stats::StatsTracker;
+use crate::HttpRequest;
+use log::debug;
+use std::collections::HashSet;
+use url::Url;
+
+/// Why a discovered link was dropped by a [`LinkFilter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnfollowableLink {
+    /// A scheme that doesn't name a page, e.g. `javascript:`, `mailto:` or
+    /// `tel:`.
+    Scheme,
+    /// A `#section` of the page the link was found on.
+    Fragment,
+}
+
+/// Drops the links returned by `Spider::parse` that can't lead to a new
+/// page before they reach the frontier: links with other schemes than
+/// `http` and `https`, and links to a fragment of their own page, which
+/// permissive sites use for `javascript:void(0)` buttons and tables of
+/// contents.
+#[derive(Debug, Clone)]
+pub struct LinkFilter {
+    schemes: HashSet<String>,
+    drop_fragments: bool,
+}
+
+impl Default for LinkFilter {
+    fn default() -> Self {
+        Self {
+            schemes: ["http", "https"].map(String::from).into(),
+            drop_fragments: true,
+        }
+    }
+}
+
+impl LinkFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps every link.
+    pub fn disabled() -> Self {
+        Self {
+            schemes: HashSet::new(),
+            drop_fragments: false,
+        }
+    }
+
+    /// Also follows links with `scheme`, e.g. for a custom scraper that
+    /// fetches `ftp` URLs.
+    pub fn with_scheme(mut self, scheme: &str) -> Self {
+        self.schemes.insert(scheme.to_ascii_lowercase());
+        self
+    }
+
+    /// Whether links to a fragment of their own page are dropped. On by
+    /// default.
+    pub fn with_drop_fragments(mut self, drop: bool) -> Self {
+        self.drop_fragments = drop;
+        self
+    }
+
+    /// Why `link`, found on `page`, should not be followed.
+    pub fn check(&self, link: &Url, page: &Url) -> Option<UnfollowableLink> {
+        if !self.schemes.is_empty() && !self.schemes.contains(link.scheme()) {
+            return Some(UnfollowableLink::Scheme);
+        }
+        if self.drop_fragments && link.fragment().is_some() && same_document(link, page) {
+            return Some(UnfollowableLink::Fragment);
+        }
+        None
+    }
+
+    /// Removes the requests of `requests` that should not be followed,
+    /// counting them in `stats`.
+    pub fn apply(&self, requests: &mut Vec<HttpRequest>, page: &Url, stats: &StatsTracker) {
+        requests.retain(|request| match self.check(&request.url, page) {
+            Some(UnfollowableLink::Scheme) => {
+                debug!("Skipping link {} - not a web page", request.url);
+                stats.record_unfollowable_scheme();
+                false
+            }
+            Some(UnfollowableLink::Fragment) => {
+                debug!("Skipping link {} - fragment of its own page", request.url);
+                stats.record_fragment_link();
+                false
+            }
+            None => true,
+        });
+    }
+}
+
+fn same_document(a: &Url, b: &Url) -> bool {
+    a[..url::Position::AfterQuery] == b[..url::Position::AfterQuery]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drops_other_schemes_and_own_fragments() {
+        let page = Url::parse("https://shop.example.com/p/1?tab=info").unwrap();
+        let check =
+            |filter: &LinkFilter, href: &str| filter.check(&page.join(href).unwrap(), &page);
+        let filter = LinkFilter::new();
+
+        assert_eq!(
+            check(&filter, "javascript:void(0)"),
+            Some(UnfollowableLink::Scheme)
+        );
+        assert_eq!(
+            check(&filter, "mailto:shop@example.com"),
+            Some(UnfollowableLink::Scheme)
+        );
+        assert_eq!(check(&filter, "#reviews"), Some(UnfollowableLink::Fragment));
+        assert_eq!(check(&filter, "#"), Some(UnfollowableLink::Fragment));
+        assert_eq!(check(&filter, "/p/2#reviews"), None);
+        assert_eq!(check(&filter, "?tab=specs#top"), None);
+        assert_eq!(check(&filter, "/p/2"), None);
+
+        let permissive = LinkFilter::new()
+            .with_scheme("mailto")
+            .with_drop_fragments(false);
+        assert_eq!(check(&permissive, "mailto:shop@example.com"), None);
+        assert_eq!(check(&permissive, "#reviews"), None);
+        assert_eq!(check(&LinkFilter::disabled(), "javascript:void(0)"), None);
+    }
+}
@@ -1,4 +1,27 @@
+pub mod builder;
+pub mod change;
+pub mod circuit;
 pub mod crawler;
+pub mod dedup;
+pub mod events;
+pub mod frontier;
+pub mod handle;
+pub mod link_filter;
+pub mod parse_pool;
+pub mod politeness;
+pub mod profile;
+pub mod revisit;
+pub mod robots;
+pub mod routes;
+pub mod scheduler;
+pub mod session;
+pub mod slots;
+pub mod subresource;
+pub mod trap;
+pub mod url_filter;
+pub mod visited;
+pub mod warmup;
+pub mod window;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,218 @@
+use crate::stats::ParsePoolStats;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Weight of the latest sample in the parse and fetch latency averages.
+const SMOOTHING: f64 = 0.2;
+
+/// Runs `Spider::parse` on blocking threads, so parsing large documents
+/// doesn't hold up the tasks downloading the next pages.
+///
+/// The pool sizes itself from the observed latencies: a crawl keeping
+/// `max_concurrency` requests in flight, each taking `fetch` to download
+/// and `parse` to parse, needs about `max_concurrency * parse / fetch`
+/// parsers to keep up. The size stays within `min..=max`, `max` being the
+/// available parallelism by default.
+///
+/// Clones share the pool.
+#[derive(Debug, Clone)]
+pub struct ParsePool {
+    min: usize,
+    max: usize,
+    semaphore: Arc<Semaphore>,
+    state: Arc<Mutex<PoolState>>,
+}
+
+#[derive(Debug)]
+struct PoolState {
+    /// Permits held back from the semaphore to shrink the pool below `max`.
+    reserved: Vec<OwnedSemaphorePermit>,
+    parse_latency: Option<f64>,
+    fetch_latency: Option<f64>,
+    busy_time: Duration,
+    /// Sum of the pool size over time, in size × seconds.
+    capacity_time: f64,
+    last_update: Instant,
+}
+
+impl PoolState {
+    fn size(&self, max: usize) -> usize {
+        max - self.reserved.len()
+    }
+
+    fn advance(&mut self, max: usize) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_update).as_secs_f64();
+        self.capacity_time += self.size(max) as f64 * elapsed;
+        self.last_update = now;
+    }
+}
+
+impl Default for ParsePool {
+    fn default() -> Self {
+        let max = std::thread::available_parallelism().map_or(4, |n| n.get());
+        Self::new(max)
+    }
+}
+
+impl ParsePool {
+    /// A pool of up to `max` parsers, starting with one.
+    pub fn new(max: usize) -> Self {
+        let max = max.max(1);
+        let semaphore = Arc::new(Semaphore::new(max));
+        let reserved = (1..max)
+            .map(|_| Arc::clone(&semaphore).try_acquire_owned().unwrap())
+            .collect();
+        Self {
+            min: 1,
+            max,
+            semaphore,
+            state: Arc::new(Mutex::new(PoolState {
+                reserved,
+                parse_latency: None,
+                fetch_latency: None,
+                busy_time: Duration::ZERO,
+                capacity_time: 0.0,
+                last_update: Instant::now(),
+            })),
+        }
+    }
+
+    /// Parsers kept even when parsing is fast compared to fetching.
+    pub fn with_min(mut self, min: usize) -> Self {
+        self.min = min.clamp(1, self.max);
+        self.resize(self.min);
+        self
+    }
+
+    /// Parsers currently allowed to run at once.
+    pub fn size(&self) -> usize {
+        self.state.lock().size(self.max)
+    }
+
+    /// Runs `parse` on a blocking thread once a parser is free, recording
+    /// how long it took.
+    pub async fn run<T, F>(&self, parse: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let permit = self.semaphore.acquire().await.expect("parse pool closed");
+        let started = Instant::now();
+        let result = tokio::task::spawn_blocking(parse).await;
+        let elapsed = started.elapsed();
+        drop(permit);
+        self.record_parse(elapsed);
+        match result {
+            Ok(result) => result,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+
+    fn record_parse(&self, elapsed: Duration) {
+        let mut state = self.state.lock();
+        state.busy_time += elapsed;
+        state.parse_latency = Some(smooth(state.parse_latency, elapsed));
+    }
+
+    /// Records how long a page took to download.
+    pub fn record_fetch(&self, elapsed: Duration) {
+        let mut state = self.state.lock();
+        state.fetch_latency = Some(smooth(state.fetch_latency, elapsed));
+    }
+
+    /// Resizes the pool for `max_concurrency` requests in flight, once both
+    /// latencies were observed.
+    pub fn adapt(&self, max_concurrency: usize) {
+        let target = {
+            let state = self.state.lock();
+            let (Some(parse), Some(fetch)) = (state.parse_latency, state.fetch_latency) else {
+                return;
+            };
+            let needed = max_concurrency as f64 * parse / fetch.max(f64::EPSILON);
+            (needed.ceil() as usize).clamp(self.min, self.max)
+        };
+        self.resize(target);
+    }
+
+    fn resize(&self, target: usize) {
+        let mut state = self.state.lock();
+        state.advance(self.max);
+        let size = state.size(self.max);
+        if target > size {
+            let released = state.reserved.len() - (self.max - target);
+            state.reserved.drain(..released);
+        } else {
+            // The places of parsers at work are taken back by a later resize.
+            for _ in target..size {
+                match Arc::clone(&self.semaphore).try_acquire_owned() {
+                    Ok(permit) => state.reserved.push(permit),
+                    Err(_) => break,
+                }
+            }
+        }
+    }
+
+    pub fn stats(&self) -> ParsePoolStats {
+        let mut state = self.state.lock();
+        state.advance(self.max);
+        let utilization = if state.capacity_time > 0.0 {
+            (state.busy_time.as_secs_f64() / state.capacity_time).min(1.0)
+        } else {
+            0.0
+        };
+        ParsePoolStats {
+            size: state.size(self.max),
+            max_size: self.max,
+            utilization,
+            parse_latency: Duration::from_secs_f64(state.parse_latency.unwrap_or(0.0)),
+            fetch_latency: Duration::from_secs_f64(state.fetch_latency.unwrap_or(0.0)),
+        }
+    }
+}
+
+fn smooth(average: Option<f64>, sample: Duration) -> f64 {
+    let sample = sample.as_secs_f64();
+    match average {
+        Some(average) => average + SMOOTHING * (sample - average),
+        None => sample,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sizes_to_parse_and_fetch_latency() {
+        let pool = ParsePool::new(8);
+        assert_eq!(pool.size(), 1);
+
+        pool.record_fetch(Duration::from_millis(100));
+        pool.adapt(10);
+        assert_eq!(pool.size(), 1);
+
+        // Parsing takes a fifth of the download, so 10 requests in flight
+        // keep two parsers busy.
+        pool.run(|| std::thread::sleep(Duration::from_millis(20)))
+            .await;
+        pool.adapt(10);
+        assert!((2..=3).contains(&pool.size()), "size {}", pool.size());
+
+        pool.adapt(100);
+        assert_eq!(pool.size(), 8);
+
+        for _ in 0..50 {
+            pool.record_fetch(Duration::from_secs(100));
+        }
+        pool.adapt(10);
+        assert_eq!(pool.size(), 1);
+
+        let stats = pool.stats();
+        assert_eq!(stats.max_size, 8);
+        assert!(stats.utilization > 0.0 && stats.utilization <= 1.0);
+        assert!(stats.parse_latency >= Duration::from_millis(20));
+    }
+}
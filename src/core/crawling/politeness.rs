@@ -0,0 +1,58 @@
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Tracks the next time each host may be contacted so requests to the same
+/// host are spaced by at least the configured delay.
+#[derive(Debug, Default)]
+pub struct PolitenessThrottle {
+    next_slots: Mutex<HashMap<String, Instant>>,
+}
+
+impl PolitenessThrottle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserves the next free slot for `host` and returns how long the caller
+    /// has to wait before sending its request.
+    pub fn reserve(&self, host: &str, delay: Duration) -> Duration {
+        if delay.is_zero() {
+            return Duration::ZERO;
+        }
+
+        let now = Instant::now();
+        let mut slots = self.next_slots.lock();
+        let slot = slots
+            .get(host)
+            .copied()
+            .filter(|slot| *slot > now)
+            .unwrap_or(now);
+        slots.insert(host.to_string(), slot + delay);
+
+        slot - now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_politeness_throttle_spaces_same_host() {
+        let throttle = PolitenessThrottle::new();
+        let delay = Duration::from_secs(1);
+
+        assert_eq!(throttle.reserve("example.com", delay), Duration::ZERO);
+        let second = throttle.reserve("example.com", delay);
+        assert!(second > Duration::from_millis(900) && second <= delay);
+        let third = throttle.reserve("example.com", delay);
+        assert!(third > Duration::from_millis(1900));
+        assert_eq!(throttle.reserve("other.com", delay), Duration::ZERO);
+        assert_eq!(
+            throttle.reserve("example.com", Duration::ZERO),
+            Duration::ZERO
+        );
+    }
+}
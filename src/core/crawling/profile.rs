@@ -0,0 +1,106 @@
+use crate::core::retry::RetryConfig;
+use crate::core::spider::SpiderConfig;
+use crate::HttpRequest;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Whether `host` is `domain` itself or one of its subdomains.
+pub(crate) fn host_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Finds the entry configured for `host`. The first matching entry wins.
+pub(crate) fn for_host<'a, T>(entries: &'a [(String, T)], host: &str) -> Option<&'a T> {
+    entries
+        .iter()
+        .find(|(domain, _)| host_matches(host, domain))
+        .map(|(_, value)| value)
+}
+
+/// Per-domain overrides of the spider configuration, resolved for every
+/// request whose host matches the profile's domain.
+#[derive(Debug, Clone, Default)]
+pub struct DomainProfile {
+    pub download_delay: Option<Duration>,
+    pub headers: HashMap<String, String>,
+    pub proxies: Vec<String>,
+    pub retry_config: Option<RetryConfig>,
+    pub respect_robots_txt: Option<bool>,
+    /// Throttling slot of the domain's requests that don't name their own.
+    pub slot: Option<String>,
+    proxy_cursor: Arc<AtomicUsize>,
+}
+
+impl DomainProfile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_download_delay(mut self, delay: Duration) -> Self {
+        self.download_delay = Some(delay);
+        self
+    }
+
+    pub fn with_headers(mut self, headers: Vec<(&str, &str)>) -> Self {
+        for (key, value) in headers {
+            self.headers.insert(key.to_string(), value.to_string());
+        }
+        self
+    }
+
+    /// Proxies used in rotation for requests that don't set their own.
+    pub fn with_proxies<P: Into<String>>(mut self, proxies: Vec<P>) -> Self {
+        self.proxies = proxies.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_retry(mut self, retry_config: RetryConfig) -> Self {
+        self.retry_config = Some(retry_config);
+        self
+    }
+
+    pub fn with_respect_robots_txt(mut self, respect: bool) -> Self {
+        self.respect_robots_txt = Some(respect);
+        self
+    }
+
+    /// Throttles the domain's requests in `slot`, e.g. to share a quota
+    /// between several domains of the same API.
+    pub fn with_slot<T: Into<String>>(mut self, slot: T) -> Self {
+        self.slot = Some(slot.into());
+        self
+    }
+
+    fn next_proxy(&self) -> Option<&String> {
+        if self.proxies.is_empty() {
+            return None;
+        }
+        let index = self.proxy_cursor.fetch_add(1, Ordering::Relaxed);
+        self.proxies.get(index % self.proxies.len())
+    }
+
+    /// Applies the profile to `request` and returns the configuration it
+    /// should be fetched with.
+    pub fn apply(&self, config: &SpiderConfig, request: &mut HttpRequest) -> SpiderConfig {
+        let mut resolved = config.clone();
+        if let Some(delay) = self.download_delay {
+            resolved.download_delay = delay;
+        }
+        resolved.headers.extend(self.headers.clone());
+        if let Some(retry_config) = &self.retry_config {
+            resolved.retry_config = retry_config.clone();
+        }
+        if let Some(respect) = self.respect_robots_txt {
+            resolved.respect_robots_txt = respect;
+        }
+        if request.proxy.is_none() {
+            request.proxy = self.next_proxy().cloned();
+        }
+        resolved
+    }
+}
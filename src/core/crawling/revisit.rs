@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use std::time::Duration;
+use url::Url;
+
+/// When a URL that was already visited may be fetched again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevisitPolicy {
+    Never,
+    /// Every request is fetched, even within the same crawl.
+    Always,
+    /// Fetched again once this long has passed since the visit recorded in
+    /// the spider's [`VisitedStore`](super::visited::VisitedStore).
+    After(Duration),
+}
+
+impl RevisitPolicy {
+    /// Whether a URL last visited at `last_visit` may be fetched at `now`.
+    pub fn is_due(&self, last_visit: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        match self {
+            RevisitPolicy::Never => false,
+            RevisitPolicy::Always => true,
+            RevisitPolicy::After(ttl) => {
+                now.signed_duration_since(last_visit)
+                    .to_std()
+                    .unwrap_or_default()
+                    >= *ttl
+            }
+        }
+    }
+}
+
+/// Revisit policies per URL pattern, e.g. listing pages hourly and detail
+/// pages weekly. The first matching pattern wins; other URLs get the default
+/// policy.
+#[derive(Debug, Clone)]
+pub struct RevisitPolicies {
+    default: RevisitPolicy,
+    rules: Vec<(Regex, RevisitPolicy)>,
+}
+
+impl Default for RevisitPolicies {
+    fn default() -> Self {
+        Self::new(RevisitPolicy::Never)
+    }
+}
+
+impl RevisitPolicies {
+    pub fn new(default: RevisitPolicy) -> Self {
+        Self {
+            default,
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn with_rule(mut self, pattern: &str, policy: RevisitPolicy) -> Result<Self, regex::Error> {
+        self.rules.push((Regex::new(pattern)?, policy));
+        Ok(self)
+    }
+
+    pub fn for_url(&self, url: &Url) -> RevisitPolicy {
+        self.rules
+            .iter()
+            .find(|(pattern, _)| pattern.is_match(url.as_str()))
+            .map(|(_, policy)| *policy)
+            .unwrap_or(self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let hour = Duration::from_secs(3600);
+        let week = Duration::from_secs(7 * 24 * 3600);
+        let policies = RevisitPolicies::default()
+            .with_rule(r"/category/", RevisitPolicy::After(hour))
+            .unwrap()
+            .with_rule(r"/product/", RevisitPolicy::After(week))
+            .unwrap();
+        let url = |path: &str| Url::parse(&format!("https://shop.com{}", path)).unwrap();
+
+        let listing = policies.for_url(&url("/category/shoes"));
+        assert_eq!(listing, RevisitPolicy::After(hour));
+        assert_eq!(policies.for_url(&url("/about")), RevisitPolicy::Never);
+
+        let now = Utc::now();
+        let two_hours_ago = now - chrono::Duration::hours(2);
+        assert!(listing.is_due(two_hours_ago, now));
+        assert!(!policies
+            .for_url(&url("/product/1"))
+            .is_due(two_hours_ago, now));
+    }
+}
@@ -0,0 +1,268 @@
+use crate::core::spider::SpiderConfig;
+use crate::core::SpiderCallback;
+use crate::{HttpRequest, Scraper};
+use log::{debug, warn};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use url::Url;
+
+#[derive(Debug, Default)]
+struct RobotsGroup {
+    agents: Vec<String>,
+    /// `(allow, path pattern)` pairs.
+    rules: Vec<(bool, String)>,
+    crawl_delay: Option<Duration>,
+}
+
+/// Rules and sitemaps parsed from a `robots.txt` file.
+#[derive(Debug, Default)]
+pub struct RobotsRules {
+    groups: Vec<RobotsGroup>,
+    sitemaps: Vec<Url>,
+}
+
+impl RobotsRules {
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(body: &str) -> Self {
+        let mut groups: Vec<RobotsGroup> = Vec::new();
+        let mut sitemaps = Vec::new();
+        let mut in_agent_lines = false;
+
+        for line in body.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if !in_agent_lines {
+                        groups.push(RobotsGroup::default());
+                    }
+                    in_agent_lines = true;
+                    if let Some(group) = groups.last_mut() {
+                        group.agents.push(value.to_ascii_lowercase());
+                    }
+                }
+                directive @ ("allow" | "disallow") => {
+                    in_agent_lines = false;
+                    // An empty Disallow allows everything.
+                    if value.is_empty() {
+                        continue;
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.rules.push((directive == "allow", value.to_string()));
+                    }
+                }
+                "crawl-delay" => {
+                    in_agent_lines = false;
+                    let delay = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|seconds| seconds.is_finite() && *seconds >= 0.0);
+                    if let (Some(group), Some(seconds)) = (groups.last_mut(), delay) {
+                        group.crawl_delay = Some(Duration::from_secs_f64(seconds));
+                    }
+                }
+                // Sitemaps are not part of any group.
+                "sitemap" => match Url::parse(value) {
+                    Ok(url) => sitemaps.push(url),
+                    Err(_) => debug!("Ignoring invalid sitemap URL {:?}", value),
+                },
+                _ => in_agent_lines = false,
+            }
+        }
+
+        Self { groups, sitemaps }
+    }
+
+    fn group_for(&self, user_agent: &str) -> Option<&RobotsGroup> {
+        let user_agent = user_agent.to_ascii_lowercase();
+        self.groups
+            .iter()
+            .find(|group| {
+                group
+                    .agents
+                    .iter()
+                    .any(|agent| agent != "*" && user_agent.contains(agent.as_str()))
+            })
+            .or_else(|| {
+                self.groups
+                    .iter()
+                    .find(|group| group.agents.iter().any(|agent| agent == "*"))
+            })
+    }
+
+    /// Checks `path` (including any query) against the most specific
+    /// matching rule; `Allow` wins ties.
+    pub fn is_allowed(&self, user_agent: &str, path: &str) -> bool {
+        let Some(group) = self.group_for(user_agent) else {
+            return true;
+        };
+        group
+            .rules
+            .iter()
+            .filter(|(_, pattern)| pattern_matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+
+    /// The `Crawl-delay` of the group matching `user_agent`.
+    pub fn crawl_delay(&self, user_agent: &str) -> Option<Duration> {
+        self.group_for(user_agent)?.crawl_delay
+    }
+
+    /// URLs listed in `Sitemap:` lines.
+    pub fn sitemaps(&self) -> &[Url] {
+        &self.sitemaps
+    }
+}
+
+/// The user agent robots rules are matched against.
+pub(crate) fn user_agent(config: &SpiderConfig) -> &str {
+    config
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("user-agent"))
+        .map(|(_, value)| value.as_str())
+        .unwrap_or("*")
+}
+
+/// Path and query of `url`, as matched by robots rules.
+pub(crate) fn robots_path(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+/// Prefix match supporting `*` wildcards and a trailing `$` anchor.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+/// Fetches and caches `robots.txt` once per origin.
+#[derive(Default)]
+pub(crate) struct RobotsCache {
+    entries: Mutex<HashMap<String, Arc<OnceCell<Arc<RobotsRules>>>>>,
+}
+
+impl RobotsCache {
+    /// The rules of the origin of `url`, fetched on first use.
+    pub(crate) async fn rules(
+        &self,
+        scraper: &dyn Scraper,
+        url: &Url,
+        config: &SpiderConfig,
+    ) -> Arc<RobotsRules> {
+        let origin = url.origin().ascii_serialization();
+        let cell = Arc::clone(self.entries.lock().entry(origin.clone()).or_default());
+        let rules = cell
+            .get_or_init(|| Self::fetch(scraper, &origin, config))
+            .await;
+        Arc::clone(rules)
+    }
+
+    async fn fetch(scraper: &dyn Scraper, origin: &str, config: &SpiderConfig) -> Arc<RobotsRules> {
+        let Ok(url) = Url::parse(origin).and_then(|origin| origin.join("/robots.txt")) else {
+            return Arc::new(RobotsRules::allow_all());
+        };
+        debug!("Fetching {}", url);
+        let request = HttpRequest::new(url.clone(), SpiderCallback::Bootstrap, 0);
+        let rules = match scraper.fetch_single(request, config).await {
+            Ok(response) if (200..300).contains(&response.status) => {
+                RobotsRules::parse(response.body_text().unwrap_or_default())
+            }
+            Ok(_) => RobotsRules::allow_all(),
+            Err(e) => {
+                warn!("Failed to fetch {}: {}", url, e);
+                RobotsRules::allow_all()
+            }
+        };
+        Arc::new(rules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROBOTS: &str = "
+        User-agent: *
+        Disallow: /private/
+        Allow: /private/public-page
+        Disallow: /*.pdf$
+
+        User-agent: turbobot
+        User-agent: otherbot
+        Disallow: /
+    ";
+
+    #[test]
+    fn test_robots_rules() {
+        let rules = RobotsRules::parse(ROBOTS);
+
+        assert!(rules.is_allowed("Mozilla/5.0", "/products"));
+        assert!(!rules.is_allowed("Mozilla/5.0", "/private/data"));
+        assert!(rules.is_allowed("Mozilla/5.0", "/private/public-page"));
+        assert!(!rules.is_allowed("Mozilla/5.0", "/files/report.pdf"));
+        assert!(rules.is_allowed("Mozilla/5.0", "/files/report.pdf?x=1"));
+        assert!(!rules.is_allowed("TurboBot/1.0", "/products"));
+        assert!(RobotsRules::allow_all().is_allowed("TurboBot/1.0", "/"));
+    }
+
+    #[test]
+    fn test_crawl_delay_and_sitemaps() {
+        let rules = RobotsRules::parse(
+            "
+            Sitemap: https://example.com/sitemap.xml
+            User-agent: *
+            Crawl-delay: 2.5
+            Disallow: /private/
+
+            User-agent: turbobot
+            Disallow:
+            Sitemap: not a url
+            Sitemap: https://example.com/news-sitemap.xml
+        ",
+        );
+
+        assert_eq!(
+            rules.crawl_delay("Mozilla/5.0"),
+            Some(Duration::from_millis(2500))
+        );
+        assert_eq!(rules.crawl_delay("TurboBot/1.0"), None);
+        assert_eq!(
+            rules.sitemaps().iter().map(Url::as_str).collect::<Vec<_>>(),
+            vec![
+                "https://example.com/sitemap.xml",
+                "https://example.com/news-sitemap.xml"
+            ]
+        );
+    }
+}
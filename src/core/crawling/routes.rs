@@ -0,0 +1,38 @@
+use crate::core::SpiderCallback;
+use crate::HttpRequest;
+use regex::Regex;
+use url::Url;
+
+/// URL pattern to callback rules declared by [`Spider::routes`](crate::Spider::routes).
+///
+/// The crawler gives every request discovered by `parse` the callback of the
+/// first route its URL matches; requests matching no route keep theirs.
+#[derive(Debug, Clone, Default)]
+pub struct Routes {
+    routes: Vec<(Regex, SpiderCallback)>,
+}
+
+impl Routes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn route(mut self, pattern: &str, callback: SpiderCallback) -> Result<Self, regex::Error> {
+        self.routes.push((Regex::new(pattern)?, callback));
+        Ok(self)
+    }
+
+    pub fn callback_for(&self, url: &Url) -> Option<&SpiderCallback> {
+        self.routes
+            .iter()
+            .find(|(pattern, _)| pattern.is_match(url.as_str()))
+            .map(|(_, callback)| callback)
+    }
+
+    /// Sets the routed callback of `request`, if a route matches.
+    pub fn apply(&self, request: &mut HttpRequest) {
+        if let Some(callback) = self.callback_for(&request.url) {
+            request.callback = callback.clone();
+        }
+    }
+}
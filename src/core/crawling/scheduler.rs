@@ -0,0 +1,190 @@
+use super::crawler::Crawler;
+use crate::stats::ScrapingStats;
+use crate::Spider;
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// When a [`CrawlScheduler`] starts its runs.
+#[derive(Debug, Clone)]
+pub enum CrawlSchedule {
+    /// Start a run every interval, measured from the start of the previous
+    /// run. A run that overruns the interval is followed immediately by the
+    /// next one; runs never overlap.
+    Every(Duration),
+    /// Start a run at every time matched by a cron expression.
+    Cron(Box<cron::Schedule>),
+}
+
+impl CrawlSchedule {
+    pub fn every(interval: Duration) -> Self {
+        CrawlSchedule::Every(interval)
+    }
+
+    /// Parses a cron expression with a leading seconds field, e.g.
+    /// `0 30 2 * * *` for every day at 02:30 UTC.
+    pub fn cron(expression: &str) -> Result<Self, cron::error::Error> {
+        Ok(CrawlSchedule::Cron(Box::new(cron::Schedule::from_str(
+            expression,
+        )?)))
+    }
+
+    /// Time to wait at `now` before the next run, given when the previous
+    /// one started.
+    fn next_delay(&self, previous: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<Duration> {
+        match self {
+            CrawlSchedule::Every(interval) => Some(match previous {
+                None => Duration::ZERO,
+                Some(start) => {
+                    let elapsed = (now - start).to_std().unwrap_or_default();
+                    interval.saturating_sub(elapsed)
+                }
+            }),
+            CrawlSchedule::Cron(schedule) => schedule
+                .after(&now)
+                .next()
+                .map(|next| (next - now).to_std().unwrap_or_default()),
+        }
+    }
+}
+
+/// Outcome of one scheduled run.
+#[derive(Debug)]
+pub struct RunReport {
+    /// 1-based run number.
+    pub run: u64,
+    /// [`Spider::version`] of the run's spider.
+    pub spider_version: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub stats: ScrapingStats,
+    pub error: Option<String>,
+}
+
+/// Runs a spider repeatedly on a [`CrawlSchedule`] with the same crawler,
+/// so middlewares, events and politeness state carry over between runs.
+/// The frontier and the visited set start fresh for each run, and each
+/// report has the stats of its own run.
+///
+/// A [`ShutdownToken`](super::handle::ShutdownToken) of the crawler ends the
+/// run in progress and the schedule, also while waiting for the next run.
+pub struct CrawlScheduler<F> {
+    crawler: Crawler,
+    schedule: CrawlSchedule,
+    spider_factory: F,
+    max_runs: Option<u64>,
+}
+
+impl<S, F> CrawlScheduler<F>
+where
+    S: Spider + Send + Sync + 'static,
+    F: FnMut() -> S,
+{
+    /// `spider_factory` builds the spider for each run.
+    pub fn new(crawler: Crawler, schedule: CrawlSchedule, spider_factory: F) -> Self {
+        Self {
+            crawler,
+            schedule,
+            spider_factory,
+            max_runs: None,
+        }
+    }
+
+    /// Stop after this many runs instead of running forever.
+    pub fn with_max_runs(mut self, max_runs: u64) -> Self {
+        self.max_runs = Some(max_runs);
+        self
+    }
+
+    /// Runs the schedule, handing each run's report to `on_report`. Returns
+    /// once `max_runs` is reached, the schedule has no future runs or the
+    /// crawler is shut down.
+    pub async fn run<R: FnMut(RunReport)>(mut self, mut on_report: R) {
+        let control = Arc::clone(&self.crawler.control);
+        let mut previous = None;
+        let mut run = 0;
+        while self.max_runs.is_none_or(|max| run < max) {
+            let Some(delay) = self.schedule.next_delay(previous, Utc::now()) else {
+                info!("Crawl schedule has no further runs");
+                return;
+            };
+            if !delay.is_zero() {
+                info!("Next scheduled crawl in {:?}", delay);
+                control.sleep(delay).await;
+            }
+            if control.is_shut_down() {
+                info!("Crawl schedule shut down after {} runs", run);
+                return;
+            }
+
+            run += 1;
+            let started_at = Utc::now();
+            previous = Some(started_at);
+            info!("Starting scheduled crawl run {}", run);
+
+            let spider = (self.spider_factory)();
+            let spider_version = spider.version();
+            let result = self.crawler.run(spider).await;
+            let error = result.err().map(|e| {
+                match e.request() {
+                    Some(request) => error!(
+                        "Scheduled crawl run {} failed on {}: {}",
+                        run, request.url, e
+                    ),
+                    None => error!("Scheduled crawl run {} failed: {}", run, e),
+                }
+                e.to_string()
+            });
+            let report = RunReport {
+                run,
+                spider_version,
+                started_at,
+                finished_at: Utc::now(),
+                stats: self.crawler.stats().snapshot_and_reset(),
+                error,
+            };
+            // Clears the stop conditions of this run, not a shutdown.
+            self.crawler.reset();
+            info!(
+                "Scheduled crawl run {} finished: {} requests, {} items",
+                run, report.stats.total_requests, report.stats.items_scraped
+            );
+            on_report(report);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_measures_from_previous_start() {
+        let schedule = CrawlSchedule::every(Duration::from_secs(60));
+        let now = Utc::now();
+        assert_eq!(schedule.next_delay(None, now), Some(Duration::ZERO));
+        assert_eq!(
+            schedule.next_delay(Some(now - chrono::Duration::seconds(20)), now),
+            Some(Duration::from_secs(40))
+        );
+        assert_eq!(
+            schedule.next_delay(Some(now - chrono::Duration::seconds(90)), now),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_cron_expression() {
+        let schedule = CrawlSchedule::cron("0 0 * * * *").unwrap();
+        let now = DateTime::parse_from_rfc3339("2024-05-01T10:15:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            schedule.next_delay(None, now),
+            Some(Duration::from_secs(45 * 60))
+        );
+        assert!(CrawlSchedule::cron("not a cron").is_err());
+    }
+}
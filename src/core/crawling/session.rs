@@ -0,0 +1,167 @@
+use crate::{HttpResponse, Scraper, ScraperError};
+use async_trait::async_trait;
+use log::{info, warn};
+use regex::Regex;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Logs the crawl back in once its session expired, e.g. by posting the
+/// login form with `scraper` so the new session cookie lands in its cookie
+/// store.
+#[async_trait]
+pub trait Reauthenticate: Send + Sync {
+    async fn login(&self, scraper: &dyn Scraper) -> Result<(), ScraperError>;
+}
+
+#[derive(Debug, Default)]
+struct SessionState {
+    /// Bumped after every successful login.
+    generation: u64,
+    failed_logins: u32,
+}
+
+/// Detects responses served to a logged-out client and re-authenticates.
+///
+/// A response counts as logged out when its status is one of the configured
+/// statuses, it was redirected to a URL matching a redirect pattern (such as
+/// `/login`), or its body matches a body pattern. The crawler then stops
+/// dispatching requests, runs the login routine once and replays every
+/// request that came back logged out. After `max_failed_logins` failed
+/// logins in a row the crawl is stopped, and a request still logged out
+/// after `max_replays` replays is dropped.
+///
+/// Clones share the same session state.
+#[derive(Clone)]
+pub struct SessionGuard {
+    statuses: Vec<u16>,
+    redirect_patterns: Vec<Regex>,
+    body_patterns: Vec<Regex>,
+    max_failed_logins: u32,
+    max_replays: u32,
+    login: Arc<dyn Reauthenticate>,
+    state: Arc<Mutex<SessionState>>,
+}
+
+impl fmt::Debug for SessionGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionGuard")
+            .field("statuses", &self.statuses)
+            .field("redirect_patterns", &self.redirect_patterns)
+            .field("body_patterns", &self.body_patterns)
+            .field("max_failed_logins", &self.max_failed_logins)
+            .field("max_replays", &self.max_replays)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SessionGuard {
+    pub fn new<R: Reauthenticate + 'static>(login: R) -> Self {
+        Self {
+            statuses: Vec::new(),
+            redirect_patterns: Vec::new(),
+            body_patterns: Vec::new(),
+            max_failed_logins: 3,
+            max_replays: 2,
+            login: Arc::new(login),
+            state: Arc::new(Mutex::new(SessionState::default())),
+        }
+    }
+
+    /// Treats responses with `status`, typically 401 or 403, as logged out.
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.statuses.push(status);
+        self
+    }
+
+    /// Treats responses redirected to a URL matching `pattern` as logged out.
+    pub fn with_redirect_pattern(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.redirect_patterns.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Treats responses whose body matches `pattern`, e.g. `Please sign in`,
+    /// as logged out.
+    pub fn with_body_pattern(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.body_patterns.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Failed logins in a row before the crawl is stopped. 3 by default.
+    pub fn with_max_failed_logins(mut self, max: u32) -> Self {
+        self.max_failed_logins = max;
+        self
+    }
+
+    /// Replays of a request after logging in again before it is dropped, so
+    /// a login that succeeds but doesn't restore the session can't loop
+    /// forever. 2 by default.
+    pub fn with_max_replays(mut self, max: u32) -> Self {
+        self.max_replays = max;
+        self
+    }
+
+    pub fn max_replays(&self) -> u32 {
+        self.max_replays
+    }
+
+    pub fn is_logged_out(&self, response: &HttpResponse) -> bool {
+        if self.statuses.contains(&response.status) {
+            return true;
+        }
+        if let Some(target) = redirect_target(response) {
+            if self.redirect_patterns.iter().any(|re| re.is_match(target)) {
+                return true;
+            }
+        }
+        !self.body_patterns.is_empty()
+            && response
+                .body_text()
+                .is_ok_and(|body| self.body_patterns.iter().any(|re| re.is_match(body)))
+    }
+
+    /// Number of successful logins so far. Requests remember the generation
+    /// they were sent with, so one expired session triggers a single login.
+    pub async fn generation(&self) -> u64 {
+        self.state.lock().await.generation
+    }
+
+    /// Logs in again unless another request already did since `generation`.
+    /// Returns false once the login failed too often.
+    pub(crate) async fn reauthenticate(&self, scraper: &dyn Scraper, generation: u64) -> bool {
+        let mut state = self.state.lock().await;
+        if state.generation > generation {
+            return true;
+        }
+        while state.failed_logins < self.max_failed_logins {
+            info!("Session expired, logging in again");
+            match self.login.login(scraper).await {
+                Ok(()) => {
+                    state.generation += 1;
+                    state.failed_logins = 0;
+                    return true;
+                }
+                Err(e) => {
+                    state.failed_logins += 1;
+                    warn!("Login attempt {} failed: {}", state.failed_logins, e);
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Final URL of a followed redirect, or the `Location` of an unfollowed one.
+fn redirect_target(response: &HttpResponse) -> Option<&str> {
+    response
+        .meta
+        .as_ref()
+        .and_then(|meta| meta["response"]["url"].as_str())
+        .filter(|url| *url != response.url.as_str())
+        .or_else(|| {
+            (300..400)
+                .contains(&response.status)
+                .then(|| response.headers.get("location").map(String::as_str))
+                .flatten()
+        })
+}
@@ -0,0 +1,91 @@
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Rate limit and concurrency quota of a throttling slot. Requests are
+/// grouped in slots by their host unless they, or the
+/// [`DomainProfile`](super::profile::DomainProfile) of their host, name a
+/// custom slot such as an API key, an account or a proxy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SlotPolicy {
+    /// Minimum delay between two requests of the slot, on top of the
+    /// download delay of their host.
+    pub delay: Option<Duration>,
+    /// Requests of the slot in flight at once.
+    pub max_concurrency: Option<usize>,
+}
+
+impl SlotPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = Some(max_concurrency.max(1));
+        self
+    }
+}
+
+/// Requests in flight per slot.
+#[derive(Debug, Default)]
+pub(crate) struct SlotLimiter {
+    active: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl SlotLimiter {
+    /// Takes a place in `slot` unless `max_concurrency` requests of it are
+    /// already in flight. The place is released when the permit drops.
+    pub(crate) fn try_acquire(&self, slot: &str, max_concurrency: usize) -> Option<SlotPermit> {
+        let mut active = self.active.lock();
+        let count = active.entry(slot.to_string()).or_default();
+        if *count >= max_concurrency {
+            return None;
+        }
+        *count += 1;
+        Some(SlotPermit {
+            slot: slot.to_string(),
+            active: Arc::clone(&self.active),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct SlotPermit {
+    slot: String,
+    active: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl Drop for SlotPermit {
+    fn drop(&mut self) {
+        let mut active = self.active.lock();
+        if let Some(count) = active.get_mut(&self.slot) {
+            *count -= 1;
+            if *count == 0 {
+                active.remove(&self.slot);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permits_are_released_on_drop() {
+        let limiter = SlotLimiter::default();
+        let first = limiter.try_acquire("account-1", 2).unwrap();
+        let _second = limiter.try_acquire("account-1", 2).unwrap();
+        assert!(limiter.try_acquire("account-1", 2).is_none());
+        assert!(limiter.try_acquire("account-2", 2).is_some());
+
+        drop(first);
+        assert!(limiter.try_acquire("account-1", 2).is_some());
+    }
+}
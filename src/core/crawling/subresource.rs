@@ -0,0 +1,125 @@
+use crate::core::SpiderCallback;
+use crate::http::ResponseType;
+use crate::parser::discover_api_endpoints;
+use crate::{HttpRequest, HttpResponse};
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde_json::json;
+use std::collections::HashSet;
+use url::Url;
+
+/// Subresources of HTML pages to fetch along with them: API endpoints
+/// called from the page's scripts (see [`discover_api_endpoints`]) and
+/// embedded documents (`iframe`, `embed`, `object`).
+///
+/// Every URL matching a rule is requested with the rule's callback at the
+/// page's depth, with the page recorded under the `parent` key of the
+/// request meta (`url`, `callback` and `meta` of the page request).
+#[derive(Debug, Clone, Default)]
+pub struct SubresourcePolicy {
+    rules: Vec<(Regex, SpiderCallback)>,
+}
+
+impl SubresourcePolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetches subresources matching `pattern` with `callback`. The first
+    /// matching rule wins.
+    pub fn with_rule(
+        mut self,
+        pattern: &str,
+        callback: SpiderCallback,
+    ) -> Result<Self, regex::Error> {
+        self.rules.push((Regex::new(pattern)?, callback));
+        Ok(self)
+    }
+
+    /// The subresource requests of `response`. Empty for non-HTML responses.
+    pub fn requests(&self, response: &HttpResponse) -> Vec<HttpRequest> {
+        if self.rules.is_empty() || response.response_type != ResponseType::Html {
+            return Vec::new();
+        }
+        let parent = &response.from_request;
+        let parent_meta = json!({
+            "url": response.url,
+            "callback": parent.callback.name(),
+            "meta": parent.meta,
+        });
+        let mut seen = HashSet::new();
+        subresource_urls(response)
+            .into_iter()
+            .filter(|url| seen.insert(url.clone()))
+            .filter_map(|url| {
+                let (_, callback) = self
+                    .rules
+                    .iter()
+                    .find(|(pattern, _)| pattern.is_match(url.as_str()))?;
+                let mut request = HttpRequest::new(url, callback.clone(), parent.depth);
+                request.meta = Some(json!({ "parent": parent_meta }));
+                Some(request)
+            })
+            .collect()
+    }
+}
+
+fn subresource_urls(response: &HttpResponse) -> Vec<Url> {
+    let mut urls: Vec<Url> = discover_api_endpoints(response)
+        .endpoints
+        .into_iter()
+        .filter_map(|endpoint| Url::parse(&endpoint.url).ok())
+        .collect();
+
+    let document = Html::parse_document(response.body_text().unwrap_or_default());
+    let embedded = Selector::parse("iframe[src], embed[src], object[data]").unwrap();
+    for element in document.select(&embedded) {
+        let element = element.value();
+        let Some(target) = element.attr("src").or_else(|| element.attr("data")) else {
+            continue;
+        };
+        if let Ok(url) = response.url.join(target.trim()) {
+            if matches!(url.scheme(), "http" | "https") {
+                urls.push(url);
+            }
+        }
+    }
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_matching_subresources() {
+        let url = Url::parse("https://shop.com/product/7").unwrap();
+        let mut from_request = HttpRequest::new(url.clone(), SpiderCallback::ParseItem, 1);
+        from_request.meta = Some(json!({"category": "lamps"}));
+        let response = HttpResponse {
+            from_request: Box::new(from_request),
+            ..HttpResponse::for_test(
+                &url,
+                br#"<html><script>fetch("/api/product/7/stock");
+                fetch("/api/track")</script>
+                <iframe src="/reviews/7"></iframe></html>"#,
+            )
+        };
+
+        let policy = SubresourcePolicy::new()
+            .with_rule(r"/api/product/", SpiderCallback::Custom("stock".into()))
+            .unwrap()
+            .with_rule(r"/reviews/", SpiderCallback::Custom("reviews".into()))
+            .unwrap();
+        let requests = policy.requests(&response);
+
+        let urls: Vec<_> = requests.iter().map(|r| r.url.path()).collect();
+        assert_eq!(urls, ["/api/product/7/stock", "/reviews/7"]);
+        assert_eq!(requests[0].callback, SpiderCallback::Custom("stock".into()));
+        assert_eq!(requests[0].depth, 1);
+        let parent = &requests[1].meta.as_ref().unwrap()["parent"];
+        assert_eq!(parent["url"], "https://shop.com/product/7");
+        assert_eq!(parent["callback"], "ParseItem");
+        assert_eq!(parent["meta"]["category"], "lamps");
+    }
+}
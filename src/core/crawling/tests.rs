@@ -1,23 +1,36 @@
 use crate::core::retry::mock_scraper::{MockResponse, MockScraper};
 use crate::core::retry::{
     BackoffPolicy, CategoryConfig, ContentRetryCondition, ParseRetryCondition, ParseRetryType,
-    RetryCategory, RetryCondition, RetryConfig,
+    RetryCategory, RetryCondition, RetryConfig, RetryState,
 };
 use crate::core::spider::{ParseResult, ParsedData, SpiderCallback, SpiderConfig, SpiderResponse};
+use crate::core::{ItemStream, Routes, TrapDetector};
 use crate::http::request::HttpRequest;
 use crate::storage::base::StorageError;
-use crate::storage::StorageManager;
-use crate::{Crawler, ScraperError, ScraperResult, Spider};
+use crate::storage::{DryRunFormat, DryRunStorage, Storage, StorageCategory, StorageManager};
+use crate::{Crawler, ScraperError, ScraperResult, ShutdownToken, Spider, StatsTracker};
 use async_trait::async_trait;
 use parking_lot::RwLock;
 use std::sync::Arc;
 use std::time::Duration;
 use url::Url;
 
+type ParseFn = dyn Fn(&SpiderResponse) -> ScraperResult<(ParseResult, ParsedData)> + Send + Sync;
+
+/// Spider whose start requests, routes and `parse` are set by each test.
+/// It records what reaches the other hooks: the item count of every stored
+/// batch, the not modified pages and the categories given up on.
 struct TestSpider {
     config: SpiderConfig,
-    retry_count: Arc<RwLock<usize>>,
-    retry_behavior: RetryBehavior,
+    storage_manager: StorageManager,
+    start_requests: Vec<HttpRequest>,
+    routes: Option<Routes>,
+    parse: Box<ParseFn>,
+    store_delay: Duration,
+    persisted: Arc<RwLock<Vec<usize>>>,
+    not_modified: Arc<RwLock<usize>>,
+    given_up: Arc<RwLock<Vec<RetryCategory>>>,
+    fail_on_give_up: bool,
 }
 
 enum RetryBehavior {
@@ -33,14 +46,69 @@ enum RetryBehavior {
 }
 
 impl TestSpider {
-    fn new(retry_count: Arc<RwLock<usize>>, behavior: RetryBehavior) -> Self {
+    /// A spider skipping every page, starting at `url`.
+    fn start_at(url: &str) -> Self {
         Self {
             config: SpiderConfig::default(),
-            retry_count,
-            retry_behavior: behavior,
+            storage_manager: StorageManager::new().register_storage(
+                StorageCategory::Error,
+                Storage::DryRun(DryRunStorage::new()),
+                "error",
+            ),
+            start_requests: vec![HttpRequest::new(
+                Url::parse(url).unwrap(),
+                SpiderCallback::Bootstrap,
+                0,
+            )],
+            routes: None,
+            parse: Box::new(|_| Ok((ParseResult::Skip, ParsedData::Empty))),
+            store_delay: Duration::ZERO,
+            persisted: Arc::new(RwLock::new(Vec::new())),
+            not_modified: Arc::new(RwLock::new(0)),
+            given_up: Arc::new(RwLock::new(Vec::new())),
+            fail_on_give_up: false,
         }
     }
 
+    fn new(retry_count: Arc<RwLock<usize>>, behavior: RetryBehavior) -> Self {
+        Self::start_at("http://example.com").with_parse(move |response| {
+            let mut count = retry_count.write();
+            *count += 1;
+
+            let (max_attempts, error) = match &behavior {
+                RetryBehavior::NoRetry => return Ok((ParseResult::Skip, ParsedData::Empty)),
+                RetryBehavior::RetryWithSame {
+                    max_attempts,
+                    error,
+                }
+                | RetryBehavior::RetryWithNew {
+                    max_attempts,
+                    error,
+                } => (*max_attempts, error),
+            };
+            if *count >= max_attempts {
+                return Ok((ParseResult::Skip, ParsedData::Empty));
+            }
+            if error.is_some() {
+                return Err(ScraperError::StorageError(StorageError::OperationError(
+                    "test storage error".to_string(),
+                )));
+            }
+            let parse_result = match &behavior {
+                RetryBehavior::RetryWithNew { .. } => {
+                    let request = HttpRequest::new(
+                        response.response.from_request.url.clone(),
+                        SpiderCallback::ParseItem,
+                        response.response.from_request.depth,
+                    );
+                    ParseResult::RetryWithNewContent(Box::new(request))
+                }
+                _ => ParseResult::RetryWithSameContent(Box::new(response.response.clone())),
+            };
+            Ok((parse_result, ParsedData::Empty))
+        })
+    }
+
     fn new_with_same_content(retry_count: Arc<RwLock<usize>>, max_attempts: usize) -> Self {
         Self::new(
             retry_count,
@@ -72,6 +140,73 @@ impl TestSpider {
             },
         )
     }
+
+    /// Keeps discovering a fresh URL from every page it parses.
+    fn endless(parsed: Arc<RwLock<usize>>) -> Self {
+        Self::start_at("http://example.com/0").with_parse(move |response| {
+            let mut parsed = parsed.write();
+            *parsed += 1;
+            let next = response.response.url.join(&parsed.to_string()).unwrap();
+            Ok((
+                ParseResult::Continue(vec![HttpRequest::new(next, SpiderCallback::ParseItem, 0)]),
+                ParsedData::Empty,
+            ))
+        })
+    }
+
+    /// Returns every link of its listing page with `ParseItem`, relying on
+    /// the routes, and records the callback each page is parsed with.
+    fn listing(callbacks: Arc<RwLock<Vec<(String, String)>>>) -> Self {
+        Self::start_at("http://example.com/list").with_parse(move |response| {
+            let url = &response.response.url;
+            callbacks
+                .write()
+                .push((url.path().to_string(), response.callback.name()));
+            if response.callback != SpiderCallback::Bootstrap {
+                return Ok((ParseResult::Skip, ParsedData::Empty));
+            }
+            let links = ["/list?page=2", "/item/1", "/about"]
+                .iter()
+                .map(|link| HttpRequest::new(url.join(link).unwrap(), SpiderCallback::ParseItem, 1))
+                .collect();
+            Ok((ParseResult::Continue(links), ParsedData::Empty))
+        })
+    }
+
+    /// Sends `requests` and records the method of every parsed page.
+    fn requesting(requests: Vec<HttpRequest>, methods: Arc<RwLock<Vec<String>>>) -> Self {
+        Self::start_at("http://example.com")
+            .with_start_requests(requests)
+            .with_parse(move |response| {
+                let method = response.response.from_request.method.to_string();
+                methods.write().push(method);
+                Ok((ParseResult::Skip, ParsedData::Empty))
+            })
+    }
+
+    fn with_start_requests(mut self, requests: Vec<HttpRequest>) -> Self {
+        self.start_requests = requests;
+        self
+    }
+
+    fn with_routes(mut self, routes: Routes) -> Self {
+        self.routes = Some(routes);
+        self
+    }
+
+    fn with_parse<F>(mut self, parse: F) -> Self
+    where
+        F: Fn(&SpiderResponse) -> ScraperResult<(ParseResult, ParsedData)> + Send + Sync + 'static,
+    {
+        self.parse = Box::new(parse);
+        self
+    }
+
+    /// Time it takes to store a batch of items.
+    fn with_store_delay(mut self, delay: Duration) -> Self {
+        self.store_delay = delay;
+        self
+    }
 }
 
 #[async_trait]
@@ -81,15 +216,11 @@ impl Spider for TestSpider {
     }
 
     fn storage_manager(&self) -> &StorageManager {
-        unimplemented!("Storage manager not needed for test spider")
+        &self.storage_manager
     }
 
     fn start_requests(&self) -> Vec<HttpRequest> {
-        vec![HttpRequest::new(
-            Url::parse("http://example.com").unwrap(),
-            SpiderCallback::Bootstrap,
-            0,
-        )]
+        self.start_requests.clone()
     }
 
     fn config(&self) -> &SpiderConfig {
@@ -100,72 +231,44 @@ impl Spider for TestSpider {
         self.config = config;
     }
 
-    fn parse(&self, response: &SpiderResponse) -> ScraperResult<(ParseResult, ParsedData)> {
-        let mut count = self.retry_count.write();
-        *count += 1;
+    fn routes(&self) -> Option<&Routes> {
+        self.routes.as_ref()
+    }
 
-        let parsed_data = ParsedData::Empty;
-        let parse_result = match &self.retry_behavior {
-            RetryBehavior::NoRetry => ParseResult::Skip,
-            RetryBehavior::RetryWithSame {
-                max_attempts,
-                error,
-            } => {
-                if *count < *max_attempts {
-                    if error.is_some() {
-                        return ScraperResult::Err((
-                            ScraperError::StorageError(StorageError::OperationError(
-                                "test storage error".to_string(),
-                            )),
-                            response.response.from_request.clone(),
-                        ));
-                    }
-                    ParseResult::RetryWithSameContent(Box::new(response.response.clone()))
-                } else {
-                    ParseResult::Skip
-                }
-            }
-            RetryBehavior::RetryWithNew {
-                max_attempts,
-                error,
-            } => {
-                if *count < *max_attempts {
-                    if error.is_some() {
-                        return ScraperResult::Err((
-                            ScraperError::StorageError(StorageError::OperationError(
-                                "test storage error".to_string(),
-                            )),
-                            response.response.from_request.clone(),
-                        ));
-                    }
-                    let request = HttpRequest::new(
-                        response.response.from_request.url.clone(),
-                        SpiderCallback::ParseItem,
-                        response.response.from_request.depth,
-                    );
-                    ParseResult::RetryWithNewContent(Box::new(request))
-                } else {
-                    ParseResult::Skip
-                }
-            }
-        };
+    fn parse(&self, response: &SpiderResponse) -> ScraperResult<(ParseResult, ParsedData)> {
+        (self.parse)(response)
+    }
 
-        Ok((parse_result, parsed_data))
+    fn not_modified(&self, response: &SpiderResponse) -> ScraperResult<ParseResult> {
+        assert!(response.response.raw_body.is_empty());
+        *self.not_modified.write() += 1;
+        Ok(ParseResult::Skip)
     }
 
     async fn persist_extracted_data(
         &self,
-        _data: ParsedData,
+        data: ParsedData,
         _response: &SpiderResponse,
     ) -> ScraperResult<()> {
+        if data.item_count() > 0 {
+            tokio::time::sleep(self.store_delay).await;
+            self.persisted.write().push(data.item_count());
+        }
         Ok(())
     }
 
     async fn handle_max_retries(
         &self,
-        _category: RetryCategory,
+        category: RetryCategory,
         _request: Box<HttpRequest>,
+        _history: RetryState,
     ) -> ScraperResult<()> {
+        self.given_up.write().push(category);
+        if self.fail_on_give_up {
+            return Err(ScraperError::StorageError(StorageError::OperationError(
+                "failed to store the given up request".to_string(),
+            )));
+        }
         Ok(())
     }
 }
@@ -197,6 +300,8 @@ async fn test_crawler_retry_with_same_content() {
                 ParseRetryType::SameContent,
             ))],
             backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            ..Default::default()
         },
     );
 
@@ -222,6 +327,94 @@ async fn test_crawler_retry_with_same_content() {
         .await;
 }
 
+#[tokio::test]
+async fn test_parse_retry_past_its_deadline_is_given_up_on() {
+    let retry_count = Arc::new(RwLock::new(0));
+    let spider = TestSpider::new_with_same_content(Arc::clone(&retry_count), 3);
+    let given_up = Arc::clone(&spider.given_up);
+
+    let mut retry_config = RetryConfig::default().with_retry_deadline(Duration::ZERO);
+    retry_config.categories.insert(
+        RetryCategory::ParseError,
+        CategoryConfig {
+            max_retries: 2,
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+            conditions: vec![RetryCondition::Parse(ParseRetryCondition::Content(
+                ContentRetryCondition {
+                    pattern: "retry".to_string(),
+                    is_regex: false,
+                },
+                ParseRetryType::SameContent,
+            ))],
+            backoff_policy: BackoffPolicy::Constant,
+            ..Default::default()
+        },
+    );
+    let retry_config_handle = retry_config.clone();
+    let spider = spider.with_config(SpiderConfig::default().with_retry(retry_config));
+
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "test content".to_string(),
+        delay: None,
+    }]));
+    Crawler::new(scraper).run(spider).await.unwrap();
+
+    // The first retry starts the deadline, the second one is past it.
+    assert_eq!(*retry_count.read(), 2);
+    assert_eq!(*given_up.read(), [RetryCategory::ParseError]);
+    let url = Url::parse("http://example.com").unwrap();
+    assert_eq!(
+        retry_config_handle.get_retry_state(&url).total_retries,
+        1,
+        "denied retries are not counted"
+    );
+}
+
+#[tokio::test]
+async fn test_parse_retries_follow_the_domain_profile() {
+    use crate::core::DomainProfile;
+
+    let retry_count = Arc::new(RwLock::new(0));
+    let spider = TestSpider::new_with_same_content(Arc::clone(&retry_count), 3);
+
+    let mut retry_config = RetryConfig::default();
+    retry_config.categories.insert(
+        RetryCategory::ParseError,
+        CategoryConfig {
+            max_retries: 2,
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+            conditions: vec![RetryCondition::Parse(ParseRetryCondition::Content(
+                ContentRetryCondition {
+                    pattern: "retry".to_string(),
+                    is_regex: false,
+                },
+                ParseRetryType::SameContent,
+            ))],
+            backoff_policy: BackoffPolicy::Constant,
+            ..Default::default()
+        },
+    );
+    // The spider itself doesn't retry parse errors; the profile of the host does.
+    let spider = spider.with_config(SpiderConfig::default().with_domain_profile(
+        "example.com",
+        DomainProfile::new().with_retry(retry_config.clone()),
+    ));
+
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "test content".to_string(),
+        delay: None,
+    }]));
+    Crawler::new(scraper).run(spider).await.unwrap();
+
+    assert_eq!(*retry_count.read(), 3);
+    let url = Url::parse("http://example.com").unwrap();
+    assert_eq!(retry_config.get_retry_state(&url).total_retries, 2);
+}
+
 #[tokio::test]
 async fn test_crawler_retry_with_new_content() {
     let retry_count = Arc::new(RwLock::new(0));
@@ -248,6 +441,8 @@ async fn test_crawler_retry_with_new_content() {
                 ParseRetryType::FetchNew,
             ))],
             backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            ..Default::default()
         },
     );
 
@@ -286,6 +481,8 @@ async fn test_crawler_storage_error_retry() {
                 ParseRetryType::SameContent,
             ))],
             backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            ..Default::default()
         },
     );
 
@@ -343,6 +540,8 @@ async fn test_crawler_max_retries_limit() {
                 ParseRetryType::SameContent,
             ))],
             backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            ..Default::default()
         },
     );
 
@@ -382,3 +581,1520 @@ async fn test_crawler_no_retry() {
         "Expected exactly one attempt with no retries"
     );
 }
+
+#[tokio::test]
+async fn test_detached_crawl_pause_resume_stop() {
+    let parsed = Arc::new(RwLock::new(0));
+    let spider = TestSpider::endless(Arc::clone(&parsed));
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: Some(Duration::from_millis(5)),
+    }]));
+
+    let handle = Crawler::new(scraper).run_detached(spider);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(*parsed.read() > 0);
+
+    handle.pause();
+    assert!(handle.is_paused());
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    let while_paused = *parsed.read();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(*parsed.read(), while_paused);
+
+    handle.resume();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(*parsed.read() > while_paused);
+
+    handle.stop();
+    tokio::time::timeout(Duration::from_secs(1), handle.join())
+        .await
+        .expect("crawl should finish after stop")
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_block_pattern_mid_crawl() {
+    let parsed = Arc::new(RwLock::new(0));
+    let spider = TestSpider::endless(Arc::clone(&parsed));
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: Some(Duration::from_millis(5)),
+    }]));
+
+    let handle = Crawler::new(scraper).run_detached(spider);
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    handle.block_pattern(r"/20$").unwrap();
+    assert!(handle.block_pattern("(").is_err());
+
+    // The crawl runs out of requests once /20 is blocked.
+    tokio::time::timeout(Duration::from_secs(5), handle.join())
+        .await
+        .expect("crawl should finish once the trap is blocked")
+        .unwrap()
+        .unwrap();
+    assert_eq!(*parsed.read(), 20);
+}
+
+#[tokio::test]
+async fn test_trap_detection_blocks_url_explosion() {
+    let parsed = Arc::new(RwLock::new(0));
+    let traps = TrapDetector::new().with_min_urls(5).with_min_pages(5);
+    let spider = TestSpider::endless(Arc::clone(&parsed))
+        .with_config(SpiderConfig::default().with_trap_detection(traps.clone()));
+    // Every page of the endless /N pattern has the same content.
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: None,
+    }]));
+
+    let crawler = Crawler::new(scraper);
+    tokio::time::timeout(Duration::from_secs(5), crawler.run(spider))
+        .await
+        .expect("crawl should finish once the trap is detected")
+        .unwrap();
+
+    // One page with new content out of ten reaches the default 0.1 yield.
+    assert_eq!(*parsed.read(), 10);
+    let suspected = traps.suspected();
+    assert_eq!(suspected.len(), 1);
+    assert_eq!(suspected[0].unique_pages, 1);
+    let stats = crawler.stats().get_stats();
+    assert_eq!(stats.suspected_traps.get(&suspected[0].pattern), Some(&1));
+}
+
+#[tokio::test]
+async fn test_routes_assign_callbacks_to_discovered_links() {
+    let callbacks = Arc::new(RwLock::new(Vec::new()));
+    let spider = TestSpider::listing(Arc::clone(&callbacks)).with_routes(
+        Routes::new()
+            .route(r"/list\?page=", SpiderCallback::ParsePagination)
+            .unwrap()
+            .route(r"/item/", SpiderCallback::Custom("item".to_string()))
+            .unwrap(),
+    );
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: None,
+    }]));
+    Crawler::new(scraper).run(spider).await.unwrap();
+
+    let mut callbacks = callbacks.read().clone();
+    callbacks.sort();
+    let expected = [
+        ("/about", "ParseItem"),
+        ("/item/1", "item"),
+        ("/list", "Bootstrap"),
+        ("/list", "ParsePagination"),
+    ];
+    assert_eq!(
+        callbacks,
+        expected.map(|(path, callback)| (path.to_string(), callback.to_string()))
+    );
+}
+
+#[tokio::test]
+async fn test_slot_concurrency_quota() {
+    use crate::core::{CrawlerEvents, DomainProfile, SlotPolicy};
+    use crate::HttpResponse;
+
+    #[derive(Default)]
+    struct InFlight {
+        current: RwLock<usize>,
+        max: RwLock<usize>,
+    }
+
+    struct Observer(Arc<InFlight>);
+
+    impl CrawlerEvents for Observer {
+        fn on_request_scheduled(&self, _request: &HttpRequest) {
+            let mut current = self.0.current.write();
+            *current += 1;
+            let mut max = self.0.max.write();
+            *max = (*max).max(*current);
+        }
+
+        fn on_response_received(&self, _response: &HttpResponse) {
+            *self.0.current.write() -= 1;
+        }
+    }
+
+    let callbacks = Arc::new(RwLock::new(Vec::new()));
+    let spider = TestSpider::listing(Arc::clone(&callbacks)).with_config(
+        SpiderConfig::default()
+            .with_domain_profile("example.com", DomainProfile::new().with_slot("api"))
+            .with_slot("api", SlotPolicy::new().with_max_concurrency(1)),
+    );
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: Some(Duration::from_millis(20)),
+    }]));
+    let in_flight = Arc::new(InFlight::default());
+    Crawler::builder(scraper)
+        .with_events(Observer(Arc::clone(&in_flight)))
+        .build()
+        .run(spider)
+        .await
+        .unwrap();
+
+    assert_eq!(callbacks.read().len(), 4);
+    assert_eq!(*in_flight.max.read(), 1);
+}
+
+/// Streams the JSON array body of the page.
+fn stream_json_array(response: &SpiderResponse) -> ScraperResult<(ParseResult, ParsedData)> {
+    let items = ItemStream::json_array(response.response.raw_body.clone());
+    Ok((ParseResult::Skip, ParsedData::Stream(items)))
+}
+
+#[tokio::test]
+async fn test_streamed_items_are_persisted_in_chunks() {
+    let spider = TestSpider::start_at("http://example.com/api/items")
+        .with_parse(stream_json_array)
+        .with_config(SpiderConfig::default().with_stream_chunk_size(2));
+    let chunks = Arc::clone(&spider.persisted);
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: r#"[{"id": 1}, {"id": 2}, {"id": 3}, {"id": 4}, {"id": 5}]"#.to_string(),
+        delay: None,
+    }]));
+    let crawler = Crawler::new(scraper);
+    crawler.run(spider).await.unwrap();
+
+    assert_eq!(*chunks.read(), [2, 2, 1]);
+    assert_eq!(crawler.stats().items_scraped(), 5);
+}
+
+#[tokio::test]
+async fn test_dry_run_prints_items_instead_of_persisting() {
+    let spider = TestSpider::start_at("http://example.com/api/items")
+        .with_parse(stream_json_array)
+        .with_config(SpiderConfig::default().with_dry_run(DryRunFormat::Jsonl));
+    let chunks = Arc::clone(&spider.persisted);
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: r#"[{"id": 1}, {"id": 2}]"#.to_string(),
+        delay: None,
+    }]));
+    let crawler = Crawler::new(scraper);
+    crawler.run(spider).await.unwrap();
+
+    assert!(chunks.read().is_empty());
+    assert_eq!(crawler.stats().items_scraped(), 2);
+}
+
+#[tokio::test]
+async fn test_in_flight_items_are_stored_or_reported_at_shutdown() {
+    let run = |store_delay, shutdown_timeout| async move {
+        let start_requests = ["stop", "a", "b"]
+            .iter()
+            .map(|path| {
+                let url = Url::parse("http://example.com/").unwrap().join(path);
+                HttpRequest::new(url.unwrap(), SpiderCallback::Bootstrap, 0)
+            })
+            .collect();
+        let spider = TestSpider::start_at("http://example.com/")
+            .with_start_requests(start_requests)
+            .with_parse(|response| {
+                if response.response.url.path() == "/stop" {
+                    return Ok((ParseResult::Stop, ParsedData::Empty));
+                }
+                let item = serde_json::json!({"url": response.response.url});
+                Ok((ParseResult::Skip, ParsedData::Item(item)))
+            })
+            .with_store_delay(store_delay)
+            .with_config(SpiderConfig::default().with_shutdown_timeout(shutdown_timeout));
+        let stored = Arc::clone(&spider.persisted);
+        let scraper = Box::new(MockScraper::new(vec![MockResponse {
+            status: 200,
+            body: "page".to_string(),
+            delay: None,
+        }]));
+        let crawler = Crawler::new(scraper);
+        crawler.run(spider).await.unwrap();
+        let stored = stored.read().iter().sum::<usize>();
+        (stored, crawler.stats().get_stats().unflushed_items)
+    };
+
+    // The spider stops while both items are being stored.
+    assert_eq!(
+        run(Duration::from_millis(50), Duration::from_secs(5)).await,
+        (2, 0)
+    );
+    assert_eq!(
+        run(Duration::from_secs(5), Duration::from_millis(50)).await,
+        (0, 2)
+    );
+}
+
+#[tokio::test]
+async fn test_in_flight_items_are_stored_when_the_crawl_fails() {
+    let mut retry_config = RetryConfig::default().with_retry_deadline(Duration::ZERO);
+    retry_config.categories.insert(
+        RetryCategory::ParseError,
+        CategoryConfig {
+            max_retries: 2,
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+            conditions: vec![RetryCondition::Parse(ParseRetryCondition::Content(
+                ContentRetryCondition {
+                    pattern: "retry".to_string(),
+                    is_regex: false,
+                },
+                ParseRetryType::SameContent,
+            ))],
+            ..Default::default()
+        },
+    );
+    let start_requests = ["fail", "a", "b"]
+        .iter()
+        .map(|path| {
+            let url = Url::parse("http://example.com/").unwrap().join(path);
+            HttpRequest::new(url.unwrap(), SpiderCallback::Bootstrap, 0)
+        })
+        .collect();
+    // The second retry of /fail is past the deadline, and giving up fails.
+    let spider = TestSpider {
+        fail_on_give_up: true,
+        ..TestSpider::start_at("http://example.com/")
+    }
+    .with_start_requests(start_requests)
+    .with_parse(|response| {
+        if response.response.url.path() == "/fail" {
+            let retry = ParseResult::RetryWithSameContent(Box::new(response.response.clone()));
+            return Ok((retry, ParsedData::Empty));
+        }
+        let item = serde_json::json!({"url": response.response.url});
+        Ok((ParseResult::Skip, ParsedData::Item(item)))
+    })
+    .with_store_delay(Duration::from_millis(50))
+    .with_config(SpiderConfig::default().with_retry(retry_config));
+    let stored = Arc::clone(&spider.persisted);
+
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: None,
+    }]));
+    let crawler = Crawler::new(scraper);
+    assert!(crawler.run(spider).await.is_err());
+
+    assert_eq!(stored.read().iter().sum::<usize>(), 2);
+    assert_eq!(crawler.stats().get_stats().unflushed_items, 0);
+}
+
+#[tokio::test]
+async fn test_crawl_recovers_once_the_circuit_closes() {
+    use crate::core::CircuitState;
+
+    let parsed = Arc::new(RwLock::new(Vec::new()));
+    let recorded = Arc::clone(&parsed);
+    let start_requests = ["a", "b"]
+        .iter()
+        .map(|path| {
+            let url = Url::parse("http://example.com/").unwrap().join(path);
+            HttpRequest::new(url.unwrap(), SpiderCallback::Bootstrap, 0)
+        })
+        .collect();
+    let config = SpiderConfig::default()
+        .with_circuit_breaker(1, Duration::from_millis(50))
+        .with_concurrency(1);
+    let breaker = config.circuit_breaker.clone().unwrap();
+    let spider = TestSpider::start_at("http://example.com/")
+        .with_start_requests(start_requests)
+        .with_parse(move |response| {
+            recorded.write().push(response.response.status);
+            Ok((ParseResult::Skip, ParsedData::Empty))
+        })
+        .with_config(config);
+    let scraper = Box::new(MockScraper::new(vec![
+        MockResponse {
+            status: 500,
+            body: "error".to_string(),
+            delay: None,
+        },
+        MockResponse {
+            status: 200,
+            body: "page".to_string(),
+            delay: None,
+        },
+    ]));
+
+    // The 500 opens the circuit; the other page is the trial request.
+    tokio::time::timeout(Duration::from_secs(5), Crawler::new(scraper).run(spider))
+        .await
+        .expect("crawl should finish once the trial request succeeds")
+        .unwrap();
+    assert_eq!(*parsed.read(), [500, 200]);
+    assert_eq!(breaker.state("example.com"), CircuitState::Closed);
+}
+
+#[tokio::test]
+async fn test_builder_shutdown_token_and_shared_stats() {
+    let parsed = Arc::new(RwLock::new(0));
+    let spider = TestSpider::endless(Arc::clone(&parsed));
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: Some(Duration::from_millis(5)),
+    }]));
+
+    let stats = Arc::new(StatsTracker::new());
+    let token = ShutdownToken::default();
+    let crawler = Crawler::builder(scraper)
+        .with_stats(Arc::clone(&stats))
+        .with_shutdown_token(token.clone())
+        .build();
+    let handle = crawler.run_detached(spider);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    token.shutdown();
+    assert!(token.is_shutdown());
+    tokio::time::timeout(Duration::from_secs(1), handle.join())
+        .await
+        .expect("crawl should finish after shutdown")
+        .unwrap()
+        .unwrap();
+    assert!(stats.get_stats().total_requests > 0);
+}
+
+#[tokio::test]
+async fn test_crawler_closes_on_max_requests() {
+    use crate::stats::CloseReason;
+
+    let parsed = Arc::new(RwLock::new(0));
+    let spider = TestSpider::endless(Arc::clone(&parsed))
+        .with_config(SpiderConfig::default().with_max_requests(5));
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: None,
+    }]));
+
+    let crawler = Crawler::new(scraper);
+    tokio::time::timeout(Duration::from_secs(1), crawler.run(spider))
+        .await
+        .expect("crawl should close on max requests")
+        .unwrap();
+
+    let stats = crawler.stats().get_stats();
+    assert_eq!(stats.total_requests, 5);
+    assert_eq!(*parsed.read(), 5);
+    assert_eq!(stats.close_reason, Some(CloseReason::MaxRequests(5)));
+}
+
+#[test]
+fn test_frontier_crawl_order() {
+    use crate::core::crawling::frontier::{CrawlOrder, Frontier};
+
+    let request = |path: &str, depth: usize| {
+        HttpRequest::new(
+            Url::parse(&format!("https://example.com/{}", path)).unwrap(),
+            SpiderCallback::Bootstrap,
+            depth,
+        )
+    };
+    let fill = |frontier: &mut Frontier| {
+        frontier.push(request("a", 1));
+        frontier.push(request("a/1", 2));
+        frontier.push(request("b", 1));
+        frontier.push(request("a/1/x", 3));
+        frontier.push(request("b/1", 2));
+    };
+    let paths = |frontier: &mut Frontier| -> Vec<String> {
+        frontier
+            .drain()
+            .into_iter()
+            .map(|r| r.url.path().to_string())
+            .collect()
+    };
+
+    let mut bfs = Frontier::new(CrawlOrder::BreadthFirst);
+    fill(&mut bfs);
+    assert_eq!(paths(&mut bfs), ["/a", "/b", "/a/1", "/b/1", "/a/1/x"]);
+
+    let mut dfs = Frontier::new(CrawlOrder::DepthFirst);
+    fill(&mut dfs);
+    assert_eq!(paths(&mut dfs), ["/a/1/x", "/b/1", "/a/1", "/b", "/a"]);
+}
+
+#[test]
+fn test_crawl_window_holds_requests_until_open() {
+    use crate::core::crawling::frontier::{CrawlOrder, Frontier};
+    use crate::core::CrawlWindow;
+    use chrono::{NaiveTime, TimeZone, Utc};
+
+    // 01:00–05:00 in Madrid, which is UTC+2 in summer.
+    let window = CrawlWindow::new(
+        NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+        NaiveTime::from_hms_opt(5, 0, 0).unwrap(),
+    )
+    .with_timezone(chrono_tz::Europe::Madrid);
+    let midday = Utc.with_ymd_and_hms(2024, 7, 1, 12, 0, 0).unwrap();
+    let night = Utc.with_ymd_and_hms(2024, 7, 1, 23, 30, 0).unwrap();
+    assert!(!window.is_open(midday));
+    assert!(window.is_open(night));
+    assert_eq!(window.until_open(midday), Duration::from_secs(11 * 3600));
+
+    let wrapping = CrawlWindow::new(
+        NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+        NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+    );
+    assert!(wrapping.is_open(Utc.with_ymd_and_hms(2024, 7, 1, 2, 0, 0).unwrap()));
+    assert!(!wrapping.is_open(midday));
+
+    let mut frontier = Frontier::new(CrawlOrder::BreadthFirst);
+    frontier.set_windows(vec![("example.com".to_string(), window)]);
+    for url in ["https://www.example.com/a", "https://other.com/b"] {
+        frontier.push(HttpRequest::new(
+            Url::parse(url).unwrap(),
+            SpiderCallback::Bootstrap,
+            0,
+        ));
+    }
+
+    let popped = frontier.pop_at(midday).unwrap();
+    assert_eq!(popped.url.host_str(), Some("other.com"));
+    assert!(frontier.pop_at(midday).is_none());
+    assert_eq!(frontier.len(), 1);
+    assert_eq!(
+        frontier.next_opening(midday),
+        Some(Duration::from_secs(11 * 3600))
+    );
+
+    let popped = frontier.pop_at(night).unwrap();
+    assert_eq!(popped.url.host_str(), Some("www.example.com"));
+    assert!(frontier.is_empty());
+}
+
+#[test]
+fn test_domain_profile_resolution() {
+    use crate::core::DomainProfile;
+
+    let config = SpiderConfig::default()
+        .with_headers(vec![("Accept", "text/html")])
+        .with_domain_profile(
+            "api.example.com",
+            DomainProfile::new()
+                .with_download_delay(Duration::from_secs(2))
+                .with_headers(vec![("Accept", "application/json")])
+                .with_proxies(vec!["http://proxy-a:8080", "http://proxy-b:8080"])
+                .with_respect_robots_txt(true),
+        );
+    let request =
+        |url: &str| HttpRequest::new(Url::parse(url).unwrap(), SpiderCallback::Bootstrap, 0);
+
+    let mut api = request("https://v2.api.example.com/items");
+    let resolved = config.resolve_for(&mut api);
+    assert_eq!(resolved.download_delay, Duration::from_secs(2));
+    assert_eq!(resolved.headers.get("Accept").unwrap(), "application/json");
+    assert!(resolved.respect_robots_txt);
+    assert_eq!(api.proxy.as_deref(), Some("http://proxy-a:8080"));
+
+    let mut next = request("https://api.example.com/items?page=2");
+    config.resolve_for(&mut next);
+    assert_eq!(next.proxy.as_deref(), Some("http://proxy-b:8080"));
+
+    let mut other = request("https://example.com/");
+    let resolved = config.resolve_for(&mut other);
+    assert_eq!(resolved.download_delay, Duration::ZERO);
+    assert_eq!(resolved.headers.get("Accept").unwrap(), "text/html");
+    assert!(!resolved.respect_robots_txt);
+    assert!(other.proxy.is_none());
+}
+
+#[test]
+fn test_frontier_weighted_fair_sources() {
+    use crate::core::crawling::frontier::{CrawlOrder, Frontier};
+    use std::collections::HashMap;
+
+    let mut frontier = Frontier::new(CrawlOrder::BreadthFirst);
+    frontier.set_source_weights(HashMap::from([("small".to_string(), 2)]));
+    for i in 0..100 {
+        frontier.push(
+            HttpRequest::new(
+                Url::parse(&format!("https://big.example.com/{}", i)).unwrap(),
+                SpiderCallback::Bootstrap,
+                0,
+            )
+            .with_source("big"),
+        );
+    }
+    for i in 0..4 {
+        frontier.push(
+            HttpRequest::new(
+                Url::parse(&format!("https://small.example.com/{}", i)).unwrap(),
+                SpiderCallback::Bootstrap,
+                0,
+            )
+            .with_source("small"),
+        );
+    }
+
+    let first: Vec<String> = (0..6)
+        .map(|_| frontier.pop().unwrap().source.unwrap())
+        .collect();
+    // "small" has twice the weight, so it gets two of every three slots.
+    assert_eq!(first.iter().filter(|s| *s == "small").count(), 4);
+    assert_eq!(frontier.len(), 98);
+    assert!(frontier
+        .drain()
+        .iter()
+        .all(|r| r.source.as_deref() == Some("big")));
+}
+
+#[test]
+fn test_held_requests_do_not_cost_their_source_its_share() {
+    use crate::core::crawling::frontier::{CrawlOrder, Frontier};
+    use crate::core::CrawlWindow;
+    use chrono::{NaiveTime, TimeZone, Utc};
+
+    let mut frontier = Frontier::new(CrawlOrder::BreadthFirst);
+    let window = CrawlWindow::new(
+        NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+        NaiveTime::from_hms_opt(5, 0, 0).unwrap(),
+    );
+    frontier.set_windows(vec![("night.example.com".to_string(), window)]);
+    let request = |url: &str, source: &str| {
+        HttpRequest::new(Url::parse(url).unwrap(), SpiderCallback::Bootstrap, 0).with_source(source)
+    };
+    for i in 0..3 {
+        frontier.push(request(&format!("https://night.example.com/{}", i), "a"));
+    }
+    for i in 0..3 {
+        frontier.push(request(&format!("https://day.example.com/{}", i), "a"));
+        frontier.push(request(&format!("https://other.com/{}", i), "b"));
+    }
+
+    let midday = Utc.with_ymd_and_hms(2024, 7, 1, 12, 0, 0).unwrap();
+    let first: Vec<String> = (0..4)
+        .map(|_| frontier.pop_at(midday).unwrap().source.unwrap())
+        .collect();
+    assert_eq!(first.iter().filter(|s| *s == "a").count(), 2);
+    assert_eq!(frontier.len(), 5);
+}
+
+#[test]
+fn test_frontier_snapshot_counts_pending_requests() {
+    use crate::core::crawling::frontier::{CrawlOrder, Frontier, PendingState};
+
+    let mut frontier = Frontier::new(CrawlOrder::BreadthFirst);
+    let request = |url: &str, callback: SpiderCallback, depth| {
+        HttpRequest::new(Url::parse(url).unwrap(), callback, depth)
+    };
+    for i in 0..3 {
+        frontier.push(request(
+            &format!("https://slow.example.com/{}", i),
+            SpiderCallback::ParseItem,
+            1,
+        ));
+    }
+    frontier.push(request(
+        "https://fast.example.com/",
+        SpiderCallback::Bootstrap,
+        0,
+    ));
+    frontier.defer(
+        request(
+            "https://slow.example.com/retry",
+            SpiderCallback::ParseItem,
+            1,
+        ),
+        chrono::Utc::now() + chrono::Duration::minutes(5),
+    );
+
+    let snapshot = frontier.snapshot();
+    assert_eq!(
+        (snapshot.total, snapshot.queued, snapshot.deferred),
+        (5, 4, 1)
+    );
+    assert_eq!(snapshot.top_domains(1), [("slow.example.com", 4)]);
+    assert_eq!(snapshot.by_callback["ParseItem"], 4);
+    assert_eq!(snapshot.by_depth[&0], 1);
+    assert!(snapshot
+        .requests
+        .iter()
+        .any(|r| matches!(r.state, PendingState::Deferred(_))));
+    assert_eq!(frontier.len(), 5);
+
+    let path = std::env::temp_dir().join(format!(
+        "turboscraper_frontier_{}.json",
+        uuid::Uuid::now_v7()
+    ));
+    snapshot.save(&path).unwrap();
+    let saved: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved["requests"].as_array().unwrap().len(), 5);
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_crawler_events_are_emitted() {
+    use crate::core::CrawlerEvents;
+    use crate::stats::{CloseReason, ScrapingStats};
+    use crate::HttpResponse;
+
+    #[derive(Default)]
+    struct Recorder {
+        log: Arc<RwLock<Vec<String>>>,
+    }
+
+    impl CrawlerEvents for Recorder {
+        fn on_request_scheduled(&self, request: &HttpRequest) {
+            self.log
+                .write()
+                .push(format!("scheduled {}", request.url.path()));
+        }
+
+        fn on_response_received(&self, response: &HttpResponse) {
+            self.log
+                .write()
+                .push(format!("response {}", response.status));
+        }
+
+        fn on_spider_closed(&self, reason: &CloseReason, stats: &ScrapingStats) {
+            self.log
+                .write()
+                .push(format!("closed {} after {}", reason, stats.total_requests));
+        }
+    }
+
+    let log = Arc::new(RwLock::new(Vec::new()));
+    let spider = TestSpider::endless(Arc::new(RwLock::new(0)))
+        .with_config(SpiderConfig::default().with_max_requests(2));
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: None,
+    }]));
+
+    let crawler = Crawler::builder(scraper)
+        .with_events(Recorder {
+            log: Arc::clone(&log),
+        })
+        .build();
+    crawler.run(spider).await.unwrap();
+
+    assert_eq!(
+        *log.read(),
+        [
+            "scheduled /0",
+            "response 200",
+            "scheduled /1",
+            "response 200",
+            "closed max requests reached (2) after 2"
+        ]
+    );
+}
+
+#[test]
+fn test_url_filters() {
+    use crate::core::UrlFilters;
+
+    let filters = UrlFilters::new()
+        .with_allow(r"^https://shop\.example\.com/")
+        .unwrap()
+        .with_deny(r"/(login|cart)")
+        .unwrap()
+        .with_default_denied_extensions();
+    let allowed = |url: &str| filters.is_allowed(&Url::parse(url).unwrap());
+
+    assert!(allowed("https://shop.example.com/products/1"));
+    assert!(allowed("https://shop.example.com/catalog.html"));
+    assert!(!allowed("https://blog.example.com/post"));
+    assert!(!allowed("https://shop.example.com/cart?item=1"));
+    assert!(!allowed("https://shop.example.com/static/logo.PNG"));
+    assert!(!allowed("https://shop.example.com/assets/app.js"));
+    assert!(UrlFilters::new().is_allowed(&Url::parse("https://any.org/a.zip").unwrap()));
+}
+
+#[tokio::test]
+async fn test_url_size_limits() {
+    use crate::core::UrlFilters;
+
+    let filters = UrlFilters::new().with_default_limits();
+    let exceeds = |url: &str| filters.exceeds_limits(&Url::parse(url).unwrap());
+    assert!(!exceeds("https://shop.com/a/b/a/b/a/b?x=1&y=2"));
+    assert!(exceeds("https://shop.com/a/b/a/b/a/b/a/b"));
+    assert!(exceeds(&format!("https://shop.com/{}", "x".repeat(2048))));
+    let query: Vec<String> = (0..33).map(|i| format!("p{}=1", i)).collect();
+    assert!(exceeds(&format!("https://shop.com/?{}", query.join("&"))));
+
+    // http://example.com/10 is the first URL over 20 bytes.
+    let spider = TestSpider::endless(Arc::new(RwLock::new(0))).with_config(
+        SpiderConfig::default().with_url_filters(UrlFilters::new().with_max_length(20)),
+    );
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: None,
+    }]));
+    let crawler = Crawler::new(scraper);
+    crawler.run(spider).await.unwrap();
+
+    let stats = crawler.stats().get_stats();
+    assert_eq!(stats.total_requests, 10);
+    assert_eq!(stats.oversized_urls, 1);
+    assert_eq!(stats.filtered_urls, 1);
+}
+
+#[tokio::test]
+async fn test_crawler_counts_filtered_urls() {
+    use crate::core::UrlFilters;
+
+    let spider = TestSpider::endless(Arc::new(RwLock::new(0))).with_config(
+        SpiderConfig::default()
+            .with_max_requests(10)
+            .with_url_filters(UrlFilters::new().with_deny(r"/2$").unwrap()),
+    );
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: None,
+    }]));
+
+    let crawler = Crawler::new(scraper);
+    crawler.run(spider).await.unwrap();
+
+    // /0 and /1 are fetched; /2 is filtered and the chain ends there.
+    let stats = crawler.stats().get_stats();
+    assert_eq!(stats.total_requests, 2);
+    assert_eq!(stats.filtered_urls, 1);
+}
+
+#[test]
+fn test_bloom_filter_scales_without_false_negatives() {
+    use crate::core::{BloomFilter, DedupFilter};
+
+    let filter = BloomFilter::new(1_000, 0.01);
+    let initial_memory = filter.memory_usage();
+    // A few new keys may already look present; that is the price of a bloom filter.
+    let inserted = (0..20_000)
+        .filter(|i| filter.insert(&format!("https://example.com/page/{}", i)))
+        .count();
+    assert!(inserted > 19_800, "only {} inserted", inserted);
+    assert!(filter.memory_usage() > initial_memory);
+    assert!((0..20_000).all(|i| filter.contains(&format!("https://example.com/page/{}", i))));
+    assert!(!filter.insert("https://example.com/page/42"));
+
+    let false_positives = (0..20_000)
+        .filter(|i| filter.contains(&format!("https://other.org/item/{}", i)))
+        .count();
+    assert!(false_positives < 200, "{} false positives", false_positives);
+}
+
+#[tokio::test]
+async fn test_crawler_with_bloom_dedup_filter() {
+    use crate::core::BloomFilter;
+
+    let spider = TestSpider::endless(Arc::new(RwLock::new(0)))
+        .with_config(SpiderConfig::default().with_max_requests(3));
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: None,
+    }]));
+
+    let crawler = Crawler::builder(scraper)
+        .with_dedup_filter(BloomFilter::new(100, 0.01))
+        .build();
+    crawler.run(spider).await.unwrap();
+    assert_eq!(crawler.stats().get_stats().total_requests, 3);
+}
+
+#[tokio::test]
+async fn test_max_requests_per_depth() {
+    use crate::stats::CloseReason;
+
+    let spider = TestSpider::endless(Arc::new(RwLock::new(0)))
+        .with_config(SpiderConfig::default().with_max_requests_per_depth(0, 3));
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: None,
+    }]));
+
+    let crawler = Crawler::new(scraper);
+    tokio::time::timeout(Duration::from_secs(1), crawler.run(spider))
+        .await
+        .expect("crawl should finish once depth 0 is capped")
+        .unwrap();
+
+    let stats = crawler.stats().get_stats();
+    assert_eq!(stats.total_requests, 3);
+    assert_eq!(stats.depths.get(&0), Some(&3));
+    assert_eq!(stats.close_reason, Some(CloseReason::Finished));
+}
+
+#[tokio::test]
+async fn test_scheduler_reports_each_run() {
+    use crate::core::{CrawlSchedule, CrawlScheduler};
+
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: None,
+    }]));
+    let mut reports = Vec::new();
+    CrawlScheduler::new(
+        Crawler::new(scraper),
+        CrawlSchedule::every(Duration::from_millis(10)),
+        || {
+            TestSpider::endless(Arc::new(RwLock::new(0)))
+                .with_config(SpiderConfig::default().with_max_requests(2))
+        },
+    )
+    .with_max_runs(2)
+    .run(|report| reports.push(report))
+    .await;
+
+    assert_eq!(reports.len(), 2);
+    for (i, report) in reports.iter().enumerate() {
+        assert_eq!(report.run, i as u64 + 1);
+        assert!(report.error.is_none());
+        // The visited set is cleared and each report has its own run's stats.
+        assert_eq!(report.stats.total_requests, 2);
+    }
+}
+
+#[tokio::test]
+async fn test_scheduler_stops_on_shutdown_and_keeps_shared_stats() {
+    use crate::core::{CrawlSchedule, CrawlScheduler};
+
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: None,
+    }]));
+    let stats = Arc::new(StatsTracker::new());
+    let token = ShutdownToken::default();
+    let crawler = Crawler::builder(scraper)
+        .with_stats(Arc::clone(&stats))
+        .with_shutdown_token(token.clone())
+        .build();
+    let scheduler = CrawlScheduler::new(
+        crawler,
+        CrawlSchedule::every(Duration::from_secs(60)),
+        || {
+            TestSpider::endless(Arc::new(RwLock::new(0)))
+                .with_config(SpiderConfig::default().with_max_requests(2))
+        },
+    );
+
+    let reports = Arc::new(RwLock::new(Vec::new()));
+    let shared = Arc::clone(&reports);
+    let scheduled = tokio::spawn(scheduler.run(move |report| shared.write().push(report)));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    // The first run went through the shared tracker, whose totals were
+    // handed to the report.
+    assert_eq!(reports.read().len(), 1);
+    assert_eq!(reports.read()[0].stats.total_requests, 2);
+    assert_eq!(stats.recent().requests, 2);
+    assert_eq!(stats.get_stats().total_requests, 0);
+
+    token.shutdown();
+    tokio::time::timeout(Duration::from_secs(1), scheduled)
+        .await
+        .expect("schedule should end on shutdown while waiting for the next run")
+        .unwrap();
+    assert_eq!(reports.read().len(), 1);
+}
+
+#[tokio::test]
+async fn test_incremental_crawl_skips_unchanged_pages() {
+    use crate::core::ChangeTracker;
+
+    let path = std::env::temp_dir().join("turboscraper_change_tracker_test.json");
+    let _ = std::fs::remove_file(&path);
+    let run = |tracker: ChangeTracker, parsed: Arc<RwLock<usize>>| async move {
+        let spider = TestSpider::endless(parsed).with_config(
+            SpiderConfig::default()
+                .with_max_requests(3)
+                .with_change_tracker(tracker),
+        );
+        let scraper = Box::new(MockScraper::new(vec![MockResponse {
+            status: 200,
+            body: "page".to_string(),
+            delay: None,
+        }]));
+        let crawler = Crawler::new(scraper);
+        crawler.run(spider).await.unwrap();
+        crawler.stats().get_stats()
+    };
+
+    let parsed = Arc::new(RwLock::new(0));
+    run(ChangeTracker::open(&path).unwrap(), Arc::clone(&parsed)).await;
+    let first_run = *parsed.read();
+    assert!(first_run > 0);
+
+    // The fingerprints were saved, so the re-crawl stops at the unchanged
+    // start page.
+    let tracker = ChangeTracker::open(&path).unwrap();
+    assert_eq!(tracker.len(), first_run);
+    let stats = run(tracker, Arc::clone(&parsed)).await;
+    assert_eq!(*parsed.read(), first_run);
+    assert_eq!(stats.unchanged_pages, 1);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn test_visited_store_skips_detail_pages_of_previous_crawls() {
+    use crate::core::VisitedStore;
+
+    let path =
+        std::env::temp_dir().join(format!("turboscraper_visited_{}.txt", uuid::Uuid::now_v7()));
+    let run = |store: VisitedStore| async move {
+        let parsed = Arc::new(RwLock::new(0));
+        let spider = TestSpider::endless(Arc::clone(&parsed)).with_config(
+            SpiderConfig::default()
+                .with_max_requests(3)
+                .with_visited_store(store),
+        );
+        let scraper = Box::new(MockScraper::new(vec![MockResponse {
+            status: 200,
+            body: "page".to_string(),
+            delay: None,
+        }]));
+        Crawler::new(scraper).run(spider).await.unwrap();
+        let parsed = *parsed.read();
+        parsed
+    };
+
+    assert_eq!(run(VisitedStore::open(&path).unwrap()).await, 3);
+
+    // Only the ParseItem pages were saved. The bootstrap page is crawled
+    // again, its already scraped child is not.
+    let store = VisitedStore::open(&path).unwrap();
+    assert_eq!(store.len(), 2);
+    assert!(!store.contains(&Url::parse("http://example.com/0").unwrap()));
+    assert_eq!(run(store).await, 1);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_visited_store_recovers_pages_stored_after_the_last_checkpoint() {
+    use crate::core::VisitedStore;
+    use serde_json::json;
+
+    let path =
+        std::env::temp_dir().join(format!("turboscraper_visited_{}.txt", uuid::Uuid::now_v7()));
+    let store = VisitedStore::open(&path).unwrap();
+    assert_eq!(store.checkpoint(), 1);
+    store.record(&Url::parse("http://example.com/1").unwrap());
+    store.save().unwrap();
+    assert_eq!(store.checkpoint(), 2);
+
+    // The crawl crashed after storing /2 and /3 during checkpoint 2.
+    let store = VisitedStore::open(&path).unwrap();
+    assert_eq!(store.checkpoint(), 2);
+    let item = |url: &str, checkpoint: u64, callback: &str| {
+        json!({
+            "url": url,
+            "timestamp": "2026-10-17T08:00:00Z",
+            "metadata": {"checkpoint": checkpoint, "callback": callback},
+        })
+    };
+    let items = [
+        item("http://example.com/1", 1, "ParseItem"),
+        item("http://example.com/2", 2, "ParseItem"),
+        item("http://example.com/2", 2, "ParseItem"),
+        item("http://example.com/3", 2, "ParseItem"),
+        item("http://example.com/", 2, "Bootstrap"),
+    ];
+    assert_eq!(store.recover(&items), 2);
+    assert!(store.contains(&Url::parse("http://example.com/3").unwrap()));
+    assert!(!store.contains(&Url::parse("http://example.com/").unwrap()));
+    assert_eq!(store.len(), 3);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_visited_store_save_keeps_pages_being_persisted_uncommitted() {
+    use crate::core::VisitedStore;
+    use serde_json::json;
+
+    let path =
+        std::env::temp_dir().join(format!("turboscraper_visited_{}.txt", uuid::Uuid::now_v7()));
+    let items_dir = std::env::temp_dir().join(format!(
+        "turboscraper_visited_items_{}",
+        uuid::Uuid::now_v7()
+    ));
+    let store = VisitedStore::open(&path).unwrap();
+    let url = Url::parse("http://example.com/1").unwrap();
+
+    // A save lands while /1 is being persisted: its items are stamped with
+    // checkpoint 1 but the URL is not in the file.
+    let page = store.begin_page();
+    std::fs::create_dir_all(items_dir.join("example.com")).unwrap();
+    let item = json!({
+        "url": url.to_string(),
+        "timestamp": "2026-10-17T08:00:00Z",
+        "metadata": {"checkpoint": store.checkpoint(), "callback": "ParseItem"},
+    });
+    std::fs::write(items_dir.join("example.com/1.json"), item.to_string()).unwrap();
+    store.save().unwrap();
+    page.record(&url);
+
+    // The crash leaves checkpoint 1 uncommitted, so resuming recovers /1.
+    let resumed = VisitedStore::open(&path)
+        .unwrap()
+        .with_recovery_dir(&items_dir);
+    assert!(!resumed.contains(&url));
+    resumed.resume_as("1.0").unwrap();
+    assert!(resumed.contains(&url));
+
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_dir_all(&items_dir);
+}
+
+#[tokio::test]
+async fn test_visited_store_of_incompatible_spider_version_is_not_resumed() {
+    use crate::core::VisitedStore;
+
+    let path =
+        std::env::temp_dir().join(format!("turboscraper_visited_{}.txt", uuid::Uuid::now_v7()));
+    std::fs::write(&path, "#version\t2.1.0\nhttp://example.com/1\n").unwrap();
+    let run = |store: VisitedStore| async move {
+        let spider = TestSpider::endless(Arc::new(RwLock::new(0))).with_config(
+            SpiderConfig::default()
+                .with_max_requests(3)
+                .with_visited_store(store),
+        );
+        let scraper = Box::new(MockScraper::new(vec![MockResponse {
+            status: 200,
+            body: "page".to_string(),
+            delay: None,
+        }]));
+        Crawler::new(scraper).run(spider).await
+    };
+
+    let result = run(VisitedStore::open(&path).unwrap()).await;
+    assert!(matches!(
+        result,
+        Err(ScraperError::IncompatibleSpiderVersion { ref saved, ref current })
+            if saved == "2.1.0" && current == "1.0.0"
+    ));
+
+    let store = VisitedStore::open(&path).unwrap().with_force_resume(true);
+    run(store).await.unwrap();
+    let store = VisitedStore::open(&path).unwrap();
+    assert_eq!(store.spider_version().as_deref(), Some("1.0.0"));
+    assert!(store.contains(&Url::parse("http://example.com/1").unwrap()));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn test_revisit_ttl_per_url_pattern() {
+    use crate::core::{RevisitPolicies, RevisitPolicy, VisitedStore};
+
+    let run = |store: VisitedStore| async move {
+        let parsed = Arc::new(RwLock::new(0));
+        // /1 is due again right away, /2 never.
+        let revisit = RevisitPolicies::default()
+            .with_rule(r"/1$", RevisitPolicy::After(Duration::ZERO))
+            .unwrap();
+        let spider = TestSpider::endless(Arc::clone(&parsed)).with_config(
+            SpiderConfig::default()
+                .with_max_requests(3)
+                .with_visited_store(store)
+                .with_revisit_policies(revisit),
+        );
+        let scraper = Box::new(MockScraper::new(vec![MockResponse {
+            status: 200,
+            body: "page".to_string(),
+            delay: None,
+        }]));
+        Crawler::new(scraper).run(spider).await.unwrap();
+        let parsed = *parsed.read();
+        parsed
+    };
+
+    let store = VisitedStore::new();
+    assert_eq!(run(store.clone()).await, 3);
+    assert!(store
+        .last_visit(&Url::parse("http://example.com/2").unwrap())
+        .is_some());
+    assert_eq!(run(store).await, 2);
+}
+
+#[tokio::test]
+async fn test_robots_sitemaps_are_queued() {
+    use crate::core::CrawlerEvents;
+
+    struct Scheduled(Arc<RwLock<Vec<(String, SpiderCallback)>>>);
+
+    impl CrawlerEvents for Scheduled {
+        fn on_request_scheduled(&self, request: &HttpRequest) {
+            self.0
+                .write()
+                .push((request.url.path().to_string(), request.callback.clone()));
+        }
+    }
+
+    let spider = TestSpider::endless(Arc::new(RwLock::new(0))).with_config(
+        SpiderConfig::default()
+            .with_max_requests(2)
+            .with_robots_sitemaps(true),
+    );
+    // Every URL, robots.txt included, gets this body.
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "User-agent: *\nSitemap: http://example.com/sitemap.xml\n".to_string(),
+        delay: None,
+    }]));
+
+    let scheduled = Arc::new(RwLock::new(Vec::new()));
+    let crawler = Crawler::builder(scraper)
+        .with_events(Scheduled(Arc::clone(&scheduled)))
+        .build();
+    crawler.run(spider).await.unwrap();
+
+    assert!(scheduled
+        .read()
+        .contains(&("/sitemap.xml".to_string(), SpiderCallback::ParseSitemap)));
+}
+
+#[tokio::test]
+async fn test_expired_session_logs_in_again_and_replays() {
+    use crate::core::{Reauthenticate, SessionGuard};
+    use crate::stats::CloseReason;
+    use crate::Scraper;
+
+    struct CountingLogin {
+        logins: Arc<RwLock<u32>>,
+        succeed: bool,
+    }
+
+    #[async_trait]
+    impl Reauthenticate for CountingLogin {
+        async fn login(&self, _scraper: &dyn Scraper) -> Result<(), ScraperError> {
+            *self.logins.write() += 1;
+            if self.succeed {
+                Ok(())
+            } else {
+                Err(ScraperError::ParsingError("bad credentials".to_string()))
+            }
+        }
+    }
+
+    let run = |succeed: bool| async move {
+        let parsed = Arc::new(RwLock::new(0));
+        let logins = Arc::new(RwLock::new(0));
+        let guard = SessionGuard::new(CountingLogin {
+            logins: Arc::clone(&logins),
+            succeed,
+        })
+        .with_body_pattern("Please sign in")
+        .unwrap()
+        .with_max_failed_logins(2);
+        let spider = TestSpider::endless(Arc::clone(&parsed)).with_config(
+            SpiderConfig::default()
+                .with_max_requests(3)
+                .with_session_guard(guard),
+        );
+        let page = |body: &str| MockResponse {
+            status: 200,
+            body: body.to_string(),
+            delay: None,
+        };
+        // /1 is served logged out once, then replayed after the login.
+        let scraper = Box::new(MockScraper::new(vec![
+            page("page"),
+            page("Please sign in"),
+            page("page"),
+            page("page"),
+        ]));
+        let crawler = Crawler::new(scraper);
+        crawler.run(spider).await.unwrap();
+        let parsed = *parsed.read();
+        let logins = *logins.read();
+        (parsed, logins, crawler.stats().get_stats().close_reason)
+    };
+
+    let (parsed, logins, reason) = run(true).await;
+    assert_eq!((parsed, logins), (3, 1));
+    assert_eq!(reason, Some(CloseReason::MaxRequests(3)));
+
+    let (parsed, logins, reason) = run(false).await;
+    assert_eq!((parsed, logins), (1, 2));
+    assert_eq!(reason, Some(CloseReason::LoginFailed));
+}
+
+#[tokio::test]
+async fn test_request_still_logged_out_after_login_is_dropped() {
+    use crate::core::{Reauthenticate, SessionGuard};
+    use crate::Scraper;
+
+    struct AcceptedLogin {
+        logins: Arc<RwLock<u32>>,
+    }
+
+    #[async_trait]
+    impl Reauthenticate for AcceptedLogin {
+        async fn login(&self, _scraper: &dyn Scraper) -> Result<(), ScraperError> {
+            *self.logins.write() += 1;
+            Ok(())
+        }
+    }
+
+    // The login goes through, but every page is still served logged out.
+    let parsed = Arc::new(RwLock::new(0));
+    let logins = Arc::new(RwLock::new(0));
+    let guard = SessionGuard::new(AcceptedLogin {
+        logins: Arc::clone(&logins),
+    })
+    .with_body_pattern("Please sign in")
+    .unwrap()
+    .with_max_replays(2);
+    let spider = TestSpider::endless(Arc::clone(&parsed))
+        .with_config(SpiderConfig::default().with_session_guard(guard));
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "Please sign in".to_string(),
+        delay: None,
+    }]));
+
+    tokio::time::timeout(Duration::from_secs(5), Crawler::new(scraper).run(spider))
+        .await
+        .expect("crawl should drop the request instead of replaying it forever")
+        .unwrap();
+    assert_eq!(*parsed.read(), 0);
+    assert_eq!(*logins.read(), 2);
+}
+
+#[tokio::test]
+async fn test_conditional_requests_surface_not_modified_pages() {
+    use crate::core::ChangeTracker;
+    use crate::scrapers::http_scraper::HttpScraper;
+    use wiremock::matchers::{header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(header("if-none-match", "\"v1\""))
+        .respond_with(ResponseTemplate::new(304))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string("<html>catalog</html>")
+                .insert_header("etag", "\"v1\"")
+                .insert_header("last-modified", "Wed, 21 Oct 2026 07:28:00 GMT"),
+        )
+        .mount(&server)
+        .await;
+
+    let tracker = ChangeTracker::new();
+    let parsed = Arc::new(RwLock::new(0));
+    let not_modified = Arc::new(RwLock::new(0));
+    let run = |conditional: bool| {
+        let parsed = Arc::clone(&parsed);
+        let spider = TestSpider {
+            not_modified: Arc::clone(&not_modified),
+            ..TestSpider::start_at(&server.uri())
+        }
+        .with_parse(move |_| {
+            *parsed.write() += 1;
+            Ok((ParseResult::Skip, ParsedData::Empty))
+        })
+        .with_config(
+            SpiderConfig::default()
+                .with_change_tracker(tracker.clone())
+                .with_conditional_requests(conditional),
+        );
+        async move {
+            let crawler = Crawler::new(Box::new(HttpScraper::new().unwrap()));
+            crawler.run(spider).await.unwrap();
+            crawler.stats().get_stats()
+        }
+    };
+
+    run(true).await;
+    assert_eq!((*parsed.read(), *not_modified.read()), (1, 0));
+
+    // The validators of the first response are sent back.
+    let stats = run(true).await;
+    assert_eq!((*parsed.read(), *not_modified.read()), (1, 1));
+    assert_eq!(stats.unchanged_pages, 1);
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(
+        requests[1].headers["if-modified-since"],
+        "Wed, 21 Oct 2026 07:28:00 GMT"
+    );
+
+    // Without them the full page is served again, and found unchanged.
+    run(false).await;
+    assert_eq!((*parsed.read(), *not_modified.read()), (1, 1));
+    assert_eq!(server.received_requests().await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_non_idempotent_requests_are_not_deduplicated() {
+    use crate::core::DedupMethods;
+    use reqwest::Method;
+
+    let url = Url::parse("http://example.com/search").unwrap();
+    let get = HttpRequest::new(url.clone(), SpiderCallback::Bootstrap, 0);
+    let post = get.clone().with_method(Method::POST).with_body("q=shoes");
+    let head = get.clone().with_method(Method::HEAD);
+    let requests = vec![get.clone(), get, post.clone(), post, head];
+
+    let run = |dedup: DedupMethods| {
+        let parsed = Arc::new(RwLock::new(Vec::new()));
+        let spider = TestSpider::requesting(requests.clone(), Arc::clone(&parsed))
+            .with_config(SpiderConfig::default().with_dedup_methods(dedup));
+        async move {
+            let scraper = Box::new(MockScraper::new(vec![MockResponse {
+                status: 200,
+                body: "results".to_string(),
+                delay: None,
+            }]));
+            Crawler::new(scraper).run(spider).await.unwrap();
+            let mut parsed = parsed.read().clone();
+            parsed.sort();
+            parsed
+        }
+    };
+
+    // Both form submissions are sent; the HEAD doesn't hide the GET.
+    assert_eq!(
+        run(DedupMethods::default()).await,
+        ["GET", "HEAD", "POST", "POST"]
+    );
+    assert_eq!(
+        run(DedupMethods::default().with_method(Method::POST)).await,
+        ["GET", "HEAD", "POST"]
+    );
+    assert_eq!(
+        run(DedupMethods::none()).await,
+        ["GET", "GET", "HEAD", "POST", "POST"]
+    );
+}
+
+#[tokio::test]
+async fn test_retries_reuse_the_idempotency_key() {
+    use crate::core::retry::RequestRetryCondition;
+    use crate::http::IDEMPOTENCY_KEY_HEADER;
+    use crate::scrapers::http_scraper::HttpScraper;
+    use reqwest::Method;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&server)
+        .await;
+
+    let mut retry_config = RetryConfig::default();
+    retry_config.categories.insert(
+        RetryCategory::ServerError,
+        CategoryConfig {
+            max_retries: 2,
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+            conditions: vec![RetryCondition::Request(RequestRetryCondition::StatusCode(
+                503,
+            ))],
+            backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            ..Default::default()
+        },
+    );
+    let url = Url::parse(&server.uri()).unwrap().join("/orders").unwrap();
+    let order = |id: &str| {
+        HttpRequest::new(url.clone(), SpiderCallback::Bootstrap, 0)
+            .with_method(Method::POST)
+            .with_body(format!("{{\"order\": \"{}\"}}", id))
+    };
+    let spider = TestSpider::requesting(
+        vec![
+            order("1"),
+            order("2").with_header(IDEMPOTENCY_KEY_HEADER, "order-2"),
+            HttpRequest::new(url.clone(), SpiderCallback::Bootstrap, 0),
+        ],
+        Arc::new(RwLock::new(Vec::new())),
+    )
+    .with_config(
+        SpiderConfig::default()
+            .with_retry(retry_config)
+            .with_idempotency_keys(IDEMPOTENCY_KEY_HEADER),
+    );
+    let crawler = Crawler::new(Box::new(HttpScraper::new().unwrap()));
+    crawler.run(spider).await.unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let keys_of = |body: &str| {
+        requests
+            .iter()
+            .filter(|r| r.body == body.as_bytes())
+            .map(|r| {
+                r.headers[IDEMPOTENCY_KEY_HEADER]
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+    };
+    let first = keys_of("{\"order\": \"1\"}");
+    let second = keys_of("{\"order\": \"2\"}");
+    // One of the orders was sent twice, with the same key.
+    assert_eq!(first.len() + second.len(), 3);
+    assert!(first.iter().all(|key| *key == first[0]));
+    assert!(second.iter().all(|key| key == "order-2"));
+    // GET requests don't get a key.
+    assert!(requests
+        .iter()
+        .filter(|r| r.method.as_str() == "GET")
+        .all(|r| !r.headers.contains_key(IDEMPOTENCY_KEY_HEADER)));
+}
+
+#[tokio::test]
+async fn test_parse_pool_runs_parse_and_reports_utilization() {
+    use crate::core::ParsePool;
+
+    let url = Url::parse("http://example.com/page").unwrap();
+    let parsed = Arc::new(RwLock::new(Vec::new()));
+    let spider = TestSpider::requesting(
+        (0..4)
+            .map(|i| {
+                HttpRequest::new(
+                    url.join(&i.to_string()).unwrap(),
+                    SpiderCallback::Bootstrap,
+                    0,
+                )
+            })
+            .collect(),
+        Arc::clone(&parsed),
+    )
+    .with_config(SpiderConfig::default().with_parse_pool(ParsePool::new(2)));
+    let scraper = Box::new(MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "page".to_string(),
+        delay: None,
+    }]));
+    let crawler = Crawler::new(scraper);
+    crawler.run(spider).await.unwrap();
+
+    assert_eq!(parsed.read().len(), 4);
+    let stats = crawler.stats().get_stats();
+    assert_eq!(stats.parsed_pages, 4);
+    let pool = stats.parse_pool.expect("parse pool stats");
+    assert_eq!(pool.max_size, 2);
+    assert!((1..=2).contains(&pool.size));
+    assert!((0.0..=1.0).contains(&pool.utilization));
+}
@@ -0,0 +1,214 @@
+use crate::{HttpResponse, StatsTracker};
+use log::warn;
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use url::Url;
+
+/// What happens to new URLs of a suspected trap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrapAction {
+    /// Drop every new URL of the pattern.
+    Block,
+    /// Keep one of every `n` new URLs of the pattern.
+    Throttle(u32),
+    /// Only report the trap.
+    Report,
+}
+
+/// A URL pattern detected as a crawl trap, see [`TrapDetector::suspected`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuspectedTrap {
+    pub pattern: String,
+    pub urls: u64,
+    pub pages: u64,
+    pub unique_pages: u64,
+}
+
+#[derive(Debug, Default)]
+struct PatternState {
+    urls: u64,
+    pages: u64,
+    signatures: HashSet<String>,
+    trap: bool,
+    /// URLs seen since the pattern was flagged, for throttling.
+    since_flagged: u64,
+}
+
+/// Detects URL explosions such as infinite calendars or faceted filters.
+///
+/// URLs are grouped by pattern: host and path with numeric segments
+/// replaced by `{n}`, query ignored. A pattern is flagged once it produced
+/// at least `min_urls` unique URLs and `min_pages` fetched pages, of which
+/// at most `max_content_yield` had content not seen before under the
+/// pattern. Page content is compared with digits stripped, so pages that
+/// only differ by dates or counters count as duplicates.
+///
+/// Flagged patterns are logged, counted in stats as suspected traps and
+/// handled according to the [`TrapAction`]. Clones share the same state.
+#[derive(Debug, Clone)]
+pub struct TrapDetector {
+    min_urls: u64,
+    min_pages: u64,
+    max_content_yield: f64,
+    action: TrapAction,
+    patterns: Arc<Mutex<HashMap<String, PatternState>>>,
+}
+
+impl Default for TrapDetector {
+    fn default() -> Self {
+        Self {
+            min_urls: 100,
+            min_pages: 20,
+            max_content_yield: 0.1,
+            action: TrapAction::Block,
+            patterns: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl TrapDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_min_urls(mut self, min_urls: u64) -> Self {
+        self.min_urls = min_urls;
+        self
+    }
+
+    pub fn with_min_pages(mut self, min_pages: u64) -> Self {
+        self.min_pages = min_pages;
+        self
+    }
+
+    /// Share of fetched pages with new content below which a pattern is a
+    /// trap. 0.1 by default.
+    pub fn with_max_content_yield(mut self, max_content_yield: f64) -> Self {
+        self.max_content_yield = max_content_yield;
+        self
+    }
+
+    pub fn with_action(mut self, action: TrapAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// The pattern `url` is grouped under, e.g. `shop.com/calendar/{n}/{n}`.
+    pub fn pattern(url: &Url) -> String {
+        let path: Vec<&str> = url
+            .path_segments()
+            .map(|segments| {
+                segments
+                    .map(|segment| {
+                        if segment.chars().any(|c| c.is_ascii_digit()) {
+                            "{n}"
+                        } else {
+                            segment
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        format!("{}/{}", url.host_str().unwrap_or_default(), path.join("/"))
+    }
+
+    /// Counts a newly discovered URL and returns whether it may be
+    /// scheduled.
+    pub fn admit(&self, url: &Url, stats: &StatsTracker) -> bool {
+        let pattern = Self::pattern(url);
+        let mut patterns = self.patterns.lock();
+        let state = patterns.entry(pattern.clone()).or_default();
+        state.urls += 1;
+        if !state.trap {
+            return true;
+        }
+        state.since_flagged += 1;
+        let admitted = match self.action {
+            TrapAction::Block => false,
+            TrapAction::Throttle(n) => state.since_flagged.is_multiple_of(u64::from(n.max(1))),
+            TrapAction::Report => true,
+        };
+        if !admitted {
+            stats.record_trap_drop(&pattern);
+        }
+        admitted
+    }
+
+    /// Counts a fetched page of `response.url`'s pattern and flags the
+    /// pattern once it looks like a trap.
+    pub fn record_page(&self, response: &HttpResponse, stats: &StatsTracker) {
+        let pattern = Self::pattern(&response.url);
+        let mut patterns = self.patterns.lock();
+        let state = patterns.entry(pattern.clone()).or_default();
+        state.pages += 1;
+        state
+            .signatures
+            .insert(content_signature(&response.raw_body));
+        if state.trap || state.urls < self.min_urls || state.pages < self.min_pages {
+            return;
+        }
+        let content_yield = state.signatures.len() as f64 / state.pages as f64;
+        if content_yield <= self.max_content_yield {
+            state.trap = true;
+            warn!(
+                "Suspected crawl trap {}: {} URLs, {} of {} pages with new content",
+                pattern,
+                state.urls,
+                state.signatures.len(),
+                state.pages
+            );
+            stats.record_suspected_trap(&pattern);
+        }
+    }
+
+    pub fn suspected(&self) -> Vec<SuspectedTrap> {
+        self.patterns
+            .lock()
+            .iter()
+            .filter(|(_, state)| state.trap)
+            .map(|(pattern, state)| SuspectedTrap {
+                pattern: pattern.clone(),
+                urls: state.urls,
+                pages: state.pages,
+                unique_pages: state.signatures.len() as u64,
+            })
+            .collect()
+    }
+}
+
+/// Hash of `body` without digits, so pages differing only by numbers match.
+fn content_signature(body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    for chunk in body.split(|b| b.is_ascii_digit()) {
+        hasher.update(chunk);
+    }
+    hex::encode(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_groups_numeric_segments() {
+        let pattern = |url: &str| TrapDetector::pattern(&Url::parse(url).unwrap());
+        assert_eq!(
+            pattern("https://shop.com/calendar/2024/05?view=week"),
+            "shop.com/calendar/{n}/{n}"
+        );
+        assert_eq!(
+            pattern("https://shop.com/calendar/2031/12"),
+            pattern("https://shop.com/calendar/2024/05")
+        );
+        assert_ne!(
+            pattern("https://shop.com/p/a1"),
+            pattern("https://shop.com/q/a1")
+        );
+        assert_eq!(
+            content_signature(b"<h1>May 2024</h1>"),
+            content_signature(b"<h1>May 2031</h1>")
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use url::Url;
+
+/// Extensions skipped by [`UrlFilters::with_default_denied_extensions`].
+pub const DEFAULT_DENIED_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "bmp", "webp", "svg", "ico", "tif", "tiff", "css", "js", "mjs",
+    "map", "zip", "rar", "7z", "tar", "gz", "bz2", "xz", "mp3", "mp4", "avi", "mov", "wmv", "webm",
+    "wav", "ogg", "woff", "woff2", "ttf", "otf", "eot", "exe", "dmg", "iso", "bin",
+];
+
+/// Allow/deny rules applied to every discovered URL before it is fetched.
+///
+/// A URL passes if it matches at least one allow pattern (when any are set),
+/// matches no deny pattern, its path does not end in a denied extension and
+/// it stays within the size limits, if set.
+#[derive(Debug, Clone, Default)]
+pub struct UrlFilters {
+    allow: Vec<Regex>,
+    deny: Vec<Regex>,
+    denied_extensions: HashSet<String>,
+    max_length: Option<usize>,
+    max_query_params: Option<usize>,
+    max_segment_repeats: Option<usize>,
+}
+
+impl UrlFilters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_allow(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.allow.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    pub fn with_deny(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.deny.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    pub fn with_denied_extensions<E: AsRef<str>>(mut self, extensions: Vec<E>) -> Self {
+        self.denied_extensions.extend(
+            extensions
+                .iter()
+                .map(|ext| ext.as_ref().trim_start_matches('.').to_ascii_lowercase()),
+        );
+        self
+    }
+
+    /// Skips images, stylesheets, scripts, archives, media and fonts.
+    pub fn with_default_denied_extensions(self) -> Self {
+        self.with_denied_extensions(DEFAULT_DENIED_EXTENSIONS.to_vec())
+    }
+
+    /// Rejects URLs longer than `max_length` bytes.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Rejects URLs with more than `max_query_params` query parameters.
+    pub fn with_max_query_params(mut self, max_query_params: usize) -> Self {
+        self.max_query_params = Some(max_query_params);
+        self
+    }
+
+    /// Rejects URLs whose path repeats a segment more than `max_repeats`
+    /// times, like `/a/b/a/b/a/b` produced by relative links resolved
+    /// against the wrong base.
+    pub fn with_max_segment_repeats(mut self, max_repeats: usize) -> Self {
+        self.max_segment_repeats = Some(max_repeats);
+        self
+    }
+
+    /// 2048 bytes, 32 query parameters and 3 repeats of a path segment.
+    pub fn with_default_limits(self) -> Self {
+        self.with_max_length(2048)
+            .with_max_query_params(32)
+            .with_max_segment_repeats(3)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty()
+            && self.deny.is_empty()
+            && self.denied_extensions.is_empty()
+            && self.max_length.is_none()
+            && self.max_query_params.is_none()
+            && self.max_segment_repeats.is_none()
+    }
+
+    /// Whether `url` is over one of the size limits, which usually means a
+    /// crawl trap or a broken link.
+    pub fn exceeds_limits(&self, url: &Url) -> bool {
+        if self.max_length.is_some_and(|max| url.as_str().len() > max) {
+            return true;
+        }
+        if self
+            .max_query_params
+            .is_some_and(|max| url.query_pairs().count() > max)
+        {
+            return true;
+        }
+        let Some(max_repeats) = self.max_segment_repeats else {
+            return false;
+        };
+        let mut repeats: HashMap<&str, usize> = HashMap::new();
+        url.path_segments()
+            .into_iter()
+            .flatten()
+            .filter(|segment| !segment.is_empty())
+            .any(|segment| {
+                let count = repeats.entry(segment).or_insert(0);
+                *count += 1;
+                *count > max_repeats
+            })
+    }
+
+    pub fn is_allowed(&self, url: &Url) -> bool {
+        if self.exceeds_limits(url) {
+            return false;
+        }
+        let url_str = url.as_str();
+        if !self.allow.is_empty() && !self.allow.iter().any(|re| re.is_match(url_str)) {
+            return false;
+        }
+        if self.deny.iter().any(|re| re.is_match(url_str)) {
+            return false;
+        }
+        let extension = url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .and_then(|last| last.rsplit_once('.'))
+            .map(|(_, ext)| ext.to_ascii_lowercase());
+        !extension.is_some_and(|ext| self.denied_extensions.contains(&ext))
+    }
+}
@@ -0,0 +1,339 @@
+use crate::core::{ScraperError, ScraperResult, SpiderCallback};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use parking_lot::{Mutex, RwLock};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use url::Url;
+
+/// Visited URLs and when they were visited, kept across crawls without
+/// resuming the frontier. Requests for a URL recorded by an earlier crawl
+/// are scheduled again only once the spider's
+/// [`RevisitPolicies`](super::revisit::RevisitPolicies) allow it.
+///
+/// Only requests with one of the tracked callbacks are recorded and skipped,
+/// `ParseItem` by default, so daily re-crawls still walk the listing pages
+/// but skip the detail pages already scraped.
+///
+/// Clones share the same URLs. Stores opened from a file are saved back to
+/// it when a crawl finishes, one `url<TAB>visited_at` line per URL after a
+/// `#version<TAB>spider_version` header. A crawl refuses to resume a store
+/// saved by an incompatible [`Spider::version`](crate::core::Spider::version)
+/// unless [forced](Self::with_force_resume).
+///
+/// Every save is a numbered checkpoint of the crawl's progress, and items
+/// stored meanwhile are stamped with the number of the
+/// [next one](Self::checkpoint). The store numbers them rather than the
+/// frontier because it is what a crawl resumes from: the frontier is not
+/// persisted, its [snapshots](super::frontier::FrontierSnapshot) only
+/// report the checkpoint in progress. Pages persisted after the last save,
+/// e.g. before a crash, are not in the file; [`recover`](Self::recover)
+/// finds them from their stored items so that a resumed crawl doesn't store
+/// them twice. Stores given a [recovery directory](Self::with_recovery_dir)
+/// do so when the crawl resumes.
+#[derive(Debug, Clone)]
+pub struct VisitedStore {
+    urls: Arc<RwLock<HashMap<String, DateTime<Utc>>>>,
+    callbacks: HashSet<SpiderCallback>,
+    path: Option<PathBuf>,
+    spider_version: Arc<RwLock<Option<String>>>,
+    saved_checkpoint: Arc<AtomicU64>,
+    next_checkpoint: Arc<AtomicU64>,
+    /// Pages being persisted, by the checkpoint they started in.
+    pending: Arc<Mutex<BTreeMap<u64, usize>>>,
+    recovery_dir: Option<PathBuf>,
+    force_resume: bool,
+}
+
+impl Default for VisitedStore {
+    fn default() -> Self {
+        Self {
+            urls: Arc::new(RwLock::new(HashMap::new())),
+            callbacks: HashSet::from([SpiderCallback::ParseItem]),
+            path: None,
+            spider_version: Arc::new(RwLock::new(None)),
+            saved_checkpoint: Arc::new(AtomicU64::new(0)),
+            next_checkpoint: Arc::new(AtomicU64::new(1)),
+            pending: Arc::new(Mutex::new(BTreeMap::new())),
+            recovery_dir: None,
+            force_resume: false,
+        }
+    }
+}
+
+impl VisitedStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the URLs saved by a previous crawl, starting empty if the file
+    /// does not exist yet.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut spider_version = None;
+        let mut checkpoint = 0;
+        let urls = match fs::File::open(&path) {
+            Ok(file) => {
+                let mut urls = HashMap::new();
+                for line in BufReader::new(file).lines() {
+                    let line = line?;
+                    if let Some(version) = line.strip_prefix("#version\t") {
+                        spider_version = Some(version.to_string());
+                        continue;
+                    }
+                    if let Some(saved) = line.strip_prefix("#checkpoint\t") {
+                        checkpoint = saved.trim().parse().unwrap_or(0);
+                        continue;
+                    }
+                    // Lines without a time count as visited long ago.
+                    let (url, visited_at) = match line.split_once('\t') {
+                        Some((url, time)) => (url, DateTime::parse_from_rfc3339(time).ok()),
+                        None => (line.as_str(), None),
+                    };
+                    if !url.is_empty() {
+                        let visited_at = visited_at
+                            .map(|time| time.with_timezone(&Utc))
+                            .unwrap_or(DateTime::UNIX_EPOCH);
+                        urls.insert(url.to_string(), visited_at);
+                    }
+                }
+                urls
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            urls: Arc::new(RwLock::new(urls)),
+            path: Some(path),
+            spider_version: Arc::new(RwLock::new(spider_version)),
+            saved_checkpoint: Arc::new(AtomicU64::new(checkpoint)),
+            next_checkpoint: Arc::new(AtomicU64::new(checkpoint + 1)),
+            ..Self::default()
+        })
+    }
+
+    /// Callbacks whose requests are recorded and skipped on later crawls.
+    pub fn with_callbacks(mut self, callbacks: Vec<SpiderCallback>) -> Self {
+        self.callbacks = callbacks.into_iter().collect();
+        self
+    }
+
+    /// Directory of the items stored by the crawl, in the layout written by
+    /// `DiskStorage`, to [recover](Self::recover) pages from when the crawl
+    /// resumes.
+    pub fn with_recovery_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.recovery_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Resume stores saved by incompatible spider versions anyway.
+    pub fn with_force_resume(mut self, force: bool) -> Self {
+        self.force_resume = force;
+        self
+    }
+
+    /// Version of the spider that last crawled with this store, if known.
+    pub fn spider_version(&self) -> Option<String> {
+        self.spider_version.read().clone()
+    }
+
+    /// Checks that a crawl by spider `version` can resume this store, then
+    /// records `version` as the one saved with it. Versions are compatible
+    /// when their major versions, the part before the first `.`, match.
+    pub fn resume_as(&self, version: &str) -> ScraperResult<()> {
+        let mut spider_version = self.spider_version.write();
+        if let Some(saved) = spider_version.as_deref() {
+            if major(saved) != major(version) {
+                if !self.force_resume {
+                    return Err(ScraperError::IncompatibleSpiderVersion {
+                        saved: saved.to_string(),
+                        current: version.to_string(),
+                    });
+                }
+                warn!(
+                    "Forcing resume of visited URLs saved by spider version {} with version {}",
+                    saved, version
+                );
+            }
+        }
+        *spider_version = Some(version.to_string());
+        drop(spider_version);
+        if let Some(dir) = &self.recovery_dir {
+            let recovered = self.recover_dir(dir)?;
+            if recovered > 0 {
+                info!(
+                    "Recovered {} pages stored after the last checkpoint",
+                    recovered
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn tracks(&self, callback: &SpiderCallback) -> bool {
+        self.callbacks.contains(callback)
+    }
+
+    pub fn contains(&self, url: &Url) -> bool {
+        self.urls.read().contains_key(url.as_str())
+    }
+
+    pub fn last_visit(&self, url: &Url) -> Option<DateTime<Utc>> {
+        self.urls.read().get(url.as_str()).copied()
+    }
+
+    pub fn record(&self, url: &Url) {
+        self.urls.write().insert(url.to_string(), Utc::now());
+    }
+
+    /// Number of the checkpoint in progress, which items stored now are
+    /// stamped with. The next save commits it, unless pages that started in
+    /// it are still being persisted.
+    pub fn checkpoint(&self) -> u64 {
+        self.next_checkpoint.load(Ordering::SeqCst)
+    }
+
+    /// Marks a page as being persisted until it is
+    /// [recorded](PendingPage::record) or dropped, so that a save meanwhile
+    /// leaves the checkpoint its items are stamped with uncommitted.
+    pub fn begin_page(&self) -> PendingPage {
+        let mut pending = self.pending.lock();
+        let checkpoint = self.checkpoint();
+        *pending.entry(checkpoint).or_default() += 1;
+        PendingPage {
+            store: self.clone(),
+            checkpoint,
+        }
+    }
+
+    /// Recovers the pages of the items stored as JSON files under `dir`,
+    /// see [`recover`](Self::recover).
+    pub fn recover_dir(&self, dir: &Path) -> io::Result<usize> {
+        let mut items = Vec::new();
+        let mut dirs = vec![dir.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            for entry in entries {
+                let path = entry?.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if path.extension().is_some_and(|ext| ext == "json") {
+                    match serde_json::from_str::<Value>(&fs::read_to_string(&path)?) {
+                        Ok(item) => items.push(item),
+                        Err(e) => warn!("Skipping unreadable item {}: {}", path.display(), e),
+                    }
+                }
+            }
+        }
+        Ok(self.recover(&items))
+    }
+
+    /// Records the pages of stored items written after the last saved
+    /// checkpoint, so a crawl resumed after a crash skips them. `items` are
+    /// stored items as JSON (`url`, `timestamp` and `metadata` with the
+    /// `checkpoint` and `callback` of the page), e.g. read back from a
+    /// `DiskStorage` directory. Returns how many pages were recorded.
+    pub fn recover<'a, I: IntoIterator<Item = &'a Value>>(&self, items: I) -> usize {
+        let saved = self.saved_checkpoint.load(Ordering::SeqCst);
+        let mut urls = self.urls.write();
+        let mut recovered = 0;
+        for item in items {
+            let metadata = &item["metadata"];
+            let uncommitted = metadata["checkpoint"]
+                .as_u64()
+                .is_some_and(|checkpoint| checkpoint > saved);
+            let tracked = metadata["callback"]
+                .as_str()
+                .is_some_and(|name| self.callbacks.iter().any(|c| c.name() == name));
+            let Some(url) = item["url"].as_str() else {
+                continue;
+            };
+            if !uncommitted || !tracked || urls.contains_key(url) {
+                continue;
+            }
+            let visited_at = item["timestamp"]
+                .as_str()
+                .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+                .map_or_else(Utc::now, |time| time.with_timezone(&Utc));
+            urls.insert(url.to_string(), visited_at);
+            recovered += 1;
+        }
+        recovered
+    }
+
+    pub fn len(&self) -> usize {
+        self.urls.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes the URLs back to the file the store was opened from, if any.
+    pub fn save(&self) -> io::Result<()> {
+        match &self.path {
+            Some(path) => self.save_to(path),
+            None => Ok(()),
+        }
+    }
+
+    pub fn save_to<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let checkpoint = {
+            let pending = self.pending.lock();
+            let closed = self.next_checkpoint.fetch_add(1, Ordering::SeqCst);
+            // Pages still being persisted were not recorded yet, so the
+            // checkpoints they started in stay uncommitted.
+            pending.keys().next().map_or(closed, |oldest| oldest - 1)
+        };
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+        if let Some(version) = self.spider_version.read().as_deref() {
+            writeln!(writer, "#version\t{}", version)?;
+        }
+        writeln!(writer, "#checkpoint\t{}", checkpoint)?;
+        for (url, visited_at) in self.urls.read().iter() {
+            writeln!(writer, "{}\t{}", url, visited_at.to_rfc3339())?;
+        }
+        writer.flush()?;
+        self.saved_checkpoint.store(checkpoint, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// A page being persisted, see [`VisitedStore::begin_page`].
+#[derive(Debug)]
+pub struct PendingPage {
+    store: VisitedStore,
+    checkpoint: u64,
+}
+
+impl PendingPage {
+    /// Records the page's URL once its items are stored.
+    pub fn record(self, url: &Url) {
+        self.store.record(url);
+    }
+}
+
+impl Drop for PendingPage {
+    fn drop(&mut self) {
+        let mut pending = self.store.pending.lock();
+        if let Some(count) = pending.get_mut(&self.checkpoint) {
+            *count -= 1;
+            if *count == 0 {
+                pending.remove(&self.checkpoint);
+            }
+        }
+    }
+}
+
+fn major(version: &str) -> &str {
+    version.split('.').next().unwrap_or(version).trim()
+}
@@ -0,0 +1,105 @@
+use crate::StatsTracker;
+use log::info;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+#[derive(Debug, Default)]
+struct RampState {
+    last_check: Option<Instant>,
+    progress: Duration,
+    done: bool,
+}
+
+/// Warm-up phase of a crawl. Concurrency starts at `initial` and grows
+/// linearly to the spider's `max_concurrency` over `ramp`, so a cold start
+/// does not trip the target's rate limits straight away.
+///
+/// The ramp only advances while the error rate over the recent stats window
+/// stays at or below `max_error_rate` (10% by default); the concurrency is
+/// held where it is otherwise.
+///
+/// Clones share the same progress.
+#[derive(Debug, Clone)]
+pub struct WarmUp {
+    initial: usize,
+    ramp: Duration,
+    max_error_rate: f64,
+    state: Arc<Mutex<RampState>>,
+}
+
+impl WarmUp {
+    pub fn new(initial: usize, ramp: Duration) -> Self {
+        Self {
+            initial: initial.max(1),
+            ramp,
+            max_error_rate: 0.1,
+            state: Arc::new(Mutex::new(RampState::default())),
+        }
+    }
+
+    /// Error rate above which the ramp is held.
+    pub fn with_max_error_rate(mut self, max_error_rate: f64) -> Self {
+        self.max_error_rate = max_error_rate;
+        self
+    }
+
+    /// Concurrency allowed right now, out of `max`. The ramp starts on the
+    /// first call.
+    pub fn concurrency(&self, max: usize, stats: &StatsTracker) -> usize {
+        let mut state = self.state.lock();
+        if state.done {
+            return max;
+        }
+        let now = Instant::now();
+        let last_check = *state.last_check.get_or_insert(now);
+        if stats.recent().error_rate() <= self.max_error_rate {
+            state.progress += now - last_check;
+        }
+        state.last_check = Some(now);
+
+        if state.progress >= self.ramp {
+            info!("Warm-up finished, crawling at concurrency {}", max);
+            state.done = true;
+            return max;
+        }
+        let initial = self.initial.min(max);
+        let ramped =
+            (max - initial) as f64 * state.progress.as_secs_f64() / self.ramp.as_secs_f64();
+        initial + ramped as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ramps_up_while_error_rate_is_low() {
+        let stats = StatsTracker::new();
+        let warm_up = WarmUp::new(2, Duration::from_millis(400));
+        assert_eq!(warm_up.concurrency(12, &stats), 2);
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let halfway = warm_up.concurrency(12, &stats);
+        assert!((6..12).contains(&halfway), "{}", halfway);
+
+        // Failures hold the ramp.
+        for _ in 0..5 {
+            stats.record_request(503, 0, chrono::Duration::zero(), true);
+        }
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(warm_up.concurrency(12, &stats), halfway);
+    }
+
+    #[test]
+    fn test_never_exceeds_max_concurrency() {
+        let stats = StatsTracker::new();
+        assert_eq!(
+            WarmUp::new(8, Duration::from_secs(60)).concurrency(4, &stats),
+            4
+        );
+        assert_eq!(WarmUp::new(3, Duration::ZERO).concurrency(4, &stats), 4);
+    }
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Days, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::time::Duration;
+
+/// Daily period, in the site's local time, during which a domain may be
+/// crawled. Requests for the domain are held in the frontier outside of it.
+///
+/// Windows may wrap past midnight, e.g. 22:00–04:00.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrawlWindow {
+    start: NaiveTime,
+    end: NaiveTime,
+    timezone: Tz,
+}
+
+impl CrawlWindow {
+    /// A window between `start` (inclusive) and `end` (exclusive), in UTC.
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Self {
+        Self {
+            start,
+            end,
+            timezone: Tz::UTC,
+        }
+    }
+
+    /// Interpret the window in the given timezone, e.g. `chrono_tz::Europe::Madrid`.
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    pub fn is_open(&self, now: DateTime<Utc>) -> bool {
+        let local = now.with_timezone(&self.timezone).time();
+        if self.start <= self.end {
+            self.start <= local && local < self.end
+        } else {
+            local >= self.start || local < self.end
+        }
+    }
+
+    /// Time left until the window next opens, zero if it is open now.
+    pub fn until_open(&self, now: DateTime<Utc>) -> Duration {
+        if self.is_open(now) {
+            return Duration::ZERO;
+        }
+        let today = now.with_timezone(&self.timezone).date_naive();
+        [today, today + Days::new(1)]
+            .into_iter()
+            .filter_map(|day| {
+                self.timezone
+                    .from_local_datetime(&day.and_time(self.start))
+                    .earliest()
+            })
+            .map(|opening| opening.with_timezone(&Utc))
+            .find(|opening| *opening > now)
+            .and_then(|opening| (opening - now).to_std().ok())
+            // The opening time fell in a DST gap; check back in an hour.
+            .unwrap_or(Duration::from_secs(3600))
+    }
+}
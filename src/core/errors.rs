@@ -1,8 +1,9 @@
 use crate::{storage::base::StorageError, HttpRequest};
+use std::fmt;
 use thiserror::Error;
 use url::Url;
 
-use super::retry::RetryCategory;
+use super::retry::{RetryCategory, RetryState};
 
 #[derive(Error, Debug)]
 pub enum ScraperError {
@@ -21,18 +22,168 @@ pub enum ScraperError {
     #[error("Extraction error: {0}")]
     ParsingError(String),
 
+    #[error("Decoding error: {0}")]
+    DecodingError(String),
+
     #[error("Middleware error: {0}")]
     MiddlewareError(String),
 
     #[error("Storage error: {0}")]
     StorageError(#[from] StorageError),
 
+    #[error("Spider error: {0}")]
+    Spider(SpiderError),
+
+    #[error("Circuit open for host {host}, retry in {retry_after:?}")]
+    CircuitOpen {
+        host: String,
+        retry_after: std::time::Duration,
+    },
+
+    #[error("Session expired on url: {url}")]
+    SessionExpired { url: Box<Url>, generation: u64 },
+
     #[error("Maximum retries of {retry_count} reached for category {category:?} on url: {url}")]
     MaxRetriesReached {
         category: RetryCategory,
         retry_count: usize,
         url: Box<Url>,
+        history: Box<RetryState>,
     },
+
+    #[error("Spider version {current} cannot resume a crawl saved by version {saved}")]
+    IncompatibleSpiderVersion { saved: String, current: String },
+
+    /// `source` raised while handling `request`. The crawler retries and
+    /// reports this request instead of the one it dispatched, e.g. after a
+    /// middleware changed it. See [`ScraperError::with_request`].
+    #[error("{source}")]
+    Request {
+        source: Box<ScraperError>,
+        request: Box<HttpRequest>,
+    },
+}
+
+impl ScraperError {
+    /// Attaches the request that failed, unless one is attached already.
+    pub fn with_request<R: Into<Box<HttpRequest>>>(self, request: R) -> Self {
+        match self {
+            ScraperError::Request { .. } => self,
+            source => ScraperError::Request {
+                source: Box::new(source),
+                request: request.into(),
+            },
+        }
+    }
+
+    /// The request attached with [`with_request`](Self::with_request).
+    pub fn request(&self) -> Option<&HttpRequest> {
+        match self {
+            ScraperError::Request { request, .. } => Some(request),
+            _ => None,
+        }
+    }
+
+    /// The error itself, without the attached request.
+    pub fn kind(&self) -> &ScraperError {
+        match self {
+            ScraperError::Request { source, .. } => source.kind(),
+            other => other,
+        }
+    }
+
+    pub fn into_parts(self) -> (ScraperError, Option<Box<HttpRequest>>) {
+        match self {
+            ScraperError::Request { source, request } => (source.into_parts().0, Some(request)),
+            other => (other, None),
+        }
+    }
 }
 
-pub type ScraperResult<T> = Result<T, (ScraperError, Box<HttpRequest>)>;
+/// Attaches a request to the error of any result convertible to
+/// [`ScraperResult`]. The request is only cloned on error.
+pub trait WithRequest<T> {
+    fn with_request(self, request: &HttpRequest) -> ScraperResult<T>;
+}
+
+impl<T, E: Into<ScraperError>> WithRequest<T> for Result<T, E> {
+    fn with_request(self, request: &HttpRequest) -> ScraperResult<T> {
+        self.map_err(|e| e.into().with_request(request.clone()))
+    }
+}
+
+pub type ScraperResult<T> = Result<T, ScraperError>;
+
+/// Whether retrying can fix a [`SpiderError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RetryHint {
+    /// Handled like a parsing error: retried when a
+    /// `ParseRetryCondition` matches it.
+    #[default]
+    Transient,
+    /// Never retried, e.g. a business rule rejecting the page.
+    Permanent,
+}
+
+/// An error raised by spider code, wrapping any error type (including
+/// `anyhow::Error`) so `?` works on it in functions returning
+/// `Result<_, SpiderError>`, and then into [`ScraperResult`].
+pub struct SpiderError {
+    error: anyhow::Error,
+    hint: RetryHint,
+}
+
+impl SpiderError {
+    pub fn new<E: Into<anyhow::Error>>(error: E) -> Self {
+        Self {
+            error: error.into(),
+            hint: RetryHint::default(),
+        }
+    }
+
+    /// A spider error with just a message.
+    pub fn msg<M: fmt::Display + fmt::Debug + Send + Sync + 'static>(message: M) -> Self {
+        Self::new(anyhow::Error::msg(message))
+    }
+
+    pub fn with_hint(mut self, hint: RetryHint) -> Self {
+        self.hint = hint;
+        self
+    }
+
+    pub fn hint(&self) -> RetryHint {
+        self.hint
+    }
+
+    /// The wrapped error, if it is an `E`.
+    pub fn downcast_ref<E: fmt::Display + fmt::Debug + Send + Sync + 'static>(&self) -> Option<&E> {
+        self.error.downcast_ref()
+    }
+}
+
+impl<E: Into<anyhow::Error>> From<E> for SpiderError {
+    fn from(error: E) -> Self {
+        Self::new(error)
+    }
+}
+
+impl From<SpiderError> for ScraperError {
+    fn from(error: SpiderError) -> Self {
+        ScraperError::Spider(error)
+    }
+}
+
+impl fmt::Display for SpiderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#}", self.error)
+    }
+}
+
+impl fmt::Debug for SpiderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpiderError")
+            .field("error", &self.error)
+            .field("hint", &self.hint)
+            .finish()
+    }
+}
@@ -0,0 +1,258 @@
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::any::{type_name, Any};
+use std::fmt;
+
+use super::ScraperError;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+
+trait AnyItem: erased_serde::Serialize + Any + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+    fn type_name(&self) -> &'static str;
+}
+
+impl<T: Serialize + Any + Send + Sync> AnyItem for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        type_name::<T>()
+    }
+}
+
+/// An item emitted as a Rust struct instead of a `json!` blob. It keeps its
+/// type through pipelines, which can downcast it, and is serialized only by
+/// the storage backend.
+pub struct TypedItem {
+    inner: Box<dyn AnyItem>,
+}
+
+impl TypedItem {
+    pub fn new<T: Serialize + Any + Send + Sync>(item: T) -> Self {
+        Self {
+            inner: Box::new(item),
+        }
+    }
+
+    /// Full Rust type name of the item, e.g. `my_crate::Book`.
+    pub fn type_name(&self) -> &'static str {
+        self.inner.type_name()
+    }
+
+    pub fn is<T: Any>(&self) -> bool {
+        self.inner.as_any().is::<T>()
+    }
+
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.inner.as_any().downcast_ref()
+    }
+
+    pub fn downcast_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.inner.as_any_mut().downcast_mut()
+    }
+
+    /// The item as `T`, or the item itself back if it has another type.
+    pub fn downcast<T: Any>(self) -> Result<T, Self> {
+        if self.is::<T>() {
+            Ok(*self.inner.into_any().downcast().unwrap())
+        } else {
+            Err(self)
+        }
+    }
+
+    pub fn to_value(&self) -> serde_json::Result<Value> {
+        serde_json::to_value(self)
+    }
+}
+
+impl Serialize for TypedItem {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        erased_serde::serialize(&*self.inner, serializer)
+    }
+}
+
+impl fmt::Debug for TypedItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TypedItem").field(&self.type_name()).finish()
+    }
+}
+
+/// Items produced lazily, see [`ParsedData::Stream`](super::spider::ParsedData::Stream).
+///
+/// The crawler pulls them in chunks of `SpiderConfig::stream_chunk_size`;
+/// every chunk goes through the item pipelines and is handed to
+/// `persist_extracted_data` as `ParsedData::Items`. An error ends the stream
+/// and fails the page after the chunks before it were stored.
+pub struct ItemStream {
+    inner: BoxStream<'static, Result<Value, ScraperError>>,
+}
+
+impl ItemStream {
+    pub fn new<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<Value, ScraperError>> + Send + 'static,
+    {
+        Self {
+            inner: stream.boxed(),
+        }
+    }
+
+    /// The elements of the JSON array in `body`, deserialized one at a time
+    /// so the whole array is never held as a single `Value`.
+    pub fn json_array(body: Vec<u8>) -> Self {
+        Self::new(stream::iter(JsonArrayItems {
+            body,
+            offset: 0,
+            started: false,
+        }))
+    }
+
+    /// Up to `size` items, fewer only at the end of the stream or on error.
+    pub async fn next_chunk(&mut self, size: usize) -> Result<Vec<Value>, ScraperError> {
+        let mut chunk = Vec::with_capacity(size.min(1024));
+        while chunk.len() < size {
+            match self.inner.next().await {
+                Some(item) => chunk.push(item?),
+                None => break,
+            }
+        }
+        Ok(chunk)
+    }
+}
+
+impl fmt::Debug for ItemStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ItemStream")
+    }
+}
+
+struct JsonArrayItems {
+    body: Vec<u8>,
+    offset: usize,
+    started: bool,
+}
+
+impl JsonArrayItems {
+    /// Skips whitespace and returns the next byte, if any.
+    fn peek(&mut self) -> Option<u8> {
+        while self
+            .body
+            .get(self.offset)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.offset += 1;
+        }
+        self.body.get(self.offset).copied()
+    }
+
+    fn error(&mut self, message: String) -> Option<Result<Value, ScraperError>> {
+        self.offset = self.body.len();
+        Some(Err(ScraperError::ParsingError(message)))
+    }
+}
+
+impl Iterator for JsonArrayItems {
+    type Item = Result<Value, ScraperError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            if self.peek() != Some(b'[') {
+                return self.error("Expected a JSON array".to_string());
+            }
+            self.offset += 1;
+        } else {
+            match self.peek()? {
+                b',' => self.offset += 1,
+                b']' => {
+                    self.offset = self.body.len();
+                    return None;
+                }
+                other => {
+                    return self.error(format!(
+                        "Unexpected {:?} at byte {} of JSON array",
+                        other as char, self.offset
+                    ))
+                }
+            }
+        }
+        if self.peek()? == b']' {
+            self.offset = self.body.len();
+            return None;
+        }
+        let mut values =
+            serde_json::Deserializer::from_slice(&self.body[self.offset..]).into_iter::<Value>();
+        match values.next()? {
+            Ok(value) => {
+                self.offset += values.byte_offset();
+                Some(Ok(value))
+            }
+            Err(e) => self.error(format!("Invalid JSON array element: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug, Serialize, PartialEq)]
+    struct Book {
+        title: String,
+        price: f64,
+    }
+
+    #[tokio::test]
+    async fn test_json_array_stream_reads_elements_in_chunks() {
+        let body = br#" [{"id": 1}, 2, "three" ,[4], null] "#.to_vec();
+        let mut items = ItemStream::json_array(body);
+        assert_eq!(
+            items.next_chunk(3).await.unwrap(),
+            [json!({"id": 1}), json!(2), json!("three")]
+        );
+        assert_eq!(
+            items.next_chunk(3).await.unwrap(),
+            [json!([4]), Value::Null]
+        );
+        assert!(items.next_chunk(3).await.unwrap().is_empty());
+
+        let mut broken = ItemStream::json_array(br#"[1, {"id": }]"#.to_vec());
+        assert!(broken.next_chunk(10).await.is_err());
+        assert!(ItemStream::json_array(b"[]".to_vec())
+            .next_chunk(10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_typed_item_downcasts_and_serializes() {
+        let mut item = TypedItem::new(Book {
+            title: "Dune".to_string(),
+            price: 9.5,
+        });
+        assert!(item.type_name().ends_with("Book"));
+        assert!(item.downcast_ref::<Value>().is_none());
+
+        item.downcast_mut::<Book>().unwrap().price = 7.0;
+        assert_eq!(
+            item.to_value().unwrap(),
+            json!({"title": "Dune", "price": 7.0})
+        );
+
+        let item = item.downcast::<Value>().unwrap_err();
+        assert_eq!(item.downcast::<Book>().unwrap().price, 7.0);
+    }
+}
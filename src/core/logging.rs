@@ -0,0 +1,235 @@
+use super::spider::{ParsedData, SpiderResponse};
+use log::{info, warn};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+struct KeyWindow {
+    started: Instant,
+    logged: u32,
+    suppressed: u64,
+}
+
+/// Rate limits repetitive warnings. Per key and window, the first `burst`
+/// messages are logged as is; the rest are only counted and reported as one
+/// summary line, e.g. `429 retries on host example.com: 1204 more in the
+/// last 60s`.
+///
+/// Clones share the same counters.
+#[derive(Debug, Clone)]
+pub struct LogThrottle {
+    window: Duration,
+    burst: u32,
+    keys: Arc<Mutex<HashMap<String, KeyWindow>>>,
+}
+
+impl Default for LogThrottle {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(60), 5)
+    }
+}
+
+impl LogThrottle {
+    pub fn new(window: Duration, burst: u32) -> Self {
+        Self {
+            window,
+            burst,
+            keys: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Logs every message.
+    pub fn disabled() -> Self {
+        Self::new(Duration::ZERO, u32::MAX)
+    }
+
+    /// Logs `message` at warn level unless `key` already used up its burst
+    /// in the current window. `message` is only built when logged.
+    pub fn warn<F: FnOnce() -> String>(&self, key: &str, message: F) {
+        if self.burst == u32::MAX {
+            warn!("{}", message());
+            return;
+        }
+
+        let now = Instant::now();
+        let mut keys = self.keys.lock();
+        let entry = keys.entry(key.to_string()).or_insert(KeyWindow {
+            started: now,
+            logged: 0,
+            suppressed: 0,
+        });
+        if now.duration_since(entry.started) >= self.window {
+            Self::summarize(key, entry, self.window);
+            *entry = KeyWindow {
+                started: now,
+                logged: 0,
+                suppressed: 0,
+            };
+        }
+
+        if entry.logged < self.burst {
+            entry.logged += 1;
+            drop(keys);
+            warn!("{}", message());
+        } else {
+            entry.suppressed += 1;
+        }
+    }
+
+    /// Reports the messages suppressed so far, e.g. when a crawl ends.
+    pub fn flush(&self) {
+        let mut keys = self.keys.lock();
+        for (key, entry) in keys.drain() {
+            Self::summarize(&key, &entry, entry.started.elapsed());
+        }
+    }
+
+    /// Number of messages currently held back for `key`.
+    pub fn suppressed(&self, key: &str) -> u64 {
+        self.keys
+            .lock()
+            .get(key)
+            .map(|entry| entry.suppressed)
+            .unwrap_or(0)
+    }
+
+    fn summarize(key: &str, entry: &KeyWindow, window: Duration) {
+        if entry.suppressed > 0 {
+            warn!(
+                "{}: {} more in the last {}s",
+                key,
+                entry.suppressed,
+                window.as_secs().max(1)
+            );
+        }
+    }
+}
+
+/// Which scraped items an [`ItemPreview`] logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewSample {
+    /// The first `n` items of the crawl.
+    First(u64),
+    /// One item out of every `k`, starting with the first.
+    OneIn(u64),
+}
+
+/// Logs a sample of the scraped items at info level, as JSON truncated to
+/// `max_len` characters, to check extraction quality during long crawls.
+///
+/// Clones share the same item count.
+#[derive(Debug, Clone)]
+pub struct ItemPreview {
+    sample: PreviewSample,
+    max_len: usize,
+    seen: Arc<AtomicU64>,
+}
+
+impl ItemPreview {
+    pub fn new(sample: PreviewSample) -> Self {
+        Self {
+            sample,
+            max_len: 300,
+            seen: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Characters of JSON logged per item. 300 by default.
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Counts the items of `data` and logs the sampled ones.
+    pub fn observe_data(&self, data: &ParsedData, response: &SpiderResponse) {
+        let url = response.response.url.as_str();
+        match data {
+            ParsedData::Item(item) => self.observe(item, url),
+            ParsedData::Items(items) => items.iter().for_each(|item| self.observe(item, url)),
+            ParsedData::Typed(items) => items.iter().for_each(|item| self.observe(item, url)),
+            ParsedData::Categorized(items) => {
+                items.iter().for_each(|(_, item)| self.observe(item, url))
+            }
+            ParsedData::Raw(text) => self.observe(text, url),
+            ParsedData::Stream(_) | ParsedData::Empty => {}
+        }
+    }
+
+    fn observe<T: Serialize + ?Sized>(&self, item: &T, url: &str) {
+        let number = self.seen.fetch_add(1, Ordering::Relaxed) + 1;
+        if !self.is_sampled(number) {
+            return;
+        }
+        let json = serde_json::to_string(item).unwrap_or_else(|e| format!("<{}>", e));
+        info!(
+            "Item #{} from {}: {}",
+            number,
+            url,
+            truncate(&json, self.max_len)
+        );
+    }
+
+    /// Whether the `number`th item (from 1) is logged.
+    fn is_sampled(&self, number: u64) -> bool {
+        match self.sample {
+            PreviewSample::First(n) => number <= n,
+            PreviewSample::OneIn(k) => (number - 1).is_multiple_of(k.max(1)),
+        }
+    }
+}
+
+fn truncate(text: &str, max_len: usize) -> String {
+    match text.char_indices().nth(max_len) {
+        Some((end, _)) => format!(
+            "{}... ({} more chars)",
+            &text[..end],
+            text[end..].chars().count()
+        ),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suppresses_after_burst_and_resets_per_window() {
+        let throttle = LogThrottle::new(Duration::from_millis(50), 2);
+        for _ in 0..5 {
+            throttle.warn("429 retries on host example.com", || {
+                "Retry triggered".to_string()
+            });
+        }
+        assert_eq!(throttle.suppressed("429 retries on host example.com"), 3);
+        assert_eq!(throttle.suppressed("429 retries on host other.com"), 0);
+
+        std::thread::sleep(Duration::from_millis(60));
+        throttle.warn("429 retries on host example.com", String::new);
+        assert_eq!(throttle.suppressed("429 retries on host example.com"), 0);
+
+        throttle.flush();
+        assert_eq!(throttle.suppressed("429 retries on host example.com"), 0);
+    }
+
+    #[test]
+    fn test_item_preview_sampling_and_truncation() {
+        let first = ItemPreview::new(PreviewSample::First(2));
+        let sampled: Vec<u64> = (1..=5).filter(|&n| first.is_sampled(n)).collect();
+        assert_eq!(sampled, [1, 2]);
+
+        let one_in = ItemPreview::new(PreviewSample::OneIn(3));
+        let sampled: Vec<u64> = (1..=7).filter(|&n| one_in.is_sampled(n)).collect();
+        assert_eq!(sampled, [1, 4, 7]);
+
+        assert_eq!(
+            truncate("{\"title\":\"Été\"}", 12),
+            "{\"title\":\"Ét... (3 more chars)"
+        );
+        assert_eq!(truncate("short", 12), "short");
+    }
+}
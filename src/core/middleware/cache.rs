@@ -0,0 +1,487 @@
+use super::downloader::{DownloaderMiddleware, RequestAction};
+use crate::core::spider::SpiderConfig;
+use crate::http::{Headers, ResponseType};
+use crate::storage::base::StorageError;
+use crate::{HttpRequest, HttpResponse, ScraperError};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use parking_lot::Mutex;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use url::Url;
+
+/// Response meta key set on responses served from the cache, holding when
+/// they were stored.
+pub const CACHE_META_KEY: &str = "cached_at";
+
+/// A response kept by an [`HttpCache`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub url: Url,
+    pub status: u16,
+    pub headers: Headers,
+    /// Kept next to the metadata by [`DiskCacheStorage`].
+    #[serde(skip)]
+    pub body: Vec<u8>,
+    pub stored_at: DateTime<Utc>,
+}
+
+/// Where an [`HttpCache`] keeps its responses, keyed by a hash of the
+/// request. Implement it to cache in a database or object store.
+#[async_trait]
+pub trait CacheStorage: Send + Sync {
+    async fn load(&self, key: &str) -> Result<Option<CachedResponse>, StorageError>;
+
+    async fn save(&self, key: &str, response: CachedResponse) -> Result<(), StorageError>;
+}
+
+/// Cache kept in memory for the life of the process. Clones share the same
+/// responses.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryCacheStorage {
+    responses: Arc<Mutex<HashMap<String, CachedResponse>>>,
+}
+
+impl MemoryCacheStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.responses.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.responses.lock().is_empty()
+    }
+}
+
+#[async_trait]
+impl CacheStorage for MemoryCacheStorage {
+    async fn load(&self, key: &str) -> Result<Option<CachedResponse>, StorageError> {
+        Ok(self.responses.lock().get(key).cloned())
+    }
+
+    async fn save(&self, key: &str, response: CachedResponse) -> Result<(), StorageError> {
+        self.responses.lock().insert(key.to_string(), response);
+        Ok(())
+    }
+}
+
+/// Cache in a directory, one `<key>.json` file with the status and headers
+/// and one `<key>.body` file with the raw body per response, spread over
+/// subdirectories named after the first two characters of the key.
+#[derive(Debug, Clone)]
+pub struct DiskCacheStorage {
+    root: PathBuf,
+}
+
+impl DiskCacheStorage {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    fn path(&self, key: &str, extension: &str) -> PathBuf {
+        self.root
+            .join(&key[..key.len().min(2)])
+            .join(format!("{}.{}", key, extension))
+    }
+}
+
+#[async_trait]
+impl CacheStorage for DiskCacheStorage {
+    async fn load(&self, key: &str) -> Result<Option<CachedResponse>, StorageError> {
+        let meta = match fs::read(self.path(key, "json")) {
+            Ok(meta) => meta,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut response: CachedResponse = serde_json::from_slice(&meta)?;
+        response.body = fs::read(self.path(key, "body"))?;
+        Ok(Some(response))
+    }
+
+    async fn save(&self, key: &str, response: CachedResponse) -> Result<(), StorageError> {
+        let meta_path = self.path(key, "json");
+        if let Some(dir) = meta_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // The body goes first so a metadata file always has its body.
+        fs::write(self.path(key, "body"), &response.body)?;
+        fs::write(meta_path, serde_json::to_vec_pretty(&response)?)?;
+        Ok(())
+    }
+}
+
+/// How an [`HttpCache`] uses its storage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Serves fresh cached responses and downloads and stores the others.
+    #[default]
+    Normal,
+    /// Downloads every request and stores the responses, to rebuild the
+    /// cache.
+    Refresh,
+    /// Serves cached responses however old they are and drops the requests
+    /// that are not cached, so parse logic can be developed offline against
+    /// previously fetched pages.
+    Replay,
+}
+
+/// Which responses are stored and for how long they stay fresh.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Every cacheable response is stored and stays fresh for the cache's
+    /// TTL.
+    #[default]
+    Always,
+    /// Follows the `Cache-Control` headers: `no-store` responses are not
+    /// stored, `no-cache` ones are always fetched again and `max-age`
+    /// overrides the TTL. Requests sent with `Cache-Control: no-cache` or
+    /// `no-store` skip the cache.
+    HonorHeaders,
+}
+
+/// Downloader middleware caching responses in a [`CacheStorage`], in front
+/// of the scraper.
+///
+/// Only GET and HEAD requests are cached, keyed by their method, URL, body
+/// and [key headers](Self::with_key_headers). Requests the crawler retries
+/// are downloaded again and their response replaces the cached one, except
+/// in [`CacheMode::Replay`]. Server errors, `304 Not Modified` and the statuses passed to
+/// [`with_ignored_statuses`](Self::with_ignored_statuses) are never stored.
+/// Responses served from the cache carry their storage time in their meta
+/// under [`CACHE_META_KEY`]. Cache read and write errors are logged and the
+/// request is downloaded as if it were not cached.
+#[derive(Clone)]
+pub struct HttpCache {
+    storage: Arc<dyn CacheStorage>,
+    mode: CacheMode,
+    policy: CachePolicy,
+    ttl: Option<Duration>,
+    ignored_statuses: HashSet<u16>,
+    key_headers: Vec<String>,
+}
+
+impl HttpCache {
+    /// A cache whose entries never expire.
+    pub fn new<S: CacheStorage + 'static>(storage: S) -> Self {
+        Self {
+            storage: Arc::new(storage),
+            mode: CacheMode::default(),
+            policy: CachePolicy::default(),
+            ttl: None,
+            ignored_statuses: HashSet::new(),
+            key_headers: vec!["authorization".to_string(), "cookie".to_string()],
+        }
+    }
+
+    pub fn with_mode(mut self, mode: CacheMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: CachePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// How long stored responses are served before being downloaded again.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn with_ignored_statuses(mut self, statuses: Vec<u16>) -> Self {
+        self.ignored_statuses.extend(statuses);
+        self
+    }
+
+    /// Request headers that are part of the cache key, so that e.g. logged
+    /// in and anonymous pages are cached apart. `Authorization` and
+    /// `Cookie` by default.
+    pub fn with_key_headers(mut self, headers: Vec<&str>) -> Self {
+        self.key_headers = headers.iter().map(|h| h.to_ascii_lowercase()).collect();
+        self
+    }
+
+    /// Cache key of `request`: the SHA-256 of its method, URL, body and key
+    /// headers.
+    pub fn key(&self, request: &HttpRequest) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(request.method.as_str());
+        hasher.update(b" ");
+        hasher.update(request.url.as_str());
+        if let Some(body) = &request.body {
+            hasher.update(b"\n");
+            hasher.update(body);
+        }
+        for name in &self.key_headers {
+            for value in request.headers.get_all(name) {
+                hasher.update(b"\n");
+                hasher.update(name);
+                hasher.update(b": ");
+                hasher.update(value);
+            }
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    fn is_cacheable(&self, request: &HttpRequest) -> bool {
+        if request.method != Method::GET && request.method != Method::HEAD {
+            return false;
+        }
+        self.policy != CachePolicy::HonorHeaders
+            || !cache_control(&request.headers)
+                .any(|directive| directive == "no-cache" || directive == "no-store")
+    }
+
+    fn is_fresh(&self, cached: &CachedResponse, now: DateTime<Utc>) -> bool {
+        let mut ttl = self.ttl;
+        if self.policy == CachePolicy::HonorHeaders {
+            for directive in cache_control(&cached.headers) {
+                if directive == "no-cache" {
+                    return false;
+                }
+                if let Some(max_age) = directive
+                    .strip_prefix("max-age=")
+                    .and_then(|age| age.trim_matches('"').parse().ok())
+                {
+                    ttl = Some(Duration::from_secs(max_age));
+                }
+            }
+        }
+        ttl.is_none_or(|ttl| {
+            now.signed_duration_since(cached.stored_at)
+                .to_std()
+                .unwrap_or_default()
+                < ttl
+        })
+    }
+
+    fn should_store(&self, response: &HttpResponse) -> bool {
+        if response.status >= 500
+            || response.is_not_modified()
+            || self.ignored_statuses.contains(&response.status)
+        {
+            return false;
+        }
+        self.policy != CachePolicy::HonorHeaders
+            || !cache_control(&response.headers).any(|directive| directive == "no-store")
+    }
+}
+
+/// Lowercased `Cache-Control` directives of `headers`.
+fn cache_control(headers: &Headers) -> impl Iterator<Item = String> + '_ {
+    headers
+        .get_all("cache-control")
+        .flat_map(|value| value.split(','))
+        .map(|directive| directive.trim().to_ascii_lowercase())
+}
+
+#[async_trait]
+impl DownloaderMiddleware for HttpCache {
+    async fn on_request(
+        &self,
+        request: &mut HttpRequest,
+        _config: &SpiderConfig,
+    ) -> Result<RequestAction, ScraperError> {
+        let refresh = self.mode == CacheMode::Refresh
+            || (request.bypass_cache && self.mode != CacheMode::Replay);
+        if refresh || !self.is_cacheable(request) {
+            return Ok(RequestAction::Continue);
+        }
+        let cached = match self.storage.load(&self.key(request)).await {
+            Ok(cached) => cached,
+            Err(e) => {
+                warn!("Failed to read {} from the cache: {}", request.url, e);
+                None
+            }
+        };
+        let cached = match (self.mode, cached) {
+            (CacheMode::Replay, None) => {
+                warn!("Dropping {} - not in the cache", request.url);
+                return Ok(RequestAction::Drop);
+            }
+            (CacheMode::Replay, Some(cached)) => cached,
+            (_, Some(cached)) if self.is_fresh(&cached, Utc::now()) => cached,
+            _ => return Ok(RequestAction::Continue),
+        };
+        debug!("Serving {} from the cache", request.url);
+        Ok(RequestAction::Respond(Box::new(HttpResponse {
+            url: cached.url,
+            status: cached.status,
+            response_type: ResponseType::detect(&cached.headers, &cached.body),
+            headers: cached.headers,
+            raw_body: cached.body,
+            decoded_body: OnceLock::new(),
+            timestamp: Utc::now(),
+            retry_count: 0,
+            retry_history: HashMap::new(),
+            meta: Some(json!({ CACHE_META_KEY: cached.stored_at })),
+            from_request: Box::new(request.clone()),
+        })))
+    }
+
+    async fn on_response(
+        &self,
+        request: &HttpRequest,
+        response: &mut HttpResponse,
+        _config: &SpiderConfig,
+    ) -> Result<(), ScraperError> {
+        let from_cache = response
+            .meta
+            .as_ref()
+            .is_some_and(|meta| meta.get(CACHE_META_KEY).is_some());
+        if from_cache || !self.is_cacheable(request) || !self.should_store(response) {
+            return Ok(());
+        }
+        let cached = CachedResponse {
+            url: response.url.clone(),
+            status: response.status,
+            headers: response.headers.clone(),
+            body: response.raw_body.clone(),
+            stored_at: Utc::now(),
+        };
+        if let Err(e) = self.storage.save(&self.key(request), cached).await {
+            warn!("Failed to cache {}: {}", request.url, e);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::middleware::DownloaderMiddlewareChain;
+    use crate::core::retry::mock_scraper::{MockResponse, MockScraper};
+    use crate::core::SpiderCallback;
+
+    fn request(path: &str) -> HttpRequest {
+        HttpRequest::new(
+            Url::parse(&format!("https://example.com{}", path)).unwrap(),
+            SpiderCallback::Bootstrap,
+            0,
+        )
+    }
+
+    fn scraper() -> MockScraper {
+        MockScraper::new(vec![
+            MockResponse {
+                status: 200,
+                body: "first".to_string(),
+                delay: None,
+            },
+            MockResponse {
+                status: 200,
+                body: "second".to_string(),
+                delay: None,
+            },
+        ])
+    }
+
+    async fn fetch(cache: &HttpCache, scraper: &MockScraper, path: &str) -> Option<String> {
+        let mut chain = DownloaderMiddlewareChain::new();
+        chain.push(cache.clone());
+        chain
+            .fetch(scraper, request(path), &SpiderConfig::default())
+            .await
+            .unwrap()
+            .map(|response| String::from_utf8(response.raw_body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_serves_cached_responses_until_they_expire() {
+        let storage = MemoryCacheStorage::new();
+        let scraper = scraper();
+        let cache = HttpCache::new(storage.clone());
+        assert_eq!(fetch(&cache, &scraper, "/a").await.unwrap(), "first");
+        assert_eq!(fetch(&cache, &scraper, "/a").await.unwrap(), "first");
+        assert_eq!(storage.len(), 1);
+
+        let expired = cache.clone().with_ttl(Duration::ZERO);
+        assert_eq!(fetch(&expired, &scraper, "/a").await.unwrap(), "second");
+
+        let refresh = cache.clone().with_mode(CacheMode::Refresh);
+        assert_eq!(fetch(&refresh, &scraper, "/a").await.unwrap(), "first");
+        assert_eq!(fetch(&cache, &scraper, "/a").await.unwrap(), "first");
+    }
+
+    #[tokio::test]
+    async fn test_replay_mode_never_downloads() {
+        let root =
+            std::env::temp_dir().join(format!("turboscraper_cache_{}", uuid::Uuid::now_v7()));
+        let scraper = scraper();
+        let cache = HttpCache::new(DiskCacheStorage::new(&root));
+        fetch(&cache, &scraper, "/a").await;
+
+        let replay = HttpCache::new(DiskCacheStorage::new(&root))
+            .with_mode(CacheMode::Replay)
+            .with_ttl(Duration::ZERO);
+        assert_eq!(fetch(&replay, &scraper, "/a").await.unwrap(), "first");
+        assert_eq!(fetch(&replay, &scraper, "/b").await, None);
+        // Only the first fetch reached the scraper.
+        assert_eq!(fetch(&cache, &scraper, "/c").await.unwrap(), "second");
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn test_retried_requests_and_key_headers_skip_cached_pages() {
+        let storage = MemoryCacheStorage::new();
+        let scraper = scraper();
+        let cache = HttpCache::new(storage.clone());
+        assert_eq!(fetch(&cache, &scraper, "/a").await.unwrap(), "first");
+
+        let mut chain = DownloaderMiddlewareChain::new();
+        chain.push(cache.clone());
+        let mut retry = request("/a");
+        retry.bypass_cache = true;
+        let response = chain
+            .fetch(&scraper, retry, &SpiderConfig::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.raw_body, b"second");
+        // The retried download replaced the cached page.
+        assert_eq!(fetch(&cache, &scraper, "/a").await.unwrap(), "second");
+
+        let anonymous = request("/a");
+        let logged_in = request("/a").with_header("Cookie", "session=1");
+        assert_ne!(cache.key(&anonymous), cache.key(&logged_in));
+        let unkeyed = cache.clone().with_key_headers(Vec::new());
+        assert_eq!(unkeyed.key(&anonymous), unkeyed.key(&logged_in));
+    }
+
+    #[test]
+    fn test_cache_control_policy() {
+        let cache = HttpCache::new(MemoryCacheStorage::new())
+            .with_policy(CachePolicy::HonorHeaders)
+            .with_ttl(Duration::from_secs(3600));
+        let cached = |cache_control: &str| CachedResponse {
+            url: request("/").url,
+            status: 200,
+            headers: Headers::from_iter([("cache-control", cache_control)]),
+            body: Vec::new(),
+            stored_at: Utc::now() - chrono::Duration::seconds(120),
+        };
+        assert!(cache.is_fresh(&cached("public"), Utc::now()));
+        assert!(!cache.is_fresh(&cached("public, max-age=60"), Utc::now()));
+        assert!(!cache.is_fresh(&cached("no-cache"), Utc::now()));
+
+        let mut no_cache = request("/");
+        no_cache.headers.insert("Cache-Control", "no-cache");
+        assert!(!cache.is_cacheable(&no_cache));
+        assert!(!cache.is_cacheable(&request("/").with_method(Method::POST)));
+    }
+}
@@ -0,0 +1,383 @@
+use super::downloader::{DownloaderMiddleware, RequestAction};
+use crate::core::spider::SpiderConfig;
+use crate::{HttpRequest, HttpResponse, ScraperError, StatsTracker};
+use async_trait::async_trait;
+use log::{info, trace};
+use parking_lot::Mutex;
+use regex::Regex;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+
+/// Request meta key holding the id of the account a request was sent with.
+pub const ACCOUNT_META_KEY: &str = "account";
+
+/// One account of a [`CredentialPool`]: the headers, such as `Authorization`
+/// or `Cookie`, sent with the requests assigned to it.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub id: String,
+    pub headers: HashMap<String, String>,
+}
+
+impl Credentials {
+    pub fn new<T: Into<String>>(id: T) -> Self {
+        Self {
+            id: id.into(),
+            headers: HashMap::new(),
+        }
+    }
+
+    pub fn with_header(mut self, key: &str, value: &str) -> Self {
+        self.headers.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+/// Health of one account, see [`CredentialPool::health`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountHealth {
+    pub id: String,
+    pub requests: u64,
+    pub bans: u64,
+    /// Time left until a banned account is used again.
+    pub cooling_down: Option<Duration>,
+    pub sessions: usize,
+}
+
+#[derive(Debug)]
+struct Account {
+    credentials: Credentials,
+    next_request: Instant,
+    banned_until: Option<Instant>,
+    requests: u64,
+    bans: u64,
+}
+
+impl Account {
+    fn is_available(&self, now: Instant) -> bool {
+        self.banned_until.is_none_or(|until| until <= now)
+    }
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+    accounts: Vec<Account>,
+    /// Session key to the index of its account.
+    sessions: HashMap<String, usize>,
+}
+
+impl PoolState {
+    /// The account for `session`, assigning the least busy available one if
+    /// it has none yet or its account is cooling down. Otherwise the time
+    /// until an account becomes available.
+    fn assign(&mut self, session: &str, now: Instant) -> Result<usize, Instant> {
+        if let Some(&index) = self.sessions.get(session) {
+            if self.accounts[index].is_available(now) {
+                return Ok(index);
+            }
+        }
+        let mut load = vec![0usize; self.accounts.len()];
+        for &index in self.sessions.values() {
+            load[index] += 1;
+        }
+        let next = self
+            .accounts
+            .iter()
+            .enumerate()
+            .filter(|(_, account)| account.is_available(now))
+            .min_by_key(|(index, account)| (load[*index], account.next_request))
+            .map(|(index, _)| index);
+        match next {
+            Some(index) => {
+                self.sessions.insert(session.to_string(), index);
+                Ok(index)
+            }
+            None => Err(self
+                .accounts
+                .iter()
+                .filter_map(|account| account.banned_until)
+                .min()
+                .unwrap_or(now)),
+        }
+    }
+}
+
+/// Spreads requests over several accounts.
+///
+/// Each session is assigned an account and keeps it until the account is
+/// banned. Sessions are keyed by the `session` string in the request meta,
+/// or by host for requests without one. Requests of one account are spaced
+/// at least `min_interval` apart. A response with a ban status (403 and 429
+/// by default) or a body matching a ban pattern puts its account in
+/// `cooldown` and moves its sessions to other accounts.
+///
+/// The account id is written to the request meta under
+/// [`ACCOUNT_META_KEY`]. Clones share the same accounts.
+#[derive(Clone)]
+pub struct CredentialPool {
+    state: Arc<Mutex<PoolState>>,
+    min_interval: Duration,
+    cooldown: Duration,
+    ban_statuses: Vec<u16>,
+    ban_patterns: Vec<Regex>,
+    stats: Option<Arc<StatsTracker>>,
+}
+
+impl CredentialPool {
+    pub fn new(accounts: Vec<Credentials>) -> Self {
+        let now = Instant::now();
+        let accounts = accounts
+            .into_iter()
+            .map(|credentials| Account {
+                credentials,
+                next_request: now,
+                banned_until: None,
+                requests: 0,
+                bans: 0,
+            })
+            .collect();
+        Self {
+            state: Arc::new(Mutex::new(PoolState {
+                accounts,
+                sessions: HashMap::new(),
+            })),
+            min_interval: Duration::ZERO,
+            cooldown: Duration::from_secs(600),
+            ban_statuses: vec![403, 429],
+            ban_patterns: Vec::new(),
+            stats: None,
+        }
+    }
+
+    /// Minimum delay between two requests of the same account.
+    pub fn with_min_interval(mut self, interval: Duration) -> Self {
+        self.min_interval = interval;
+        self
+    }
+
+    /// How long a banned account is left unused. 10 minutes by default.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    pub fn with_ban_statuses(mut self, statuses: Vec<u16>) -> Self {
+        self.ban_statuses = statuses;
+        self
+    }
+
+    /// Treats responses whose body matches `pattern` as a ban, e.g.
+    /// `account (suspended|locked)`.
+    pub fn with_ban_pattern(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.ban_patterns.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Records per-account requests and bans in `stats`, usually the
+    /// tracker shared with the crawler through `CrawlerBuilder::with_stats`.
+    pub fn with_stats(mut self, stats: Arc<StatsTracker>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    pub fn health(&self) -> Vec<AccountHealth> {
+        let state = self.state.lock();
+        let now = Instant::now();
+        state
+            .accounts
+            .iter()
+            .enumerate()
+            .map(|(index, account)| AccountHealth {
+                id: account.credentials.id.clone(),
+                requests: account.requests,
+                bans: account.bans,
+                cooling_down: account
+                    .banned_until
+                    .filter(|until| *until > now)
+                    .map(|until| until - now),
+                sessions: state.sessions.values().filter(|&&i| i == index).count(),
+            })
+            .collect()
+    }
+
+    fn session_key(request: &HttpRequest) -> String {
+        request
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.get("session"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| request.url.host_str().unwrap_or_default().to_string())
+    }
+
+    /// Picks the account for `session` and reserves its next request slot.
+    async fn acquire(&self, session: &str) -> Result<Credentials, ScraperError> {
+        loop {
+            let reserved = {
+                let mut state = self.state.lock();
+                if state.accounts.is_empty() {
+                    return Err(ScraperError::MiddlewareError(
+                        "Credential pool has no accounts".to_string(),
+                    ));
+                }
+                let now = Instant::now();
+                state.assign(session, now).map(|index| {
+                    let account = &mut state.accounts[index];
+                    let start = account.next_request.max(now);
+                    account.next_request = start + self.min_interval;
+                    account.requests += 1;
+                    (account.credentials.clone(), start - now)
+                })
+            };
+            match reserved {
+                Ok((credentials, wait)) => {
+                    if !wait.is_zero() {
+                        trace!(
+                            "Delaying request of account {} by {:?}",
+                            credentials.id,
+                            wait
+                        );
+                        sleep(wait).await;
+                    }
+                    return Ok(credentials);
+                }
+                Err(available_at) => {
+                    info!("All accounts are cooling down, waiting for the next one");
+                    tokio::time::sleep_until(available_at).await;
+                }
+            }
+        }
+    }
+
+    fn is_ban(&self, response: &HttpResponse) -> bool {
+        self.ban_statuses.contains(&response.status)
+            || (!self.ban_patterns.is_empty()
+                && response
+                    .body_text()
+                    .is_ok_and(|body| self.ban_patterns.iter().any(|re| re.is_match(body))))
+    }
+
+    fn ban(&self, id: &str) {
+        let mut state = self.state.lock();
+        let Some(index) = state
+            .accounts
+            .iter()
+            .position(|account| account.credentials.id == id)
+        else {
+            return;
+        };
+        let account = &mut state.accounts[index];
+        account.banned_until = Some(Instant::now() + self.cooldown);
+        account.bans += 1;
+        info!(
+            "Account {} banned, cooling down for {:?}",
+            id, self.cooldown
+        );
+        state.sessions.retain(|_, assigned| *assigned != index);
+    }
+}
+
+#[async_trait]
+impl DownloaderMiddleware for CredentialPool {
+    async fn on_request(
+        &self,
+        request: &mut HttpRequest,
+        _config: &SpiderConfig,
+    ) -> Result<RequestAction, ScraperError> {
+        let credentials = self.acquire(&Self::session_key(request)).await?;
+        request.headers.extend(credentials.headers);
+        match &mut request.meta {
+            Some(Value::Object(meta)) => {
+                meta.insert(ACCOUNT_META_KEY.to_string(), json!(credentials.id));
+            }
+            Some(_) => {}
+            None => request.meta = Some(json!({ ACCOUNT_META_KEY: credentials.id })),
+        }
+        if let Some(stats) = &self.stats {
+            stats.record_account_request(&credentials.id);
+        }
+        Ok(RequestAction::Continue)
+    }
+
+    async fn on_response(
+        &self,
+        request: &HttpRequest,
+        response: &mut HttpResponse,
+        _config: &SpiderConfig,
+    ) -> Result<(), ScraperError> {
+        let account = request
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.get(ACCOUNT_META_KEY))
+            .and_then(Value::as_str);
+        if let Some(id) = account {
+            if self.is_ban(response) {
+                self.ban(id);
+                if let Some(stats) = &self.stats {
+                    stats.record_account_ban(id);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::middleware::DownloaderMiddlewareChain;
+    use crate::core::retry::mock_scraper::{MockResponse, MockScraper};
+    use crate::core::SpiderCallback;
+    use url::Url;
+
+    fn request(host: &str) -> HttpRequest {
+        HttpRequest::new(
+            Url::parse(&format!("https://{}/", host)).unwrap(),
+            SpiderCallback::ParseItem,
+            0,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_sessions_keep_their_account_until_banned() {
+        let stats = Arc::new(StatsTracker::new());
+        let pool = CredentialPool::new(vec![
+            Credentials::new("alice").with_header("authorization", "Bearer a"),
+            Credentials::new("bob").with_header("authorization", "Bearer b"),
+        ])
+        .with_stats(Arc::clone(&stats));
+        let mut chain = DownloaderMiddlewareChain::new();
+        chain.push(pool.clone());
+        let status = |status| MockResponse {
+            status,
+            body: String::new(),
+            delay: None,
+        };
+        // a.com: 200, b.com: 200, a.com: 429, a.com: 200
+        let scraper = MockScraper::new(vec![status(200), status(200), status(429), status(200)]);
+        let config = SpiderConfig::default();
+
+        let mut accounts = Vec::new();
+        for host in ["a.com", "b.com", "a.com", "a.com"] {
+            let response = chain
+                .fetch(&scraper, request(host), &config)
+                .await
+                .unwrap()
+                .unwrap();
+            let sent = response.from_request.headers["authorization"].clone();
+            accounts.push(sent);
+        }
+        assert_eq!(accounts, ["Bearer a", "Bearer b", "Bearer a", "Bearer b"]);
+
+        let health = pool.health();
+        assert_eq!((health[0].requests, health[0].bans), (2, 1));
+        assert!(health[0].cooling_down.is_some());
+        assert_eq!(health[1].sessions, 2);
+        let accounts = stats.get_stats().accounts;
+        assert_eq!(accounts["alice"].bans, 1);
+        assert_eq!(accounts["bob"].requests, 2);
+    }
+}
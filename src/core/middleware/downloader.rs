@@ -0,0 +1,240 @@
+use crate::core::spider::SpiderConfig;
+use crate::{HttpRequest, HttpResponse, Scraper, ScraperError, ScraperResult};
+use async_trait::async_trait;
+use log::debug;
+use std::sync::Arc;
+
+/// Outcome of [`DownloaderMiddleware::on_request`].
+#[derive(Debug)]
+pub enum RequestAction {
+    /// Hand the (possibly modified) request to the next middleware.
+    Continue,
+    /// Skip the download and use this response instead.
+    Respond(Box<HttpResponse>),
+    /// Discard the request without downloading it.
+    Drop,
+}
+
+/// Hooks around [`Scraper::fetch`].
+///
+/// `on_request` runs in registration order before the download;
+/// `on_response` and `on_error` run in reverse order afterwards, and only for
+/// the middlewares whose `on_request` was reached.
+#[async_trait]
+pub trait DownloaderMiddleware: Send + Sync {
+    async fn on_request(
+        &self,
+        _request: &mut HttpRequest,
+        _config: &SpiderConfig,
+    ) -> Result<RequestAction, ScraperError> {
+        Ok(RequestAction::Continue)
+    }
+
+    async fn on_response(
+        &self,
+        _request: &HttpRequest,
+        _response: &mut HttpResponse,
+        _config: &SpiderConfig,
+    ) -> Result<(), ScraperError> {
+        Ok(())
+    }
+
+    /// Return a response to recover from the error, or the error to pass it on.
+    async fn on_error(
+        &self,
+        _request: &HttpRequest,
+        error: ScraperError,
+        _config: &SpiderConfig,
+    ) -> Result<HttpResponse, ScraperError> {
+        Err(error)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct DownloaderMiddlewareChain {
+    middlewares: Vec<Arc<dyn DownloaderMiddleware>>,
+}
+
+impl DownloaderMiddlewareChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push<M: DownloaderMiddleware + 'static>(&mut self, middleware: M) {
+        self.middlewares.push(Arc::new(middleware));
+    }
+
+    pub fn len(&self) -> usize {
+        self.middlewares.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.middlewares.is_empty()
+    }
+
+    /// Fetches `request` through the chain. Returns `Ok(None)` if a middleware
+    /// dropped the request.
+    pub async fn fetch(
+        &self,
+        scraper: &dyn Scraper,
+        mut request: HttpRequest,
+        config: &SpiderConfig,
+    ) -> ScraperResult<Option<HttpResponse>> {
+        let mut reached = 0;
+        let mut short_circuit = None;
+
+        for middleware in &self.middlewares {
+            reached += 1;
+            match middleware.on_request(&mut request, config).await {
+                Ok(RequestAction::Continue) => {}
+                Ok(RequestAction::Respond(response)) => {
+                    debug!(
+                        "Middleware responded to {} without downloading",
+                        request.url
+                    );
+                    short_circuit = Some(Ok(*response));
+                    break;
+                }
+                Ok(RequestAction::Drop) => {
+                    debug!("Middleware dropped request to {}", request.url);
+                    return Ok(None);
+                }
+                Err(error) => {
+                    short_circuit = Some(Err(error));
+                    break;
+                }
+            }
+        }
+
+        let mut result = match short_circuit {
+            Some(result) => result,
+            // Middlewares see the error itself; the request is attached below.
+            None => scraper
+                .fetch(request.clone(), config)
+                .await
+                .map_err(|error| error.into_parts().0),
+        };
+
+        for middleware in self.middlewares[..reached].iter().rev() {
+            result = match result {
+                Ok(mut response) => middleware
+                    .on_response(&request, &mut response, config)
+                    .await
+                    .map(|_| response),
+                Err(error) => middleware.on_error(&request, error, config).await,
+            };
+        }
+
+        result
+            .map(Some)
+            .map_err(|error| error.with_request(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::retry::mock_scraper::{MockResponse, MockScraper};
+    use crate::core::SpiderCallback;
+    use parking_lot::Mutex;
+    use url::Url;
+
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl DownloaderMiddleware for Recorder {
+        async fn on_request(
+            &self,
+            request: &mut HttpRequest,
+            _config: &SpiderConfig,
+        ) -> Result<RequestAction, ScraperError> {
+            self.log.lock().push(format!("{}:request", self.name));
+            if request.url.path() == "/old" {
+                request.url.set_path("/new");
+            }
+            if request.url.path() == "/blocked" {
+                return Ok(RequestAction::Drop);
+            }
+            Ok(RequestAction::Continue)
+        }
+
+        async fn on_response(
+            &self,
+            _request: &HttpRequest,
+            response: &mut HttpResponse,
+            _config: &SpiderConfig,
+        ) -> Result<(), ScraperError> {
+            self.log.lock().push(format!("{}:response", self.name));
+            response
+                .headers
+                .insert("x-seen-by".to_string(), self.name.to_string());
+            Ok(())
+        }
+    }
+
+    fn request(path: &str) -> HttpRequest {
+        HttpRequest::new(
+            Url::parse(&format!("https://example.com{}", path)).unwrap(),
+            SpiderCallback::Bootstrap,
+            0,
+        )
+    }
+
+    fn scraper() -> MockScraper {
+        MockScraper::new(vec![MockResponse {
+            status: 200,
+            body: "ok".to_string(),
+            delay: None,
+        }])
+    }
+
+    #[tokio::test]
+    async fn test_chain_runs_hooks_in_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut chain = DownloaderMiddlewareChain::new();
+        for name in ["first", "second"] {
+            chain.push(Recorder {
+                name,
+                log: Arc::clone(&log),
+            });
+        }
+
+        let response = chain
+            .fetch(&scraper(), request("/old"), &SpiderConfig::default())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(response.url.path(), "/new");
+        assert_eq!(response.headers.get("x-seen-by").unwrap(), "first");
+        assert_eq!(
+            *log.lock(),
+            [
+                "first:request",
+                "second:request",
+                "second:response",
+                "first:response"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chain_drops_request() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut chain = DownloaderMiddlewareChain::new();
+        chain.push(Recorder {
+            name: "only",
+            log: Arc::clone(&log),
+        });
+
+        let response = chain
+            .fetch(&scraper(), request("/blocked"), &SpiderConfig::default())
+            .await
+            .unwrap();
+        assert!(response.is_none());
+        assert_eq!(*log.lock(), ["only:request"]);
+    }
+}
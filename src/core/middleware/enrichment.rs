@@ -0,0 +1,366 @@
+use super::spider::SpiderMiddleware;
+use crate::core::item::TypedItem;
+use crate::core::spider::{ParsedData, SpiderResponse};
+use log::debug;
+use regex::Regex;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+/// Exchange rates used by [`CurrencyConverter`].
+pub trait RatesProvider: Send + Sync {
+    /// How many units of `to` one unit of `from` buys.
+    fn rate(&self, from: &str, to: &str) -> Option<f64>;
+}
+
+/// Fixed rates quoted against a base currency, e.g. `USD`.
+#[derive(Debug, Clone)]
+pub struct StaticRates {
+    base: String,
+    rates: HashMap<String, f64>,
+}
+
+impl StaticRates {
+    pub fn new(base: &str) -> Self {
+        let base = base.to_ascii_uppercase();
+        let rates = HashMap::from([(base.clone(), 1.0)]);
+        Self { base, rates }
+    }
+
+    /// Sets how many units of `currency` one unit of the base currency buys.
+    pub fn with_rate(mut self, currency: &str, per_base: f64) -> Self {
+        self.rates.insert(currency.to_ascii_uppercase(), per_base);
+        self
+    }
+
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+}
+
+impl RatesProvider for StaticRates {
+    fn rate(&self, from: &str, to: &str) -> Option<f64> {
+        let from = self.rates.get(&from.to_ascii_uppercase())?;
+        let to = self.rates.get(&to.to_ascii_uppercase())?;
+        Some(to / from)
+    }
+}
+
+/// Converts a price field of every item to a target currency and stores the
+/// result next to it as `<field>_converted`, keeping the original value.
+pub struct CurrencyConverter {
+    field: String,
+    target: String,
+    default_currency: Option<String>,
+    provider: Arc<dyn RatesProvider>,
+}
+
+impl CurrencyConverter {
+    pub fn new<P: RatesProvider + 'static>(field: &str, target: &str, provider: P) -> Self {
+        Self {
+            field: field.to_string(),
+            target: target.to_ascii_uppercase(),
+            default_currency: None,
+            provider: Arc::new(provider),
+        }
+    }
+
+    /// Currency assumed for prices without a symbol or code.
+    pub fn with_default_currency(mut self, currency: &str) -> Self {
+        self.default_currency = Some(currency.to_ascii_uppercase());
+        self
+    }
+
+    fn convert(&self, value: &Value) -> Option<Value> {
+        let (amount, currency) = match value {
+            Value::Number(n) => (n.as_f64()?, self.default_currency.clone()?),
+            Value::String(s) => {
+                let (amount, currency) = parse_price(s)?;
+                (amount, currency.or_else(|| self.default_currency.clone())?)
+            }
+            _ => return None,
+        };
+        let rate = self.provider.rate(&currency, &self.target)?;
+        Some(json!({
+            "original_amount": amount,
+            "original_currency": currency,
+            "amount": round(amount * rate, 2),
+            "currency": self.target,
+            "rate": rate,
+        }))
+    }
+}
+
+impl SpiderMiddleware for CurrencyConverter {
+    fn process_data(&self, response: &SpiderResponse, data: ParsedData) -> ParsedData {
+        map_items(data, |item| {
+            match item.get(&self.field).and_then(|value| self.convert(value)) {
+                Some(converted) => {
+                    item[format!("{}_converted", self.field)] = converted;
+                }
+                None if item.get(&self.field).is_some() => {
+                    debug!(
+                        "Could not convert {} on {} to {}",
+                        self.field, response.response.url, self.target
+                    );
+                }
+                None => {}
+            }
+        })
+    }
+}
+
+/// Normalizes a quantity field of every item to metric units (`kg` for
+/// weights, `ml` for volumes) and stores it as `<field>_normalized`.
+///
+/// A bare `oz` is read as a weight; volumes must be written as `fl oz`.
+pub struct UnitNormalizer {
+    field: String,
+}
+
+impl UnitNormalizer {
+    pub fn new(field: &str) -> Self {
+        Self {
+            field: field.to_string(),
+        }
+    }
+}
+
+impl SpiderMiddleware for UnitNormalizer {
+    fn process_data(&self, _response: &SpiderResponse, data: ParsedData) -> ParsedData {
+        map_items(data, |item| {
+            let normalized = item
+                .get(&self.field)
+                .and_then(Value::as_str)
+                .and_then(normalize_quantity);
+            if let Some(normalized) = normalized {
+                item[format!("{}_normalized", self.field)] = normalized;
+            }
+        })
+    }
+}
+
+/// Applies `f` to every item. A typed item is converted to JSON for `f`
+/// and, as in item pipelines, replaced by that JSON only if `f` changed it.
+fn map_items(data: ParsedData, mut f: impl FnMut(&mut Value)) -> ParsedData {
+    match data {
+        ParsedData::Item(mut item) => {
+            f(&mut item);
+            ParsedData::Item(item)
+        }
+        ParsedData::Items(mut items) => {
+            items.iter_mut().for_each(&mut f);
+            ParsedData::Items(items)
+        }
+        ParsedData::Typed(items) => ParsedData::Typed(
+            items
+                .into_iter()
+                .map(|item| {
+                    let Ok(mut value) = item.to_value() else {
+                        return item;
+                    };
+                    let original = value.clone();
+                    f(&mut value);
+                    if value == original {
+                        item
+                    } else {
+                        TypedItem::new(value)
+                    }
+                })
+                .collect(),
+        ),
+        ParsedData::Categorized(mut items) => {
+            items.iter_mut().for_each(|(_, item)| f(item));
+            ParsedData::Categorized(items)
+        }
+        other => other,
+    }
+}
+
+fn price_pattern() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(\d[\d.,\s]*)").unwrap())
+}
+
+fn code_pattern() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\b([A-Z]{3})\b").unwrap())
+}
+
+/// Parses strings such as `£51.77`, `1.234,50 €` or `USD 12`.
+fn parse_price(text: &str) -> Option<(f64, Option<String>)> {
+    let amount = parse_number(price_pattern().captures(text)?.get(1)?.as_str())?;
+    let currency = code_pattern()
+        .captures(text)
+        .map(|c| c[1].to_string())
+        .or_else(|| {
+            text.chars()
+                .find_map(|c| match c {
+                    '$' => Some("USD"),
+                    '€' => Some("EUR"),
+                    '£' => Some("GBP"),
+                    '¥' => Some("JPY"),
+                    '₹' => Some("INR"),
+                    _ => None,
+                })
+                .map(str::to_string)
+        });
+    Some((amount, currency))
+}
+
+/// Accepts both `1,234.50` and `1.234,50`: the last separator followed by one
+/// or two digits is the decimal point, every other one groups thousands.
+fn parse_number(raw: &str) -> Option<f64> {
+    let digits: String = raw.chars().filter(|c| !c.is_whitespace()).collect();
+    let digits = digits.trim_end_matches(['.', ',']);
+    let decimal = digits
+        .rfind(['.', ','])
+        .filter(|&i| (1..=2).contains(&(digits.len() - i - 1)));
+    let normalized: String = digits
+        .char_indices()
+        .filter_map(|(i, c)| match c {
+            '.' | ',' if Some(i) == decimal => Some('.'),
+            '.' | ',' => None,
+            c => Some(c),
+        })
+        .collect();
+    normalized.parse().ok()
+}
+
+fn quantity_pattern() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?i)(\d+(?:[.,]\d+)?)\s*(fl\.?\s*oz|kg|g|lbs?|oz|ml|cl|l)\b").unwrap()
+    })
+}
+
+fn normalize_quantity(text: &str) -> Option<Value> {
+    let captures = quantity_pattern().captures(text)?;
+    let value: f64 = captures[1].replace(',', ".").parse().ok()?;
+    let unit = captures[2].to_ascii_lowercase().replace(['.', ' '], "");
+    let (factor, target) = match unit.as_str() {
+        "kg" => (1.0, "kg"),
+        "g" => (0.001, "kg"),
+        "lb" | "lbs" => (0.453_592_37, "kg"),
+        "oz" => (0.028_349_523_125, "kg"),
+        "ml" => (1.0, "ml"),
+        "cl" => (10.0, "ml"),
+        "l" => (1000.0, "ml"),
+        "floz" => (29.573_529_562_5, "ml"),
+        _ => return None,
+    };
+    Some(json!({
+        "original_value": value,
+        "original_unit": unit,
+        "value": round(value * factor, 4),
+        "unit": target,
+    }))
+}
+
+fn round(value: f64, decimals: i32) -> f64 {
+    let factor = 10f64.powi(decimals);
+    (value * factor).round() / factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::SpiderCallback;
+    use crate::http::HttpRequest;
+    use crate::HttpResponse;
+
+    use url::Url;
+
+    fn spider_response() -> SpiderResponse {
+        let url = Url::parse("https://books.example.com/item").unwrap();
+        SpiderResponse {
+            response: HttpResponse {
+                from_request: Box::new(HttpRequest::new(url.clone(), SpiderCallback::ParseItem, 1)),
+                ..HttpResponse::for_test(&url, "")
+            },
+            callback: SpiderCallback::ParseItem,
+            content: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_number_formats() {
+        assert_eq!(parse_number("1,234.50"), Some(1234.5));
+        assert_eq!(parse_number("1.234,50"), Some(1234.5));
+        assert_eq!(parse_number("1,234"), Some(1234.0));
+        assert_eq!(parse_number("51.77"), Some(51.77));
+    }
+
+    #[test]
+    fn test_converts_prices_and_keeps_original() {
+        let rates = StaticRates::new("USD")
+            .with_rate("GBP", 0.8)
+            .with_rate("EUR", 0.9);
+        let converter = CurrencyConverter::new("price", "usd", rates);
+        let data = ParsedData::Items(vec![
+            json!({"price": "£51.77"}),
+            json!({"price": "1.234,50 €"}),
+            json!({"price": "unknown"}),
+        ]);
+
+        let ParsedData::Items(items) = converter.process_data(&spider_response(), data) else {
+            panic!("expected items");
+        };
+        assert_eq!(items[0]["price"], "£51.77");
+        assert_eq!(items[0]["price_converted"]["original_currency"], "GBP");
+        assert_eq!(items[0]["price_converted"]["amount"], 64.71);
+        assert_eq!(items[1]["price_converted"]["amount"], 1371.67);
+        assert!(items[2].get("price_converted").is_none());
+    }
+
+    #[test]
+    fn test_normalizes_units() {
+        let normalizer = UnitNormalizer::new("size");
+        let data = ParsedData::Items(vec![
+            json!({"size": "2 lb"}),
+            json!({"size": "12 fl oz"}),
+            json!({"size": "1,5 L"}),
+        ]);
+
+        let ParsedData::Items(items) = normalizer.process_data(&spider_response(), data) else {
+            panic!("expected items");
+        };
+        assert_eq!(items[0]["size_normalized"]["value"], 0.9072);
+        assert_eq!(items[0]["size_normalized"]["unit"], "kg");
+        assert_eq!(items[1]["size_normalized"]["value"], 354.8824);
+        assert_eq!(items[2]["size_normalized"]["value"], 1500.0);
+        assert_eq!(items[2]["size_normalized"]["original_unit"], "l");
+    }
+
+    #[test]
+    fn test_enriches_typed_and_categorized_items() {
+        use crate::storage::StorageCategory;
+
+        #[derive(serde::Serialize)]
+        struct Product {
+            size: &'static str,
+        }
+
+        let normalizer = UnitNormalizer::new("size");
+        let data = ParsedData::Typed(vec![
+            TypedItem::new(Product { size: "500 g" }),
+            TypedItem::new(Product { size: "large" }),
+        ]);
+        let ParsedData::Typed(items) = normalizer.process_data(&spider_response(), data) else {
+            panic!("expected typed items");
+        };
+        assert_eq!(
+            items[0].to_value().unwrap()["size_normalized"]["value"],
+            0.5
+        );
+        // Items left unchanged keep their type.
+        assert!(items[1].downcast_ref::<Product>().is_some());
+
+        let data = ParsedData::Categorized(vec![(StorageCategory::Data, json!({"size": "2 l"}))]);
+        let ParsedData::Categorized(items) = normalizer.process_data(&spider_response(), data)
+        else {
+            panic!("expected categorized items");
+        };
+        assert_eq!(items[0].1["size_normalized"]["value"], 2000.0);
+    }
+}
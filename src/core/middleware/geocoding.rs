@@ -0,0 +1,319 @@
+use crate::core::crawling::politeness::PolitenessThrottle;
+use crate::core::item::TypedItem;
+use crate::core::spider::ParsedData;
+use crate::ScraperError;
+use async_trait::async_trait;
+use log::{debug, warn};
+use parking_lot::Mutex;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Coordinates {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// Resolves a free-form address to coordinates.
+#[async_trait]
+pub trait GeocodingProvider: Send + Sync {
+    /// Host the provider sends lookups to, used to rate limit them.
+    fn host(&self) -> &str;
+
+    /// `Ok(None)` means the provider found no match for the address.
+    async fn geocode(&self, address: &str) -> Result<Option<Coordinates>, ScraperError>;
+}
+
+/// Geocoding through an OpenStreetMap Nominatim instance.
+pub struct NominatimProvider {
+    client: Client,
+    base_url: Url,
+}
+
+impl NominatimProvider {
+    /// Nominatim's usage policy requires an identifying user agent.
+    pub fn new(user_agent: &str) -> Result<Self, ScraperError> {
+        Ok(Self {
+            client: Client::builder().user_agent(user_agent).build()?,
+            base_url: Url::parse("https://nominatim.openstreetmap.org/").unwrap(),
+        })
+    }
+
+    /// Points the provider at a self-hosted instance.
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+}
+
+#[async_trait]
+impl GeocodingProvider for NominatimProvider {
+    fn host(&self) -> &str {
+        self.base_url.host_str().unwrap_or_default()
+    }
+
+    async fn geocode(&self, address: &str) -> Result<Option<Coordinates>, ScraperError> {
+        let mut url = self.base_url.join("search")?;
+        url.query_pairs_mut()
+            .append_pair("q", address)
+            .append_pair("format", "json")
+            .append_pair("limit", "1");
+        let results: Vec<Value> = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let coordinates = results.first().and_then(|result| {
+            Some(Coordinates {
+                lat: result["lat"].as_str()?.parse().ok()?,
+                lon: result["lon"].as_str()?.parse().ok()?,
+            })
+        });
+        Ok(coordinates)
+    }
+}
+
+/// Enrichment stage that geocodes an address field of every item and adds
+/// `lat` and `lon` fields. Lookups are cached per address and spaced by the
+/// configured delay.
+///
+/// Geocoding is asynchronous, so call [`Geocoder::enrich`] from
+/// `Spider::persist_extracted_data` before storing the data.
+pub struct Geocoder {
+    field: String,
+    provider: Arc<dyn GeocodingProvider>,
+    cache: Mutex<HashMap<String, Option<Coordinates>>>,
+    throttle: Arc<PolitenessThrottle>,
+    delay: Duration,
+}
+
+impl Geocoder {
+    pub fn new<P: GeocodingProvider + 'static>(field: &str, provider: P) -> Self {
+        Self {
+            field: field.to_string(),
+            provider: Arc::new(provider),
+            cache: Mutex::new(HashMap::new()),
+            throttle: Arc::new(PolitenessThrottle::new()),
+            // Nominatim allows one request per second.
+            delay: Duration::from_secs(1),
+        }
+    }
+
+    /// Minimum delay between two lookups.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Shares a throttle with other components that call the same provider.
+    pub fn with_throttle(mut self, throttle: Arc<PolitenessThrottle>) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    pub async fn lookup(&self, address: &str) -> Option<Coordinates> {
+        let key = address
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        if let Some(cached) = self.cache.lock().get(&key) {
+            return *cached;
+        }
+
+        let wait = self.throttle.reserve(self.provider.host(), self.delay);
+        if !wait.is_zero() {
+            sleep(wait).await;
+        }
+        match self.provider.geocode(address).await {
+            Ok(coordinates) => {
+                debug!("Geocoded {:?} to {:?}", address, coordinates);
+                self.cache.lock().insert(key, coordinates);
+                coordinates
+            }
+            Err(e) => {
+                // Errors are not cached so the address is retried next time.
+                warn!("Failed to geocode {:?}: {}", address, e);
+                None
+            }
+        }
+    }
+
+    /// Adds `lat` and `lon` to every item with an address. A typed item
+    /// that gets coordinates is replaced by its JSON, as in item pipelines.
+    pub async fn enrich(&self, data: ParsedData) -> ParsedData {
+        match data {
+            ParsedData::Item(mut item) => {
+                self.enrich_item(&mut item).await;
+                ParsedData::Item(item)
+            }
+            ParsedData::Items(mut items) => {
+                for item in items.iter_mut() {
+                    self.enrich_item(item).await;
+                }
+                ParsedData::Items(items)
+            }
+            ParsedData::Typed(items) => {
+                let mut enriched = Vec::with_capacity(items.len());
+                for item in items {
+                    let mut value = match item.to_value() {
+                        Ok(value) => value,
+                        Err(_) => {
+                            enriched.push(item);
+                            continue;
+                        }
+                    };
+                    if self.enrich_item(&mut value).await {
+                        enriched.push(TypedItem::new(value));
+                    } else {
+                        enriched.push(item);
+                    }
+                }
+                ParsedData::Typed(enriched)
+            }
+            ParsedData::Categorized(mut items) => {
+                for (_, item) in items.iter_mut() {
+                    self.enrich_item(item).await;
+                }
+                ParsedData::Categorized(items)
+            }
+            other => other,
+        }
+    }
+
+    /// Whether coordinates were added.
+    async fn enrich_item(&self, item: &mut Value) -> bool {
+        let Some(address) = item
+            .get(&self.field)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+        else {
+            return false;
+        };
+        let Some(coordinates) = self.lookup(&address).await else {
+            return false;
+        };
+        item["lat"] = json!(coordinates.lat);
+        item["lon"] = json!(coordinates.lon);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    struct CountingProvider {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl GeocodingProvider for CountingProvider {
+        fn host(&self) -> &str {
+            "geo.example.com"
+        }
+
+        async fn geocode(&self, address: &str) -> Result<Option<Coordinates>, ScraperError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(address.contains("Berlin").then_some(Coordinates {
+                lat: 52.52,
+                lon: 13.405,
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_enrich_caches_lookups() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let geocoder = Geocoder::new(
+            "address",
+            CountingProvider {
+                calls: Arc::clone(&calls),
+            },
+        )
+        .with_delay(Duration::ZERO);
+
+        let data = ParsedData::Items(vec![
+            json!({"address": "Alexanderplatz, Berlin"}),
+            json!({"address": "alexanderplatz,  berlin"}),
+            json!({"address": "Nowhere"}),
+            json!({"name": "no address"}),
+        ]);
+        let ParsedData::Items(items) = geocoder.enrich(data).await else {
+            panic!("expected items");
+        };
+
+        assert_eq!(items[0]["lat"], 52.52);
+        assert_eq!(items[1]["lon"], 13.405);
+        assert!(items[2].get("lat").is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_enrich_typed_and_categorized_items() {
+        use crate::storage::StorageCategory;
+
+        #[derive(Serialize)]
+        struct Store {
+            address: &'static str,
+        }
+
+        let geocoder = Geocoder::new(
+            "address",
+            CountingProvider {
+                calls: Arc::new(AtomicUsize::new(0)),
+            },
+        )
+        .with_delay(Duration::ZERO);
+
+        let data = ParsedData::Typed(vec![
+            TypedItem::new(Store { address: "Berlin" }),
+            TypedItem::new(Store { address: "Nowhere" }),
+        ]);
+        let ParsedData::Typed(items) = geocoder.enrich(data).await else {
+            panic!("expected typed items");
+        };
+        assert_eq!(items[0].to_value().unwrap()["lat"], 52.52);
+        assert!(items[1].is::<Store>());
+
+        let data =
+            ParsedData::Categorized(vec![(StorageCategory::Data, json!({"address": "Berlin"}))]);
+        let ParsedData::Categorized(items) = geocoder.enrich(data).await else {
+            panic!("expected categorized items");
+        };
+        assert_eq!(items[0].1["lon"], 13.405);
+    }
+
+    #[tokio::test]
+    async fn test_nominatim_provider() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .and(query_param("q", "Berlin"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!([{"lat": "52.5170365", "lon": "13.3888599"}])),
+            )
+            .mount(&server)
+            .await;
+
+        let provider = NominatimProvider::new("turboscraper-test")
+            .unwrap()
+            .with_base_url(Url::parse(&server.uri()).unwrap());
+        let coordinates = provider.geocode("Berlin").await.unwrap().unwrap();
+        assert_eq!(coordinates.lat, 52.5170365);
+        assert_eq!(coordinates.lon, 13.3888599);
+    }
+}
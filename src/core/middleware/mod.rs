@@ -0,0 +1,16 @@
+pub mod cache;
+pub mod credentials;
+pub mod downloader;
+pub mod enrichment;
+pub mod geocoding;
+pub mod spider;
+
+pub use cache::{
+    CacheMode, CachePolicy, CacheStorage, CachedResponse, DiskCacheStorage, HttpCache,
+    MemoryCacheStorage,
+};
+pub use credentials::{AccountHealth, CredentialPool, Credentials};
+pub use downloader::{DownloaderMiddleware, DownloaderMiddlewareChain, RequestAction};
+pub use enrichment::{CurrencyConverter, RatesProvider, StaticRates, UnitNormalizer};
+pub use geocoding::{Coordinates, Geocoder, GeocodingProvider, NominatimProvider};
+pub use spider::{OffsiteMiddleware, SpiderMiddleware, SpiderMiddlewareChain};
@@ -0,0 +1,163 @@
+use crate::core::crawling::profile::host_matches;
+use crate::core::spider::{ParseResult, ParsedData, SpiderResponse};
+use crate::HttpRequest;
+use log::debug;
+use std::sync::Arc;
+
+/// Hooks applied to the output of `Spider::parse` before the crawler
+/// schedules the follow-up requests and the data is persisted.
+pub trait SpiderMiddleware: Send + Sync {
+    /// Filter or annotate the requests of a `ParseResult::Continue` batch.
+    fn process_requests(
+        &self,
+        _response: &SpiderResponse,
+        requests: Vec<HttpRequest>,
+    ) -> Vec<HttpRequest> {
+        requests
+    }
+
+    /// Transform the extracted data, e.g. to enrich every item.
+    fn process_data(&self, _response: &SpiderResponse, data: ParsedData) -> ParsedData {
+        data
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct SpiderMiddlewareChain {
+    middlewares: Vec<Arc<dyn SpiderMiddleware>>,
+}
+
+impl SpiderMiddlewareChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push<M: SpiderMiddleware + 'static>(&mut self, middleware: M) {
+        self.middlewares.push(Arc::new(middleware));
+    }
+
+    pub fn len(&self) -> usize {
+        self.middlewares.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.middlewares.is_empty()
+    }
+
+    /// Runs every middleware, in registration order, over the parse output.
+    pub fn apply(
+        &self,
+        response: &SpiderResponse,
+        (mut result, mut data): (ParseResult, ParsedData),
+    ) -> (ParseResult, ParsedData) {
+        for middleware in &self.middlewares {
+            if let ParseResult::Continue(requests) = result {
+                result = ParseResult::Continue(middleware.process_requests(response, requests));
+            }
+            data = middleware.process_data(response, data);
+        }
+        (result, data)
+    }
+}
+
+/// Drops requests to hosts outside the allowed domains (subdomains included).
+pub struct OffsiteMiddleware {
+    domains: Vec<String>,
+}
+
+impl OffsiteMiddleware {
+    pub fn new<D: Into<String>>(domains: Vec<D>) -> Self {
+        Self {
+            domains: domains.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl SpiderMiddleware for OffsiteMiddleware {
+    fn process_requests(
+        &self,
+        _response: &SpiderResponse,
+        requests: Vec<HttpRequest>,
+    ) -> Vec<HttpRequest> {
+        requests
+            .into_iter()
+            .filter(|request| {
+                let host = request.url.host_str().unwrap_or_default();
+                let allowed = self.domains.iter().any(|domain| host_matches(host, domain));
+                if !allowed {
+                    debug!("Filtered offsite request to {}", request.url);
+                }
+                allowed
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::SpiderCallback;
+
+    use crate::HttpResponse;
+
+    use serde_json::json;
+
+    use url::Url;
+
+    struct SourceTagger;
+
+    impl SpiderMiddleware for SourceTagger {
+        fn process_data(&self, response: &SpiderResponse, data: ParsedData) -> ParsedData {
+            match data {
+                ParsedData::Item(mut item) => {
+                    item["source"] = json!(response.response.url.as_str());
+                    ParsedData::Item(item)
+                }
+                other => other,
+            }
+        }
+    }
+
+    fn request(url: &str) -> HttpRequest {
+        HttpRequest::new(Url::parse(url).unwrap(), SpiderCallback::ParseItem, 1)
+    }
+
+    #[test]
+    fn test_chain_filters_offsite_and_enriches_items() {
+        let url = Url::parse("https://example.com/list").unwrap();
+        let response = SpiderResponse {
+            response: HttpResponse {
+                from_request: Box::new(request(url.as_str())),
+                ..HttpResponse::for_test(&url, "")
+            },
+            callback: SpiderCallback::Bootstrap,
+            content: None,
+        };
+
+        let mut chain = SpiderMiddlewareChain::new();
+        chain.push(OffsiteMiddleware::new(vec!["example.com"]));
+        chain.push(SourceTagger);
+
+        let (result, data) = chain.apply(
+            &response,
+            (
+                ParseResult::Continue(vec![
+                    request("https://example.com/a"),
+                    request("https://shop.example.com/b"),
+                    request("https://tracker.net/c"),
+                ]),
+                ParsedData::Item(json!({"name": "a"})),
+            ),
+        );
+
+        let ParseResult::Continue(requests) = result else {
+            panic!("expected Continue");
+        };
+        let hosts: Vec<_> = requests.iter().filter_map(|r| r.url.host_str()).collect();
+        assert_eq!(hosts, ["example.com", "shop.example.com"]);
+        let ParsedData::Item(item) = data else {
+            panic!("expected Item");
+        };
+        assert_eq!(item["source"], "https://example.com/list");
+    }
+}
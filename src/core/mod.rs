@@ -1,8 +1,46 @@
+pub mod backfill;
 pub mod crawling;
 mod errors;
+pub mod item;
+pub mod logging;
+pub mod middleware;
+pub mod pipeline;
 pub mod retry;
+pub mod sitemap;
 pub mod spider;
 
+pub use backfill::{Backfill, BackfillReport};
+pub use crawling::builder::CrawlerBuilder;
+pub use crawling::change::{ChangeTracker, PageFingerprint};
+pub use crawling::circuit::{CircuitBreaker, CircuitState};
 pub use crawling::crawler::Crawler;
-pub use errors::{ScraperError, ScraperResult};
+pub use crawling::dedup::{BloomFilter, DedupFilter, DedupMethods, HashSetFilter};
+pub use crawling::events::CrawlerEvents;
+pub use crawling::frontier::{CrawlOrder, FrontierSnapshot, PendingRequest, PendingState};
+pub use crawling::handle::{CrawlerHandle, ShutdownToken};
+pub use crawling::link_filter::{LinkFilter, UnfollowableLink};
+pub use crawling::parse_pool::ParsePool;
+pub use crawling::profile::DomainProfile;
+pub use crawling::revisit::{RevisitPolicies, RevisitPolicy};
+pub use crawling::routes::Routes;
+pub use crawling::scheduler::{CrawlSchedule, CrawlScheduler, RunReport};
+pub use crawling::session::{Reauthenticate, SessionGuard};
+pub use crawling::slots::SlotPolicy;
+pub use crawling::subresource::SubresourcePolicy;
+pub use crawling::trap::{SuspectedTrap, TrapAction, TrapDetector};
+pub use crawling::url_filter::UrlFilters;
+pub use crawling::visited::{PendingPage, VisitedStore};
+pub use crawling::warmup::WarmUp;
+pub use crawling::window::CrawlWindow;
+pub use errors::{RetryHint, ScraperError, ScraperResult, SpiderError, WithRequest};
+pub use item::{ItemStream, TypedItem};
+pub use logging::{ItemPreview, LogThrottle, PreviewSample};
+pub use middleware::{
+    CacheMode, CachePolicy, CredentialPool, Credentials, DiskCacheStorage, DownloaderMiddleware,
+    HttpCache, MemoryCacheStorage, RequestAction, SpiderMiddleware,
+};
+pub use pipeline::{
+    ItemContext, ItemDedup, ItemPipeline, ItemValidation, PipelineResult, Validate,
+};
+pub use sitemap::SitemapSpider;
 pub use spider::{Spider, SpiderCallback};
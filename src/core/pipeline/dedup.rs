@@ -0,0 +1,175 @@
+use super::{ItemContext, ItemPipeline, PipelineResult};
+use async_trait::async_trait;
+use log::{debug, warn};
+use parking_lot::RwLock;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+type KeyFn = dyn Fn(&Value, &ItemContext<'_>) -> Option<String> + Send + Sync;
+
+/// Drops items whose key was already seen, e.g. products listed in several
+/// overlapping categories. Items without a key are kept.
+///
+/// Clones share the same keys. With [`open`](Self::open) the keys are loaded
+/// from a file and saved back to it, one per line, when the crawl finishes,
+/// so later crawls drop items stored before.
+#[derive(Clone)]
+pub struct ItemDedup {
+    key: Arc<KeyFn>,
+    keys: Arc<RwLock<HashSet<String>>>,
+    path: Option<PathBuf>,
+}
+
+impl ItemDedup {
+    /// Keys items by a top level field such as `upc`. Strings are used as
+    /// is, other values by their JSON form.
+    pub fn by_field(field: &str) -> Self {
+        let field = field.to_string();
+        Self::by_key(move |item, _| match item.get(&field)? {
+            Value::Null => None,
+            Value::String(value) => Some(value.clone()),
+            value => Some(value.to_string()),
+        })
+    }
+
+    /// Keys items by the URL of the page they were scraped from.
+    pub fn by_url() -> Self {
+        Self::by_key(|_, context| Some(context.response.response.url.to_string()))
+    }
+
+    pub fn by_key<F>(key: F) -> Self
+    where
+        F: Fn(&Value, &ItemContext<'_>) -> Option<String> + Send + Sync + 'static,
+    {
+        Self {
+            key: Arc::new(key),
+            keys: Arc::new(RwLock::new(HashSet::new())),
+            path: None,
+        }
+    }
+
+    /// Loads the keys saved by a previous crawl, starting empty if the file
+    /// does not exist yet.
+    pub fn open<P: AsRef<Path>>(mut self, path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let keys = match fs::File::open(&path) {
+            Ok(file) => BufReader::new(file)
+                .lines()
+                .filter(|line| !line.as_ref().is_ok_and(|line| line.is_empty()))
+                .collect::<io::Result<HashSet<String>>>()?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashSet::new(),
+            Err(e) => return Err(e),
+        };
+        self.keys = Arc::new(RwLock::new(keys));
+        self.path = Some(path);
+        Ok(self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes the keys back to the file the pipeline was opened with, if any.
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+        for key in self.keys.read().iter() {
+            writeln!(writer, "{}", key.replace('\n', " "))?;
+        }
+        writer.flush()
+    }
+}
+
+#[async_trait]
+impl ItemPipeline for ItemDedup {
+    async fn process_item(&self, item: &Value, context: &ItemContext<'_>) -> PipelineResult {
+        let Some(key) = (self.key)(item, context) else {
+            return PipelineResult::Keep;
+        };
+        if self.keys.write().insert(key.replace('\n', " ")) {
+            PipelineResult::Keep
+        } else {
+            debug!("Dropping duplicate item {} from {}", key, context.spider);
+            PipelineResult::Drop
+        }
+    }
+
+    async fn close(&self) {
+        if let Err(e) = self.save() {
+            warn!("Failed to save item dedup keys: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::pipeline::ItemPipelineChain;
+    use crate::core::spider::{ParsedData, SpiderResponse};
+    use crate::core::SpiderCallback;
+    use crate::http::HttpRequest;
+    use crate::HttpResponse;
+
+    use serde_json::json;
+
+    use url::Url;
+
+    fn response() -> SpiderResponse {
+        let url = Url::parse("https://example.com/category/1").unwrap();
+        SpiderResponse {
+            response: HttpResponse {
+                from_request: Box::new(HttpRequest::new(url.clone(), SpiderCallback::ParseItem, 0)),
+                ..HttpResponse::for_test(&url, "")
+            },
+            callback: SpiderCallback::ParseItem,
+            content: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drops_seen_keys_across_crawls() {
+        let path = std::env::temp_dir().join(format!(
+            "turboscraper_item_dedup_{}.txt",
+            uuid::Uuid::now_v7()
+        ));
+        let response = response();
+        let context = ItemContext {
+            spider: "shop",
+            response: &response,
+            stats: &crate::StatsTracker::new(),
+        };
+        let items = || {
+            ParsedData::Items(vec![
+                json!({"upc": "A1"}),
+                json!({"upc": "B2"}),
+                json!({"upc": "A1"}),
+                json!({"name": "no key"}),
+            ])
+        };
+
+        let mut chain = ItemPipelineChain::new();
+        chain.push(ItemDedup::by_field("upc").open(&path).unwrap());
+        let (_, dropped) = chain.process(items(), &context).await;
+        assert_eq!(dropped, 1);
+        chain.close().await;
+
+        let dedup = ItemDedup::by_field("upc").open(&path).unwrap();
+        assert_eq!(dedup.len(), 2);
+        let mut chain = ItemPipelineChain::new();
+        chain.push(dedup);
+        let (data, dropped) = chain.process(items(), &context).await;
+        assert_eq!(dropped, 3);
+        assert_eq!(data.item_count(), 1);
+        let _ = fs::remove_file(path);
+    }
+}
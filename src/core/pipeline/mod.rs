@@ -0,0 +1,271 @@
+mod dedup;
+mod validation;
+
+pub use dedup::ItemDedup;
+#[cfg(feature = "json-schema")]
+pub use validation::JsonSchema;
+pub use validation::{ItemValidation, Validate};
+
+use crate::core::item::TypedItem;
+use crate::core::spider::{ParsedData, SpiderResponse};
+use crate::StatsTracker;
+use async_trait::async_trait;
+use log::debug;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// What an [`ItemPipeline`] decides for an item.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PipelineResult {
+    /// Pass the item on unchanged.
+    Keep,
+    /// Discard the item; later pipelines and storage never see it.
+    Drop,
+    /// Replace the item with this value.
+    Modified(Value),
+}
+
+/// The spider and page an item was scraped from.
+pub struct ItemContext<'a> {
+    pub spider: &'a str,
+    pub response: &'a SpiderResponse,
+    pub stats: &'a StatsTracker,
+}
+
+/// A processing step for every scraped item, run after `Spider::parse` and
+/// spider middlewares and before `Spider::persist_extracted_data`. Used for
+/// cleaning, validation, dedup and enrichment shared across spiders.
+#[async_trait]
+pub trait ItemPipeline: Send + Sync {
+    async fn process_item(&self, item: &Value, context: &ItemContext<'_>) -> PipelineResult;
+
+    /// Called for items of `ParsedData::Typed`. Override to work on the
+    /// struct itself, e.g. through `TypedItem::downcast_mut`. The default
+    /// runs `process_item` on the item's JSON form; `Modified` then replaces
+    /// the typed item with the returned JSON.
+    async fn process_typed_item(
+        &self,
+        item: &mut TypedItem,
+        context: &ItemContext<'_>,
+    ) -> PipelineResult {
+        match item.to_value() {
+            Ok(value) => self.process_item(&value, context).await,
+            Err(e) => {
+                debug!("Failed to convert {} for pipeline: {}", item.type_name(), e);
+                PipelineResult::Keep
+            }
+        }
+    }
+
+    /// Called once when the crawl finishes, e.g. to flush state to disk.
+    async fn close(&self) {}
+}
+
+#[derive(Clone, Default)]
+pub struct ItemPipelineChain {
+    pipelines: Vec<Arc<dyn ItemPipeline>>,
+}
+
+impl ItemPipelineChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push<P: ItemPipeline + 'static>(&mut self, pipeline: P) {
+        self.pipelines.push(Arc::new(pipeline));
+    }
+
+    pub fn len(&self) -> usize {
+        self.pipelines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pipelines.is_empty()
+    }
+
+    /// Runs every item through the pipelines in registration order. Returns
+    /// the surviving data and the number of dropped items. Raw and empty
+    /// data pass through untouched.
+    pub async fn process(&self, data: ParsedData, context: &ItemContext<'_>) -> (ParsedData, u64) {
+        if self.pipelines.is_empty() {
+            return (data, 0);
+        }
+        match data {
+            ParsedData::Item(item) => match self.process_one(item, context).await {
+                Some(item) => (ParsedData::Item(item), 0),
+                None => (ParsedData::Empty, 1),
+            },
+            ParsedData::Items(items) => {
+                let total = items.len() as u64;
+                let mut kept = Vec::with_capacity(items.len());
+                for item in items {
+                    if let Some(item) = self.process_one(item, context).await {
+                        kept.push(item);
+                    }
+                }
+                let dropped = total - kept.len() as u64;
+                (ParsedData::Items(kept), dropped)
+            }
+            ParsedData::Typed(items) => {
+                let total = items.len() as u64;
+                let mut kept = Vec::with_capacity(items.len());
+                for item in items {
+                    if let Some(item) = self.process_typed(item, context).await {
+                        kept.push(item);
+                    }
+                }
+                let dropped = total - kept.len() as u64;
+                (ParsedData::Typed(kept), dropped)
+            }
+            ParsedData::Categorized(items) => {
+                let total = items.len() as u64;
+                let mut kept = Vec::with_capacity(items.len());
+                for (category, item) in items {
+                    if let Some(item) = self.process_one(item, context).await {
+                        kept.push((category, item));
+                    }
+                }
+                let dropped = total - kept.len() as u64;
+                (ParsedData::Categorized(kept), dropped)
+            }
+            other => (other, 0),
+        }
+    }
+
+    pub async fn close(&self) {
+        for pipeline in &self.pipelines {
+            pipeline.close().await;
+        }
+    }
+
+    async fn process_one(&self, mut item: Value, context: &ItemContext<'_>) -> Option<Value> {
+        for pipeline in &self.pipelines {
+            match pipeline.process_item(&item, context).await {
+                PipelineResult::Keep => {}
+                PipelineResult::Modified(modified) => item = modified,
+                PipelineResult::Drop => {
+                    debug!(
+                        "Item pipeline dropped an item from {}",
+                        context.response.response.url
+                    );
+                    return None;
+                }
+            }
+        }
+        Some(item)
+    }
+
+    async fn process_typed(
+        &self,
+        mut item: TypedItem,
+        context: &ItemContext<'_>,
+    ) -> Option<TypedItem> {
+        for pipeline in &self.pipelines {
+            match pipeline.process_typed_item(&mut item, context).await {
+                PipelineResult::Keep => {}
+                PipelineResult::Modified(modified) => item = TypedItem::new(modified),
+                PipelineResult::Drop => {
+                    debug!(
+                        "Item pipeline dropped a {} from {}",
+                        item.type_name(),
+                        context.response.response.url
+                    );
+                    return None;
+                }
+            }
+        }
+        Some(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::SpiderCallback;
+    use crate::http::HttpRequest;
+    use crate::HttpResponse;
+
+    use serde_json::json;
+
+    use url::Url;
+
+    struct TrimTitle;
+
+    #[async_trait]
+    impl ItemPipeline for TrimTitle {
+        async fn process_item(&self, item: &Value, _context: &ItemContext<'_>) -> PipelineResult {
+            match item["title"].as_str() {
+                Some(title) if title.trim() != title => {
+                    let mut item = item.clone();
+                    item["title"] = json!(title.trim());
+                    PipelineResult::Modified(item)
+                }
+                _ => PipelineResult::Keep,
+            }
+        }
+    }
+
+    struct RequireTitle;
+
+    #[async_trait]
+    impl ItemPipeline for RequireTitle {
+        async fn process_item(&self, item: &Value, _context: &ItemContext<'_>) -> PipelineResult {
+            match item["title"].as_str() {
+                Some(title) if !title.is_empty() => PipelineResult::Keep,
+                _ => PipelineResult::Drop,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chain_runs_in_order_and_drops() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let response = SpiderResponse {
+            response: HttpResponse {
+                from_request: Box::new(HttpRequest::new(url.clone(), SpiderCallback::ParseItem, 0)),
+                ..HttpResponse::for_test(&url, "")
+            },
+            callback: SpiderCallback::ParseItem,
+            content: None,
+        };
+        let context = ItemContext {
+            spider: "books",
+            response: &response,
+            stats: &StatsTracker::new(),
+        };
+
+        let mut chain = ItemPipelineChain::new();
+        chain.push(TrimTitle);
+        chain.push(RequireTitle);
+        let items = ParsedData::Items(vec![
+            json!({"title": "  Dune "}),
+            json!({"title": "   "}),
+            json!({"price": 3}),
+        ]);
+        let (data, dropped) = chain.process(items, &context).await;
+
+        assert_eq!(dropped, 2);
+        match data {
+            ParsedData::Items(items) => assert_eq!(items, [json!({"title": "Dune"})]),
+            other => panic!("unexpected data: {:?}", other),
+        }
+
+        #[derive(serde::Serialize)]
+        struct Book {
+            title: String,
+        }
+        let books = ParsedData::typed_items(["Dune", ""].map(|title| Book {
+            title: title.to_string(),
+        }));
+        let (data, dropped) = chain.process(books, &context).await;
+
+        assert_eq!(dropped, 1);
+        let ParsedData::Typed(mut books) = data else {
+            panic!("typed items should stay typed");
+        };
+        assert_eq!(
+            books.pop().unwrap().downcast::<Book>().unwrap().title,
+            "Dune"
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use super::{ItemContext, ItemPipeline, PipelineResult};
+use crate::storage::{StorageBackend, StorageCategory, StorageItem, StorageManager};
+use async_trait::async_trait;
+use chrono::Utc;
+use log::warn;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Checks an extracted item, returning every problem found.
+pub trait Validate: Send + Sync {
+    fn validate(&self, item: &Value) -> Result<(), Vec<String>>;
+}
+
+impl<F> Validate for F
+where
+    F: Fn(&Value) -> Result<(), Vec<String>> + Send + Sync,
+{
+    fn validate(&self, item: &Value) -> Result<(), Vec<String>> {
+        self(item)
+    }
+}
+
+/// Validates items against a JSON Schema.
+#[cfg(feature = "json-schema")]
+pub struct JsonSchema {
+    validator: jsonschema::Validator,
+}
+
+#[cfg(feature = "json-schema")]
+impl JsonSchema {
+    pub fn new(schema: &Value) -> Result<Self, crate::ScraperError> {
+        jsonschema::validator_for(schema)
+            .map(|validator| Self { validator })
+            .map_err(|e| crate::ScraperError::ParsingError(format!("Invalid JSON schema: {}", e)))
+    }
+}
+
+#[cfg(feature = "json-schema")]
+impl Validate for JsonSchema {
+    fn validate(&self, item: &Value) -> Result<(), Vec<String>> {
+        let errors: Vec<String> = self
+            .validator
+            .iter_errors(item)
+            .map(|e| format!("{}: {}", e.instance_path, e))
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Drops items that fail validation and counts them as invalid in stats, so
+/// selector drift after a layout change shows up instead of storing
+/// half-empty items. With an error storage, the rejected items and their
+/// errors are written to its `Error` category.
+pub struct ItemValidation {
+    validator: Arc<dyn Validate>,
+    error_storage: Option<StorageManager>,
+}
+
+impl ItemValidation {
+    pub fn new<V: Validate + 'static>(validator: V) -> Self {
+        Self {
+            validator: Arc::new(validator),
+            error_storage: None,
+        }
+    }
+
+    pub fn with_error_storage(mut self, storage: StorageManager) -> Self {
+        self.error_storage = Some(storage);
+        self
+    }
+
+    async fn store_invalid(
+        &self,
+        storage: &StorageManager,
+        item: &Value,
+        errors: &[String],
+        context: &ItemContext<'_>,
+    ) {
+        let (backend, config) = storage.get_storage(&StorageCategory::Error);
+        let url = &context.response.response.url;
+        let error_item = StorageItem {
+            url: url.clone(),
+            timestamp: Utc::now(),
+            data: json!({
+                "item": item,
+                "errors": errors,
+                "spider": context.spider,
+            }),
+            metadata: Some(json!({
+                "error_type": "validation",
+                "callback": context.response.callback.name(),
+            })),
+            id: format!("{}_invalid_{}", context.spider, Uuid::now_v7()),
+        };
+        if let Err(e) = backend
+            .store_serialized(error_item.into_serialized(), &**config)
+            .await
+        {
+            warn!("Failed to store invalid item from {}: {}", url, e);
+        }
+    }
+}
+
+#[async_trait]
+impl ItemPipeline for ItemValidation {
+    async fn process_item(&self, item: &Value, context: &ItemContext<'_>) -> PipelineResult {
+        let Err(errors) = self.validator.validate(item) else {
+            return PipelineResult::Keep;
+        };
+        warn!(
+            "Invalid item from {}: {}",
+            context.response.response.url,
+            errors.join("; ")
+        );
+        context.stats.record_invalid_items(1);
+        if let Some(storage) = &self.error_storage {
+            self.store_invalid(storage, item, &errors, context).await;
+        }
+        PipelineResult::Drop
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::pipeline::ItemPipelineChain;
+    use crate::core::spider::{ParsedData, SpiderResponse};
+    use crate::core::SpiderCallback;
+    use crate::http::HttpRequest;
+    use crate::storage::{create_storage, StorageType};
+    use crate::{HttpResponse, StatsTracker};
+
+    use url::Url;
+
+    fn response() -> SpiderResponse {
+        let url = Url::parse("https://example.com/p/1").unwrap();
+        SpiderResponse {
+            response: HttpResponse {
+                from_request: Box::new(HttpRequest::new(url.clone(), SpiderCallback::ParseItem, 0)),
+                ..HttpResponse::for_test(&url, "")
+            },
+            callback: SpiderCallback::ParseItem,
+            content: None,
+        }
+    }
+
+    fn require_price(item: &Value) -> Result<(), Vec<String>> {
+        match item["price"].as_f64() {
+            Some(_) => Ok(()),
+            None => Err(vec!["price is missing".to_string()]),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_items_are_counted_and_stored() {
+        let dir = std::env::temp_dir().join(format!("turboscraper_validation_{}", Uuid::now_v7()));
+        let storage = create_storage(StorageType::Disk {
+            path: dir.to_string_lossy().to_string(),
+        })
+        .await
+        .unwrap();
+        let errors =
+            StorageManager::new().register_storage(StorageCategory::Error, storage, "invalid");
+
+        let response = response();
+        let stats = StatsTracker::new();
+        let context = ItemContext {
+            spider: "shop",
+            response: &response,
+            stats: &stats,
+        };
+        let mut chain = ItemPipelineChain::new();
+        chain.push(ItemValidation::new(require_price).with_error_storage(errors));
+        let items = ParsedData::Items(vec![json!({"price": 3.5}), json!({"price": ""})]);
+        let (data, dropped) = chain.process(items, &context).await;
+
+        assert_eq!((data.item_count(), dropped), (1, 1));
+        assert_eq!(stats.get_stats().invalid_items, 1);
+        let stored = std::fs::read_dir(dir.join("invalid").join("example.com"))
+            .unwrap()
+            .count();
+        assert_eq!(stored, 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[cfg(feature = "json-schema")]
+    #[test]
+    fn test_json_schema_reports_every_error() {
+        let schema = JsonSchema::new(&json!({
+            "type": "object",
+            "required": ["title", "price"],
+            "properties": {"price": {"type": "number"}}
+        }))
+        .unwrap();
+
+        assert!(schema
+            .validate(&json!({"title": "Dune", "price": 9.5}))
+            .is_ok());
+        let errors = schema.validate(&json!({"price": "9.5"})).unwrap_err();
+        assert_eq!(errors.len(), 2);
+    }
+}
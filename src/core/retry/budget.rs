@@ -0,0 +1,90 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Caps the retries of a whole crawl across all categories, so a failing
+/// site can't turn it into an endless retry storm. Once the budget is
+/// spent, retryable failures are given up on as if their category had run
+/// out of retries.
+///
+/// Clones share their counters.
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    ratio: Option<f64>,
+    max_retries: Option<u64>,
+    min_retries: u64,
+    requests: Arc<AtomicU64>,
+    retries: Arc<AtomicU64>,
+}
+
+impl RetryBudget {
+    /// Allows at most `ratio` retries per request sent, e.g. `0.1` for 10%.
+    pub fn ratio(ratio: f64) -> Self {
+        Self::unlimited().with_ratio(ratio)
+    }
+
+    /// Allows at most `max_retries` retries in total.
+    pub fn max(max_retries: u64) -> Self {
+        Self::unlimited().with_max_retries(max_retries)
+    }
+
+    fn unlimited() -> Self {
+        Self {
+            ratio: None,
+            max_retries: None,
+            min_retries: 10,
+            requests: Arc::new(AtomicU64::new(0)),
+            retries: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn with_ratio(mut self, ratio: f64) -> Self {
+        self.ratio = Some(ratio.max(0.0));
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u64) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    /// Retries always allowed by the ratio, so the first failures of a
+    /// crawl can be retried. 10 by default; the absolute cap still applies.
+    pub fn with_min_retries(mut self, min_retries: u64) -> Self {
+        self.min_retries = min_retries;
+        self
+    }
+
+    /// Counts a request sent, retries included.
+    pub fn record_request(&self) {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Takes one retry from the budget, returning whether there was one left.
+    pub fn try_spend(&self) -> bool {
+        self.retries
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |retries| {
+                self.allows(retries + 1).then_some(retries + 1)
+            })
+            .is_ok()
+    }
+
+    fn allows(&self, retries: u64) -> bool {
+        if self.max_retries.is_some_and(|max| retries > max) {
+            return false;
+        }
+        let Some(ratio) = self.ratio else {
+            return true;
+        };
+        let requests = self.requests.load(Ordering::SeqCst);
+        retries <= self.min_retries || retries as f64 <= requests as f64 * ratio
+    }
+
+    /// Retries spent so far.
+    pub fn retries(&self) -> u64 {
+        self.retries.load(Ordering::SeqCst)
+    }
+
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::SeqCst)
+    }
+}
@@ -1,9 +1,16 @@
-use crate::ScraperError;
+use crate::http::Headers;
+use crate::{HttpRequest, HttpResponse, ScraperError};
 
+use chrono::Utc;
+
+use super::budget::RetryBudget;
+use super::transformer::RetryRequestTransformer;
 use super::types::*;
 use super::utils::*;
+use crate::core::SpiderCallback;
 use parking_lot::RwLock;
 use std::collections::HashMap;
+use std::ops::RangeInclusive;
 use std::sync::Arc;
 use std::time::Duration;
 use url::Url;
@@ -16,6 +23,8 @@ impl Default for CategoryConfig {
             max_delay: Duration::from_secs(60),
             backoff_policy: BackoffPolicy::Exponential { factor: 2.0 },
             conditions: Vec::new(),
+            matcher: None,
+            scope: RetryScope::Url,
         }
     }
 }
@@ -31,34 +40,184 @@ impl RetryState {
         Self {
             counts: HashMap::new(),
             total_retries: 0,
+            attempts: Vec::new(),
+            last_response: None,
+            last_delay: None,
+        }
+    }
+
+    fn record_attempt(&mut self, category: &RetryCategory, status: Option<u16>) {
+        *self.counts.entry(category.clone()).or_insert(0) += 1;
+        self.total_retries += 1;
+        self.attempts.push(RetryAttempt {
+            category: category.clone(),
+            timestamp: Utc::now(),
+            status,
+        });
+    }
+}
+
+/// Bytes of the response body kept in a [`ResponseSnapshot`].
+pub const RESPONSE_SNAPSHOT_LIMIT: usize = 2048;
+
+impl ResponseSnapshot {
+    fn new(status: u16, body: &str) -> Self {
+        let mut end = body.len().min(RESPONSE_SNAPSHOT_LIMIT);
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        Self {
+            status,
+            body: body[..end].to_string(),
+            timestamp: Utc::now(),
         }
     }
 }
 
 impl RetryConfig {
+    /// Matches the conditions on status and content only: the `Header` and
+    /// `MissingHeader` conditions never match without the response headers.
     pub fn should_retry_request(
         &self,
-        url: &Url,
+        request: &HttpRequest,
+        status: u16,
+        content: &str,
+    ) -> Option<(RetryCategory, Duration)> {
+        self.retry_for_response(request, status, None, &|| content)
+    }
+
+    /// Like [`should_retry_request`](Self::should_retry_request), also
+    /// matching the `Header` and `MissingHeader` conditions against `headers`.
+    pub fn should_retry_response(
+        &self,
+        request: &HttpRequest,
         status: u16,
+        headers: &Headers,
         content: &str,
     ) -> Option<(RetryCategory, Duration)> {
-        let url_str = url.to_string();
+        self.retry_for_response(request, status, Some(headers), &|| content)
+    }
+
+    /// Like [`should_retry_response`](Self::should_retry_response) for a
+    /// fetched `response`, whose body is only decoded for the `Content`
+    /// conditions and the snapshot of a retried response.
+    pub fn should_retry_http_response(
+        &self,
+        request: &HttpRequest,
+        response: &HttpResponse,
+    ) -> Option<(RetryCategory, Duration)> {
+        let (category, delay) = self.http_retry(request, response)?;
+        if !self.record_http_retry(request, response, &category, delay) {
+            return Some((category, Duration::ZERO));
+        }
+        Some((category, delay))
+    }
+
+    /// Category and delay of a retry of `request` after `response`, like
+    /// [`should_retry_http_response`](Self::should_retry_http_response) but
+    /// without counting it, so the caller can check the deadline and budget
+    /// first and [record](Self::record_http_retry) only the retries it makes.
+    /// The delay is zero for a category out of retries on the host.
+    pub fn http_retry(
+        &self,
+        request: &HttpRequest,
+        response: &HttpResponse,
+    ) -> Option<(RetryCategory, Duration)> {
+        let states = self.retry_states.read();
+        let state = states
+            .get(request.url.as_str())
+            .cloned()
+            .unwrap_or_default();
+        self.response_retry(
+            &state,
+            request,
+            response.status,
+            Some(&response.headers),
+            &|| response.body_text().unwrap_or_default(),
+        )
+    }
+
+    /// Counts a retry of `request` found by [`http_retry`](Self::http_retry)
+    /// and keeps a snapshot of `response`. `false`, counting nothing, if the
+    /// retries of the category ran out in the meantime.
+    pub fn record_http_retry(
+        &self,
+        request: &HttpRequest,
+        response: &HttpResponse,
+        category: &RetryCategory,
+        delay: Duration,
+    ) -> bool {
+        let Some(config) = self.categories.get(category) else {
+            return false;
+        };
+        let mut states = self.retry_states.write();
+        let state = states.entry(request.url.to_string()).or_default();
+        if !self.record_scoped_attempt(
+            state,
+            request,
+            config,
+            category,
+            Some(response.status),
+            delay,
+        ) {
+            return false;
+        }
+        state.last_response = Some(ResponseSnapshot::new(
+            response.status,
+            response.body_text().unwrap_or_default(),
+        ));
+        true
+    }
+
+    fn retry_for_response<'a>(
+        &self,
+        request: &HttpRequest,
+        status: u16,
+        headers: Option<&Headers>,
+        content: &dyn Fn() -> &'a str,
+    ) -> Option<(RetryCategory, Duration)> {
         let mut states = self.retry_states.write();
-        let state = states.entry(url_str).or_default();
+        let state = states.entry(request.url.to_string()).or_default();
+        let (category, delay) = self.response_retry(state, request, status, headers, content)?;
+        let config = &self.categories[&category];
+        if !self.record_scoped_attempt(state, request, config, &category, Some(status), delay) {
+            // Out of retries on the host: the request is given up on.
+            return Some((category, Duration::ZERO));
+        }
+        state.last_response = Some(ResponseSnapshot::new(status, content()));
+        Some((category, delay))
+    }
 
+    /// The first category whose request conditions match the response, with
+    /// the delay before its next retry. A category scoped to a host that is
+    /// out of retries there matches with a zero delay.
+    fn response_retry<'a>(
+        &self,
+        state: &RetryState,
+        request: &HttpRequest,
+        status: u16,
+        headers: Option<&Headers>,
+        content: &dyn Fn() -> &'a str,
+    ) -> Option<(RetryCategory, Duration)> {
         for (category, config) in &self.categories {
-            let current_retries = state.counts.get(category).copied().unwrap_or(0);
-            if current_retries >= config.max_retries {
+            if !config.applies_to(request) {
+                continue;
+            }
+            let (current_retries, last_delay) =
+                self.scoped_retries(state, request, config, category);
+            let exhausted = current_retries >= config.max_retries;
+            if exhausted && config.scope == RetryScope::Url {
                 continue;
             }
 
             for condition in &config.conditions {
                 if let RetryCondition::Request(req_condition) = condition {
-                    if retry_request_condition_should_apply(req_condition, status, content) {
-                        let new_count = current_retries + 1;
-                        state.counts.insert(category.clone(), new_count);
-                        state.total_retries += 1;
-                        let delay = calculate_delay(config, current_retries);
+                    if retry_request_condition_should_apply(req_condition, status, headers, content)
+                    {
+                        if exhausted {
+                            return Some((category.clone(), Duration::ZERO));
+                        }
+                        let delay = calculate_delay_after(config, current_retries, last_delay);
                         return Some((category.clone(), delay));
                     }
                 }
@@ -69,15 +228,35 @@ impl RetryConfig {
 
     pub fn should_retry_parse(
         &self,
-        url: &Url,
+        request: &HttpRequest,
         error: &ScraperError,
     ) -> Option<(RetryCategory, Duration)> {
-        let url_str = url.to_string();
-        let mut states = self.retry_states.write();
-        let state = states.entry(url_str).or_default();
+        let (category, delay) = self.parse_retry(request, error)?;
+        self.record_parse_retry(request, &category, delay)
+            .then_some((category, delay))
+    }
+
+    /// Category and delay of a retry of `request` after `error`, like
+    /// [`should_retry_parse`](Self::should_retry_parse) but without counting
+    /// it, so the caller can check the deadline and budget first and
+    /// [record](Self::record_parse_retry) only the retries it makes.
+    pub fn parse_retry(
+        &self,
+        request: &HttpRequest,
+        error: &ScraperError,
+    ) -> Option<(RetryCategory, Duration)> {
+        let states = self.retry_states.read();
+        let state = states
+            .get(request.url.as_str())
+            .cloned()
+            .unwrap_or_default();
 
         for (category, config) in &self.categories {
-            let current_retries = state.counts.get(category).copied().unwrap_or(0);
+            if !config.applies_to(request) {
+                continue;
+            }
+            let (current_retries, last_delay) =
+                self.scoped_retries(&state, request, config, category);
             if current_retries >= config.max_retries {
                 continue;
             }
@@ -85,10 +264,7 @@ impl RetryConfig {
             for condition in &config.conditions {
                 if let RetryCondition::Parse(parse_condition) = condition {
                     if retry_parse_condition_should_apply(parse_condition, error) {
-                        let new_count = current_retries + 1;
-                        state.counts.insert(category.clone(), new_count);
-                        state.total_retries += 1;
-                        let delay = calculate_delay(config, current_retries);
+                        let delay = calculate_delay_after(config, current_retries, last_delay);
                         return Some((category.clone(), delay));
                     }
                 }
@@ -97,6 +273,161 @@ impl RetryConfig {
         None
     }
 
+    /// Counts a parse retry of `request` found by
+    /// [`parse_retry`](Self::parse_retry). `false`, counting nothing, if the
+    /// retries of the category ran out in the meantime.
+    pub fn record_parse_retry(
+        &self,
+        request: &HttpRequest,
+        category: &RetryCategory,
+        delay: Duration,
+    ) -> bool {
+        let Some(config) = self.categories.get(category) else {
+            return false;
+        };
+        let mut states = self.retry_states.write();
+        let state = states.entry(request.url.to_string()).or_default();
+        self.record_scoped_attempt(state, request, config, category, None, delay)
+    }
+
+    /// Resets the retries of the categories scoped to a host on the host of
+    /// `request` after it got a response that needs no retry, so a host
+    /// that recovered gets its retries back.
+    pub fn record_success(&self, request: &HttpRequest) {
+        let mut host_states = self.host_states.write();
+        let Some(state) = host_states.get_mut(host(&request.url)) else {
+            return;
+        };
+        for (category, config) in &self.categories {
+            if config.scope == RetryScope::Host && config.applies_to(request) {
+                state.counts.remove(category);
+            }
+        }
+        if state.counts.is_empty() {
+            host_states.remove(host(&request.url));
+        }
+    }
+
+    /// Retries of `category` counted against `request` in the category's
+    /// scope, with the delay of the last one.
+    fn scoped_retries(
+        &self,
+        state: &RetryState,
+        request: &HttpRequest,
+        config: &CategoryConfig,
+        category: &RetryCategory,
+    ) -> (usize, Option<Duration>) {
+        let retries = |state: &RetryState| {
+            let count = state.counts.get(category).copied().unwrap_or(0);
+            (count, state.last_delay)
+        };
+        match config.scope {
+            RetryScope::Url => retries(state),
+            RetryScope::Host => self
+                .host_states
+                .read()
+                .get(host(&request.url))
+                .map_or((0, None), retries),
+        }
+    }
+
+    /// Counts a retry in the category's scope if it has retries left,
+    /// checking and counting under the same lock.
+    fn record_scoped_attempt(
+        &self,
+        state: &mut RetryState,
+        request: &HttpRequest,
+        config: &CategoryConfig,
+        category: &RetryCategory,
+        status: Option<u16>,
+        delay: Duration,
+    ) -> bool {
+        let retries = |state: &RetryState| state.counts.get(category).copied().unwrap_or(0);
+        if config.scope == RetryScope::Host {
+            let mut host_states = self.host_states.write();
+            let host_state = host_states
+                .entry(host(&request.url).to_string())
+                .or_default();
+            if retries(host_state) >= config.max_retries {
+                return false;
+            }
+            host_state.record_attempt(category, status);
+            host_state.last_delay = Some(delay);
+        } else if retries(state) >= config.max_retries {
+            return false;
+        }
+        state.record_attempt(category, status);
+        state.last_delay = Some(delay);
+        true
+    }
+
+    /// Retries of `category` for `url`, counted in the category's scope.
+    pub fn retry_count(&self, url: &Url, category: &RetryCategory) -> usize {
+        let scope = self.categories.get(category).map(|config| config.scope);
+        let state = match scope.unwrap_or_default() {
+            RetryScope::Url => self.get_retry_state(url),
+            RetryScope::Host => self.get_host_retry_state(host(url)),
+        };
+        state.counts.get(category).copied().unwrap_or(0)
+    }
+
+    /// A category scoped to a host that has used up its retries on the
+    /// host of `request`, with the host's retry state.
+    pub fn exhausted_on_host(&self, request: &HttpRequest) -> Option<(RetryCategory, RetryState)> {
+        let host_states = self.host_states.read();
+        let state = host_states.get(host(&request.url))?;
+        self.categories
+            .iter()
+            .filter(|(_, config)| config.scope == RetryScope::Host && config.applies_to(request))
+            .find(|(category, config)| {
+                state.counts.get(*category).copied().unwrap_or(0) >= config.max_retries
+            })
+            .map(|(category, _)| (category.clone(), state.clone()))
+    }
+
+    /// Applies `transformer` to requests before every scraper-level retry.
+    pub fn with_request_transformer<T: RetryRequestTransformer + 'static>(
+        mut self,
+        transformer: T,
+    ) -> Self {
+        self.request_transformer = Some(Arc::new(transformer));
+        self
+    }
+
+    pub fn with_retry_deadline(mut self, deadline: Duration) -> Self {
+        self.retry_deadline = Some(deadline);
+        self
+    }
+
+    pub fn with_retry_budget(mut self, budget: RetryBudget) -> Self {
+        self.retry_budget = Some(budget);
+        self
+    }
+
+    /// Takes a retry from the retry budget. `false` once it is spent.
+    pub fn spend_retry_budget(&self) -> bool {
+        self.retry_budget
+            .as_ref()
+            .is_none_or(|budget| budget.try_spend())
+    }
+
+    /// Whether waiting `next_delay` before retrying `url` would take it past
+    /// the retry deadline, counted from its first retry.
+    pub fn deadline_exceeded(&self, url: &Url, next_delay: Duration) -> bool {
+        let Some(deadline) = self.retry_deadline else {
+            return false;
+        };
+        let states = self.retry_states.read();
+        let Some(first) = states
+            .get(&url.to_string())
+            .and_then(|state| state.attempts.first())
+        else {
+            return false;
+        };
+        let elapsed = (Utc::now() - first.timestamp).to_std().unwrap_or_default();
+        elapsed + next_delay > deadline
+    }
+
     pub fn get_retry_state(&self, url: &Url) -> RetryState {
         self.retry_states
             .read()
@@ -104,6 +435,19 @@ impl RetryConfig {
             .cloned()
             .unwrap_or_else(RetryState::new)
     }
+
+    /// Retries of the categories scoped to a host on `host`.
+    pub fn get_host_retry_state(&self, host: &str) -> RetryState {
+        self.host_states
+            .read()
+            .get(host)
+            .cloned()
+            .unwrap_or_else(RetryState::new)
+    }
+}
+
+fn host(url: &Url) -> &str {
+    url.host_str().unwrap_or_default()
 }
 
 impl Default for RetryConfig {
@@ -111,6 +455,10 @@ impl Default for RetryConfig {
         Self {
             categories: Default::default(),
             retry_states: Arc::new(RwLock::new(HashMap::new())),
+            host_states: Arc::new(RwLock::new(HashMap::new())),
+            request_transformer: None,
+            retry_deadline: None,
+            retry_budget: None,
         }
     }
 }
@@ -119,4 +467,44 @@ impl CategoryConfig {
     pub fn calculate_delay(&self, attempt: usize) -> Duration {
         calculate_delay(self, attempt)
     }
+
+    /// Restrict the category to requests accepted by `matcher`.
+    pub fn with_matcher(mut self, matcher: RetryMatcher) -> Self {
+        self.matcher = Some(matcher);
+        self
+    }
+
+    pub fn with_scope(mut self, scope: RetryScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    pub fn applies_to(&self, request: &HttpRequest) -> bool {
+        self.matcher
+            .as_ref()
+            .is_none_or(|matcher| matcher.matches(request))
+    }
+}
+
+impl RetryMatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_depths(mut self, depths: RangeInclusive<usize>) -> Self {
+        self.depths = Some(depths);
+        self
+    }
+
+    pub fn with_callback(mut self, callback: SpiderCallback) -> Self {
+        self.callbacks.push(callback);
+        self
+    }
+
+    pub fn matches(&self, request: &HttpRequest) -> bool {
+        self.depths
+            .as_ref()
+            .is_none_or(|depths| depths.contains(&request.depth))
+            && (self.callbacks.is_empty() || self.callbacks.contains(&request.callback))
+    }
 }
@@ -3,16 +3,10 @@ use crate::core::spider::SpiderConfig;
 #[cfg(test)]
 use crate::http::HttpRequest;
 #[cfg(test)]
-use crate::http::ResponseType;
-#[cfg(test)]
 use crate::{HttpResponse, Scraper, ScraperResult, StatsTracker};
 #[cfg(test)]
 use async_trait::async_trait;
 #[cfg(test)]
-use chrono::Utc;
-#[cfg(test)]
-use std::collections::HashMap;
-#[cfg(test)]
 use std::sync::Arc;
 #[cfg(test)]
 use std::sync::RwLock;
@@ -64,17 +58,9 @@ impl Scraper for MockScraper {
         }
 
         Ok(HttpResponse {
-            url: request.url.clone(),
             status: response.status,
-            headers: HashMap::new(),
-            raw_body: response.body.as_bytes().to_vec(),
-            decoded_body: response.body.clone(),
-            timestamp: Utc::now(),
-            retry_count: 0,
-            retry_history: HashMap::new(),
-            meta: None,
-            response_type: ResponseType::Html,
-            from_request: Box::new(request),
+            from_request: Box::new(request.clone()),
+            ..HttpResponse::for_test(&request.url, &response.body)
         })
     }
 
@@ -1,8 +1,13 @@
+mod budget;
 mod r#impl;
 pub(crate) mod mock_scraper;
+mod transformer;
 mod types;
 mod utils;
 
+pub use budget::RetryBudget;
+pub use r#impl::RESPONSE_SNAPSHOT_LIMIT;
+pub use transformer::RetryRequestTransformer;
 pub use types::*;
 
 #[cfg(test)]
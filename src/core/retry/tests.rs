@@ -1,6 +1,6 @@
 use crate::core::retry::{
     BackoffPolicy, CategoryConfig, ContentRetryCondition, RequestRetryCondition, RetryCategory,
-    RetryCondition, RetryConfig,
+    RetryCondition, RetryConfig, RetryScope,
 };
 use crate::core::spider::SpiderConfig;
 use crate::core::SpiderCallback;
@@ -39,6 +39,8 @@ async fn test_rate_limit_retry() {
                 429,
             ))],
             backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            scope: RetryScope::Url,
         },
     );
 
@@ -56,7 +58,7 @@ async fn test_rate_limit_retry() {
         .unwrap();
 
     assert_eq!(response.status, 200);
-    assert_eq!(response.decoded_body, "Success");
+    assert_eq!(response.body_text().unwrap(), "Success");
     assert_eq!(response.retry_count, 1);
     assert_eq!(
         response.retry_history.get(&RetryCategory::RateLimit),
@@ -93,6 +95,8 @@ async fn test_bot_detection_retry() {
                 },
             ))],
             backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            scope: RetryScope::Url,
         },
     );
 
@@ -110,7 +114,7 @@ async fn test_bot_detection_retry() {
         .unwrap();
 
     assert_eq!(response.status, 200);
-    assert_eq!(response.decoded_body, "Welcome user");
+    assert_eq!(response.body_text().unwrap(), "Welcome user");
     assert_eq!(response.retry_count, 1);
     assert_eq!(
         response.retry_history.get(&RetryCategory::BotDetection),
@@ -149,6 +153,8 @@ async fn test_exponential_backoff() {
                 429,
             ))],
             backoff_policy: BackoffPolicy::Exponential { factor: 2.0 },
+            matcher: None,
+            scope: RetryScope::Url,
         },
     );
 
@@ -196,6 +202,8 @@ async fn test_max_retries_exceeded() {
                 429,
             ))],
             backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            scope: RetryScope::Url,
         },
     );
 
@@ -213,18 +221,28 @@ async fn test_max_retries_exceeded() {
         .await;
 
     assert!(response.is_err());
-    let (error, request) = response.err().unwrap();
+    let (error, request) = response.err().unwrap().into_parts();
+    let request = request.unwrap();
 
     match error {
         ScraperError::MaxRetriesReached {
             category,
             retry_count,
             url: error_url,
+            history,
         } => {
             assert_eq!(category, RetryCategory::RateLimit);
             assert_eq!(retry_count, 2);
             assert_eq!(error_url, Box::new(url.clone()));
             assert_eq!(request.url, url);
+            assert_eq!(history.attempts.len(), 2);
+            assert!(history
+                .attempts
+                .iter()
+                .all(|a| a.category == RetryCategory::RateLimit && a.status == Some(429)));
+            let last = history.last_response.unwrap();
+            assert_eq!(last.status, 429);
+            assert_eq!(last.body, "Rate limited");
         }
         _ => panic!("Expected MaxRetriesReached error"),
     }
@@ -261,6 +279,8 @@ async fn test_multiple_retry_categories() {
                 429,
             ))],
             backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            scope: RetryScope::Url,
         },
     );
     retry_config.categories.insert(
@@ -276,6 +296,8 @@ async fn test_multiple_retry_categories() {
                 },
             ))],
             backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            scope: RetryScope::Url,
         },
     );
 
@@ -293,7 +315,7 @@ async fn test_multiple_retry_categories() {
         .unwrap();
 
     assert_eq!(response.status, 200);
-    assert_eq!(response.decoded_body, "Success");
+    assert_eq!(response.body_text().unwrap(), "Success");
     assert_eq!(response.retry_count, 2);
     assert_eq!(
         response.retry_history.get(&RetryCategory::RateLimit),
@@ -334,6 +356,8 @@ async fn test_regex_content_retry() {
                 },
             ))],
             backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            scope: RetryScope::Url,
         },
     );
 
@@ -351,7 +375,7 @@ async fn test_regex_content_retry() {
         .unwrap();
 
     assert_eq!(response.status, 200);
-    assert_eq!(response.decoded_body, "Success");
+    assert_eq!(response.body_text().unwrap(), "Success");
     assert_eq!(response.retry_count, 1);
     assert_eq!(
         response.retry_history.get(&RetryCategory::Blacklisted),
@@ -388,6 +412,8 @@ async fn test_custom_category() {
                 },
             ))],
             backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            scope: RetryScope::Url,
         },
     );
 
@@ -405,7 +431,7 @@ async fn test_custom_category() {
         .unwrap();
 
     assert_eq!(response.status, 200);
-    assert_eq!(response.decoded_body, "Success");
+    assert_eq!(response.body_text().unwrap(), "Success");
     assert_eq!(response.retry_count, 1);
     assert_eq!(
         response
@@ -441,3 +467,721 @@ async fn test_no_matching_retry_condition() {
     assert_eq!(response.retry_count, 0);
     assert!(response.retry_history.is_empty());
 }
+
+#[tokio::test]
+async fn test_retry_request_transformer() {
+    let responses = vec![
+        MockResponse {
+            status: 429,
+            body: "Rate limited".to_string(),
+            delay: None,
+        },
+        MockResponse {
+            status: 200,
+            body: "Success".to_string(),
+            delay: None,
+        },
+    ];
+
+    let mut retry_config = RetryConfig::default().with_request_transformer(
+        |request: &mut HttpRequest, category: &RetryCategory, attempt: usize| {
+            assert_eq!(*category, RetryCategory::RateLimit);
+            request
+                .headers
+                .insert("X-Attempt".to_string(), attempt.to_string());
+            request.proxy = Some("http://backup-proxy:8080".to_string());
+        },
+    );
+    retry_config.categories.insert(
+        RetryCategory::RateLimit,
+        CategoryConfig {
+            max_retries: 3,
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_secs(1),
+            conditions: vec![RetryCondition::Request(RequestRetryCondition::StatusCode(
+                429,
+            ))],
+            backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            scope: RetryScope::Url,
+        },
+    );
+
+    let scraper = MockScraper::new(responses);
+    let url = Url::parse("https://example.com").unwrap();
+    let response = scraper
+        .fetch(
+            HttpRequest::new(url, SpiderCallback::Bootstrap, 0),
+            &SpiderConfig {
+                retry_config,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.from_request.headers.get("X-Attempt").unwrap(), "1");
+    assert_eq!(
+        response.from_request.proxy.as_deref(),
+        Some("http://backup-proxy:8080")
+    );
+}
+
+#[tokio::test]
+async fn test_retry_deadline_gives_up_before_max_retries() {
+    let responses = vec![MockResponse {
+        status: 429,
+        body: "Rate limited".to_string(),
+        delay: None,
+    }];
+
+    let mut retry_config = RetryConfig::default().with_retry_deadline(Duration::from_millis(250));
+    retry_config.categories.insert(
+        RetryCategory::RateLimit,
+        CategoryConfig {
+            max_retries: 100,
+            initial_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(10),
+            conditions: vec![RetryCondition::Request(RequestRetryCondition::StatusCode(
+                429,
+            ))],
+            backoff_policy: BackoffPolicy::Exponential { factor: 2.0 },
+            matcher: None,
+            scope: RetryScope::Url,
+        },
+    );
+
+    let scraper = MockScraper::new(responses);
+    let url = Url::parse("https://example.com").unwrap();
+    let result = scraper
+        .fetch(
+            HttpRequest::new(url, SpiderCallback::Bootstrap, 0),
+            &SpiderConfig {
+                retry_config,
+                ..Default::default()
+            },
+        )
+        .await;
+
+    match result.as_ref().map_err(ScraperError::kind) {
+        Err(ScraperError::MaxRetriesReached { retry_count, .. }) => {
+            // 50ms + 100ms fit in the deadline, the 200ms backoff does not
+            // and is not counted as a retry.
+            assert_eq!(*retry_count, 2);
+        }
+        _ => panic!("Expected the retry deadline to stop the request"),
+    }
+}
+
+#[tokio::test]
+async fn test_retry_budget_is_shared_across_urls() {
+    use crate::core::retry::RetryBudget;
+
+    let responses = vec![MockResponse {
+        status: 503,
+        body: "Service Unavailable".to_string(),
+        delay: None,
+    }];
+    let budget = RetryBudget::max(3);
+    let mut retry_config = RetryConfig::default().with_retry_budget(budget.clone());
+    retry_config.categories.insert(
+        RetryCategory::ServerError,
+        CategoryConfig {
+            max_retries: 10,
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(10),
+            conditions: vec![RetryCondition::Request(RequestRetryCondition::StatusCode(
+                503,
+            ))],
+            backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            scope: RetryScope::Url,
+        },
+    );
+    let config = SpiderConfig {
+        retry_config,
+        ..Default::default()
+    };
+
+    let scraper = MockScraper::new(responses);
+    let mut given_up_after = Vec::new();
+    for path in ["a", "b"] {
+        let url = Url::parse("https://example.com")
+            .unwrap()
+            .join(path)
+            .unwrap();
+        let result = scraper
+            .fetch(HttpRequest::new(url, SpiderCallback::Bootstrap, 0), &config)
+            .await;
+        match result.as_ref().map_err(ScraperError::kind) {
+            Err(ScraperError::MaxRetriesReached { history, .. }) => {
+                given_up_after.push(history.total_retries)
+            }
+            _ => panic!("Expected the retry budget to stop the request"),
+        }
+    }
+    // Only the retries the budget allowed are counted.
+    assert_eq!(given_up_after, [3, 0]);
+    assert_eq!(budget.retries(), 3);
+    assert_eq!(budget.requests(), 5);
+}
+
+#[test]
+fn test_retry_budget_ratio() {
+    use crate::core::retry::RetryBudget;
+
+    let budget = RetryBudget::ratio(0.1).with_min_retries(2);
+    assert!(budget.try_spend());
+    assert!(budget.try_spend());
+    assert!(!budget.try_spend());
+
+    for _ in 0..30 {
+        budget.record_request();
+    }
+    assert!(budget.try_spend());
+    assert!(!budget.try_spend());
+
+    let capped = RetryBudget::ratio(1.0).with_max_retries(1);
+    assert!(capped.try_spend());
+    assert!(!capped.try_spend());
+}
+
+#[test]
+fn test_category_matcher_by_depth_and_callback() {
+    use crate::core::retry::RetryMatcher;
+
+    let mut retry_config = RetryConfig::default();
+    retry_config.categories.insert(
+        RetryCategory::ServerError,
+        CategoryConfig {
+            conditions: vec![RetryCondition::Request(RequestRetryCondition::StatusCode(
+                503,
+            ))],
+            ..Default::default()
+        }
+        .with_matcher(
+            RetryMatcher::new()
+                .with_depths(0..=1)
+                .with_callback(SpiderCallback::ParseItem),
+        ),
+    );
+
+    let request = |path: &str, callback: SpiderCallback, depth: usize| {
+        HttpRequest::new(
+            Url::parse(&format!("https://example.com/{}", path)).unwrap(),
+            callback,
+            depth,
+        )
+    };
+
+    let detail = request("item", SpiderCallback::ParseItem, 1);
+    assert!(retry_config
+        .should_retry_request(&detail, 503, "")
+        .is_some());
+
+    let deep_detail = request("deep-item", SpiderCallback::ParseItem, 4);
+    assert!(retry_config
+        .should_retry_request(&deep_detail, 503, "")
+        .is_none());
+
+    let pagination = request("page/2", SpiderCallback::ParsePagination, 1);
+    assert!(retry_config
+        .should_retry_request(&pagination, 503, "")
+        .is_none());
+}
+
+#[test]
+fn test_status_range_and_class_conditions() {
+    use crate::core::retry::StatusClass;
+
+    let mut retry_config = RetryConfig::default();
+    retry_config.categories.insert(
+        RetryCategory::ServerError,
+        CategoryConfig {
+            max_retries: 10,
+            conditions: vec![RetryCondition::Request(RequestRetryCondition::StatusClass(
+                StatusClass::ServerError,
+            ))],
+            ..Default::default()
+        },
+    );
+    retry_config.categories.insert(
+        RetryCategory::Authentication,
+        CategoryConfig {
+            max_retries: 10,
+            conditions: vec![RetryCondition::Request(RequestRetryCondition::StatusRange(
+                401..=403,
+            ))],
+            ..Default::default()
+        },
+    );
+
+    let request = HttpRequest::new(
+        Url::parse("https://example.com").unwrap(),
+        SpiderCallback::Bootstrap,
+        0,
+    );
+    let category = |status| {
+        retry_config
+            .should_retry_request(&request, status, "")
+            .map(|(category, _)| category)
+    };
+
+    assert_eq!(category(500), Some(RetryCategory::ServerError));
+    assert_eq!(category(599), Some(RetryCategory::ServerError));
+    assert_eq!(category(403), Some(RetryCategory::Authentication));
+    assert_eq!(category(404), None);
+    assert_eq!(category(200), None);
+}
+
+#[test]
+fn test_jittered_backoff_stays_within_bounds() {
+    use crate::core::retry::Jitter;
+
+    let config = |jitter| CategoryConfig {
+        initial_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(2),
+        backoff_policy: BackoffPolicy::ExponentialJitter {
+            factor: 3.0,
+            jitter,
+        },
+        ..Default::default()
+    };
+
+    let full = config(Jitter::Full);
+    let delays: Vec<Duration> = (0..50).map(|_| full.calculate_delay(2)).collect();
+    assert!(delays.iter().all(|d| *d <= Duration::from_millis(900)));
+    assert!(delays.iter().any(|d| *d != delays[0]));
+    assert!((0..50).all(|_| full.calculate_delay(10) <= Duration::from_secs(2)));
+
+    let mut retry_config = RetryConfig::default();
+    retry_config.categories.insert(
+        RetryCategory::ServerError,
+        CategoryConfig {
+            max_retries: 20,
+            conditions: vec![RetryCondition::Request(RequestRetryCondition::StatusCode(
+                503,
+            ))],
+            ..config(Jitter::Decorrelated)
+        },
+    );
+    let request = HttpRequest::new(
+        Url::parse("https://example.com").unwrap(),
+        SpiderCallback::Bootstrap,
+        0,
+    );
+    let mut previous = Duration::from_millis(100);
+    for _ in 0..20 {
+        let (_, delay) = retry_config
+            .should_retry_request(&request, 503, "")
+            .unwrap();
+        assert!(delay >= Duration::from_millis(100));
+        assert!(delay <= (previous * 3).min(Duration::from_secs(2)));
+        previous = delay;
+    }
+}
+
+#[test]
+fn test_header_conditions() {
+    use crate::http::Headers;
+
+    let mut retry_config = RetryConfig::default();
+    retry_config.categories.insert(
+        RetryCategory::BotDetection,
+        CategoryConfig {
+            max_retries: 10,
+            conditions: vec![
+                RetryCondition::Request(RequestRetryCondition::Header {
+                    name: "cf-mitigated".to_string(),
+                    pattern: Some(ContentRetryCondition {
+                        pattern: "challenge".to_string(),
+                        is_regex: false,
+                    }),
+                }),
+                RetryCondition::Request(RequestRetryCondition::Header {
+                    name: "x-amzn-waf-action".to_string(),
+                    pattern: None,
+                }),
+                RetryCondition::Request(RequestRetryCondition::MissingHeader(
+                    "content-type".to_string(),
+                )),
+            ],
+            ..Default::default()
+        },
+    );
+    let request = HttpRequest::new(
+        Url::parse("https://example.com").unwrap(),
+        SpiderCallback::Bootstrap,
+        0,
+    );
+    let retried = |headers: &[(&str, &str)]| {
+        let mut headers: Headers = headers.iter().copied().collect();
+        headers.append("Content-Type", "text/html");
+        retry_config
+            .should_retry_response(&request, 200, &headers, "")
+            .is_some()
+    };
+
+    assert!(retried(&[("CF-Mitigated", "Challenge")]));
+    assert!(!retried(&[("cf-mitigated", "none")]));
+    assert!(retried(&[("x-amzn-waf-action", "captcha")]));
+    assert!(!retried(&[]));
+    assert!(retry_config
+        .should_retry_response(&request, 200, &Headers::new(), "")
+        .is_some());
+    // Without the headers, header conditions don't match.
+    assert!(retry_config
+        .should_retry_request(&request, 200, "")
+        .is_none());
+}
+
+#[test]
+fn test_body_is_decoded_only_for_content_conditions() {
+    use crate::HttpResponse;
+
+    let category = |condition| CategoryConfig {
+        max_retries: 10,
+        conditions: vec![RetryCondition::Request(condition)],
+        ..Default::default()
+    };
+    let mut retry_config = RetryConfig::default();
+    retry_config.categories.insert(
+        RetryCategory::ServerError,
+        category(RequestRetryCondition::StatusCode(503)),
+    );
+    let url = Url::parse("https://example.com").unwrap();
+    let request = HttpRequest::new(url.clone(), SpiderCallback::Bootstrap, 0);
+
+    let response = HttpResponse::for_test(&url, "Access denied");
+    assert!(retry_config
+        .should_retry_http_response(&request, &response)
+        .is_none());
+    assert!(response.decoded_body.get().is_none());
+
+    retry_config.categories.insert(
+        RetryCategory::BotDetection,
+        category(RequestRetryCondition::Content(ContentRetryCondition {
+            pattern: "Access denied".to_string(),
+            is_regex: false,
+        })),
+    );
+    let (category, _) = retry_config
+        .should_retry_http_response(&request, &response)
+        .unwrap();
+    assert_eq!(category, RetryCategory::BotDetection);
+    assert!(response.decoded_body.get().is_some());
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_stops_retries() {
+    let responses = vec![MockResponse {
+        status: 503,
+        body: "Service Unavailable".to_string(),
+        delay: None,
+    }];
+
+    let mut retry_config = RetryConfig::default();
+    retry_config.categories.insert(
+        RetryCategory::ServerError,
+        CategoryConfig {
+            max_retries: 10,
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(10),
+            conditions: vec![RetryCondition::Request(RequestRetryCondition::StatusCode(
+                503,
+            ))],
+            backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            scope: RetryScope::Url,
+        },
+    );
+
+    let scraper = MockScraper::new(responses);
+    let url = Url::parse("https://example.com").unwrap();
+    let config = SpiderConfig {
+        retry_config,
+        ..Default::default()
+    }
+    .with_circuit_breaker(3, Duration::from_secs(60));
+    let result = scraper
+        .fetch(
+            HttpRequest::new(url.clone(), SpiderCallback::Bootstrap, 0),
+            &config,
+        )
+        .await;
+
+    assert!(matches!(
+        result.as_ref().map_err(ScraperError::kind),
+        Err(ScraperError::CircuitOpen { host, .. }) if host == "example.com"
+    ));
+    // The circuit opens on the third failure, well before max_retries.
+    assert_eq!(config.retry_config.get_retry_state(&url).total_retries, 3);
+}
+
+#[test]
+fn test_spider_errors_follow_their_retry_hint() {
+    use crate::core::retry::{ParseRetryCondition, ParseRetryType};
+    use crate::core::{RetryHint, SpiderError};
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("price missing on {0}")]
+    struct MissingPrice(String);
+
+    fn check_price(page: &str) -> Result<(), SpiderError> {
+        Err(MissingPrice(page.to_string()))?
+    }
+
+    let mut retry_config = RetryConfig::default();
+    retry_config.categories.insert(
+        RetryCategory::Custom("incomplete_page".to_string()),
+        CategoryConfig {
+            conditions: vec![RetryCondition::Parse(
+                ParseRetryCondition::ErrorWhileParsing(ParseRetryType::FetchNew),
+            )],
+            ..Default::default()
+        },
+    );
+    let request = |path: &str| {
+        HttpRequest::new(
+            Url::parse(&format!("https://example.com/{}", path)).unwrap(),
+            SpiderCallback::ParseItem,
+            1,
+        )
+    };
+
+    let transient = ScraperError::from(check_price("item/1").unwrap_err());
+    assert_eq!(
+        transient.to_string(),
+        "Spider error: price missing on item/1"
+    );
+    assert!(retry_config
+        .should_retry_parse(&request("item/1"), &transient)
+        .is_some());
+
+    let error = check_price("item/2").unwrap_err();
+    assert!(error.downcast_ref::<MissingPrice>().is_some());
+    let permanent = ScraperError::from(error.with_hint(RetryHint::Permanent));
+    assert!(retry_config
+        .should_retry_parse(&request("item/2"), &permanent)
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_retry_after_header_overrides_backoff() {
+    use crate::scrapers::http_scraper::HttpScraper;
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    for (route, retry_after) in [("/capped", "120"), ("/soon", "0")] {
+        Mock::given(path(route))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", retry_after))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+    }
+    Mock::given(path("/capped"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    Mock::given(path("/soon"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let mut retry_config = RetryConfig::default();
+    retry_config.categories.insert(
+        RetryCategory::RateLimit,
+        CategoryConfig {
+            max_retries: 3,
+            initial_delay: Duration::from_secs(5),
+            max_delay: Duration::from_millis(300),
+            conditions: vec![RetryCondition::Request(RequestRetryCondition::StatusCode(
+                429,
+            ))],
+            backoff_policy: BackoffPolicy::Constant,
+            matcher: None,
+            scope: RetryScope::Url,
+        },
+    );
+    let config = SpiderConfig {
+        retry_config,
+        ..Default::default()
+    };
+    let scraper = HttpScraper::new().unwrap();
+    let url = |route: &str| Url::parse(&server.uri()).unwrap().join(route).unwrap();
+    let fetch = |route: &str| {
+        scraper.fetch(
+            HttpRequest::new(url(route), SpiderCallback::Bootstrap, 0),
+            &config,
+        )
+    };
+    let last_delay = |route: &str| config.retry_config.get_retry_state(&url(route)).last_delay;
+
+    // Retry-After wins over the configured delay, capped by `max_delay`.
+    let start = std::time::Instant::now();
+    let response = fetch("/capped").await.unwrap();
+    assert_eq!(response.status, 200);
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(300), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(2), "{:?}", elapsed);
+    // The delay waited is the one later backoffs build on.
+    assert_eq!(last_delay("/capped"), Some(Duration::from_millis(300)));
+
+    let start = std::time::Instant::now();
+    assert_eq!(fetch("/soon").await.unwrap().retry_count, 1);
+    assert!(start.elapsed() < Duration::from_millis(300));
+    assert_eq!(last_delay("/soon"), Some(Duration::ZERO));
+}
+
+#[tokio::test]
+async fn test_host_scope_shares_retries_across_urls() {
+    let mut retry_config = RetryConfig::default();
+    retry_config.categories.insert(
+        RetryCategory::Blacklisted,
+        CategoryConfig {
+            max_retries: 2,
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(100),
+            conditions: vec![RetryCondition::Request(RequestRetryCondition::StatusCode(
+                403,
+            ))],
+            ..Default::default()
+        }
+        .with_scope(RetryScope::Host),
+    );
+    let request =
+        |url: &str| HttpRequest::new(Url::parse(url).unwrap(), SpiderCallback::Bootstrap, 0);
+    let first = request("https://example.com/a");
+    let second = request("https://example.com/b");
+    let elsewhere = request("https://other.example.org/a");
+
+    // The second URL picks up the backoff where the first one left it.
+    assert_eq!(
+        retry_config.should_retry_request(&first, 403, ""),
+        Some((RetryCategory::Blacklisted, Duration::from_millis(10)))
+    );
+    assert_eq!(
+        retry_config.should_retry_request(&second, 403, ""),
+        Some((RetryCategory::Blacklisted, Duration::from_millis(20)))
+    );
+    assert_eq!(
+        retry_config.retry_count(&second.url, &RetryCategory::Blacklisted),
+        2
+    );
+    assert!(retry_config.exhausted_on_host(&first).is_some());
+    assert!(retry_config.exhausted_on_host(&elsewhere).is_none());
+
+    // Other requests to the host are given up on without being fetched.
+    let scraper = MockScraper::new(vec![MockResponse {
+        status: 200,
+        body: "ok".to_string(),
+        delay: None,
+    }]);
+    let config = SpiderConfig {
+        retry_config,
+        ..Default::default()
+    };
+    let third = request("https://example.com/c");
+    let result = scraper.fetch(third, &config).await;
+    assert!(matches!(
+        result.as_ref().map_err(ScraperError::kind),
+        Err(ScraperError::MaxRetriesReached {
+            category: RetryCategory::Blacklisted,
+            retry_count: 2,
+            ..
+        })
+    ));
+    assert!(scraper.fetch(elsewhere, &config).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_host_scope_resets_on_success() {
+    let mut retry_config = RetryConfig::default();
+    retry_config.categories.insert(
+        RetryCategory::Blacklisted,
+        CategoryConfig {
+            max_retries: 2,
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+            conditions: vec![RetryCondition::Request(RequestRetryCondition::StatusCode(
+                403,
+            ))],
+            ..Default::default()
+        }
+        .with_scope(RetryScope::Host),
+    );
+    let request =
+        |url: &str| HttpRequest::new(Url::parse(url).unwrap(), SpiderCallback::Bootstrap, 0);
+
+    let scraper = MockScraper::new(vec![
+        MockResponse {
+            status: 403,
+            body: "blocked".to_string(),
+            delay: None,
+        },
+        MockResponse {
+            status: 200,
+            body: "ok".to_string(),
+            delay: None,
+        },
+    ]);
+    let config = SpiderConfig {
+        retry_config,
+        ..Default::default()
+    };
+    let response = scraper
+        .fetch(request("https://example.com/a"), &config)
+        .await;
+    assert_eq!(response.unwrap().status, 200);
+
+    // The host recovered, so it has its retries back.
+    let next = request("https://example.com/b");
+    assert_eq!(
+        config
+            .retry_config
+            .retry_count(&next.url, &RetryCategory::Blacklisted),
+        0
+    );
+    assert!(config.retry_config.exhausted_on_host(&next).is_none());
+}
+
+#[test]
+fn test_host_scope_never_overshoots_under_concurrency() {
+    use crate::core::retry::{ParseRetryCondition, ParseRetryType};
+
+    let mut retry_config = RetryConfig::default();
+    retry_config.categories.insert(
+        RetryCategory::ParseError,
+        CategoryConfig {
+            max_retries: 3,
+            conditions: vec![RetryCondition::Parse(
+                ParseRetryCondition::ErrorWhileParsing(ParseRetryType::FetchNew),
+            )],
+            ..Default::default()
+        }
+        .with_scope(RetryScope::Host),
+    );
+
+    let taken = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..16)
+            .map(|i| {
+                let retry_config = &retry_config;
+                scope.spawn(move || {
+                    let url = Url::parse(&format!("https://example.com/{}", i)).unwrap();
+                    let request = HttpRequest::new(url, SpiderCallback::ParseItem, 1);
+                    let error = ScraperError::ParsingError("broken".to_string());
+                    retry_config.should_retry_parse(&request, &error).is_some()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap())
+            .filter(|taken| *taken)
+            .count()
+    });
+    assert_eq!(taken, 3);
+}
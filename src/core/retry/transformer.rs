@@ -0,0 +1,28 @@
+use super::types::RetryCategory;
+use crate::HttpRequest;
+use std::fmt;
+
+/// Mutates a request before the scraper re-fetches it after a retryable
+/// response, e.g. to rotate the proxy, user agent or session after a 429.
+///
+/// Retry state stays keyed by the original URL, so limits still apply if the
+/// transformer rewrites it.
+pub trait RetryRequestTransformer: Send + Sync {
+    /// `attempt` is the retry number for `category`, starting at 1.
+    fn transform(&self, request: &mut HttpRequest, category: &RetryCategory, attempt: usize);
+}
+
+impl<F> RetryRequestTransformer for F
+where
+    F: Fn(&mut HttpRequest, &RetryCategory, usize) + Send + Sync,
+{
+    fn transform(&self, request: &mut HttpRequest, category: &RetryCategory, attempt: usize) {
+        self(request, category, attempt)
+    }
+}
+
+impl fmt::Debug for dyn RetryRequestTransformer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RetryRequestTransformer")
+    }
+}
@@ -1,6 +1,11 @@
+use super::budget::RetryBudget;
+use super::transformer::RetryRequestTransformer;
+use crate::core::SpiderCallback;
 use crate::storage::base::StorageError;
+use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
 use std::collections::HashMap;
+use std::ops::RangeInclusive;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -13,7 +18,47 @@ pub struct ContentRetryCondition {
 #[derive(Debug, Clone)]
 pub enum RequestRetryCondition {
     StatusCode(u16),
+    /// Any status in the range, e.g. `StatusRange(500..=599)`.
+    StatusRange(RangeInclusive<u16>),
+    StatusClass(StatusClass),
     Content(ContentRetryCondition),
+    /// A response header `name` whose value matches `pattern`, or with any
+    /// value when `pattern` is `None`, e.g. `cf-mitigated: challenge`.
+    ///
+    /// Matched against the headers kept by `SpiderConfig::header_capture`.
+    Header {
+        name: String,
+        pattern: Option<ContentRetryCondition>,
+    },
+    /// A response without header `name`, e.g. a block page served without
+    /// `content-type`.
+    ///
+    /// Headers dropped by `SpiderConfig::header_capture` count as missing:
+    /// with the default filter, `MissingHeader("set-cookie")` always matches.
+    MissingHeader(String),
+}
+
+/// The standard HTTP status classes (1xx to 5xx).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusClass {
+    Informational,
+    Success,
+    Redirection,
+    ClientError,
+    ServerError,
+}
+
+impl StatusClass {
+    pub fn of(status: u16) -> Option<Self> {
+        match status {
+            100..=199 => Some(Self::Informational),
+            200..=299 => Some(Self::Success),
+            300..=399 => Some(Self::Redirection),
+            400..=499 => Some(Self::ClientError),
+            500..=599 => Some(Self::ServerError),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -33,7 +78,24 @@ pub enum ParseRetryCondition {
 pub enum BackoffPolicy {
     Constant,
     Linear,
-    Exponential { factor: f32 },
+    Exponential {
+        factor: f32,
+    },
+    /// Exponential backoff with a random delay, so that requests failing
+    /// together don't all retry at the same moment.
+    ExponentialJitter {
+        factor: f32,
+        jitter: Jitter,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Jitter {
+    /// Anywhere between zero and the exponential delay.
+    Full,
+    /// Anywhere between the initial delay and `factor` times the previous
+    /// delay of the same URL.
+    Decorrelated,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -62,16 +124,69 @@ pub struct CategoryConfig {
     pub max_delay: Duration,
     pub backoff_policy: BackoffPolicy,
     pub conditions: Vec<RetryCondition>,
+    /// Only apply this category to matching requests; `None` applies it to all.
+    pub matcher: Option<RetryMatcher>,
+    pub scope: RetryScope,
+}
+
+/// What the retries of a category are counted against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RetryScope {
+    /// Each URL gets `max_retries` retries.
+    #[default]
+    Url,
+    /// The URLs of a host share `max_retries` retries and back off together,
+    /// e.g. for an IP ban that applies to the whole site. Once they are used
+    /// up, the remaining requests to the host are given up on unfetched.
+    Host,
+}
+
+/// Selects requests by depth and callback, so a category can e.g. retry
+/// detail pages aggressively while giving up quickly on deep pagination.
+#[derive(Debug, Clone, Default)]
+pub struct RetryMatcher {
+    pub depths: Option<RangeInclusive<usize>>,
+    /// Matches any callback when empty.
+    pub callbacks: Vec<SpiderCallback>,
+}
+
+/// A single retry decision for a URL.
+#[derive(Debug, Clone)]
+pub struct RetryAttempt {
+    pub category: RetryCategory,
+    pub timestamp: DateTime<Utc>,
+    /// Status of the response that triggered the retry, for request retries.
+    pub status: Option<u16>,
+}
+
+/// The response that triggered the most recent request retry.
+#[derive(Debug, Clone)]
+pub struct ResponseSnapshot {
+    pub status: u16,
+    /// Start of the body, truncated to `RESPONSE_SNAPSHOT_LIMIT` bytes.
+    pub body: String,
+    pub timestamp: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone)]
 pub struct RetryState {
     pub counts: HashMap<RetryCategory, usize>,
     pub total_retries: usize,
+    pub attempts: Vec<RetryAttempt>,
+    pub last_response: Option<ResponseSnapshot>,
+    /// Delay computed for the most recent retry.
+    pub last_delay: Option<Duration>,
 }
 
 #[derive(Debug, Clone)]
 pub struct RetryConfig {
     pub categories: HashMap<RetryCategory, CategoryConfig>,
     pub(crate) retry_states: Arc<RwLock<HashMap<String, RetryState>>>,
+    /// Retries of the categories scoped to a host, by host.
+    pub(crate) host_states: Arc<RwLock<HashMap<String, RetryState>>>,
+    pub request_transformer: Option<Arc<dyn RetryRequestTransformer>>,
+    /// Give up on a URL once this much time has passed since its first retry.
+    pub retry_deadline: Option<Duration>,
+    /// Crawl-wide cap on retries, see [`RetryBudget`].
+    pub retry_budget: Option<RetryBudget>,
 }
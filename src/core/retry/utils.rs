@@ -1,18 +1,36 @@
+use crate::core::RetryHint;
+use crate::http::Headers;
 use crate::{storage::base::StorageError, ScraperError};
 
 use super::types::*;
 use regex::Regex;
 use std::time::Duration;
 
-pub fn retry_request_condition_should_apply(
+/// Whether `condition` matches a response. Header conditions never match
+/// when the `headers` are unknown.
+/// `content` is only called, and a body decoded, for `Content` conditions.
+pub fn retry_request_condition_should_apply<'a>(
     condition: &RequestRetryCondition,
     status: u16,
-    content: &str,
+    headers: Option<&Headers>,
+    content: &dyn Fn() -> &'a str,
 ) -> bool {
     match condition {
         RequestRetryCondition::StatusCode(code) => *code == status,
+        RequestRetryCondition::StatusRange(range) => range.contains(&status),
+        RequestRetryCondition::StatusClass(class) => StatusClass::of(status) == Some(*class),
         RequestRetryCondition::Content(content_condition) => {
-            check_content_condition(content_condition, content)
+            check_content_condition(content_condition, content())
+        }
+        RequestRetryCondition::Header { name, pattern } => headers.is_some_and(|headers| {
+            headers.get_all(name).any(|value| {
+                pattern
+                    .as_ref()
+                    .is_none_or(|p| check_content_condition(p, value))
+            })
+        }),
+        RequestRetryCondition::MissingHeader(name) => {
+            headers.is_some_and(|headers| !headers.contains_key(name))
         }
     }
 }
@@ -22,13 +40,13 @@ pub fn retry_parse_condition_should_apply(
     error: &ScraperError,
 ) -> bool {
     match condition {
-        ParseRetryCondition::Content(content_condition, _) => {
-            if let ScraperError::ParsingError(msg) = error {
-                check_content_condition(content_condition, msg)
-            } else {
-                false
+        ParseRetryCondition::Content(content_condition, _) => match error {
+            ScraperError::ParsingError(msg) => check_content_condition(content_condition, msg),
+            ScraperError::Spider(e) if e.hint() == RetryHint::Transient => {
+                check_content_condition(content_condition, &e.to_string())
             }
-        }
+            _ => false,
+        },
         ParseRetryCondition::StorageError(expected_error, _) => {
             if let ScraperError::StorageError(actual_error) = error {
                 matches!(
@@ -48,7 +66,11 @@ pub fn retry_parse_condition_should_apply(
                 false
             }
         }
-        ParseRetryCondition::ErrorWhileParsing(_) => matches!(error, ScraperError::ParsingError(_)),
+        ParseRetryCondition::ErrorWhileParsing(_) => match error {
+            ScraperError::ParsingError(_) => true,
+            ScraperError::Spider(e) => e.hint() == RetryHint::Transient,
+            _ => false,
+        },
     }
 }
 
@@ -65,11 +87,21 @@ fn check_content_condition(condition: &ContentRetryCondition, content: &str) ->
 }
 
 pub fn calculate_delay(config: &CategoryConfig, attempt: usize) -> Duration {
-    if attempt == 0 {
-        return config.initial_delay;
-    }
+    calculate_delay_after(config, attempt, None)
+}
 
+/// Delay before retry number `attempt`, `previous` being the delay of the
+/// retry before it, which decorrelated jitter builds on.
+pub fn calculate_delay_after(
+    config: &CategoryConfig,
+    attempt: usize,
+    previous: Option<Duration>,
+) -> Duration {
     let delay = match config.backoff_policy {
+        BackoffPolicy::ExponentialJitter { factor, jitter } => {
+            return jittered_delay(config, factor, jitter, attempt, previous)
+        }
+        _ if attempt == 0 => return config.initial_delay,
         BackoffPolicy::Constant => config.initial_delay,
         BackoffPolicy::Linear => config.initial_delay.mul_f32(attempt as f32),
         BackoffPolicy::Exponential { factor } => {
@@ -79,3 +111,28 @@ pub fn calculate_delay(config: &CategoryConfig, attempt: usize) -> Duration {
 
     std::cmp::min(delay, config.max_delay)
 }
+
+fn jittered_delay(
+    config: &CategoryConfig,
+    factor: f32,
+    jitter: Jitter,
+    attempt: usize,
+    previous: Option<Duration>,
+) -> Duration {
+    let (floor, ceiling) = match jitter {
+        Jitter::Full => (
+            Duration::ZERO,
+            config.initial_delay.mul_f32(factor.powi(attempt as i32)),
+        ),
+        Jitter::Decorrelated => {
+            let previous = previous.unwrap_or(config.initial_delay);
+            (
+                config.initial_delay,
+                previous.mul_f32(factor).max(config.initial_delay),
+            )
+        }
+    };
+    let ceiling = std::cmp::min(ceiling, config.max_delay);
+    let floor = std::cmp::min(floor, ceiling);
+    floor + (ceiling - floor).mul_f64(fastrand::f64())
+}
@@ -0,0 +1,269 @@
+use crate::core::retry::{RetryCategory, RetryState};
+use crate::core::spider::{ParseResult, ParsedData, SpiderCallback, SpiderConfig, SpiderResponse};
+use crate::parser::sitemap::Sitemap;
+use crate::storage::StorageManager;
+use crate::{HttpRequest, ScraperResult, Spider};
+use async_trait::async_trait;
+use log::{debug, warn};
+use regex::Regex;
+use std::sync::Arc;
+use url::Url;
+
+type PageCallback =
+    dyn Fn(&SpiderResponse) -> ScraperResult<(ParseResult, ParsedData)> + Send + Sync;
+
+/// A spider that starts from `sitemap.xml` files instead of link discovery.
+///
+/// Sitemap indexes are followed (optionally only the entries matching a
+/// follow pattern), and the page URLs of every sitemap are dispatched to
+/// `parse` with the callback of the first matching rule. Without rules all
+/// pages are dispatched with `ParseItem`; with rules, pages matching none
+/// are skipped. Extracted items are stored by the default
+/// `persist_extracted_data`, in the `Data` category.
+pub struct SitemapSpider {
+    name: String,
+    config: SpiderConfig,
+    storage_manager: StorageManager,
+    sitemap_urls: Vec<Url>,
+    rules: Vec<(Regex, SpiderCallback)>,
+    follow: Vec<Regex>,
+    parse: Arc<PageCallback>,
+}
+
+impl SitemapSpider {
+    pub fn new<F>(
+        name: &str,
+        sitemap_urls: Vec<Url>,
+        storage_manager: StorageManager,
+        parse: F,
+    ) -> Self
+    where
+        F: Fn(&SpiderResponse) -> ScraperResult<(ParseResult, ParsedData)> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            config: SpiderConfig::default(),
+            storage_manager,
+            sitemap_urls,
+            rules: Vec::new(),
+            follow: Vec::new(),
+            parse: Arc::new(parse),
+        }
+    }
+
+    /// Dispatches page URLs matching `pattern` with `callback`.
+    pub fn with_rule(
+        mut self,
+        pattern: &str,
+        callback: SpiderCallback,
+    ) -> Result<Self, regex::Error> {
+        self.rules.push((Regex::new(pattern)?, callback));
+        Ok(self)
+    }
+
+    /// Only follows sitemap index entries matching one of the follow
+    /// patterns, e.g. `products` to skip blog sitemaps.
+    pub fn with_follow(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.follow.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    fn page_callback(&self, url: &Url) -> Option<SpiderCallback> {
+        if self.rules.is_empty() {
+            return Some(SpiderCallback::ParseItem);
+        }
+        self.rules
+            .iter()
+            .find(|(pattern, _)| pattern.is_match(url.as_str()))
+            .map(|(_, callback)| callback.clone())
+    }
+
+    fn parse_sitemap(&self, response: &SpiderResponse) -> ScraperResult<Vec<HttpRequest>> {
+        let response = &response.response;
+        // Nested sitemaps keep the depth of their index, so only pages count
+        // towards `max_depth`.
+        let depth = response.from_request.depth;
+        let requests = match Sitemap::from_response(response)? {
+            Sitemap::Index(sitemaps) => sitemaps
+                .into_iter()
+                .filter(|entry| {
+                    self.follow.is_empty()
+                        || self.follow.iter().any(|re| re.is_match(entry.loc.as_str()))
+                })
+                .map(|entry| HttpRequest::new(entry.loc, SpiderCallback::ParseSitemap, depth))
+                .collect(),
+            Sitemap::UrlSet(urls) => urls
+                .into_iter()
+                .filter_map(|entry| {
+                    let callback = self.page_callback(&entry.loc)?;
+                    Some(HttpRequest::new(entry.loc, callback, depth + 1))
+                })
+                .collect::<Vec<_>>(),
+        };
+        debug!("Sitemap {} lists {} URLs", response.url, requests.len());
+        Ok(requests)
+    }
+}
+
+#[async_trait]
+impl Spider for SitemapSpider {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn config(&self) -> &SpiderConfig {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: SpiderConfig) {
+        self.config = config;
+    }
+
+    fn start_requests(&self) -> Vec<HttpRequest> {
+        self.sitemap_urls
+            .iter()
+            .map(|url| HttpRequest::new(url.clone(), SpiderCallback::ParseSitemap, 0))
+            .collect()
+    }
+
+    fn get_initial_callback(&self) -> SpiderCallback {
+        SpiderCallback::ParseSitemap
+    }
+
+    fn parse(&self, response: &SpiderResponse) -> ScraperResult<(ParseResult, ParsedData)> {
+        match response.callback {
+            SpiderCallback::ParseSitemap => Ok((
+                ParseResult::Continue(self.parse_sitemap(response)?),
+                ParsedData::Empty,
+            )),
+            _ => (self.parse)(response),
+        }
+    }
+
+    async fn handle_max_retries(
+        &self,
+        category: RetryCategory,
+        request: Box<HttpRequest>,
+        history: RetryState,
+    ) -> ScraperResult<()> {
+        warn!(
+            "Giving up on {} after {} retries (category: {:?})",
+            request.url, history.total_retries, category
+        );
+        Ok(())
+    }
+
+    fn storage_manager(&self) -> &StorageManager {
+        &self.storage_manager
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrapers::HttpScraper;
+    use crate::storage::{create_storage, StorageCategory, StorageType};
+    use crate::Crawler;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use parking_lot::Mutex;
+    use serde_json::json;
+    use std::io::Write;
+    use wiremock::matchers::{path, path_regex};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_follows_index_and_dispatches_matching_pages() {
+        let server = MockServer::start().await;
+        let base = server.uri();
+        let index = format!(
+            r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+                 <sitemap><loc>{base}/products.xml.gz</loc></sitemap>
+                 <sitemap><loc>{base}/blog.xml</loc></sitemap>
+               </sitemapindex>"#
+        );
+        let products = format!(
+            r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+                 <url><loc>{base}/p/1</loc></url>
+                 <url><loc>{base}/p/2</loc></url>
+                 <url><loc>{base}/about</loc></url>
+               </urlset>"#
+        );
+        let mut gzipped = GzEncoder::new(Vec::new(), Compression::default());
+        gzipped.write_all(products.as_bytes()).unwrap();
+        let gzipped = gzipped.finish().unwrap();
+
+        Mock::given(path("/sitemap.xml"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(index))
+            .mount(&server)
+            .await;
+        Mock::given(path("/products.xml.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(gzipped, "application/x-gzip"))
+            .mount(&server)
+            .await;
+        Mock::given(path("/blog.xml"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&server)
+            .await;
+        Mock::given(path_regex(r"^/p/\d+$"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<h1>Product</h1>"))
+            .mount(&server)
+            .await;
+
+        let dir =
+            std::env::temp_dir().join(format!("turboscraper_sitemap_{}", uuid::Uuid::now_v7()));
+        let storage = create_storage(StorageType::Disk {
+            path: dir.to_string_lossy().to_string(),
+        })
+        .await
+        .unwrap();
+        let parsed = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&parsed);
+        let spider = SitemapSpider::new(
+            "sitemap",
+            vec![Url::parse(&format!("{}/sitemap.xml", base)).unwrap()],
+            StorageManager::new()
+                .register_storage(StorageCategory::Data, storage.clone(), "data")
+                .register_storage(
+                    StorageCategory::Custom("sellers".into()),
+                    storage,
+                    "sellers",
+                ),
+            move |response| {
+                seen.lock().push(response.response.url.path().to_string());
+                Ok((
+                    ParseResult::Skip,
+                    ParsedData::Categorized(vec![
+                        (StorageCategory::Data, json!({"url": response.response.url})),
+                        (
+                            StorageCategory::Custom("sellers".into()),
+                            json!({"seller": "acme"}),
+                        ),
+                    ]),
+                ))
+            },
+        )
+        .with_rule("/p/", SpiderCallback::ParseItem)
+        .unwrap()
+        .with_follow("products")
+        .unwrap();
+
+        let crawler = Crawler::new(Box::new(HttpScraper::new().unwrap()));
+        crawler.run(spider).await.unwrap();
+
+        let mut parsed = parsed.lock().clone();
+        parsed.sort();
+        assert_eq!(parsed, ["/p/1", "/p/2"]);
+        assert_eq!(crawler.stats().items_scraped(), 4);
+        let stored = |folder: &str| {
+            let host = Url::parse(&base).unwrap().host_str().unwrap().to_string();
+            std::fs::read_dir(dir.join(folder).join(host))
+                .unwrap()
+                .count()
+        };
+        assert_eq!(stored("data"), 2);
+        assert_eq!(stored("sellers"), 2);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::core::pipeline::{ItemDedup, ItemValidation};
+use crate::core::retry::{RetryCategory, RetryState};
+use crate::core::spider::{ParsedData, SpiderConfig, SpiderResponse};
+use crate::core::{SpiderCallback, VisitedStore};
+use crate::http::HttpRequest;
+use crate::parser::{next_link, CallbackParsers, LinkExtractor};
+use crate::storage::StorageManager;
+use crate::{Crawler, Scraper, ScraperError, ScraperResult, Spider};
+use async_trait::async_trait;
+use log::error;
+use scraper::{Html, Selector};
+use serde_json::{json, Value};
+use std::io;
+use std::path::Path;
+use url::Url;
+
+/// Delta crawl of quotes.toscrape.com: every run walks the listing pages
+/// again, but only stores the quotes and fetches the author pages it has not
+/// seen in an earlier run.
+///
+/// Authors are skipped by the [`VisitedStore`] of
+/// [`with_state_dir`](Self::with_state_dir), quotes by the [`ItemDedup`]
+/// pipeline of [`crawler`](Self::crawler), which also validates every item
+/// before it is stored. Both keep their state in the same directory.
+///
+/// The pages are parsed by the [`CallbackParsers`] of the spider.
+pub struct DeltaQuotesSpider {
+    config: SpiderConfig,
+    base_url: Url,
+    storage_manager: StorageManager,
+    parsers: CallbackParsers,
+}
+
+impl DeltaQuotesSpider {
+    pub fn new(storage_manager: StorageManager) -> Self {
+        Self {
+            config: SpiderConfig::default(),
+            base_url: Url::parse("https://quotes.toscrape.com/").unwrap(),
+            storage_manager,
+            parsers: CallbackParsers::new()
+                .with_parser(SpiderCallback::Bootstrap, parse_listing)
+                .with_parser(SpiderCallback::ParsePagination, parse_listing)
+                .with_parser(SpiderCallback::ParseItem, parse_author),
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// Remembers the author pages fetched by earlier runs in `dir`.
+    pub fn with_state_dir<P: AsRef<Path>>(mut self, dir: P) -> io::Result<Self> {
+        let store = VisitedStore::open(dir.as_ref().join("visited.tsv"))?
+            .with_callbacks(vec![SpiderCallback::ParseItem]);
+        self.config = self.config.with_visited_store(store);
+        Ok(self)
+    }
+
+    /// A crawler validating the scraped items, then dropping the quotes
+    /// stored by earlier runs, as recorded in `state_dir`.
+    pub fn crawler<P: AsRef<Path>>(scraper: Box<dyn Scraper>, state_dir: P) -> io::Result<Crawler> {
+        let dedup = ItemDedup::by_field("text").open(state_dir.as_ref().join("quotes.txt"))?;
+        Ok(Crawler::builder(scraper)
+            .with_item_pipeline(ItemValidation::new(validate))
+            .with_item_pipeline(dedup)
+            .build())
+    }
+}
+
+fn parse_listing(response: &SpiderResponse) -> ScraperResult<(Vec<HttpRequest>, ParsedData)> {
+    let response = &response.response;
+    let document = Html::parse_document(response.body_text()?);
+    let quote_selector = Selector::parse("div.quote").unwrap();
+    let text_selector = Selector::parse("span.text").unwrap();
+    let author_selector = Selector::parse("small.author").unwrap();
+    let text_of = |quote: scraper::ElementRef, selector: &Selector| {
+        quote
+            .select(selector)
+            .next()
+            .map(|e| e.text().collect::<String>().trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let quotes = document
+        .select(&quote_selector)
+        .map(|quote| {
+            json!({
+                "kind": "quote",
+                "text": text_of(quote, &text_selector).trim_matches(|c| c == '“' || c == '”'),
+                "author": text_of(quote, &author_selector),
+            })
+        })
+        .collect();
+
+    let authors = LinkExtractor::new()
+        .with_restrict_css("div.quote")?
+        .with_allow("/author/")
+        .map_err(|e| ScraperError::ParsingError(e.to_string()))?
+        .extract(response);
+    let mut requests = next_link(
+        response,
+        "li.next a",
+        SpiderCallback::ParsePagination,
+        response.from_request.depth + 1,
+    )?;
+    requests.extend(authors);
+    Ok((requests, ParsedData::Items(quotes)))
+}
+
+fn parse_author(response: &SpiderResponse) -> ScraperResult<(Vec<HttpRequest>, ParsedData)> {
+    let document = Html::parse_document(response.response.body_text()?);
+    let field = |css: &str| {
+        let selector = Selector::parse(css).unwrap();
+        document
+            .select(&selector)
+            .next()
+            .map(|e| e.text().collect::<String>().trim().to_string())
+            .unwrap_or_default()
+    };
+    let author = json!({
+        "kind": "author",
+        "name": field("h3.author-title"),
+        "born": field("span.author-born-date"),
+    });
+    Ok((Vec::new(), ParsedData::Item(author)))
+}
+
+/// Quotes need their text and author, authors their name.
+fn validate(item: &Value) -> Result<(), Vec<String>> {
+    let required: &[&str] = match item["kind"].as_str() {
+        Some("author") => &["name"],
+        _ => &["text", "author"],
+    };
+    let missing: Vec<String> = required
+        .iter()
+        .filter(|field| item[**field].as_str().is_none_or(str::is_empty))
+        .map(|field| format!("missing {}", field))
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(missing)
+    }
+}
+
+#[async_trait]
+impl Spider for DeltaQuotesSpider {
+    fn config(&self) -> &SpiderConfig {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: SpiderConfig) {
+        self.config = config;
+    }
+
+    fn storage_manager(&self) -> &StorageManager {
+        &self.storage_manager
+    }
+
+    fn name(&self) -> String {
+        "delta_quotes_spider".to_string()
+    }
+
+    fn start_requests(&self) -> Vec<HttpRequest> {
+        vec![HttpRequest::new(
+            self.base_url.clone(),
+            SpiderCallback::ParsePagination,
+            0,
+        )]
+    }
+
+    fn parsers(&self) -> Option<&CallbackParsers> {
+        Some(&self.parsers)
+    }
+
+    async fn handle_max_retries(
+        &self,
+        category: RetryCategory,
+        request: Box<HttpRequest>,
+        history: RetryState,
+    ) -> ScraperResult<()> {
+        error!(
+            "Giving up on {} after {} retries (category: {:?})",
+            request.url, history.total_retries, category
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrapers::HttpScraper;
+    use crate::storage::{create_storage, StorageCategory, StorageType};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn serve(server: &MockServer, quotes: &[(&str, &str)]) {
+        server.reset().await;
+        let listing: String = quotes
+            .iter()
+            .map(|(text, author)| {
+                let about = match *author {
+                    "" => String::new(),
+                    author => format!(r#"<a href="/author/{}">(about)</a>"#, author),
+                };
+                format!(
+                    r#"<div class="quote"><span class="text">“{}”</span>
+                    <small class="author">{}</small>{}</div>"#,
+                    text, author, about
+                )
+            })
+            .collect();
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(listing))
+            .mount(server)
+            .await;
+        for (_, author) in quotes.iter().filter(|(_, author)| !author.is_empty()) {
+            Mock::given(method("GET"))
+                .and(path(format!("/author/{}", author)))
+                .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+                    r#"<h3 class="author-title">{}</h3>
+                    <span class="author-born-date">1815</span>"#,
+                    author
+                )))
+                .mount(server)
+                .await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_later_runs_store_only_new_items() {
+        let server = MockServer::start().await;
+        let dir = std::env::temp_dir().join(format!("delta_quotes_{}", uuid::Uuid::now_v7()));
+        let host = Url::parse(&server.uri())
+            .unwrap()
+            .host_str()
+            .unwrap()
+            .to_string();
+
+        let run = || async {
+            let storage = create_storage(StorageType::Disk {
+                path: dir.join("output").to_string_lossy().to_string(),
+            })
+            .await
+            .unwrap();
+            let storage_manager =
+                StorageManager::new().register_storage(StorageCategory::Data, storage, "data");
+            let spider = DeltaQuotesSpider::new(storage_manager)
+                .with_base_url(Url::parse(&server.uri()).unwrap())
+                .with_state_dir(&dir)
+                .unwrap();
+            let crawler =
+                DeltaQuotesSpider::crawler(Box::new(HttpScraper::new().unwrap()), &dir).unwrap();
+            crawler.run(spider).await.unwrap();
+            let requests = server.received_requests().await.unwrap();
+            let stored = std::fs::read_dir(dir.join("output").join("data").join(&host))
+                .unwrap()
+                .count();
+            (requests.len(), stored)
+        };
+
+        serve(&server, &[("First", "Ada"), ("Second", "")]).await;
+        // The quote without an author fails validation.
+        assert_eq!(run().await, (2, 2));
+
+        serve(&server, &[("First", "Ada"), ("Third", "Alan")]).await;
+        // Only Alan's page is fetched, and only the third quote is new.
+        assert_eq!(run().await, (2, 4));
+    }
+}
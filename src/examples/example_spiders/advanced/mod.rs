@@ -0,0 +1 @@
+pub mod delta_quotes_spider;
@@ -1,7 +1,8 @@
-use crate::core::retry::RetryCategory;
+use crate::core::retry::{RetryCategory, RetryState};
 use crate::core::spider::{ParseResult, ParsedData, SpiderConfig, SpiderResponse};
 use crate::core::SpiderCallback;
 use crate::http::{HttpRequest, HttpResponse};
+use crate::parser::next_link;
 use crate::storage::{StorageCategory, StorageItem, StorageManager};
 use crate::{ScraperResult, Spider};
 use async_trait::async_trait;
@@ -27,7 +28,7 @@ impl BookSpider {
     }
 
     fn parse_book_list(&self, response: &HttpResponse) -> ScraperResult<Vec<HttpRequest>> {
-        let document = Html::parse_document(&response.decoded_body);
+        let document = Html::parse_document(response.body_text()?);
         let book_selector = Selector::parse("article.product_pod h3 a").unwrap();
         let url = response.from_request.url.clone();
         let depth = response.from_request.depth;
@@ -51,24 +52,12 @@ impl BookSpider {
     }
 
     fn next_page(&self, response: &HttpResponse) -> ScraperResult<Vec<HttpRequest>> {
-        let document = Html::parse_document(&response.decoded_body);
-        let next_page_selector = Selector::parse("li.next a").unwrap();
-        let url = response.from_request.url.clone();
-        let depth = response.from_request.depth;
-        let mut requests = Vec::new();
-
-        if let Some(next_element) = document.select(&next_page_selector).next() {
-            if let Some(href) = next_element.value().attr("href") {
-                if let Ok(next_url) = url.join(href) {
-                    requests.push(HttpRequest::new(
-                        next_url,
-                        SpiderCallback::ParsePagination,
-                        depth,
-                    ));
-                }
-            }
-        }
-        Ok(requests)
+        next_link(
+            response,
+            "li.next a",
+            SpiderCallback::ParsePagination,
+            response.from_request.depth,
+        )
     }
 
     fn parse_book_details(&self, body: &str) -> Value {
@@ -162,13 +151,17 @@ impl Spider for BookSpider {
                 Ok((ParseResult::Continue(requests), ParsedData::Empty))
             }
             SpiderCallback::ParseItem => {
-                let details = self.parse_book_details(&spider_response.response.decoded_body);
+                let details = self.parse_book_details(spider_response.response.body_text()?);
                 Ok((ParseResult::Skip, ParsedData::Item(details)))
             }
             SpiderCallback::Custom(ref name) => {
                 error!("Unhandled custom callback: {}", name);
                 Ok((ParseResult::Skip, ParsedData::Empty))
             }
+            SpiderCallback::ParseSitemap => {
+                error!("Unhandled sitemap callback");
+                Ok((ParseResult::Skip, ParsedData::Empty))
+            }
         }
     }
 
@@ -190,7 +183,7 @@ impl Spider for BookSpider {
                     "parser": "book_details",
                     "response": {
                         "status": response.response.status,
-                        "headers": response.response.headers,
+                        "headers": self.config.header_capture.apply(&response.response.headers),
                     }
                 })),
                 id: self.name(),
@@ -210,6 +203,7 @@ impl Spider for BookSpider {
         &self,
         category: RetryCategory,
         request: Box<HttpRequest>,
+        history: RetryState,
     ) -> ScraperResult<()> {
         let error_item = StorageItem {
             url: request.url.clone(),
@@ -222,6 +216,17 @@ impl Spider for BookSpider {
             metadata: Some(json!({
                 "error_type": "max_retries",
                 "category": format!("{:?}", category),
+                "total_retries": history.total_retries,
+                "attempts": history
+                    .attempts
+                    .iter()
+                    .map(|attempt| json!({
+                        "category": format!("{:?}", attempt.category),
+                        "timestamp": attempt.timestamp,
+                        "status": attempt.status,
+                    }))
+                    .collect::<Vec<_>>(),
+                "last_status": history.last_response.as_ref().map(|r| r.status),
             })),
             id: format!("{}_errors", self.name()),
         };
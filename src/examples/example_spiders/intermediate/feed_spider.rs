@@ -0,0 +1,190 @@
+use crate::core::retry::{RetryCategory, RetryState};
+use crate::core::spider::{ParseResult, ParsedData, SpiderConfig, SpiderResponse};
+use crate::core::SpiderCallback;
+use crate::http::{HttpRequest, HttpResponse};
+use crate::parser::Feed;
+use crate::storage::{StorageCategory, StorageItem, StorageManager};
+use crate::{ScraperResult, Spider};
+use async_trait::async_trait;
+use chrono::Utc;
+use log::{error, info};
+use scraper::{Html, Selector};
+use serde_json::{json, Value};
+use url::Url;
+
+/// Reads RSS/Atom feeds and stores every linked article with the title and
+/// publication date from its feed entry.
+pub struct FeedSpider {
+    config: SpiderConfig,
+    feed_urls: Vec<Url>,
+    storage_manager: StorageManager,
+}
+
+impl FeedSpider {
+    pub fn new(feed_urls: Vec<Url>, storage_manager: StorageManager) -> Self {
+        Self {
+            config: SpiderConfig::default(),
+            feed_urls,
+            storage_manager,
+        }
+    }
+
+    fn parse_article(&self, response: &HttpResponse) -> ScraperResult<Value> {
+        let document = Html::parse_document(response.body_text()?);
+        let paragraph_selector = Selector::parse("article p, p").unwrap();
+        let text: Vec<String> = document
+            .select(&paragraph_selector)
+            .map(|p| p.text().collect::<String>().trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect();
+        let entry = response.from_request.meta.clone().unwrap_or(Value::Null);
+
+        Ok(json!({
+            "url": response.url,
+            "title": entry["title"],
+            "published": entry["published"],
+            "text": text.join("\n"),
+        }))
+    }
+}
+
+#[async_trait]
+impl Spider for FeedSpider {
+    fn config(&self) -> &SpiderConfig {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: SpiderConfig) {
+        self.config = config;
+    }
+
+    fn storage_manager(&self) -> &StorageManager {
+        &self.storage_manager
+    }
+
+    fn name(&self) -> String {
+        "feed_spider".to_string()
+    }
+
+    fn start_requests(&self) -> Vec<HttpRequest> {
+        self.feed_urls
+            .iter()
+            .map(|url| HttpRequest::new(url.clone(), SpiderCallback::Bootstrap, 0))
+            .collect()
+    }
+
+    fn parse(&self, spider_response: &SpiderResponse) -> ScraperResult<(ParseResult, ParsedData)> {
+        let response = &spider_response.response;
+        match spider_response.callback {
+            SpiderCallback::Bootstrap => {
+                let feed = Feed::from_response(response)?;
+                info!(
+                    "Feed {} has {} entries",
+                    feed.title.as_deref().unwrap_or(response.url.as_str()),
+                    feed.entries.len()
+                );
+                let requests =
+                    feed.requests(SpiderCallback::ParseItem, response.from_request.depth + 1);
+                Ok((ParseResult::Continue(requests), ParsedData::Empty))
+            }
+            SpiderCallback::ParseItem => Ok((
+                ParseResult::Skip,
+                ParsedData::Item(self.parse_article(response)?),
+            )),
+            _ => {
+                error!("Unhandled callback: {:?}", spider_response.callback);
+                Ok((ParseResult::Skip, ParsedData::Empty))
+            }
+        }
+    }
+
+    async fn persist_extracted_data(
+        &self,
+        data: ParsedData,
+        response: &SpiderResponse,
+    ) -> ScraperResult<()> {
+        if let ParsedData::Item(article) = data {
+            let item = StorageItem {
+                url: response.response.url.clone(),
+                timestamp: Utc::now(),
+                data: article,
+                metadata: Some(json!({
+                    "depth": response.response.from_request.depth,
+                    "parser": "feed_article",
+                })),
+                id: self.name(),
+            };
+
+            self.store_data(
+                item,
+                StorageCategory::Data,
+                response.response.from_request.clone(),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_max_retries(
+        &self,
+        category: RetryCategory,
+        request: Box<HttpRequest>,
+        history: RetryState,
+    ) -> ScraperResult<()> {
+        error!(
+            "Giving up on {} after {} retries (category: {:?})",
+            request.url, history.total_retries, category
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrapers::HttpScraper;
+    use crate::storage::{create_storage, StorageType};
+    use crate::Crawler;
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_stores_feed_articles() {
+        let server = MockServer::start().await;
+        Mock::given(path("/rss"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"<rss version="2.0"><channel><title>News</title>
+                <item><title>First</title><link>/news/1</link></item>
+                <item><title>Second</title><link>/news/2</link></item>
+                </channel></rss>"#,
+            ))
+            .mount(&server)
+            .await;
+        for id in ["1", "2"] {
+            Mock::given(path(format!("/news/{}", id)))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_string("<html><article><p>Body</p></article></html>"),
+                )
+                .mount(&server)
+                .await;
+        }
+
+        let output = std::env::temp_dir().join(format!("feed_spider_{}", uuid::Uuid::now_v7()));
+        let storage = create_storage(StorageType::Disk {
+            path: output.to_string_lossy().to_string(),
+        })
+        .await
+        .unwrap();
+        let storage_manager =
+            StorageManager::new().register_storage(StorageCategory::Data, storage, "data");
+
+        let feed_url = Url::parse(&format!("{}/rss", server.uri())).unwrap();
+        let spider = FeedSpider::new(vec![feed_url], storage_manager);
+        let crawler = Crawler::new(Box::new(HttpScraper::new().unwrap()));
+        crawler.run(spider).await.unwrap();
+
+        assert_eq!(crawler.stats().items_scraped(), 2);
+        let _ = std::fs::remove_dir_all(output);
+    }
+}
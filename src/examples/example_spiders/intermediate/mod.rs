@@ -0,0 +1,2 @@
+pub mod feed_spider;
+pub mod quotes_spider;
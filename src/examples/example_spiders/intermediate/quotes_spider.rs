@@ -0,0 +1,295 @@
+use crate::core::retry::{RetryCategory, RetryState};
+use crate::core::spider::{ParseResult, ParsedData, SpiderConfig, SpiderResponse};
+use crate::core::{RevisitPolicy, SpiderCallback};
+use crate::http::{HttpRequest, HttpResponse};
+use crate::parser::{next_link, FormParser};
+use crate::storage::{StorageCategory, StorageItem, StorageManager};
+use crate::{ScraperError, ScraperResult, Spider};
+use async_trait::async_trait;
+use chrono::Utc;
+use log::{error, info};
+use scraper::{Html, Selector};
+use serde_json::{json, Value};
+use url::Url;
+
+const LOGIN_CALLBACK: &str = "login";
+
+/// Logs into quotes.toscrape.com, then walks every listing page and stores
+/// each quote with its author and tags.
+///
+/// The login form posts back to the URL it was served from, so the spider
+/// needs `RevisitPolicy::Always` for the POST not to be dropped as already
+/// visited.
+pub struct QuotesSpider {
+    config: SpiderConfig,
+    base_url: Url,
+    username: String,
+    password: String,
+    storage_manager: StorageManager,
+}
+
+impl QuotesSpider {
+    pub fn new(storage_manager: StorageManager) -> Self {
+        Self {
+            config: SpiderConfig::default().with_revisit_policy(RevisitPolicy::Always),
+            base_url: Url::parse("https://quotes.toscrape.com/").unwrap(),
+            username: "turboscraper".to_string(),
+            password: "turboscraper".to_string(),
+            storage_manager,
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn with_credentials<U: Into<String>, P: Into<String>>(
+        mut self,
+        username: U,
+        password: P,
+    ) -> Self {
+        self.username = username.into();
+        self.password = password.into();
+        self
+    }
+
+    fn login_request(&self, response: &HttpResponse) -> ScraperResult<HttpRequest> {
+        let form = FormParser::new().parse(response)?;
+        if form.get("csrf_token").is_none() {
+            return Err(ScraperError::ParsingError(
+                "Missing csrf_token on login page".to_string(),
+            ));
+        }
+
+        Ok(form
+            .set("username", &self.username)
+            .set("password", &self.password)
+            .into_request(
+                SpiderCallback::Custom(LOGIN_CALLBACK.to_string()),
+                response.from_request.depth,
+            ))
+    }
+
+    fn parse_quotes(&self, response: &HttpResponse) -> ScraperResult<Vec<Value>> {
+        let document = Html::parse_document(response.body_text()?);
+        let quote_selector = Selector::parse("div.quote").unwrap();
+        let text_selector = Selector::parse("span.text").unwrap();
+        let author_selector = Selector::parse("small.author").unwrap();
+        let tag_selector = Selector::parse("a.tag").unwrap();
+
+        Ok(document
+            .select(&quote_selector)
+            .map(|quote| {
+                let text = quote
+                    .select(&text_selector)
+                    .next()
+                    .map(|e| e.text().collect::<String>())
+                    .unwrap_or_default();
+                let author = quote
+                    .select(&author_selector)
+                    .next()
+                    .map(|e| e.text().collect::<String>())
+                    .unwrap_or_default();
+                let tags: Vec<String> = quote
+                    .select(&tag_selector)
+                    .map(|e| e.text().collect::<String>())
+                    .collect();
+
+                json!({
+                    "text": text.trim().trim_matches(|c| c == '“' || c == '”'),
+                    "author": author.trim(),
+                    "tags": tags,
+                })
+            })
+            .collect())
+    }
+
+    fn next_page(&self, response: &HttpResponse) -> ScraperResult<Vec<HttpRequest>> {
+        next_link(
+            response,
+            "li.next a",
+            SpiderCallback::ParsePagination,
+            response.from_request.depth + 1,
+        )
+    }
+}
+
+#[async_trait]
+impl Spider for QuotesSpider {
+    fn config(&self) -> &SpiderConfig {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: SpiderConfig) {
+        self.config = config;
+    }
+
+    fn storage_manager(&self) -> &StorageManager {
+        &self.storage_manager
+    }
+
+    fn name(&self) -> String {
+        "quotes_spider".to_string()
+    }
+
+    fn start_requests(&self) -> Vec<HttpRequest> {
+        vec![HttpRequest::new(
+            self.base_url.join("login").unwrap(),
+            SpiderCallback::Bootstrap,
+            0,
+        )]
+    }
+
+    fn parse(&self, spider_response: &SpiderResponse) -> ScraperResult<(ParseResult, ParsedData)> {
+        let response = &spider_response.response;
+        match spider_response.callback {
+            SpiderCallback::Bootstrap => {
+                let login = self.login_request(response)?;
+                Ok((ParseResult::Continue(vec![login]), ParsedData::Empty))
+            }
+            SpiderCallback::Custom(ref name) if name == LOGIN_CALLBACK => {
+                let document = Html::parse_document(response.body_text()?);
+                let logout_selector = Selector::parse("a[href='/logout']").unwrap();
+                if document.select(&logout_selector).next().is_none() {
+                    return Err(ScraperError::ParsingError("Login failed".to_string()));
+                }
+                info!("Logged in as {}", self.username);
+
+                let quotes = self.parse_quotes(response)?;
+                Ok((
+                    ParseResult::Continue(self.next_page(response)?),
+                    ParsedData::Items(quotes),
+                ))
+            }
+            SpiderCallback::ParsePagination => {
+                let quotes = self.parse_quotes(response)?;
+                Ok((
+                    ParseResult::Continue(self.next_page(response)?),
+                    ParsedData::Items(quotes),
+                ))
+            }
+            SpiderCallback::ParseItem
+            | SpiderCallback::ParseSitemap
+            | SpiderCallback::Custom(_) => {
+                error!("Unhandled callback: {:?}", spider_response.callback);
+                Ok((ParseResult::Skip, ParsedData::Empty))
+            }
+        }
+    }
+
+    async fn persist_extracted_data(
+        &self,
+        data: ParsedData,
+        response: &SpiderResponse,
+    ) -> ScraperResult<()> {
+        if let ParsedData::Items(quotes) = data {
+            for quote in quotes {
+                let item = StorageItem {
+                    url: response.response.url.clone(),
+                    timestamp: Utc::now(),
+                    data: quote,
+                    metadata: Some(json!({
+                        "depth": response.response.from_request.depth,
+                        "parser": "quotes",
+                    })),
+                    id: self.name(),
+                };
+
+                self.store_data(
+                    item,
+                    StorageCategory::Data,
+                    response.response.from_request.clone(),
+                )
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_max_retries(
+        &self,
+        category: RetryCategory,
+        request: Box<HttpRequest>,
+        history: RetryState,
+    ) -> ScraperResult<()> {
+        error!(
+            "Giving up on {} after {} retries (category: {:?})",
+            request.url, history.total_retries, category
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrapers::HttpScraper;
+    use crate::storage::{create_storage, StorageType};
+    use crate::Crawler;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_login_and_pagination() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/login"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"<html><form method="post"><input name="csrf_token" value="abc123"></form></html>"#,
+            ))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/login"))
+            .and(body_string_contains("csrf_token=abc123"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"<html><a href="/logout">Logout</a>
+                <div class="quote"><span class="text">“First”</span>
+                <small class="author">Ada</small><a class="tag">math</a></div>
+                <ul><li class="next"><a href="/page/2/">Next</a></li></ul></html>"#,
+            ))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/page/2/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"<html><div class="quote"><span class="text">“Second”</span>
+                <small class="author">Alan</small></div></html>"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let output = std::env::temp_dir().join(format!("quotes_spider_{}", uuid::Uuid::now_v7()));
+        let storage = create_storage(StorageType::Disk {
+            path: output.to_string_lossy().to_string(),
+        })
+        .await
+        .unwrap();
+        let storage_manager =
+            StorageManager::new().register_storage(StorageCategory::Data, storage, "data");
+
+        let spider = QuotesSpider::new(storage_manager)
+            .with_base_url(Url::parse(&server.uri()).unwrap())
+            .with_config(
+                SpiderConfig::default()
+                    .with_depth(5)
+                    .with_revisit_policy(RevisitPolicy::Always),
+            );
+        let crawler = Crawler::new(Box::new(HttpScraper::new().unwrap()));
+        crawler.run(spider).await.unwrap();
+
+        let host = Url::parse(&server.uri())
+            .unwrap()
+            .host_str()
+            .unwrap()
+            .to_string();
+        let stored = std::fs::read_dir(output.join("data").join(host))
+            .unwrap()
+            .count();
+        assert_eq!(stored, 2);
+    }
+}
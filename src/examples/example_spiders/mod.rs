@@ -1 +1,3 @@
+pub mod advanced;
 pub mod beginner;
+pub mod intermediate;
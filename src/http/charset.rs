@@ -0,0 +1,121 @@
+use encoding_rs::{Encoding, SHIFT_JIS, UTF_8, WINDOWS_1251, WINDOWS_1252};
+use regex::bytes::Regex;
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+/// Bytes searched for a `<meta>` charset or an XML encoding declaration.
+const PRESCAN_LEN: usize = 1024;
+
+/// Encoding of a text body, from the first source that names a known one:
+/// its byte order mark, the `charset` of its content type, a `<meta>`
+/// charset or XML encoding declaration near its start, and finally a guess
+/// from the bytes themselves (see [`guess_encoding`]).
+pub fn detect_encoding(charset: Option<&str>, body: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(body) {
+        return encoding;
+    }
+    let head = &body[..body.len().min(PRESCAN_LEN)];
+    charset
+        .and_then(|charset| Encoding::for_label(charset.trim().as_bytes()))
+        .or_else(|| declared_encoding(head))
+        .unwrap_or_else(|| guess_encoding(body))
+}
+
+fn declared_encoding(head: &[u8]) -> Option<&'static Encoding> {
+    let label = meta_charset_pattern()
+        .captures(head)
+        .or_else(|| xml_encoding_pattern().captures(head))?;
+    let encoding = Encoding::for_label(&label[1])?;
+    // A document that could be read to find the declaration is not UTF-16.
+    if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE {
+        return Some(UTF_8);
+    }
+    Some(encoding)
+}
+
+fn meta_charset_pattern() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?\s*([a-z0-9_:.\-]+)"#).unwrap()
+    })
+}
+
+fn xml_encoding_pattern() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"(?i)^\s*<\?xml[^>]+encoding\s*=\s*["']([a-z0-9_:.\-]+)["']"#).unwrap()
+    })
+}
+
+/// Guesses the encoding of a body that doesn't declare one: UTF-8 if it is
+/// valid UTF-8, Shift_JIS if it reads as Japanese text with kana,
+/// Windows-1251 if most of its letters are Cyrillic, else Windows-1252
+/// (which also covers ISO-8859-1).
+pub fn guess_encoding(body: &[u8]) -> &'static Encoding {
+    if std::str::from_utf8(body).is_ok() {
+        return UTF_8;
+    }
+    if let Some(text) = SHIFT_JIS.decode_without_bom_handling_and_without_replacement(body) {
+        if text.chars().any(|c| ('\u{3040}'..='\u{30ff}').contains(&c)) {
+            return SHIFT_JIS;
+        }
+    }
+    let ascii_letters = body.iter().filter(|b| b.is_ascii_alphabetic()).count();
+    let high = body.iter().filter(|&&b| b >= 0x80).count();
+    // Windows-1251 puts the Cyrillic alphabet at 0xC0-0xFF.
+    let cyrillic = body.iter().filter(|&&b| b >= 0xC0).count();
+    if cyrillic * 10 >= high * 6 && high * 10 >= (ascii_letters + high) * 3 {
+        return WINDOWS_1251;
+    }
+    WINDOWS_1252
+}
+
+/// `body` decoded from `encoding`, with malformed sequences replaced. The
+/// byte order mark, if any, takes precedence and is stripped.
+pub fn decode<'a>(body: &'a [u8], encoding: &'static Encoding) -> Cow<'a, str> {
+    encoding.decode(body).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_declared_encodings() {
+        let latin1 = b"caf\xe9";
+        assert_eq!(detect_encoding(Some("ISO-8859-1"), latin1), WINDOWS_1252);
+        assert_eq!(detect_encoding(Some("utf-8"), b"\xef\xbb\xbfabc"), UTF_8);
+        assert_eq!(detect_encoding(Some("latin1"), b"\xef\xbb\xbfabc"), UTF_8);
+
+        let meta = b"<html><head><meta charset=\"windows-1251\"></head>";
+        assert_eq!(detect_encoding(None, meta), WINDOWS_1251);
+        let http_equiv =
+            b"<meta http-equiv=\"Content-Type\" content=\"text/html; charset=Shift_JIS\">";
+        assert_eq!(detect_encoding(None, http_equiv), SHIFT_JIS);
+        // The content type wins over the document.
+        assert_eq!(detect_encoding(Some("utf-8"), meta), UTF_8);
+
+        let xml = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><rss/>";
+        assert_eq!(detect_encoding(None, xml), WINDOWS_1252);
+        // Unknown labels fall through to the next source.
+        assert_eq!(detect_encoding(Some("x-unknown"), meta), WINDOWS_1251);
+    }
+
+    #[test]
+    fn test_guessed_encodings() {
+        assert_eq!(guess_encoding("naïve café".as_bytes()), UTF_8);
+
+        let (russian, _, _) = WINDOWS_1251.encode("Привет, мир! Это тестовая страница.");
+        assert_eq!(guess_encoding(&russian), WINDOWS_1251);
+
+        let (japanese, _, _) = SHIFT_JIS.encode("こんにちは、世界。テストページです。");
+        assert_eq!(guess_encoding(&japanese), SHIFT_JIS);
+
+        let (french, _, _) = WINDOWS_1252.encode("Le café de la Réunion est très bon.");
+        assert_eq!(guess_encoding(&french), WINDOWS_1252);
+        assert_eq!(
+            decode(&french, guess_encoding(&french)),
+            "Le café de la Réunion est très bon."
+        );
+    }
+}
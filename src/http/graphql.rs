@@ -0,0 +1,88 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Body of a GraphQL response, see [`HttpResponse::graphql`](super::HttpResponse::graphql).
+///
+/// A response may carry both `data` and `errors` when only some fields
+/// failed to resolve.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct GraphqlResponse {
+    #[serde(default)]
+    pub data: Option<Value>,
+    #[serde(default)]
+    pub errors: Vec<GraphqlError>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GraphqlError {
+    pub message: String,
+    /// Path of the field that failed, e.g. `["product", "price"]`.
+    #[serde(default)]
+    pub path: Option<Vec<Value>>,
+    #[serde(default)]
+    pub extensions: Option<Value>,
+}
+
+impl GraphqlResponse {
+    /// All error messages joined with `; `.
+    pub fn error_message(&self) -> String {
+        self.errors
+            .iter()
+            .map(|error| error.message.as_str())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::SpiderCallback;
+    use crate::http::ResponseType;
+    use crate::{HttpRequest, HttpResponse, ScraperError};
+
+    use serde_json::json;
+
+    use url::Url;
+
+    fn response(request: HttpRequest, body: &str) -> HttpResponse {
+        HttpResponse {
+            response_type: ResponseType::Json,
+            from_request: Box::new(request.clone()),
+            ..HttpResponse::for_test(&request.url, body)
+        }
+    }
+
+    #[test]
+    fn test_graphql_request_and_response() {
+        let request = HttpRequest::graphql(
+            Url::parse("https://shop.com/graphql").unwrap(),
+            SpiderCallback::ParseItem,
+            1,
+            "query Product($id: ID!) { product(id: $id) { name } }",
+            json!({"id": "42"}),
+        );
+        assert_eq!(request.method, reqwest::Method::POST);
+        assert_eq!(request.headers["Content-Type"], "application/json");
+        let body: serde_json::Value = serde_json::from_str(request.body.as_ref().unwrap()).unwrap();
+        assert_eq!(body["variables"]["id"], "42");
+        assert!(body["query"].as_str().unwrap().starts_with("query Product"));
+
+        let ok = response(
+            request.clone(),
+            r#"{"data": {"product": {"name": "Lamp"}}}"#,
+        );
+        assert_eq!(ok.graphql_data().unwrap()["product"]["name"], "Lamp");
+
+        let partial = response(
+            request,
+            r#"{"data": {"product": null},
+                "errors": [{"message": "Not found", "path": ["product"]}]}"#,
+        );
+        let body = partial.graphql().unwrap();
+        assert_eq!(body.errors[0].path, Some(vec![json!("product")]));
+        let error = partial.graphql_data().unwrap_err();
+        assert!(
+            matches!(error, ScraperError::ParsingError(message) if message.contains("Not found"))
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use super::Headers;
+use std::collections::HashSet;
+
+/// Response headers dropped by [`HeaderFilter::default`].
+pub const SENSITIVE_HEADERS: &[&str] = &[
+    "set-cookie",
+    "cookie",
+    "authorization",
+    "proxy-authorization",
+    "proxy-authenticate",
+];
+
+/// Response headers the crate itself reads, e.g. for response type
+/// detection, retry delays, change tracking and link extraction. An
+/// allowlist always keeps them; only the denylist drops them.
+pub const REQUIRED_HEADERS: &[&str] = &[
+    "content-type",
+    "content-encoding",
+    "retry-after",
+    "etag",
+    "last-modified",
+    "location",
+    "link",
+    "x-robots-tag",
+];
+
+/// Decides which response headers are captured into `HttpResponse::headers`
+/// and written to storage metadata.
+///
+/// A header is kept if it is in the allowlist (when one is set) or in
+/// [`REQUIRED_HEADERS`], and not in the denylist. Names are compared
+/// case-insensitively.
+#[derive(Debug, Clone)]
+pub struct HeaderFilter {
+    allow: Option<HashSet<String>>,
+    deny: HashSet<String>,
+}
+
+impl Default for HeaderFilter {
+    fn default() -> Self {
+        Self::capture_all().with_denied(SENSITIVE_HEADERS.to_vec())
+    }
+}
+
+impl HeaderFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps every header, including the sensitive ones.
+    pub fn capture_all() -> Self {
+        Self {
+            allow: None,
+            deny: HashSet::new(),
+        }
+    }
+
+    /// Restricts capture to the given headers.
+    pub fn with_allowed<H: AsRef<str>>(mut self, headers: Vec<H>) -> Self {
+        self.allow
+            .get_or_insert_with(HashSet::new)
+            .extend(headers.iter().map(|h| h.as_ref().to_ascii_lowercase()));
+        self
+    }
+
+    pub fn with_denied<H: AsRef<str>>(mut self, headers: Vec<H>) -> Self {
+        self.deny
+            .extend(headers.iter().map(|h| h.as_ref().to_ascii_lowercase()));
+        self
+    }
+
+    pub fn is_allowed(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        if let Some(allow) = &self.allow {
+            if !allow.contains(&name) && !REQUIRED_HEADERS.contains(&name.as_str()) {
+                return false;
+            }
+        }
+        !self.deny.contains(&name)
+    }
+
+    pub fn apply(&self, headers: &Headers) -> Headers {
+        headers
+            .iter()
+            .filter(|(name, _)| self.is_allowed(name))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers() -> Headers {
+        [
+            ("content-type", "text/html"),
+            ("set-cookie", "session=secret"),
+            ("etag", "\"abc\""),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn test_default_drops_sensitive_headers() {
+        let filtered = HeaderFilter::default().apply(&headers());
+        assert_eq!(filtered.len(), 2);
+        assert!(!filtered.contains_key("set-cookie"));
+        assert_eq!(HeaderFilter::capture_all().apply(&headers()).len(), 3);
+    }
+
+    #[test]
+    fn test_allowlist_is_case_insensitive_and_denylist_wins() {
+        let filter = HeaderFilter::capture_all()
+            .with_allowed(vec!["Content-Type", "ETag"])
+            .with_denied(vec!["etag"]);
+        let filtered = filter.apply(&headers());
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered["content-type"], "text/html");
+    }
+
+    #[test]
+    fn test_allowlist_keeps_required_headers() {
+        let filter = HeaderFilter::default().with_allowed(vec!["x-request-id"]);
+        let filtered = filter.apply(&headers());
+        assert_eq!(filtered.len(), 2);
+        assert_eq!(filtered["content-type"], "text/html");
+        assert_eq!(filtered["etag"], "\"abc\"");
+    }
+}
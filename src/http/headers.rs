@@ -0,0 +1,260 @@
+use serde::de::{Deserializer, MapAccess, Visitor};
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Index;
+
+/// HTTP headers in the order they were added, keeping repeated headers such
+/// as `Set-Cookie` or `Link`. Lookups ignore the case of header names.
+///
+/// Serialized as a map of names to values, with the values of repeated
+/// headers in an array.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Headers(Vec<(String, String)>);
+
+impl Headers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The first value of `name`.
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.0
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// Every value of `name`, in order.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.0
+            .iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Sets `name` to `value`, replacing its previous values. Returns the
+    /// first of them.
+    pub fn insert<K: Into<String>, V: Into<String>>(
+        &mut self,
+        name: K,
+        value: V,
+    ) -> Option<String> {
+        let name = name.into();
+        let previous = self.remove(&name);
+        self.0.push((name, value.into()));
+        previous
+    }
+
+    /// Adds another value for `name`, keeping the existing ones.
+    pub fn append<K: Into<String>, V: Into<String>>(&mut self, name: K, value: V) {
+        self.0.push((name.into(), value.into()));
+    }
+
+    /// Removes every value of `name`, returning the first one.
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let mut removed = None;
+        self.0.retain(|(key, value)| {
+            if !key.eq_ignore_ascii_case(name) {
+                return true;
+            }
+            removed.get_or_insert_with(|| value.clone());
+            false
+        });
+        removed
+    }
+
+    /// Name and value pairs, repeated headers once per value.
+    pub fn iter(&self) -> Iter<'_> {
+        self.0.iter().map(pair)
+    }
+
+    /// Number of values.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Headers from `iter` replace those of the same name, all values of a
+/// repeated header being kept.
+impl<K: Into<String>, V: Into<String>> Extend<(K, V)> for Headers {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let added: Vec<(String, String)> = iter
+            .into_iter()
+            .map(|(name, value)| (name.into(), value.into()))
+            .collect();
+        for (name, _) in &added {
+            self.remove(name);
+        }
+        self.0.extend(added);
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Headers {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(name, value)| (name.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+impl From<HashMap<String, String>> for Headers {
+    fn from(headers: HashMap<String, String>) -> Self {
+        headers.into_iter().collect()
+    }
+}
+
+fn pair((name, value): &(String, String)) -> (&String, &String) {
+    (name, value)
+}
+
+pub type Iter<'a> = std::iter::Map<
+    std::slice::Iter<'a, (String, String)>,
+    fn(&(String, String)) -> (&String, &String),
+>;
+
+impl<'a> IntoIterator for &'a Headers {
+    type Item = (&'a String, &'a String);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for Headers {
+    type Item = (String, String);
+    type IntoIter = std::vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl Index<&str> for Headers {
+    type Output = String;
+
+    /// The first value of `name`. Panics if there is none.
+    fn index(&self, name: &str) -> &String {
+        self.get(name)
+            .unwrap_or_else(|| panic!("no header named {}", name))
+    }
+}
+
+impl Serialize for Headers {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut names: Vec<&str> = Vec::new();
+        for (name, _) in &self.0 {
+            if !names.iter().any(|seen| seen.eq_ignore_ascii_case(name)) {
+                names.push(name);
+            }
+        }
+        let mut map = serializer.serialize_map(Some(names.len()))?;
+        for name in names {
+            let values: Vec<&str> = self.get_all(name).collect();
+            match values.as_slice() {
+                [value] => map.serialize_entry(name, value)?,
+                values => map.serialize_entry(name, values)?,
+            }
+        }
+        map.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HeaderValues {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl<'de> Deserialize<'de> for Headers {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct HeadersVisitor;
+
+        impl<'de> Visitor<'de> for HeadersVisitor {
+            type Value = Headers;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of header names to a value or an array of values")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Headers, A::Error> {
+                let mut headers = Headers::new();
+                while let Some((name, values)) = access.next_entry::<String, HeaderValues>()? {
+                    match values {
+                        HeaderValues::One(value) => headers.append(name, value),
+                        HeaderValues::Many(values) => {
+                            for value in values {
+                                headers.append(name.clone(), value);
+                            }
+                        }
+                    }
+                }
+                Ok(headers)
+            }
+        }
+
+        deserializer.deserialize_map(HeadersVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_repeated_headers_and_case_insensitive_lookup() {
+        let mut headers = Headers::new();
+        headers.append("Set-Cookie", "a=1");
+        headers.append("set-cookie", "b=2");
+        headers.insert("Content-Type", "text/html");
+
+        assert_eq!(headers.get("SET-COOKIE").unwrap(), "a=1");
+        assert_eq!(
+            headers.get_all("set-cookie").collect::<Vec<_>>(),
+            ["a=1", "b=2"]
+        );
+        assert_eq!(headers["content-type"], "text/html");
+        assert_eq!(headers.len(), 3);
+
+        assert_eq!(headers.insert("set-cookie", "c=3").as_deref(), Some("a=1"));
+        assert_eq!(headers.get_all("Set-Cookie").collect::<Vec<_>>(), ["c=3"]);
+
+        headers.extend([("Link", "</2>; rel=next"), ("link", "</1>; rel=prev")]);
+        headers.extend([("content-type", "application/json")]);
+        assert_eq!(headers.get_all("link").count(), 2);
+        assert_eq!(headers["Content-Type"], "application/json");
+    }
+
+    #[test]
+    fn test_serializes_repeated_headers_as_arrays() {
+        let headers: Headers = [("vary", "accept"), ("Vary", "cookie"), ("etag", "\"x\"")]
+            .into_iter()
+            .collect();
+        let value = serde_json::to_value(&headers).unwrap();
+        assert_eq!(
+            value,
+            json!({"vary": ["accept", "cookie"], "etag": "\"x\""})
+        );
+
+        let headers: Headers = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            headers.get_all("vary").collect::<Vec<_>>(),
+            ["accept", "cookie"]
+        );
+        assert_eq!(headers["etag"], "\"x\"");
+    }
+}
@@ -1,5 +1,24 @@
+pub mod charset;
+pub mod graphql;
+pub mod header_filter;
+pub mod headers;
+pub mod multipart;
 pub(crate) mod request;
 pub(crate) mod response;
+pub mod signing;
+pub mod timeouts;
+pub mod tls;
+pub mod version;
 
-pub use request::HttpRequest;
-pub use response::{HttpResponse, ResponseType};
+pub use encoding_rs::Encoding;
+pub use graphql::{GraphqlError, GraphqlResponse};
+pub use header_filter::HeaderFilter;
+pub use headers::Headers;
+pub use mime::Mime;
+pub use multipart::{MultipartForm, MultipartPart};
+pub use request::{HttpRequest, IDEMPOTENCY_KEY_HEADER};
+pub use response::{ContentEncoding, HttpResponse, ResponseType};
+pub use signing::{AwsSigV4Signer, HmacSigner, RequestSigner};
+pub use timeouts::Timeouts;
+pub use tls::CertificateInfo;
+pub use version::HttpVersion;
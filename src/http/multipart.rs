@@ -0,0 +1,91 @@
+use reqwest::multipart::{Form, Part};
+use serde::Serialize;
+
+/// A `multipart/form-data` body, see [`HttpRequest::from_multipart`](super::HttpRequest::from_multipart).
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MultipartForm {
+    parts: Vec<MultipartPart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum MultipartPart {
+    Text {
+        name: String,
+        value: String,
+    },
+    /// File contents are not serialized, so requests stay small in
+    /// frontier snapshots and logs.
+    File {
+        name: String,
+        file_name: String,
+        mime: Option<String>,
+        #[serde(skip_serializing)]
+        content: Vec<u8>,
+    },
+}
+
+impl MultipartForm {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.parts.push(MultipartPart::Text {
+            name: name.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    pub fn file<N, F, C>(mut self, name: N, file_name: F, content: C) -> Self
+    where
+        N: Into<String>,
+        F: Into<String>,
+        C: Into<Vec<u8>>,
+    {
+        self.parts.push(MultipartPart::File {
+            name: name.into(),
+            file_name: file_name.into(),
+            mime: None,
+            content: content.into(),
+        });
+        self
+    }
+
+    /// Sets the content type of the last file part, e.g. `image/png`.
+    pub fn with_mime(mut self, mime: &str) -> Self {
+        if let Some(MultipartPart::File {
+            mime: part_mime, ..
+        }) = self.parts.last_mut()
+        {
+            *part_mime = Some(mime.to_string());
+        }
+        self
+    }
+
+    pub fn parts(&self) -> &[MultipartPart] {
+        &self.parts
+    }
+
+    pub(crate) fn to_form(&self) -> Result<Form, reqwest::Error> {
+        let mut form = Form::new();
+        for part in &self.parts {
+            form = match part {
+                MultipartPart::Text { name, value } => form.text(name.clone(), value.clone()),
+                MultipartPart::File {
+                    name,
+                    file_name,
+                    mime,
+                    content,
+                } => {
+                    let mut file = Part::bytes(content.clone()).file_name(file_name.clone());
+                    if let Some(mime) = mime {
+                        file = file.mime_str(mime)?;
+                    }
+                    form.part(name.clone(), file)
+                }
+            };
+        }
+        Ok(form)
+    }
+}
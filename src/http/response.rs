@@ -1,18 +1,27 @@
 use crate::core::retry::RetryCategory;
+use crate::parser::xml::XmlDocument;
+use crate::{ScraperError, ScraperResult};
 use chrono::prelude::*;
+use log::warn;
+use mime::Mime;
 use serde_json::Value;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::io::Read;
+use std::sync::OnceLock;
 use url::Url;
 
-use super::HttpRequest;
+use super::{charset, Encoding, GraphqlResponse, Headers, HttpRequest};
 
 #[derive(Debug, Clone)]
 pub struct HttpResponse {
     pub url: Url,
     pub status: u16,
-    pub headers: HashMap<String, String>,
+    pub headers: Headers,
+    /// Body as received, the only way to read binary responses.
     pub raw_body: Vec<u8>,
-    pub decoded_body: String,
+    /// Text of `raw_body`, decoded on first [`HttpResponse::body_text`] call.
+    pub decoded_body: OnceLock<String>,
     pub timestamp: DateTime<Utc>,
     pub retry_count: usize,
     pub retry_history: HashMap<RetryCategory, usize>,
@@ -21,14 +30,19 @@ pub struct HttpResponse {
     pub from_request: Box<HttpRequest>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ResponseType {
     Html,
     Json,
+    Xml,
     Text,
     Binary,
 }
 
+/// Largest body [`ContentEncoding::decode`] produces, so a small compressed
+/// response can't expand into an unbounded allocation.
+pub const MAX_DECOMPRESSED_SIZE: u64 = 256 * 1024 * 1024;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ContentEncoding {
     Gzip,
@@ -37,52 +51,297 @@ pub enum ContentEncoding {
     None,
 }
 
-impl HttpResponse {
-    pub fn detect_content_type(
-        &self,
-        headers: &HashMap<String, String>,
-        body: &str,
-    ) -> ResponseType {
-        fn detect_content_type_from_body(body: &str) -> ResponseType {
-            let trimmed_body = body.trim_start();
-            if trimmed_body.starts_with('{') || trimmed_body.starts_with('[') {
-                ResponseType::Json
-            } else if trimmed_body.starts_with("<!DOCTYPE") || trimmed_body.starts_with("<html") {
-                ResponseType::Html
-            } else {
-                ResponseType::Text
+impl ContentEncoding {
+    /// Coding named by a `Content-Encoding` token, `None` for `identity`
+    /// and unknown codings.
+    fn from_token(token: &str) -> Option<Self> {
+        match token.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(ContentEncoding::Gzip),
+            "deflate" => Some(ContentEncoding::Deflate),
+            "br" => Some(ContentEncoding::Brotli),
+            "identity" => Some(ContentEncoding::None),
+            _ => None,
+        }
+    }
+
+    /// Undoes this coding. Deflate bodies may come with or without their
+    /// zlib wrapper, as many servers send raw deflate data. Fails once the
+    /// output exceeds [`MAX_DECOMPRESSED_SIZE`].
+    pub fn decode(&self, body: &[u8]) -> std::io::Result<Vec<u8>> {
+        self.decode_capped(body, MAX_DECOMPRESSED_SIZE)
+    }
+
+    fn decode_capped(&self, body: &[u8], limit: u64) -> std::io::Result<Vec<u8>> {
+        let mut decoded = Vec::new();
+        let read_capped = |reader: &mut dyn Read, decoded: &mut Vec<u8>| {
+            reader.take(limit + 1).read_to_end(decoded).map(|_| ())
+        };
+        match self {
+            ContentEncoding::Gzip => {
+                read_capped(&mut flate2::read::MultiGzDecoder::new(body), &mut decoded)?;
+            }
+            ContentEncoding::Deflate => {
+                if read_capped(&mut flate2::read::ZlibDecoder::new(body), &mut decoded).is_err() {
+                    decoded.clear();
+                    read_capped(&mut flate2::read::DeflateDecoder::new(body), &mut decoded)?;
+                }
+            }
+            ContentEncoding::Brotli => {
+                read_capped(&mut brotli::Decompressor::new(body, 4096), &mut decoded)?;
             }
+            ContentEncoding::None => decoded.extend_from_slice(body),
+        }
+        if decoded.len() as u64 > limit {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("decompressed body exceeds {} bytes", limit),
+            ));
+        }
+        Ok(decoded)
+    }
+
+    /// Decompresses a body sent with `content_encoding`, e.g. `gzip` or
+    /// `deflate, br`. Codings are undone in the reverse of the order they
+    /// are listed in. Returns `None` when a coding is not supported, so the
+    /// caller can keep the body as received along with its header.
+    pub fn decompress(content_encoding: &str, body: &[u8]) -> ScraperResult<Option<Vec<u8>>> {
+        let tokens: Vec<&str> = content_encoding
+            .rsplit(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .collect();
+        let Some(codings) = tokens
+            .iter()
+            .map(|token| Self::from_token(token))
+            .collect::<Option<Vec<_>>>()
+        else {
+            warn!(
+                "Unsupported content encoding {}, keeping the body as received",
+                content_encoding
+            );
+            return Ok(None);
+        };
+
+        let mut body = Cow::Borrowed(body);
+        for (token, coding) in tokens.iter().zip(codings) {
+            let decoded = coding.decode(&body).map_err(|e| {
+                ScraperError::DecodingError(format!("Invalid {} body: {}", token, e))
+            })?;
+            body = Cow::Owned(decoded);
+        }
+        Ok(Some(body.into_owned()))
+    }
+}
+
+#[cfg(test)]
+impl HttpResponse {
+    /// A `200` HTML response with `body`, to a `Bootstrap` request for `url`.
+    pub(crate) fn for_test<U: AsRef<str>, B: AsRef<[u8]>>(url: U, body: B) -> Self {
+        let url = Url::parse(url.as_ref()).unwrap();
+        Self {
+            url: url.clone(),
+            status: 200,
+            headers: Headers::new(),
+            raw_body: body.as_ref().to_vec(),
+            decoded_body: OnceLock::new(),
+            timestamp: Utc::now(),
+            retry_count: 0,
+            retry_history: HashMap::new(),
+            meta: None,
+            response_type: ResponseType::Html,
+            from_request: Box::new(HttpRequest::new(
+                url,
+                crate::core::SpiderCallback::Bootstrap,
+                0,
+            )),
+        }
+    }
+}
+
+impl HttpResponse {
+    pub fn detect_content_type(&self, headers: &Headers, body: &str) -> ResponseType {
+        ResponseType::detect(headers, body.as_bytes())
+    }
+
+    /// Decodes the body from its [`encoding`](Self::encoding), caching the
+    /// result for later calls. Binary responses are never decoded, read
+    /// their `raw_body` instead.
+    pub fn body_text(&self) -> ScraperResult<&str> {
+        if let Some(text) = self.decoded_body.get() {
+            return Ok(text);
+        }
+        if !self.response_type.is_text() {
+            return Err(ScraperError::DecodingError(format!(
+                "{} has a binary body of {} bytes",
+                self.url,
+                self.raw_body.len()
+            )));
+        }
+        let text = charset::decode(&self.raw_body, self.encoding());
+        Ok(self.decoded_body.get_or_init(|| text.into_owned()))
+    }
+
+    /// Encoding of the body, see [`charset::detect_encoding`].
+    pub fn encoding(&self) -> &'static Encoding {
+        charset::detect_encoding(self.charset().as_deref(), &self.raw_body)
+    }
+
+    /// Parses the body as an XML document.
+    pub fn xml(&self) -> ScraperResult<XmlDocument> {
+        XmlDocument::parse(self.body_text()?).map_err(|e| ScraperError::ParsingError(e.to_string()))
+    }
+
+    /// Parses the body as a GraphQL response.
+    pub fn graphql(&self) -> ScraperResult<GraphqlResponse> {
+        serde_json::from_str(self.body_text()?)
+            .map_err(|e| ScraperError::ParsingError(format!("Invalid GraphQL response: {}", e)))
+    }
+
+    /// The `data` of a GraphQL response, or a parsing error carrying the
+    /// messages if the response reports any error.
+    pub fn graphql_data(&self) -> ScraperResult<Value> {
+        let response = self.graphql()?;
+        let error = |message: String| ScraperError::ParsingError(message);
+        if !response.errors.is_empty() {
+            return Err(error(format!(
+                "GraphQL errors: {}",
+                response.error_message()
+            )));
+        }
+        response
+            .data
+            .ok_or_else(|| error("GraphQL response without data".to_string()))
+    }
+
+    /// Wait requested by the `Retry-After` header, given in seconds or as an
+    /// HTTP date. Dates in the past mean no wait.
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        let value = self.headers.get("retry-after")?.trim();
+        if let Ok(seconds) = value.parse::<u64>() {
+            return Some(std::time::Duration::from_secs(seconds));
         }
+        let date = DateTime::parse_from_rfc2822(value).ok()?;
+        Some(
+            date.with_timezone(&Utc)
+                .signed_duration_since(Utc::now())
+                .to_std()
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Whether the server answered a conditional request with
+    /// `304 Not Modified`.
+    pub fn is_not_modified(&self) -> bool {
+        self.status == 304
+    }
+
+    /// Media type of the `content-type` header, with its parameters.
+    pub fn mime(&self) -> Option<Mime> {
+        self.headers.get("content-type")?.parse().ok()
+    }
+
+    /// `charset` parameter of the `content-type` header, lowercased.
+    pub fn charset(&self) -> Option<String> {
+        let mime = self.mime()?;
+        Some(mime.get_param(mime::CHARSET)?.as_str().to_ascii_lowercase())
+    }
+
+    pub fn get_content_encoding(&self) -> ContentEncoding {
+        self.headers
+            .get("content-encoding")
+            .and_then(|encoding| ContentEncoding::from_token(encoding))
+            .unwrap_or(ContentEncoding::None)
+    }
+
+    /// Decompresses `raw_body` according to its `content-encoding` headers,
+    /// for bodies that were stored or fetched still compressed. The headers
+    /// are removed and the response type detected again once the body is
+    /// the real document. On error or an unsupported coding the response is
+    /// left untouched.
+    pub fn decompress(&mut self) -> ScraperResult<()> {
+        let content_encoding = self
+            .headers
+            .get_all("content-encoding")
+            .collect::<Vec<_>>()
+            .join(", ");
+        if content_encoding.is_empty() {
+            return Ok(());
+        }
+        let Some(raw_body) = ContentEncoding::decompress(&content_encoding, &self.raw_body)? else {
+            return Ok(());
+        };
+        self.raw_body = raw_body;
+        self.headers.remove("content-encoding");
+        self.headers.remove("content-length");
+        self.decoded_body = OnceLock::new();
+        self.response_type = ResponseType::detect(&self.headers, &self.raw_body);
+        Ok(())
+    }
+}
 
+impl ResponseType {
+    /// Type from the `content-type` header, or sniffed from the start of
+    /// the body when the header is missing or not a valid media type.
+    pub fn detect(headers: &Headers, body: &[u8]) -> Self {
         headers
             .get("content-type")
-            .filter(|content_type| !content_type.trim().is_empty())
-            .map(|content_type| {
-                if content_type.contains("text/html") {
-                    ResponseType::Html
-                } else if content_type.contains("application/json") {
-                    ResponseType::Json
-                } else if content_type.contains("text/") {
-                    ResponseType::Text
-                } else {
-                    ResponseType::Binary
-                }
-            })
-            .unwrap_or_else(|| detect_content_type_from_body(body))
+            .and_then(|content_type| content_type.parse::<Mime>().ok())
+            .map(|mime| Self::from_mime(&mime))
+            .unwrap_or_else(|| Self::sniff(body))
     }
 
-    pub fn get_content_encoding(&self) -> ContentEncoding {
-        if let Some(encoding) = self.headers.get("content-encoding") {
-            match encoding.to_lowercase().as_str() {
-                "gzip" => ContentEncoding::Gzip,
-                "deflate" => ContentEncoding::Deflate,
-                "br" => ContentEncoding::Brotli,
-                _ => ContentEncoding::None,
+    /// Type of a media type, by its `+json` or `+xml` suffix if it has one.
+    pub fn from_mime(mime: &Mime) -> Self {
+        let subtype = mime.subtype().as_str();
+        let suffix = mime.suffix().map(|suffix| suffix.as_str());
+        match (mime.type_(), subtype, suffix) {
+            (mime::TEXT, "html", _) | (mime::APPLICATION, "xhtml", Some("xml")) => {
+                ResponseType::Html
             }
+            (_, "json", _) | (_, _, Some("json")) => ResponseType::Json,
+            (_, "xml", _) | (_, _, Some("xml")) => ResponseType::Xml,
+            (mime::TEXT, _, _) => ResponseType::Text,
+            (
+                mime::APPLICATION,
+                "javascript"
+                | "ecmascript"
+                | "x-javascript"
+                | "x-www-form-urlencoded"
+                | "csv"
+                | "yaml"
+                | "x-yaml"
+                | "graphql",
+                _,
+            ) => ResponseType::Text,
+            _ => ResponseType::Binary,
+        }
+    }
+
+    /// Type of a body without a content type. Bodies with control bytes
+    /// other than whitespace in their first bytes are binary.
+    pub fn sniff(body: &[u8]) -> Self {
+        let head = &body[..body.len().min(512)];
+        let is_control = |b: &u8| b.is_ascii_control() && !b"\t\n\x0c\r\x1b".contains(b);
+        if head.iter().any(is_control) {
+            return ResponseType::Binary;
+        }
+        let text = String::from_utf8_lossy(head);
+        let text = text.trim_start();
+        if text.starts_with('{') || text.starts_with('[') {
+            ResponseType::Json
+        } else if text.starts_with("<!DOCTYPE") || text.starts_with("<html") {
+            ResponseType::Html
+        } else if text.starts_with("<?xml") {
+            ResponseType::Xml
         } else {
-            ContentEncoding::None
+            ResponseType::Text
         }
     }
+
+    /// Whether bodies of this type are text that can be decoded.
+    pub fn is_text(&self) -> bool {
+        *self != ResponseType::Binary
+    }
 }
 
 impl std::fmt::Display for ResponseType {
@@ -90,8 +349,155 @@ impl std::fmt::Display for ResponseType {
         match self {
             ResponseType::Html => write!(f, "html"),
             ResponseType::Json => write!(f, "json"),
+            ResponseType::Xml => write!(f, "xml"),
             ResponseType::Text => write!(f, "text"),
             ResponseType::Binary => write!(f, "binary"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_type_detection() {
+        let headers = |content_type: &str| Headers::from_iter([("content-type", content_type)]);
+        let detect = |content_type: &str| ResponseType::detect(&headers(content_type), b"");
+        assert_eq!(detect("text/html; charset=utf-8"), ResponseType::Html);
+        assert_eq!(detect("Text/HTML"), ResponseType::Html);
+        assert_eq!(detect("application/xhtml+xml"), ResponseType::Html);
+        assert_eq!(detect("application/ld+json"), ResponseType::Json);
+        assert_eq!(
+            detect("application/vnd.api+json; charset=utf-8"),
+            ResponseType::Json
+        );
+        assert_eq!(detect("application/rss+xml"), ResponseType::Xml);
+        assert_eq!(detect("text/xml"), ResponseType::Xml);
+        assert_eq!(detect("application/javascript"), ResponseType::Text);
+        assert_eq!(detect("text/csv"), ResponseType::Text);
+        assert_eq!(detect("image/png"), ResponseType::Binary);
+        assert_eq!(detect("application/gzip"), ResponseType::Binary);
+        // Not fooled by substrings of other media types.
+        assert_eq!(detect("application/x-xmlrpc-binary"), ResponseType::Binary);
+        assert_eq!(
+            detect("multipart/form-data; boundary=\"text/html\""),
+            ResponseType::Binary
+        );
+        // Invalid media types fall back to sniffing.
+        assert_eq!(
+            ResponseType::detect(&headers("not a type"), b"<html>"),
+            ResponseType::Html
+        );
+
+        let sniff = |body: &[u8]| ResponseType::detect(&Headers::new(), body);
+        assert_eq!(sniff(b"  {\"a\": 1}"), ResponseType::Json);
+        assert_eq!(sniff(b"<!DOCTYPE html>"), ResponseType::Html);
+        assert_eq!(sniff("plain caf\u{e9}".as_bytes()), ResponseType::Text);
+        assert_eq!(sniff(&[0x1f, 0x8b, 0x08, 0x00]), ResponseType::Binary);
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n"), ResponseType::Binary);
+        assert_eq!(sniff(b"caf\xe9 cr\xe8me"), ResponseType::Text);
+    }
+
+    #[test]
+    fn test_mime_charset_and_transcoding() {
+        let response = HttpResponse {
+            headers: Headers::from_iter([("content-type", "text/html; Charset=\"ISO-8859-1\"")]),
+            ..HttpResponse::for_test("https://example.com/", b"<p>caf\xe9</p>")
+        };
+        assert_eq!(response.mime().unwrap().essence_str(), "text/html");
+        assert_eq!(response.charset().as_deref(), Some("iso-8859-1"));
+        assert_eq!(response.encoding(), encoding_rs::WINDOWS_1252);
+        assert_eq!(response.body_text().unwrap(), "<p>café</p>");
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let response = |retry_after: &str| HttpResponse {
+            status: 429,
+            headers: Headers::from_iter([("Retry-After", retry_after)]),
+            response_type: ResponseType::Text,
+            ..HttpResponse::for_test("https://example.com/", "")
+        };
+        let seconds = |retry_after: &str| response(retry_after).retry_after().map(|d| d.as_secs());
+        assert_eq!(seconds("120"), Some(120));
+        assert_eq!(seconds("Wed, 21 Oct 2015 07:28:00 GMT"), Some(0));
+        let in_a_minute = (Utc::now() + chrono::Duration::seconds(61)).to_rfc2822();
+        assert!(seconds(&in_a_minute).is_some_and(|s| (59..=61).contains(&s)));
+        assert_eq!(seconds("soon"), None);
+    }
+
+    #[test]
+    fn test_decompresses_stacked_content_encodings() {
+        use flate2::write::{DeflateEncoder, GzEncoder};
+        use flate2::Compression;
+        use std::io::Write;
+
+        let html = b"<html><body>compressed</body></html>";
+        let mut deflate = DeflateEncoder::new(Vec::new(), Compression::default());
+        deflate.write_all(html).unwrap();
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(&deflate.finish().unwrap()).unwrap();
+        let body = gzip.finish().unwrap();
+
+        let mut response = HttpResponse {
+            headers: Headers::from_iter([
+                ("content-encoding", "deflate"),
+                ("Content-Encoding", "gzip"),
+            ]),
+            response_type: ResponseType::detect(&Headers::new(), &body),
+            ..HttpResponse::for_test("https://example.com/", &body)
+        };
+        assert_eq!(response.response_type, ResponseType::Binary);
+        response.decompress().unwrap();
+        assert_eq!(response.raw_body, html);
+        assert_eq!(response.response_type, ResponseType::Html);
+        assert_eq!(response.get_content_encoding(), ContentEncoding::None);
+        assert_eq!(
+            response.body_text().unwrap(),
+            "<html><body>compressed</body></html>"
+        );
+
+        let mut brotli = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+        brotli.write_all(html).unwrap();
+        let body = brotli.into_inner();
+        assert_eq!(
+            ContentEncoding::decompress("br", &body).unwrap().unwrap(),
+            html
+        );
+
+        assert!(matches!(
+            ContentEncoding::decompress("gzip", html),
+            Err(ScraperError::DecodingError(_))
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_content_encoding_keeps_the_body() {
+        let mut response = HttpResponse {
+            headers: Headers::from_iter([("content-encoding", "zstd")]),
+            ..HttpResponse::for_test("https://example.com/", "compressed")
+        };
+        assert_eq!(
+            ContentEncoding::decompress("gzip, zstd", b"x").unwrap(),
+            None
+        );
+        response.decompress().unwrap();
+        assert_eq!(response.raw_body, b"compressed");
+        assert_eq!(response.headers.get("content-encoding").unwrap(), "zstd");
+    }
+
+    #[test]
+    fn test_decompressed_size_is_capped() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::best());
+        gzip.write_all(&[0; 4096]).unwrap();
+        let body = gzip.finish().unwrap();
+        let gzip = ContentEncoding::Gzip;
+        assert_eq!(gzip.decode_capped(&body, 4096).unwrap().len(), 4096);
+        assert!(gzip.decode_capped(&body, 4095).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+use serde::Serialize;
+use std::time::Duration;
+
+/// Connect, read and total timeouts of a request. Unset timeouts fall back
+/// to the spider config, see [`Timeouts::or`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+pub struct Timeouts {
+    /// Time to establish the connection.
+    pub connect: Option<Duration>,
+    /// Time without receiving any data once connected.
+    pub read: Option<Duration>,
+    /// Time for the whole request, body included.
+    pub total: Option<Duration>,
+}
+
+impl Timeouts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_connect(mut self, timeout: Duration) -> Self {
+        self.connect = Some(timeout);
+        self
+    }
+
+    pub fn with_read(mut self, timeout: Duration) -> Self {
+        self.read = Some(timeout);
+        self
+    }
+
+    pub fn with_total(mut self, timeout: Duration) -> Self {
+        self.total = Some(timeout);
+        self
+    }
+
+    /// These timeouts, with the unset ones taken from `fallback`.
+    pub fn or(self, fallback: Timeouts) -> Self {
+        Self {
+            connect: self.connect.or(fallback.connect),
+            read: self.read.or(fallback.read),
+            total: self.total.or(fallback.total),
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use serde::Serialize;
+
+/// Subject and issuer of the certificate a server presented, e.g.
+/// `C=US, O=Example, CN=example.com`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+}
+
+impl CertificateInfo {
+    /// Reads the subject and issuer of a DER encoded X.509 certificate.
+    pub fn from_der(der: &[u8]) -> Option<Self> {
+        let certificate = Der(der).expect(SEQUENCE)?;
+        let mut tbs = Der(Der(certificate).expect(SEQUENCE)?);
+        // The explicitly tagged version is absent from v1 certificates.
+        if tbs.0.first() == Some(&VERSION) {
+            tbs.read()?;
+        }
+        tbs.read()?; // serial number
+        tbs.read()?; // signature algorithm
+        let issuer = name(tbs.expect(SEQUENCE)?)?;
+        tbs.read()?; // validity
+        let subject = name(tbs.expect(SEQUENCE)?)?;
+        Some(Self { subject, issuer })
+    }
+}
+
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const OBJECT_IDENTIFIER: u8 = 0x06;
+const BMP_STRING: u8 = 0x1e;
+const VERSION: u8 = 0xa0;
+
+/// Reader over consecutive DER elements.
+struct Der<'a>(&'a [u8]);
+
+impl<'a> Der<'a> {
+    /// The tag and content of the next element.
+    fn read(&mut self) -> Option<(u8, &'a [u8])> {
+        let (&tag, rest) = self.0.split_first()?;
+        let (&first, mut rest) = rest.split_first()?;
+        let len = if first < 0x80 {
+            first as usize
+        } else {
+            let octets = (first & 0x7f) as usize;
+            if octets == 0 || octets > 4 || rest.len() < octets {
+                return None;
+            }
+            let (len, after) = rest.split_at(octets);
+            rest = after;
+            len.iter().fold(0, |len, &b| (len << 8) | b as usize)
+        };
+        if rest.len() < len {
+            return None;
+        }
+        let (content, rest) = rest.split_at(len);
+        self.0 = rest;
+        Some((tag, content))
+    }
+
+    fn expect(&mut self, tag: u8) -> Option<&'a [u8]> {
+        self.read()
+            .filter(|(read, _)| *read == tag)
+            .map(|(_, content)| content)
+    }
+}
+
+/// An X.501 name as comma separated attributes, in certificate order.
+fn name(der: &[u8]) -> Option<String> {
+    let mut attributes = Vec::new();
+    let mut sets = Der(der);
+    while !sets.0.is_empty() {
+        let mut set = Der(sets.expect(SET)?);
+        while !set.0.is_empty() {
+            let mut attribute = Der(set.expect(SEQUENCE)?);
+            let oid = attribute.expect(OBJECT_IDENTIFIER)?;
+            let (tag, value) = attribute.read()?;
+            let value = if tag == BMP_STRING {
+                let units: Vec<u16> = value
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                    .collect();
+                String::from_utf16_lossy(&units)
+            } else {
+                String::from_utf8_lossy(value).into_owned()
+            };
+            attributes.push(format!("{}={}", attribute_name(oid), value));
+        }
+    }
+    Some(attributes.join(", "))
+}
+
+fn attribute_name(oid: &[u8]) -> String {
+    match oid {
+        [0x55, 0x04, 0x03] => "CN".to_string(),
+        [0x55, 0x04, 0x06] => "C".to_string(),
+        [0x55, 0x04, 0x07] => "L".to_string(),
+        [0x55, 0x04, 0x08] => "ST".to_string(),
+        [0x55, 0x04, 0x0a] => "O".to_string(),
+        [0x55, 0x04, 0x0b] => "OU".to_string(),
+        _ => dotted_oid(oid),
+    }
+}
+
+fn dotted_oid(oid: &[u8]) -> String {
+    let mut arcs = Vec::new();
+    let mut value: u64 = 0;
+    for &b in oid {
+        value = (value << 7) | (b & 0x7f) as u64;
+        if b & 0x80 == 0 {
+            if arcs.is_empty() {
+                let first = (value / 40).min(2);
+                arcs.push(first);
+                arcs.push(value - first * 40);
+            } else {
+                arcs.push(value);
+            }
+            value = 0;
+        }
+    }
+    arcs.iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Issued by "C=US, O=Test CA, CN=Test Root".
+    const LEAF: &str = concat!(
+        "308201b63082015ca00302010202145599461cfb10c8f7e2eeb4e7f49634c23f95d58c300a06082a8648ce3d04030230",
+        "33310b30090603550406130255533110300e060355040a0c07546573742043413112301006035504030c095465737420",
+        "526f6f743020170d3236313031373036353430315a180f32313236303932333036353430315a303d310b300906035504",
+        "061302465231133011060355040a0c0a547572626f2053686f703119301706035504030c1073686f702e6578616d706c",
+        "652e636f6d3059301306072a8648ce3d020106082a8648ce3d03010703420004d9adbcf5205a205ebdc163f1eb9a5140",
+        "ec649b2fb0f1b55859eef32222a4940ced1719dc0b1845c2c56c5398576c8ecfd43b2e5f109f5e1ed5d25df35f95820e",
+        "a3423040301d0603551d0e041604140929fc6dcccb2defba67eb119388825e56492e37301f0603551d23041830168014",
+        "6fe3816f8c0d84ed9a4adb4a953bcb4ed753e89a300a06082a8648ce3d040302034800304502206fc48589f5998fdb55",
+        "0c0d42018b98995f744bd68c48514be4f3205cb2054441022100bb8948ed0287df324110f8972cb31f31fe0d3bbf8f85",
+        "4d23146a8ef95f5f59fa",
+    );
+
+    #[test]
+    fn test_reads_subject_and_issuer() {
+        let der = hex::decode(LEAF).unwrap();
+        let info = CertificateInfo::from_der(&der).unwrap();
+        assert_eq!(info.subject, "C=FR, O=Turbo Shop, CN=shop.example.com");
+        assert_eq!(info.issuer, "C=US, O=Test CA, CN=Test Root");
+
+        assert_eq!(CertificateInfo::from_der(&der[..100]), None);
+        assert_eq!(CertificateInfo::from_der(b"not a certificate"), None);
+    }
+
+    #[test]
+    fn test_unknown_attributes_use_dotted_oids() {
+        // 1.2.840.113549.1.9.1, the e-mail address attribute.
+        let oid = [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x01];
+        assert_eq!(attribute_name(&oid), "1.2.840.113549.1.9.1");
+    }
+}
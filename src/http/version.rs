@@ -0,0 +1,16 @@
+/// HTTP version used to talk to servers. Some anti-bot systems fingerprint
+/// it, so spiders can match the browsers they impersonate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum HttpVersion {
+    /// HTTP/2 when the server offers it during the TLS handshake, HTTP/1.1
+    /// otherwise, as browsers do.
+    #[default]
+    Auto,
+    /// HTTP/1.1 only.
+    Http1,
+    /// HTTP/2 only, also over plain HTTP.
+    Http2,
+    /// HTTP/3 over QUIC. Needs the `http3` feature, which reqwest only builds
+    /// with `RUSTFLAGS="--cfg reqwest_unstable"`.
+    Http3,
+}
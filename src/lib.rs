@@ -7,8 +7,8 @@ pub mod storage;
 
 pub mod examples;
 
-pub use core::Crawler;
-pub use core::{ScraperError, ScraperResult, Spider};
+pub use core::{Crawler, CrawlerBuilder, CrawlerHandle, ShutdownToken};
+pub use core::{ScraperError, ScraperResult, Spider, SpiderError, WithRequest};
 pub use http::{HttpRequest, HttpResponse};
 pub use parser::Parser;
 pub use scrapers::Scraper;
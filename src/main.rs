@@ -3,7 +3,7 @@ use turboscraper::examples::example_spiders::beginner::simple_spider::BookSpider
 
 use turboscraper::core::retry::{
     BackoffPolicy, CategoryConfig, ContentRetryCondition, RequestRetryCondition, RetryCategory,
-    RetryCondition, RetryConfig,
+    RetryCondition, RetryConfig, RetryScope,
 };
 use turboscraper::core::spider::SpiderConfig;
 use turboscraper::scrapers::http_scraper::HttpScraper;
@@ -35,6 +35,8 @@ async fn main() -> ScraperResult<()> {
                 })),
             ],
             backoff_policy: BackoffPolicy::Exponential { factor: 2.0 },
+            matcher: None,
+            scope: RetryScope::Url,
         },
     );
 
@@ -42,7 +44,6 @@ async fn main() -> ScraperResult<()> {
         .with_retry(retry_config)
         .with_depth(999999)
         .with_concurrency(30)
-        .with_allow_url_revisit(false)
         .with_headers(vec![("User-Agent", "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")]);
 
     let storage = create_storage(StorageType::Disk {
@@ -1,13 +1,121 @@
-use crate::{http::HttpRequest, HttpResponse, ScraperResult};
-use async_trait::async_trait;
-use url::Url;
+use crate::core::spider::{ParseResult, ParsedData, SpiderResponse};
+use crate::core::SpiderCallback;
+use crate::{http::HttpRequest, ScraperResult};
+use std::collections::HashMap;
+use std::sync::Arc;
 
-#[async_trait]
+/// A reusable parsing component that turns a response into follow-up
+/// requests and extracted data. It gets the [`SpiderResponse`], so it can use
+/// the content the crawler already parsed.
 pub trait Parser: Send + Sync {
-    async fn parse(
+    fn parse(&self, response: &SpiderResponse) -> ScraperResult<(Vec<HttpRequest>, ParsedData)>;
+}
+
+impl<F> Parser for F
+where
+    F: Fn(&SpiderResponse) -> ScraperResult<(Vec<HttpRequest>, ParsedData)> + Send + Sync,
+{
+    fn parse(&self, response: &SpiderResponse) -> ScraperResult<(Vec<HttpRequest>, ParsedData)> {
+        self(response)
+    }
+}
+
+/// Parsers keyed by spider callback. A spider declaring them in
+/// [`Spider::parsers`](crate::Spider::parsers) needs no `parse` of its own.
+#[derive(Clone, Default)]
+pub struct CallbackParsers {
+    parsers: HashMap<SpiderCallback, Arc<dyn Parser>>,
+}
+
+impl CallbackParsers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_parser<P: Parser + 'static>(mut self, callback: SpiderCallback, parser: P) -> Self {
+        self.parsers.insert(callback, Arc::new(parser));
+        self
+    }
+
+    pub fn contains(&self, callback: &SpiderCallback) -> bool {
+        self.parsers.contains_key(callback)
+    }
+
+    /// Runs the parser registered for the response's callback, or returns
+    /// `None` if there is none.
+    pub fn parse(
         &self,
-        response: HttpResponse,
-        url: Url,
-        depth: usize,
-    ) -> ScraperResult<Vec<HttpRequest>>;
+        response: &SpiderResponse,
+    ) -> Option<ScraperResult<(ParseResult, ParsedData)>> {
+        let parser = self.parsers.get(&response.callback)?;
+        Some(
+            parser
+                .parse(response)
+                .map(|(requests, data)| (ParseResult::Continue(requests), data)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpResponse;
+
+    use serde_json::json;
+
+    use url::Url;
+
+    struct NextPage;
+
+    impl Parser for NextPage {
+        fn parse(
+            &self,
+            response: &SpiderResponse,
+        ) -> ScraperResult<(Vec<HttpRequest>, ParsedData)> {
+            let next = response.response.url.join("page-2").unwrap();
+            Ok((
+                vec![HttpRequest::new(next, SpiderCallback::ParsePagination, 1)],
+                ParsedData::Empty,
+            ))
+        }
+    }
+
+    fn spider_response(callback: SpiderCallback) -> SpiderResponse {
+        let url = Url::parse("https://example.com/page-1").unwrap();
+        SpiderResponse {
+            response: HttpResponse {
+                from_request: Box::new(HttpRequest::new(url.clone(), callback.clone(), 0)),
+                ..HttpResponse::for_test(&url, b"<h1>Title</h1>")
+            },
+            callback,
+            content: None,
+        }
+    }
+
+    #[test]
+    fn test_delegates_by_callback() {
+        let parsers = CallbackParsers::new()
+            .with_parser(SpiderCallback::ParsePagination, NextPage)
+            .with_parser(SpiderCallback::ParseItem, |response: &SpiderResponse| {
+                let body = response.response.body_text()?;
+                Ok((Vec::new(), ParsedData::Item(json!({ "html": body }))))
+            });
+
+        let (result, data) = parsers
+            .parse(&spider_response(SpiderCallback::ParsePagination))
+            .unwrap()
+            .unwrap();
+        assert!(matches!(result, ParseResult::Continue(ref r) if r[0].url.path() == "/page-2"));
+        assert!(matches!(data, ParsedData::Empty));
+
+        let (_, data) = parsers
+            .parse(&spider_response(SpiderCallback::ParseItem))
+            .unwrap()
+            .unwrap();
+        assert!(matches!(data, ParsedData::Item(item) if item["html"] == "<h1>Title</h1>"));
+
+        assert!(parsers
+            .parse(&spider_response(SpiderCallback::Bootstrap))
+            .is_none());
+    }
 }
@@ -0,0 +1,276 @@
+use crate::HttpResponse;
+use percent_encoding::percent_decode_str;
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+use serde_json::{json, Value};
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum BreadcrumbSource {
+    /// A schema.org `BreadcrumbList` in an `application/ld+json` script.
+    JsonLd,
+    /// A schema.org `BreadcrumbList` marked up with `itemprop` attributes.
+    Microdata,
+    /// A `breadcrumb` navigation list without structured data.
+    Markup,
+    /// Derived from the URL path segments.
+    UrlPath,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Crumb {
+    pub name: String,
+    pub url: Option<String>,
+}
+
+/// A breadcrumb trail, from the most general category to the most specific.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Breadcrumbs {
+    pub crumbs: Vec<Crumb>,
+    pub source: BreadcrumbSource,
+}
+
+impl Breadcrumbs {
+    pub fn names(&self) -> Vec<&str> {
+        self.crumbs.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    /// The trail joined with `separator`, e.g. `Books > Fiction > Mystery`.
+    pub fn category_path(&self, separator: &str) -> String {
+        self.names().join(separator)
+    }
+
+    /// Adds `breadcrumbs` and `category_path` fields to a JSON object item.
+    /// Other values are left untouched.
+    pub fn apply_to(&self, item: &mut Value) {
+        if let Value::Object(map) = item {
+            map.insert("breadcrumbs".to_string(), json!(self.crumbs));
+            map.insert("category_path".to_string(), json!(self.names()));
+        }
+    }
+}
+
+/// Extracts the breadcrumb trail of an HTML response, preferring structured
+/// data over plain markup and falling back to the URL path.
+pub fn extract_breadcrumbs(response: &HttpResponse) -> Option<Breadcrumbs> {
+    let body = response.body_text().unwrap_or_default();
+    let document = Html::parse_document(body);
+
+    from_json_ld(&document, &response.url)
+        .or_else(|| from_microdata(&document, &response.url))
+        .or_else(|| from_markup(&document, &response.url))
+        .or_else(|| breadcrumbs_from_url(&response.url))
+}
+
+/// Builds a trail from the directory segments of `url`, so
+/// `/books/science-fiction/dune.html` yields `books > science fiction`.
+pub fn breadcrumbs_from_url(url: &Url) -> Option<Breadcrumbs> {
+    let segments: Vec<&str> = url
+        .path_segments()?
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let directories = segments.len().saturating_sub(1);
+
+    let mut path = String::new();
+    let crumbs: Vec<Crumb> = segments[..directories]
+        .iter()
+        .map(|segment| {
+            path.push('/');
+            path.push_str(segment);
+            Crumb {
+                name: humanize(segment),
+                url: url.join(&format!("{}/", path)).ok().map(|u| u.to_string()),
+            }
+        })
+        .collect();
+
+    trail(crumbs, BreadcrumbSource::UrlPath)
+}
+
+fn from_json_ld(document: &Html, base: &Url) -> Option<Breadcrumbs> {
+    let selector = Selector::parse(r#"script[type="application/ld+json"]"#).unwrap();
+    document
+        .select(&selector)
+        .filter_map(|script| serde_json::from_str::<Value>(&script.text().collect::<String>()).ok())
+        .find_map(|value| find_breadcrumb_list(&value).cloned())
+        .and_then(|list| {
+            let mut elements = list["itemListElement"].as_array()?.clone();
+            elements.sort_by_key(|e| e["position"].as_u64().unwrap_or(u64::MAX));
+            let crumbs = elements
+                .iter()
+                .filter_map(|element| {
+                    let item = &element["item"];
+                    let name = element["name"].as_str().or_else(|| item["name"].as_str())?;
+                    let url = item
+                        .as_str()
+                        .or_else(|| item["@id"].as_str())
+                        .or_else(|| item["url"].as_str());
+                    Some(crumb(name, url, base))
+                })
+                .collect();
+            trail(crumbs, BreadcrumbSource::JsonLd)
+        })
+}
+
+fn find_breadcrumb_list(value: &Value) -> Option<&Value> {
+    match value {
+        Value::Array(values) => values.iter().find_map(find_breadcrumb_list),
+        Value::Object(map) => {
+            if map.get("@type").and_then(Value::as_str) == Some("BreadcrumbList") {
+                Some(value)
+            } else {
+                map.get("@graph").and_then(find_breadcrumb_list)
+            }
+        }
+        _ => None,
+    }
+}
+
+fn from_microdata(document: &Html, base: &Url) -> Option<Breadcrumbs> {
+    let list_selector = Selector::parse(r#"[itemtype$="BreadcrumbList"]"#).unwrap();
+    let element_selector = Selector::parse(r#"[itemprop="itemListElement"]"#).unwrap();
+    let name_selector = Selector::parse(r#"[itemprop="name"]"#).unwrap();
+    let item_selector = Selector::parse(r#"[itemprop="item"]"#).unwrap();
+
+    let list = document.select(&list_selector).next()?;
+    let crumbs = list
+        .select(&element_selector)
+        .filter_map(|element| {
+            let name = element.select(&name_selector).next().map(|e| {
+                e.value()
+                    .attr("content")
+                    .map(str::to_string)
+                    .unwrap_or_else(|| text(e))
+            })?;
+            let url = element.select(&item_selector).next().and_then(|e| {
+                e.value()
+                    .attr("href")
+                    .or_else(|| e.value().attr("itemid"))
+                    .or_else(|| e.value().attr("content"))
+            });
+            Some(crumb(&name, url, base))
+        })
+        .collect();
+    trail(crumbs, BreadcrumbSource::Microdata)
+}
+
+fn from_markup(document: &Html, base: &Url) -> Option<Breadcrumbs> {
+    let selector = Selector::parse(
+        r#"nav[aria-label*="readcrumb"] li, .breadcrumb li, .breadcrumbs li, #breadcrumbs li"#,
+    )
+    .unwrap();
+    let link_selector = Selector::parse("a[href]").unwrap();
+
+    let crumbs = document
+        .select(&selector)
+        .map(|item| {
+            let url = item
+                .select(&link_selector)
+                .next()
+                .and_then(|a| a.value().attr("href"));
+            crumb(&text(item), url, base)
+        })
+        .collect();
+    trail(crumbs, BreadcrumbSource::Markup)
+}
+
+fn crumb(name: &str, url: Option<&str>, base: &Url) -> Crumb {
+    Crumb {
+        name: name.trim().to_string(),
+        url: url.and_then(|u| base.join(u).ok()).map(|u| u.to_string()),
+    }
+}
+
+/// Drops empty crumbs and separator-only entries such as `>` or `/`.
+fn trail(crumbs: Vec<Crumb>, source: BreadcrumbSource) -> Option<Breadcrumbs> {
+    let crumbs: Vec<Crumb> = crumbs
+        .into_iter()
+        .filter(|c| c.name.chars().any(char::is_alphanumeric))
+        .collect();
+    (!crumbs.is_empty()).then_some(Breadcrumbs { crumbs, source })
+}
+
+fn text(element: ElementRef) -> String {
+    element
+        .text()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn humanize(segment: &str) -> String {
+    percent_decode_str(segment)
+        .decode_utf8_lossy()
+        .replace(['-', '_', '+'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::SpiderCallback;
+    use crate::http::HttpRequest;
+
+    fn html_response(url: &str, body: &str) -> HttpResponse {
+        let url = Url::parse(url).unwrap();
+        HttpResponse {
+            from_request: Box::new(HttpRequest::new(url.clone(), SpiderCallback::ParseItem, 1)),
+            ..HttpResponse::for_test(&url, body)
+        }
+    }
+
+    #[test]
+    fn test_json_ld_breadcrumbs() {
+        let body = r#"<script type="application/ld+json">
+            {"@context": "https://schema.org", "@graph": [{"@type": "BreadcrumbList", "itemListElement": [
+                {"@type": "ListItem", "position": 2, "name": "Fiction", "item": "/fiction/"},
+                {"@type": "ListItem", "position": 1, "name": "Books", "item": {"@id": "/books/"}}
+            ]}]}
+        </script>"#;
+        let breadcrumbs =
+            extract_breadcrumbs(&html_response("https://shop.example.com/p/1", body)).unwrap();
+
+        assert_eq!(breadcrumbs.source, BreadcrumbSource::JsonLd);
+        assert_eq!(breadcrumbs.category_path(" > "), "Books > Fiction");
+        assert_eq!(
+            breadcrumbs.crumbs[0].url.as_deref(),
+            Some("https://shop.example.com/books/")
+        );
+    }
+
+    #[test]
+    fn test_markup_breadcrumbs_skip_separators() {
+        let body = r#"<ul class="breadcrumb">
+            <li><a href="/">Home</a></li><li>&gt;</li>
+            <li><a href="/books/">Books</a></li>
+            <li class="active">A Light in the Attic</li>
+        </ul>"#;
+        let mut item = json!({"title": "A Light in the Attic"});
+        extract_breadcrumbs(&html_response("https://shop.example.com/p/1", body))
+            .unwrap()
+            .apply_to(&mut item);
+
+        assert_eq!(
+            item["category_path"],
+            json!(["Home", "Books", "A Light in the Attic"])
+        );
+        assert_eq!(item["breadcrumbs"][2]["url"], Value::Null);
+    }
+
+    #[test]
+    fn test_falls_back_to_url_path() {
+        let response = html_response(
+            "https://shop.example.com/books/science-fiction/dune.html",
+            "<p>No breadcrumbs here</p>",
+        );
+        let breadcrumbs = extract_breadcrumbs(&response).unwrap();
+
+        assert_eq!(breadcrumbs.source, BreadcrumbSource::UrlPath);
+        assert_eq!(breadcrumbs.names(), vec!["books", "science fiction"]);
+        assert_eq!(
+            breadcrumbs.crumbs[1].url.as_deref(),
+            Some("https://shop.example.com/books/science-fiction/")
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use crate::http::ResponseType;
+use crate::parser::xml::XmlDocument;
+use crate::{HttpResponse, ScraperError, ScraperResult};
+use scraper::{Html, Selector};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use url::Url;
+
+/// Response body in the structure produced by a [`ContentParser`].
+#[derive(Debug, Clone)]
+pub enum ParsedContent {
+    Html(HtmlContent),
+    Json(Value),
+    Xml(XmlDocument),
+    Binary(BinaryContent),
+    Text(String),
+}
+
+/// An HTML page with its title and absolute links already extracted.
+///
+/// `scraper::Html` cannot be sent between tasks, so the full DOM is rebuilt
+/// from `source` by [`HtmlContent::document`].
+#[derive(Debug, Clone)]
+pub struct HtmlContent {
+    pub source: String,
+    pub title: Option<String>,
+    pub links: Vec<Url>,
+}
+
+impl HtmlContent {
+    pub fn document(&self) -> Html {
+        Html::parse_document(&self.source)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinaryContent {
+    pub content_type: Option<String>,
+    pub size: usize,
+}
+
+/// Turns a response body into [`ParsedContent`] before the spider callback
+/// runs.
+pub trait ContentParser: Send + Sync {
+    fn parse(&self, response: &HttpResponse) -> ScraperResult<ParsedContent>;
+}
+
+pub struct HtmlParser;
+
+impl ContentParser for HtmlParser {
+    fn parse(&self, response: &HttpResponse) -> ScraperResult<ParsedContent> {
+        let source = response.body_text()?.to_string();
+        let document = Html::parse_document(&source);
+        let title_selector = Selector::parse("title").unwrap();
+        let link_selector = Selector::parse("a[href]").unwrap();
+
+        let title = document
+            .select(&title_selector)
+            .next()
+            .map(|e| e.text().collect::<String>().trim().to_string())
+            .filter(|title| !title.is_empty());
+        let links = document
+            .select(&link_selector)
+            .filter_map(|e| e.value().attr("href"))
+            .filter_map(|href| response.url.join(href).ok())
+            .collect();
+
+        Ok(ParsedContent::Html(HtmlContent {
+            source,
+            title,
+            links,
+        }))
+    }
+}
+
+pub struct JsonParser;
+
+impl ContentParser for JsonParser {
+    fn parse(&self, response: &HttpResponse) -> ScraperResult<ParsedContent> {
+        serde_json::from_slice(&response.raw_body)
+            .map(ParsedContent::Json)
+            .map_err(|e| ScraperError::ParsingError(e.to_string()))
+    }
+}
+
+pub struct XmlParser;
+
+impl ContentParser for XmlParser {
+    fn parse(&self, response: &HttpResponse) -> ScraperResult<ParsedContent> {
+        response.xml().map(ParsedContent::Xml)
+    }
+}
+
+/// Describes binary bodies without decoding them; the bytes stay on
+/// `HttpResponse::raw_body`.
+pub struct BinaryHandler;
+
+impl ContentParser for BinaryHandler {
+    fn parse(&self, response: &HttpResponse) -> ScraperResult<ParsedContent> {
+        Ok(ParsedContent::Binary(BinaryContent {
+            content_type: response.headers.get("content-type").cloned(),
+            size: response.raw_body.len(),
+        }))
+    }
+}
+
+/// Routes each response to the parser registered for its `ResponseType`.
+/// Responses without a registered parser get no pre-parsed content.
+#[derive(Clone, Default)]
+pub struct ContentDispatcher {
+    parsers: HashMap<ResponseType, Arc<dyn ContentParser>>,
+}
+
+impl ContentDispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `HtmlParser`, `JsonParser`, `XmlParser` and `BinaryHandler`.
+    pub fn with_defaults() -> Self {
+        let mut dispatcher = Self::new();
+        dispatcher.register_defaults();
+        dispatcher
+    }
+
+    /// Registers the built-in parsers of [`with_defaults`](Self::with_defaults)
+    /// for the response types that have no parser yet.
+    pub fn register_defaults(&mut self) {
+        let defaults: [(ResponseType, Arc<dyn ContentParser>); 4] = [
+            (ResponseType::Html, Arc::new(HtmlParser)),
+            (ResponseType::Json, Arc::new(JsonParser)),
+            (ResponseType::Xml, Arc::new(XmlParser)),
+            (ResponseType::Binary, Arc::new(BinaryHandler)),
+        ];
+        for (response_type, parser) in defaults {
+            self.parsers.entry(response_type).or_insert(parser);
+        }
+    }
+
+    /// Registers `parser` for `response_type`, replacing any previous one.
+    pub fn register<P: ContentParser + 'static>(&mut self, response_type: ResponseType, parser: P) {
+        self.parsers.insert(response_type, Arc::new(parser));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parsers.is_empty()
+    }
+
+    pub fn dispatch(&self, response: &HttpResponse) -> ScraperResult<Option<ParsedContent>> {
+        self.parsers
+            .get(&response.response_type)
+            .map(|parser| parser.parse(response))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(response_type: ResponseType, body: &[u8]) -> HttpResponse {
+        let url = Url::parse("https://example.com/catalog/").unwrap();
+        HttpResponse {
+            response_type,
+            ..HttpResponse::for_test(&url, body)
+        }
+    }
+
+    #[test]
+    fn test_dispatches_by_response_type() {
+        let dispatcher = ContentDispatcher::with_defaults();
+
+        let html = response(
+            ResponseType::Html,
+            b"<html><head><title> Books </title></head><body><a href=\"page-2.html\">next</a></body></html>",
+        );
+        match dispatcher.dispatch(&html).unwrap() {
+            Some(ParsedContent::Html(content)) => {
+                assert_eq!(content.title.as_deref(), Some("Books"));
+                assert_eq!(
+                    content.links[0].as_str(),
+                    "https://example.com/catalog/page-2.html"
+                );
+            }
+            other => panic!("unexpected content: {:?}", other),
+        }
+
+        let json = response(ResponseType::Json, br#"{"items": [1, 2]}"#);
+        match dispatcher.dispatch(&json).unwrap() {
+            Some(ParsedContent::Json(value)) => assert_eq!(value["items"][1], 2),
+            other => panic!("unexpected content: {:?}", other),
+        }
+
+        let text = response(ResponseType::Text, b"plain");
+        assert!(dispatcher.dispatch(&text).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_defaults_keep_registered_parsers() {
+        struct Raw;
+
+        impl ContentParser for Raw {
+            fn parse(&self, response: &HttpResponse) -> ScraperResult<ParsedContent> {
+                Ok(ParsedContent::Json(response.body_text()?.into()))
+            }
+        }
+
+        let mut dispatcher = ContentDispatcher::new();
+        dispatcher.register(ResponseType::Html, Raw);
+        dispatcher.register_defaults();
+
+        let html = response(ResponseType::Html, b"<title>Books</title>");
+        assert!(matches!(
+            dispatcher.dispatch(&html).unwrap(),
+            Some(ParsedContent::Json(value)) if value == "<title>Books</title>"
+        ));
+        let json = response(ResponseType::Json, b"[1]");
+        assert!(matches!(
+            dispatcher.dispatch(&json).unwrap(),
+            Some(ParsedContent::Json(value)) if value[0] == 1
+        ));
+    }
+
+    #[test]
+    fn test_invalid_json_is_a_parsing_error() {
+        let dispatcher = ContentDispatcher::with_defaults();
+        let json = response(ResponseType::Json, b"{not json");
+        assert!(matches!(
+            dispatcher.dispatch(&json),
+            Err(ScraperError::ParsingError(_))
+        ));
+    }
+}
@@ -0,0 +1,194 @@
+use super::xml::{XmlDocument, XmlElement, XmlError};
+use crate::core::SpiderCallback;
+use crate::{HttpRequest, HttpResponse, ScraperError, ScraperResult};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum FeedKind {
+    Rss,
+    Atom,
+}
+
+/// One item of an RSS feed or entry of an Atom feed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeedEntry {
+    pub title: Option<String>,
+    /// Absolute link to the article.
+    pub link: Option<Url>,
+    pub published: Option<DateTime<Utc>>,
+    /// `<guid>` or `<id>` of the entry.
+    pub id: Option<String>,
+    pub summary: Option<String>,
+}
+
+/// An RSS 2.0, RSS 1.0 (RDF) or Atom feed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Feed {
+    pub kind: FeedKind,
+    pub title: Option<String>,
+    pub entries: Vec<FeedEntry>,
+}
+
+impl Feed {
+    /// Parses `xml`, resolving relative links against `base`.
+    pub fn parse(xml: &str, base: &Url) -> Result<Self, XmlError> {
+        let document = XmlDocument::parse(xml)?;
+        let root = document.root();
+        match root.name.as_str() {
+            "rss" => {
+                let channel = root
+                    .child("channel")
+                    .ok_or_else(|| XmlError::Malformed("RSS feed without <channel>".to_string()))?;
+                Ok(Self::rss(channel, channel, base))
+            }
+            // RSS 1.0 lists its items next to the channel.
+            "RDF" => {
+                let title = root.child("channel").and_then(|c| text(c, "title"));
+                Ok(Feed {
+                    title,
+                    ..Self::rss(root, root, base)
+                })
+            }
+            "feed" => Ok(Feed {
+                kind: FeedKind::Atom,
+                title: text(root, "title"),
+                entries: root
+                    .elements()
+                    .filter(|e| e.name == "entry")
+                    .map(|e| atom_entry(e, base))
+                    .collect(),
+            }),
+            other => Err(XmlError::Malformed(format!(
+                "Unexpected feed root element <{}>",
+                other
+            ))),
+        }
+    }
+
+    fn rss(channel: &XmlElement, items: &XmlElement, base: &Url) -> Self {
+        Feed {
+            kind: FeedKind::Rss,
+            title: text(channel, "title"),
+            entries: items
+                .elements()
+                .filter(|e| e.name == "item")
+                .map(|item| FeedEntry {
+                    title: text(item, "title"),
+                    link: text(item, "link").and_then(|link| base.join(&link).ok()),
+                    published: text(item, "pubDate")
+                        .or_else(|| text(item, "date"))
+                        .and_then(|date| parse_date(&date)),
+                    id: text(item, "guid"),
+                    summary: text(item, "description"),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn from_response(response: &HttpResponse) -> ScraperResult<Self> {
+        Self::parse(response.body_text()?, &response.url)
+            .map_err(|e| ScraperError::ParsingError(e.to_string()))
+    }
+
+    /// A request for the link of every entry, carrying the entry as request
+    /// meta so the article callback can use its title and date.
+    pub fn requests(&self, callback: SpiderCallback, depth: usize) -> Vec<HttpRequest> {
+        self.entries
+            .iter()
+            .filter_map(|entry| {
+                let mut request = HttpRequest::new(entry.link.clone()?, callback.clone(), depth);
+                request.meta = serde_json::to_value(entry).ok();
+                Some(request)
+            })
+            .collect()
+    }
+}
+
+fn atom_entry(entry: &XmlElement, base: &Url) -> FeedEntry {
+    let link = entry
+        .elements()
+        .filter(|e| e.name == "link")
+        .find(|e| e.attr("rel").is_none_or(|rel| rel == "alternate"))
+        .and_then(|e| e.attr("href"))
+        .and_then(|href| base.join(href).ok());
+    FeedEntry {
+        title: text(entry, "title"),
+        link,
+        published: text(entry, "published")
+            .or_else(|| text(entry, "updated"))
+            .and_then(|date| parse_date(&date)),
+        id: text(entry, "id"),
+        summary: text(entry, "summary").or_else(|| text(entry, "content")),
+    }
+}
+
+/// Trimmed text of the `name` child, if present and not blank.
+fn text(element: &XmlElement, name: &str) -> Option<String> {
+    element
+        .child_text(name)
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+/// RSS dates are RFC 2822, Atom and Dublin Core dates RFC 3339.
+fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(date)
+        .or_else(|_| DateTime::parse_from_rfc3339(date))
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_rss_and_atom_entries() {
+        let base = Url::parse("https://news.example.com/feed").unwrap();
+        let rss = r#"<?xml version="1.0"?>
+            <rss version="2.0"><channel>
+              <title>Example News</title>
+              <item>
+                <title>Launch day</title>
+                <link>/2024/launch</link>
+                <pubDate>Tue, 07 May 2024 10:00:00 +0200</pubDate>
+                <guid>launch-1</guid>
+              </item>
+              <item><title>No link</title></item>
+            </channel></rss>"#;
+        let feed = Feed::parse(rss, &base).unwrap();
+        assert_eq!(feed.kind, FeedKind::Rss);
+        assert_eq!(feed.title.as_deref(), Some("Example News"));
+        let entry = &feed.entries[0];
+        assert_eq!(
+            entry.link.as_ref().unwrap().as_str(),
+            "https://news.example.com/2024/launch"
+        );
+        assert_eq!(
+            entry.published.unwrap().to_rfc3339(),
+            "2024-05-07T08:00:00+00:00"
+        );
+        let requests = feed.requests(SpiderCallback::ParseItem, 1);
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].meta.as_ref().unwrap()["title"], "Launch day");
+
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+              <title>Changelog</title>
+              <entry>
+                <title>v2</title>
+                <link rel="self" href="https://news.example.com/v2.atom"/>
+                <link href="https://news.example.com/v2"/>
+                <updated>2024-05-01T12:00:00Z</updated>
+                <id>urn:v2</id>
+              </entry>
+            </feed>"#;
+        let feed = Feed::parse(atom, &base).unwrap();
+        assert_eq!(feed.kind, FeedKind::Atom);
+        let entry = &feed.entries[0];
+        assert_eq!(entry.link.as_ref().unwrap().path(), "/v2");
+        assert_eq!(entry.id.as_deref(), Some("urn:v2"));
+        assert!(entry.published.is_some());
+    }
+}
@@ -0,0 +1,201 @@
+use crate::core::SpiderCallback;
+use crate::{HttpRequest, HttpResponse, ScraperError, ScraperResult};
+use reqwest::Method;
+use scraper::{ElementRef, Html, Selector};
+use url::Url;
+
+/// A `<form>` read from a page: where it submits to, how, and the values it
+/// would send as is, hidden inputs such as CSRF tokens included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Form {
+    pub action: Url,
+    pub method: Method,
+    pub fields: Vec<(String, String)>,
+}
+
+impl Form {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets `name`, replacing the value read from the page if there was one.
+    pub fn set(mut self, name: &str, value: &str) -> Self {
+        match self.fields.iter_mut().find(|(field, _)| field == name) {
+            Some((_, current)) => *current = value.to_string(),
+            None => self.fields.push((name.to_string(), value.to_string())),
+        }
+        self
+    }
+
+    /// The request submitting the form: fields go in the query string of a
+    /// GET form and in a url-encoded body otherwise.
+    pub fn into_request(self, callback: SpiderCallback, depth: usize) -> HttpRequest {
+        if self.method == Method::GET {
+            let mut url = self.action;
+            url.query_pairs_mut().clear().extend_pairs(&self.fields);
+            HttpRequest::new(url, callback, depth)
+        } else {
+            HttpRequest::from_form(self.action, callback, depth, &self.fields)
+                .with_method(self.method)
+        }
+    }
+}
+
+/// Extracts a [`Form`] from an HTML response.
+#[derive(Debug, Clone)]
+pub struct FormParser {
+    selector: Selector,
+}
+
+impl Default for FormParser {
+    fn default() -> Self {
+        Self {
+            selector: Selector::parse("form").unwrap(),
+        }
+    }
+}
+
+impl FormParser {
+    /// Reads the first form of the page.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the first form matching `css`, e.g. `form#login`.
+    pub fn with_selector(mut self, css: &str) -> Result<Self, ScraperError> {
+        self.selector = Selector::parse(css)
+            .map_err(|e| ScraperError::ParsingError(format!("Invalid selector {}: {}", css, e)))?;
+        Ok(self)
+    }
+
+    pub fn parse(&self, response: &HttpResponse) -> ScraperResult<Form> {
+        let error = |message: String| ScraperError::ParsingError(message);
+        let document = Html::parse_document(response.body_text()?);
+        let form = document
+            .select(&self.selector)
+            .next()
+            .ok_or_else(|| error(format!("No form found on {}", response.url)))?;
+
+        // A missing or empty action submits to the page itself.
+        let action = match form.value().attr("action").map(str::trim) {
+            Some(action) if !action.is_empty() => response
+                .url
+                .join(action)
+                .map_err(|e| error(format!("Invalid form action {}: {}", action, e)))?,
+            _ => response.url.clone(),
+        };
+        let method = match form.value().attr("method") {
+            Some(method) if method.eq_ignore_ascii_case("post") => Method::POST,
+            _ => Method::GET,
+        };
+        Ok(Form {
+            action,
+            method,
+            fields: form_fields(&form),
+        })
+    }
+}
+
+/// Values of the named controls, as a browser would submit them without
+/// clicking a particular button.
+fn form_fields(form: &ElementRef) -> Vec<(String, String)> {
+    let controls = Selector::parse("input[name], textarea[name], select[name]").unwrap();
+    let options = Selector::parse("option").unwrap();
+    let mut fields = Vec::new();
+    for control in form.select(&controls) {
+        let element = control.value();
+        let name = element.attr("name").unwrap_or_default().to_string();
+        if element.attr("disabled").is_some() {
+            continue;
+        }
+        let value = match element.name() {
+            "textarea" => control.text().collect::<String>(),
+            "select" => {
+                let selected = control
+                    .select(&options)
+                    .find(|option| option.value().attr("selected").is_some())
+                    .or_else(|| control.select(&options).next());
+                match selected {
+                    Some(option) => option
+                        .value()
+                        .attr("value")
+                        .map(str::to_string)
+                        .unwrap_or_else(|| option.text().collect::<String>().trim().to_string()),
+                    None => continue,
+                }
+            }
+            _ => {
+                let kind = element.attr("type").unwrap_or("text").to_ascii_lowercase();
+                match kind.as_str() {
+                    "submit" | "button" | "image" | "reset" | "file" => continue,
+                    "checkbox" | "radio" if element.attr("checked").is_none() => continue,
+                    "checkbox" | "radio" => element.attr("value").unwrap_or("on").to_string(),
+                    _ => element.attr("value").unwrap_or_default().to_string(),
+                }
+            }
+        };
+        fields.push((name, value));
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn html_response(body: &str) -> HttpResponse {
+        HttpResponse::for_test("https://example.com/account/login", body)
+    }
+
+    #[test]
+    fn test_reads_form_and_builds_request() {
+        let response = html_response(
+            r#"<form id="search" action="/search"><input name="q"></form>
+            <form id="login" action="/session" method="post">
+              <input type="hidden" name="csrf_token" value="abc123">
+              <input name="username" value="">
+              <input type="password" name="password">
+              <input type="checkbox" name="remember" checked>
+              <input type="checkbox" name="newsletter">
+              <select name="lang"><option value="en">English</option>
+                <option value="fr" selected>French</option></select>
+              <input type="submit" name="go" value="Sign in">
+            </form>"#,
+        );
+        let form = FormParser::new()
+            .with_selector("form#login")
+            .unwrap()
+            .parse(&response)
+            .unwrap()
+            .set("username", "ada")
+            .set("password", "s3cret");
+
+        assert_eq!(form.action.as_str(), "https://example.com/session");
+        assert_eq!(form.method, Method::POST);
+        assert_eq!(form.get("csrf_token"), Some("abc123"));
+        assert_eq!(form.get("remember"), Some("on"));
+        assert_eq!(form.get("lang"), Some("fr"));
+        assert_eq!(form.get("newsletter"), None);
+        assert_eq!(form.get("go"), None);
+
+        let request = form.into_request(SpiderCallback::ParseItem, 1);
+        assert_eq!(request.method, Method::POST);
+        assert_eq!(
+            request.body.as_deref(),
+            Some("csrf_token=abc123&username=ada&password=s3cret&remember=on&lang=fr")
+        );
+
+        let search = FormParser::new()
+            .parse(&response)
+            .unwrap()
+            .set("q", "rust books")
+            .into_request(SpiderCallback::ParseItem, 1);
+        assert_eq!(
+            search.url.as_str(),
+            "https://example.com/search?q=rust+books"
+        );
+    }
+}
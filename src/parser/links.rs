@@ -0,0 +1,308 @@
+use crate::core::spider::{SpiderCallback, SpiderConfig};
+use crate::{HttpRequest, HttpResponse, ScraperError};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashSet;
+use url::Url;
+
+/// Page-level robots directives from `<meta name="robots">` and the
+/// `X-Robots-Tag` header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RobotsDirectives {
+    pub noindex: bool,
+    pub nofollow: bool,
+}
+
+impl RobotsDirectives {
+    pub fn from_response(response: &HttpResponse, document: &Html) -> Self {
+        let mut directives = Self::default();
+        for header in response.headers.get_all("x-robots-tag") {
+            directives.apply(header);
+        }
+        let selector = Selector::parse(r#"meta[name="robots" i][content]"#).unwrap();
+        for meta in document.select(&selector) {
+            directives.apply(meta.value().attr("content").unwrap_or_default());
+        }
+        directives
+    }
+
+    /// Applies a comma separated directive list such as `noindex, nofollow`.
+    /// Directives scoped to a crawler (`googlebot: nofollow`) are ignored.
+    fn apply(&mut self, value: &str) {
+        for directive in value.split(',') {
+            match directive.trim().to_ascii_lowercase().as_str() {
+                "none" => {
+                    self.noindex = true;
+                    self.nofollow = true;
+                }
+                "noindex" => self.noindex = true,
+                "nofollow" => self.nofollow = true,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Links of a page and the robots directives they were filtered with.
+#[derive(Debug, Clone, Default)]
+pub struct PageLinks {
+    pub links: Vec<Url>,
+    pub directives: RobotsDirectives,
+}
+
+/// Extracts the absolute links of an HTML response that may be followed.
+///
+/// Unless `SpiderConfig::respect_nofollow` is turned off, links marked
+/// `rel="nofollow"` are dropped, and a page-level `nofollow` from a robots
+/// meta tag or `X-Robots-Tag` header drops every link.
+pub fn extract_links(response: &HttpResponse, config: &SpiderConfig) -> PageLinks {
+    let document = Html::parse_document(response.body_text().unwrap_or_default());
+    let directives = RobotsDirectives::from_response(response, &document);
+    if config.respect_nofollow && directives.nofollow {
+        return PageLinks {
+            links: Vec::new(),
+            directives,
+        };
+    }
+
+    let selector = Selector::parse("a[href]").unwrap();
+    let links = document
+        .select(&selector)
+        .filter(|a| !config.respect_nofollow || !is_nofollow(a))
+        .filter_map(|a| a.value().attr("href"))
+        .filter_map(|href| response.url.join(href).ok())
+        .collect();
+    PageLinks { links, directives }
+}
+
+/// Extracts follow-up requests from HTML responses: matching elements are
+/// read from the configured tag attributes, joined with the page URL,
+/// filtered and turned into [`HttpRequest`]s one level deeper than the page.
+///
+/// Defaults to `a[href]` and `area[href]` anywhere in the page, drops
+/// `nofollow` links like [`extract_links`] and removes duplicates.
+#[derive(Debug, Clone)]
+pub struct LinkExtractor {
+    allow: Vec<Regex>,
+    deny: Vec<Regex>,
+    restrict: Vec<Selector>,
+    tags: Vec<(String, String)>,
+    callback: SpiderCallback,
+    respect_nofollow: bool,
+}
+
+impl Default for LinkExtractor {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            deny: Vec::new(),
+            restrict: Vec::new(),
+            tags: vec![
+                ("a".to_string(), "href".to_string()),
+                ("area".to_string(), "href".to_string()),
+            ],
+            callback: SpiderCallback::ParseItem,
+            respect_nofollow: true,
+        }
+    }
+}
+
+impl LinkExtractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps only URLs matching one of the allow patterns.
+    pub fn with_allow(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.allow.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    pub fn with_deny(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.deny.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Only looks for links inside elements matching `css`, e.g.
+    /// `ul.pagination` or `#content`.
+    pub fn with_restrict_css(mut self, css: &str) -> Result<Self, ScraperError> {
+        let selector = Selector::parse(css)
+            .map_err(|e| ScraperError::ParsingError(format!("Invalid selector {}: {}", css, e)))?;
+        self.restrict.push(selector);
+        Ok(self)
+    }
+
+    /// Replaces the default tags with `tag[attr]` pairs, e.g.
+    /// `[("link", "href"), ("img", "src")]`.
+    pub fn with_tags(mut self, tags: &[(&str, &str)]) -> Self {
+        self.tags = tags
+            .iter()
+            .map(|(tag, attr)| (tag.to_string(), attr.to_string()))
+            .collect();
+        self
+    }
+
+    /// Callback of the extracted requests. `ParseItem` by default.
+    pub fn with_callback(mut self, callback: SpiderCallback) -> Self {
+        self.callback = callback;
+        self
+    }
+
+    pub fn with_respect_nofollow(mut self, respect: bool) -> Self {
+        self.respect_nofollow = respect;
+        self
+    }
+
+    /// Absolute URLs of the matching links, in document order.
+    pub fn extract_urls(&self, response: &HttpResponse) -> Vec<Url> {
+        let document = Html::parse_document(response.body_text().unwrap_or_default());
+        if self.respect_nofollow && RobotsDirectives::from_response(response, &document).nofollow {
+            return Vec::new();
+        }
+
+        let roots: Vec<ElementRef> = if self.restrict.is_empty() {
+            vec![document.root_element()]
+        } else {
+            self.restrict
+                .iter()
+                .flat_map(|selector| document.select(selector))
+                .collect()
+        };
+
+        let mut seen = HashSet::new();
+        let mut urls = Vec::new();
+        for (tag, attr) in &self.tags {
+            let Ok(selector) = Selector::parse(&format!("{}[{}]", tag, attr)) else {
+                continue;
+            };
+            for element in roots.iter().flat_map(|root| root.select(&selector)) {
+                if self.respect_nofollow && is_nofollow(&element) {
+                    continue;
+                }
+                let Some(url) = element
+                    .value()
+                    .attr(attr)
+                    .and_then(|href| response.url.join(href.trim()).ok())
+                else {
+                    continue;
+                };
+                if self.is_allowed(&url) && seen.insert(url.to_string()) {
+                    urls.push(url);
+                }
+            }
+        }
+        urls
+    }
+
+    /// Requests for [`extract_urls`](Self::extract_urls), ready to return
+    /// from `Spider::parse`.
+    pub fn extract(&self, response: &HttpResponse) -> Vec<HttpRequest> {
+        let depth = response.from_request.depth + 1;
+        self.extract_urls(response)
+            .into_iter()
+            .map(|url| HttpRequest::new(url, self.callback.clone(), depth))
+            .collect()
+    }
+
+    fn is_allowed(&self, url: &Url) -> bool {
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+        let url = url.as_str();
+        (self.allow.is_empty() || self.allow.iter().any(|re| re.is_match(url)))
+            && !self.deny.iter().any(|re| re.is_match(url))
+    }
+}
+
+fn is_nofollow(element: &ElementRef) -> bool {
+    element.value().attr("rel").is_some_and(|rel| {
+        rel.split_whitespace()
+            .any(|token| token.eq_ignore_ascii_case("nofollow"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn html_response(body: &str, headers: &[(&str, &str)]) -> HttpResponse {
+        let url = Url::parse("https://example.com/list").unwrap();
+        HttpResponse {
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..HttpResponse::for_test(&url, body)
+        }
+    }
+
+    const LINKS: &str = r#"<a href="/a">A</a><a rel="external NoFollow" href="/b">B</a>"#;
+
+    #[test]
+    fn test_drops_nofollow_links() {
+        let response = html_response(LINKS, &[]);
+        let paths = |links: PageLinks| -> Vec<String> {
+            links.links.iter().map(|u| u.path().to_string()).collect()
+        };
+
+        assert_eq!(
+            paths(extract_links(&response, &SpiderConfig::default())),
+            ["/a"]
+        );
+        let config = SpiderConfig::default().with_respect_nofollow(false);
+        assert_eq!(paths(extract_links(&response, &config)), ["/a", "/b"]);
+    }
+
+    #[test]
+    fn test_page_level_nofollow() {
+        let meta = format!(
+            r#"<meta name="ROBOTS" content="noindex, nofollow">{}"#,
+            LINKS
+        );
+        let links = extract_links(&html_response(&meta, &[]), &SpiderConfig::default());
+        assert!(links.links.is_empty());
+        assert!(links.directives.noindex);
+
+        let header = html_response(LINKS, &[("x-robots-tag", "none")]);
+        assert!(extract_links(&header, &SpiderConfig::default())
+            .links
+            .is_empty());
+        let scoped = html_response(LINKS, &[("x-robots-tag", "otherbot: nofollow")]);
+        assert_eq!(
+            extract_links(&scoped, &SpiderConfig::default()).links.len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_link_extractor_scope_filters_and_requests() {
+        let body = r#"
+            <a href="/about">About</a>
+            <ul class="products">
+              <li><a href=" /p/1 ">One</a></li>
+              <li><a href="/p/1#reviews">One again</a></li>
+              <li><a href="/p/2?ref=ad">Ad</a></li>
+              <li><a href="mailto:shop@example.com">Mail</a></li>
+              <li><a rel="nofollow" href="/p/3">Hidden</a></li>
+            </ul>"#;
+        let response = html_response(body, &[]);
+        let extractor = LinkExtractor::new()
+            .with_restrict_css("ul.products")
+            .unwrap()
+            .with_allow(r"/p/\d+")
+            .unwrap()
+            .with_deny(r"ref=ad")
+            .unwrap();
+
+        let requests = extractor.extract(&response);
+        let urls: Vec<&str> = requests.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            ["https://example.com/p/1", "https://example.com/p/1#reviews"]
+        );
+        assert!(requests
+            .iter()
+            .all(|r| r.depth == 1 && r.callback == SpiderCallback::ParseItem));
+        assert!(LinkExtractor::new().with_restrict_css("ul[").is_err());
+    }
+}
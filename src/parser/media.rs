@@ -0,0 +1,200 @@
+use crate::core::spider::ParsedData;
+use crate::{HttpResponse, ScraperError, ScraperResult};
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ImageError {
+    #[error("Response is not a supported image")]
+    NotImage,
+    #[error("Failed to decode image: {0}")]
+    Decode(String),
+}
+
+/// Properties of a downloaded image, computed once so downstream dedup and
+/// quality filters don't need to decode it again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImageMetadata {
+    pub width: u32,
+    pub height: u32,
+    /// Lowercase format name, e.g. `png` or `jpeg`.
+    pub format: String,
+    /// Most common color as `#rrggbb`, ignoring transparent pixels.
+    pub dominant_color: String,
+    /// 64-bit difference hash as 16 hex digits. Visually similar images have
+    /// hashes a small Hamming distance apart.
+    pub perceptual_hash: String,
+    /// Encoded size in bytes.
+    pub size: usize,
+}
+
+impl ImageMetadata {
+    /// Number of differing bits between the two perceptual hashes.
+    pub fn hash_distance(&self, other: &ImageMetadata) -> Option<u32> {
+        let a = u64::from_str_radix(&self.perceptual_hash, 16).ok()?;
+        let b = u64::from_str_radix(&other.perceptual_hash, 16).ok()?;
+        Some((a ^ b).count_ones())
+    }
+
+    /// Whether the images look the same, e.g. one is a resized copy of the
+    /// other. A `max_distance` of about 10 out of 64 bits works well.
+    pub fn is_near_duplicate(&self, other: &ImageMetadata, max_distance: u32) -> bool {
+        self.hash_distance(other)
+            .is_some_and(|distance| distance <= max_distance)
+    }
+}
+
+/// Whether the response carries an image, judged by content type or magic
+/// bytes.
+pub fn is_image(response: &HttpResponse) -> bool {
+    response
+        .headers
+        .get("content-type")
+        .is_some_and(|ct| ct.starts_with("image/"))
+        || image::guess_format(&response.raw_body).is_ok()
+}
+
+pub fn extract_image_metadata(bytes: &[u8]) -> Result<ImageMetadata, ImageError> {
+    let format = image::guess_format(bytes).map_err(|_| ImageError::NotImage)?;
+    let image = image::load_from_memory_with_format(bytes, format)
+        .map_err(|e| ImageError::Decode(e.to_string()))?;
+    let (width, height) = image.dimensions();
+
+    Ok(ImageMetadata {
+        width,
+        height,
+        format: format!("{:?}", format).to_lowercase(),
+        dominant_color: dominant_color(&image),
+        perceptual_hash: format!("{:016x}", difference_hash(&image)),
+        size: bytes.len(),
+    })
+}
+
+/// Buckets the pixels of a thumbnail into 512 coarse colors and averages the
+/// largest bucket.
+fn dominant_color(image: &DynamicImage) -> String {
+    let thumbnail = image.thumbnail(64, 64).to_rgba8();
+    let mut buckets: HashMap<(u8, u8, u8), (u64, [u64; 3])> = HashMap::new();
+    for pixel in thumbnail.pixels().filter(|p| p[3] >= 128) {
+        let [r, g, b, _] = pixel.0;
+        let (count, sums) = buckets.entry((r >> 5, g >> 5, b >> 5)).or_default();
+        *count += 1;
+        sums[0] += r as u64;
+        sums[1] += g as u64;
+        sums[2] += b as u64;
+    }
+
+    buckets
+        .values()
+        .max_by_key(|(count, _)| *count)
+        .map(|(count, sums)| {
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                sums[0] / count,
+                sums[1] / count,
+                sums[2] / count
+            )
+        })
+        .unwrap_or_else(|| "#000000".to_string())
+}
+
+/// dHash: compares horizontally adjacent pixels of a 9x8 grayscale thumbnail.
+fn difference_hash(image: &DynamicImage) -> u64 {
+    let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+/// Converts image responses into a `ParsedData::Item` holding the image URL
+/// and its [`ImageMetadata`].
+#[derive(Debug, Clone, Default)]
+pub struct ImageMetadataExtractor;
+
+impl ImageMetadataExtractor {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn extract(&self, response: &HttpResponse) -> ScraperResult<ParsedData> {
+        let metadata = extract_image_metadata(&response.raw_body)
+            .map_err(|e| ScraperError::ParsingError(e.to_string()))?;
+
+        let mut item = json!(metadata);
+        item["url"] = json!(response.url.as_str());
+        Ok(ParsedData::Item(item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgb, RgbImage};
+    use std::io::Cursor;
+
+    fn encode(image: RgbImage, format: ImageFormat) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgb8(image)
+            .write_to(&mut Cursor::new(&mut bytes), format)
+            .unwrap();
+        bytes
+    }
+
+    fn gradient(width: u32, height: u32) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| {
+            if x < width / 4 {
+                Rgb([200, 30, 30])
+            } else {
+                Rgb([(x * 255 / width) as u8, (y * 255 / height) as u8, 128])
+            }
+        })
+    }
+
+    #[test]
+    fn test_extracts_metadata() {
+        let image = RgbImage::from_fn(40, 20, |x, _| {
+            if x < 30 {
+                Rgb([250, 0, 0])
+            } else {
+                Rgb([0, 0, 250])
+            }
+        });
+        let bytes = encode(image, ImageFormat::Png);
+        let metadata = extract_image_metadata(&bytes).unwrap();
+
+        assert_eq!((metadata.width, metadata.height), (40, 20));
+        assert_eq!(metadata.format, "png");
+        assert_eq!(metadata.dominant_color, "#fa0000");
+        assert_eq!(metadata.size, bytes.len());
+        assert!(matches!(
+            extract_image_metadata(b"<html></html>"),
+            Err(ImageError::NotImage)
+        ));
+    }
+
+    #[test]
+    fn test_resized_copy_is_near_duplicate() {
+        let original = extract_image_metadata(&encode(gradient(200, 100), ImageFormat::Png));
+        let resized = extract_image_metadata(&encode(gradient(100, 50), ImageFormat::Jpeg));
+        let flipped = extract_image_metadata(&encode(
+            image::imageops::flip_horizontal(&gradient(200, 100)),
+            ImageFormat::Png,
+        ));
+        let (original, resized, flipped) = (original.unwrap(), resized.unwrap(), flipped.unwrap());
+
+        assert_eq!(resized.format, "jpeg");
+        assert!(original.is_near_duplicate(&resized, 10));
+        assert!(!original.is_near_duplicate(&flipped, 10));
+    }
+}
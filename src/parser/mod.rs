@@ -1,2 +1,36 @@
+pub mod api_discovery;
 mod base;
-pub use base::Parser;
+pub mod breadcrumbs;
+pub mod content;
+pub mod feed;
+pub mod form;
+pub mod links;
+#[cfg(feature = "images")]
+pub mod media;
+pub mod pagination;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+pub mod sitemap;
+pub mod spreadsheet;
+pub mod xml;
+
+pub use api_discovery::{discover_api_endpoints, ApiDiscoveryReport};
+pub use base::{CallbackParsers, Parser};
+pub use breadcrumbs::{breadcrumbs_from_url, extract_breadcrumbs, Breadcrumbs, Crumb};
+pub use content::{
+    BinaryContent, BinaryHandler, ContentDispatcher, ContentParser, HtmlContent, HtmlParser,
+    JsonParser, ParsedContent, XmlParser,
+};
+pub use feed::{Feed, FeedEntry, FeedKind};
+pub use form::{Form, FormParser};
+pub use links::{extract_links, LinkExtractor, PageLinks, RobotsDirectives};
+#[cfg(feature = "images")]
+pub use media::{extract_image_metadata, ImageMetadata, ImageMetadataExtractor};
+pub use pagination::{
+    link_header_next, next_link, numbered_pages, parse_link_header, ApiPaginator,
+};
+#[cfg(feature = "pdf")]
+pub use pdf::{PdfExtractor, PdfOutput};
+pub use sitemap::{Sitemap, SitemapEntry};
+pub use spreadsheet::SpreadsheetParser;
+pub use xml::{XmlDocument, XmlElement, XmlError};
@@ -0,0 +1,375 @@
+//! Requests for the next pages of a listing.
+//!
+//! Every helper takes the callback and depth of the page requests, so a
+//! spider decides whether pagination counts towards `max_depth`.
+
+use crate::core::SpiderCallback;
+use crate::{HttpRequest, HttpResponse, ScraperError, ScraperResult};
+use scraper::{Html, Selector};
+use serde_json::Value;
+use std::ops::RangeInclusive;
+use url::Url;
+
+/// Follows the first link matching `css`, e.g. `li.next a` or
+/// `a[rel=next]`. Empty on the last page.
+pub fn next_link(
+    response: &HttpResponse,
+    css: &str,
+    callback: SpiderCallback,
+    depth: usize,
+) -> ScraperResult<Vec<HttpRequest>> {
+    let selector = Selector::parse(css)
+        .map_err(|e| ScraperError::ParsingError(format!("Invalid selector {}: {}", css, e)))?;
+    let document = Html::parse_document(response.body_text()?);
+    Ok(document
+        .select(&selector)
+        .filter_map(|link| link.value().attr("href"))
+        .find_map(|href| response.url.join(href.trim()).ok())
+        .map(|url| HttpRequest::new(url, callback, depth))
+        .into_iter()
+        .collect())
+}
+
+/// One request per page number, replacing `{n}` in `template`, e.g.
+/// `https://shop.com/list?page={n}` with `2..=10`.
+pub fn numbered_pages(
+    template: &str,
+    pages: RangeInclusive<u32>,
+    callback: SpiderCallback,
+    depth: usize,
+) -> Result<Vec<HttpRequest>, url::ParseError> {
+    pages
+        .map(|page| {
+            let url = Url::parse(&template.replace("{n}", &page.to_string()))?;
+            Ok(HttpRequest::new(url, callback.clone(), depth))
+        })
+        .collect()
+}
+
+/// Follows the `rel="next"` target of the response's `Link` header, as sent
+/// by paginated APIs such as GitHub's.
+pub fn link_header_next(
+    response: &HttpResponse,
+    callback: SpiderCallback,
+    depth: usize,
+) -> Vec<HttpRequest> {
+    response
+        .headers
+        .get_all("link")
+        .find_map(|header| {
+            parse_link_header(header, &response.url)
+                .into_iter()
+                .find(|(rel, _)| rel == "next")
+        })
+        .map(|(_, url)| HttpRequest::new(url, callback, depth))
+        .into_iter()
+        .collect()
+}
+
+/// `(rel, url)` pairs of an RFC 8288 `Link` header, relative targets
+/// resolved against `base`. Links with several relations appear once per
+/// relation.
+pub fn parse_link_header(header: &str, base: &Url) -> Vec<(String, Url)> {
+    let mut links = Vec::new();
+    for link in header.split(',') {
+        let mut parts = link.split(';');
+        let Some(target) = parts
+            .next()
+            .map(str::trim)
+            .and_then(|t| t.strip_prefix('<'))
+            .and_then(|t| t.strip_suffix('>'))
+        else {
+            continue;
+        };
+        let Ok(url) = base.join(target) else {
+            continue;
+        };
+        for param in parts {
+            let Some((name, value)) = param.split_once('=') else {
+                continue;
+            };
+            if name.trim().eq_ignore_ascii_case("rel") {
+                for rel in value.trim().trim_matches('"').split_whitespace() {
+                    links.push((rel.to_ascii_lowercase(), url.clone()));
+                }
+            }
+        }
+    }
+    links
+}
+
+/// How a JSON API points to its next page.
+#[derive(Debug, Clone, PartialEq)]
+enum ApiPagination {
+    Cursor {
+        pointer: String,
+        param: String,
+    },
+    NextUrl {
+        pointer: String,
+    },
+    Offset {
+        param: String,
+        limit: u64,
+        items: String,
+        total: Option<String>,
+    },
+}
+
+/// Derives the next request of a paginated JSON API from the body of the
+/// current page. Locations in the body are JSON pointers such as
+/// `/meta/next_cursor`.
+///
+/// The next request is a copy of the current one (method, headers, body and
+/// meta are kept) with the new URL, so a spider can drive the whole listing
+/// from `parse` by returning [`ApiPaginator::next_request`] along with the
+/// page's items.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiPaginator {
+    pagination: ApiPagination,
+}
+
+impl ApiPaginator {
+    /// Sends the token at `pointer` back as the `param` query parameter.
+    /// Stops when the token is missing, null or empty.
+    pub fn cursor(pointer: &str, param: &str) -> Self {
+        Self {
+            pagination: ApiPagination::Cursor {
+                pointer: pointer.to_string(),
+                param: param.to_string(),
+            },
+        }
+    }
+
+    /// Follows the URL at `pointer`, resolved against the current URL.
+    /// Stops when it is missing or null.
+    pub fn next_url(pointer: &str) -> Self {
+        Self {
+            pagination: ApiPagination::NextUrl {
+                pointer: pointer.to_string(),
+            },
+        }
+    }
+
+    /// Advances the `param` query parameter by `limit`. Stops when the array
+    /// at `items` has fewer than `limit` entries.
+    pub fn offset(param: &str, limit: u64, items: &str) -> Self {
+        Self {
+            pagination: ApiPagination::Offset {
+                param: param.to_string(),
+                limit,
+                items: items.to_string(),
+                total: None,
+            },
+        }
+    }
+
+    /// For offset pagination, also stops once the offset reaches the total
+    /// count at `pointer`.
+    pub fn with_total(mut self, pointer: &str) -> Self {
+        if let ApiPagination::Offset { total, .. } = &mut self.pagination {
+            *total = Some(pointer.to_string());
+        }
+        self
+    }
+
+    /// The request for the page after `response`, `None` on the last page.
+    pub fn next_request(
+        &self,
+        response: &HttpResponse,
+        callback: SpiderCallback,
+        depth: usize,
+    ) -> ScraperResult<Option<HttpRequest>> {
+        let body: Value = serde_json::from_str(response.body_text()?).map_err(|e| {
+            ScraperError::ParsingError(format!("Invalid JSON page {}: {}", response.url, e))
+        })?;
+        let url = match &self.pagination {
+            ApiPagination::Cursor { pointer, param } => {
+                let cursor = match body.pointer(pointer) {
+                    Some(Value::String(cursor)) if !cursor.is_empty() => cursor.clone(),
+                    Some(Value::Number(cursor)) => cursor.to_string(),
+                    _ => return Ok(None),
+                };
+                Some(with_query_param(&response.from_request.url, param, &cursor))
+            }
+            ApiPagination::NextUrl { pointer } => body
+                .pointer(pointer)
+                .and_then(Value::as_str)
+                .filter(|next| !next.is_empty())
+                .and_then(|next| response.url.join(next).ok()),
+            ApiPagination::Offset {
+                param,
+                limit,
+                items,
+                total,
+            } => {
+                let url = &response.from_request.url;
+                let offset = url
+                    .query_pairs()
+                    .find(|(name, _)| name == param)
+                    .and_then(|(_, value)| value.parse::<u64>().ok())
+                    .unwrap_or(0);
+                let next = offset + limit;
+                let count = body
+                    .pointer(items)
+                    .and_then(Value::as_array)
+                    .map_or(0, |items| items.len() as u64);
+                let total = total
+                    .as_ref()
+                    .and_then(|pointer| body.pointer(pointer))
+                    .and_then(Value::as_u64);
+                if count < *limit || total.is_some_and(|total| next >= total) {
+                    None
+                } else {
+                    Some(with_query_param(url, param, &next.to_string()))
+                }
+            }
+        };
+        Ok(url.map(|url| {
+            let mut request = (*response.from_request).clone();
+            request.url = url;
+            request.callback = callback;
+            request.depth = depth;
+            request
+        }))
+    }
+}
+
+/// `url` with the `name` query parameter set to `value`, other parameters
+/// kept in order.
+fn with_query_param(url: &Url, name: &str, value: &str) -> Url {
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| key != name)
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    let mut url = url.clone();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair(name, value);
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(body: &str, headers: &[(&str, &str)]) -> HttpResponse {
+        let url = Url::parse("https://shop.com/list?page=1").unwrap();
+        HttpResponse {
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..HttpResponse::for_test(&url, body)
+        }
+    }
+
+    #[test]
+    fn test_pagination_helpers() {
+        let page = response(
+            r#"<ul><li class="next"><a href="?page=2">Next</a></li></ul>"#,
+            &[(
+                "link",
+                r#"<https://api.shop.com/items?page=2>; rel="next", </items?page=9>; rel="last""#,
+            )],
+        );
+
+        let next = next_link(&page, "li.next a", SpiderCallback::ParsePagination, 0).unwrap();
+        assert_eq!(next[0].url.as_str(), "https://shop.com/list?page=2");
+        assert_eq!(next[0].callback, SpiderCallback::ParsePagination);
+        assert!(
+            next_link(&page, "a.missing", SpiderCallback::ParsePagination, 0)
+                .unwrap()
+                .is_empty()
+        );
+
+        let next = link_header_next(&page, SpiderCallback::ParseItem, 1);
+        assert_eq!(next[0].url.as_str(), "https://api.shop.com/items?page=2");
+        assert_eq!(next[0].depth, 1);
+        let links = parse_link_header(page.headers.get("link").unwrap(), &page.url);
+        assert_eq!(links[1].0, "last");
+        assert_eq!(links[1].1.as_str(), "https://shop.com/items?page=9");
+
+        let pages = numbered_pages(
+            "https://shop.com/list?page={n}",
+            2..=4,
+            SpiderCallback::ParseItem,
+            1,
+        )
+        .unwrap();
+        let urls: Vec<_> = pages.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://shop.com/list?page=2",
+                "https://shop.com/list?page=3",
+                "https://shop.com/list?page=4"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_api_paginator() {
+        let json_page = |url: &str, body: &str| {
+            let mut page = response(body, &[]);
+            page.url = Url::parse(url).unwrap();
+            page.from_request.url = page.url.clone();
+            page.from_request
+                .headers
+                .insert("Authorization", "Bearer t");
+            page
+        };
+
+        let cursor = ApiPaginator::cursor("/meta/next", "cursor");
+        let page = json_page(
+            "https://api.shop.com/items?cursor=a&size=2",
+            r#"{"items": [1, 2], "meta": {"next": "b"}}"#,
+        );
+        let next = cursor
+            .next_request(&page, SpiderCallback::ParseItem, 1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            next.url.as_str(),
+            "https://api.shop.com/items?size=2&cursor=b"
+        );
+        assert_eq!(next.headers["Authorization"], "Bearer t");
+        let last = json_page("https://api.shop.com/items", r#"{"meta": {"next": null}}"#);
+        assert!(cursor
+            .next_request(&last, SpiderCallback::ParseItem, 1)
+            .unwrap()
+            .is_none());
+
+        let next_url = ApiPaginator::next_url("/links/next");
+        let page = json_page(
+            "https://api.shop.com/items",
+            r#"{"links": {"next": "/items?page=2"}}"#,
+        );
+        let next = next_url
+            .next_request(&page, SpiderCallback::ParseItem, 1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.url.as_str(), "https://api.shop.com/items?page=2");
+
+        let offset = ApiPaginator::offset("offset", 2, "/items").with_total("/total");
+        let page = json_page(
+            "https://api.shop.com/items?offset=2",
+            r#"{"items": [3, 4], "total": 6}"#,
+        );
+        let next = offset
+            .next_request(&page, SpiderCallback::ParseItem, 1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.url.as_str(), "https://api.shop.com/items?offset=4");
+        let page = json_page(
+            "https://api.shop.com/items?offset=4",
+            r#"{"items": [5, 6], "total": 6}"#,
+        );
+        assert!(offset
+            .next_request(&page, SpiderCallback::ParseItem, 1)
+            .unwrap()
+            .is_none());
+    }
+}
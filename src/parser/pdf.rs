@@ -0,0 +1,154 @@
+use crate::core::spider::ParsedData;
+use crate::{HttpResponse, ScraperError, ScraperResult};
+use serde_json::{json, Value};
+use std::panic;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PdfError {
+    #[error("Response is not a PDF document")]
+    NotPdf,
+    #[error("Failed to extract PDF text: {0}")]
+    Extraction(String),
+}
+
+/// Whether the response carries a PDF, judged by content type or magic bytes.
+pub fn is_pdf(response: &HttpResponse) -> bool {
+    response
+        .headers
+        .get("content-type")
+        .is_some_and(|ct| ct.contains("application/pdf"))
+        || response.raw_body.starts_with(b"%PDF")
+}
+
+/// Extracts the text of every page of a PDF document.
+pub fn extract_pages(bytes: &[u8]) -> Result<Vec<String>, PdfError> {
+    if !bytes.starts_with(b"%PDF") {
+        return Err(PdfError::NotPdf);
+    }
+
+    // pdf-extract panics on some malformed documents instead of erroring.
+    panic::catch_unwind(|| pdf_extract::extract_text_from_mem_by_pages(bytes))
+        .map_err(|_| PdfError::Extraction("extractor panicked".to_string()))?
+        .map_err(|e| PdfError::Extraction(e.to_string()))
+}
+
+pub fn extract_text(bytes: &[u8]) -> Result<String, PdfError> {
+    Ok(extract_pages(bytes)?.join("\n"))
+}
+
+/// Splits page text into table rows, treating runs of two or more spaces
+/// (or tabs) as column separators. Lines with a single cell are skipped.
+pub fn extract_table_rows(text: &str) -> Vec<Vec<String>> {
+    text.lines()
+        .map(|line| {
+            line.replace('\t', "  ")
+                .split("  ")
+                .map(str::trim)
+                .filter(|cell| !cell.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .filter(|cells| cells.len() > 1)
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PdfOutput {
+    /// The whole document as a single `ParsedData::Raw` string.
+    #[default]
+    Text,
+    /// One `{page, text}` item per page.
+    Pages,
+    /// One item per detected table row, with cells under `columns`.
+    TableRows,
+}
+
+/// Converts binary PDF responses into `ParsedData`, so a spider callback can
+/// treat a PDF like any other page.
+#[derive(Debug, Clone, Default)]
+pub struct PdfExtractor {
+    output: PdfOutput,
+}
+
+impl PdfExtractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_output(mut self, output: PdfOutput) -> Self {
+        self.output = output;
+        self
+    }
+
+    pub fn extract(&self, response: &HttpResponse) -> ScraperResult<ParsedData> {
+        let pages = extract_pages(&response.raw_body)
+            .map_err(|e| ScraperError::ParsingError(e.to_string()))?;
+
+        Ok(match self.output {
+            PdfOutput::Text => ParsedData::Raw(pages.join("\n")),
+            PdfOutput::Pages => ParsedData::Items(
+                pages
+                    .iter()
+                    .enumerate()
+                    .map(|(index, text)| json!({ "page": index + 1, "text": text.trim() }))
+                    .collect(),
+            ),
+            PdfOutput::TableRows => ParsedData::Items(
+                pages
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(index, text)| {
+                        extract_table_rows(text)
+                            .into_iter()
+                            .map(move |columns| json!({ "page": index + 1, "columns": columns }))
+                    })
+                    .collect::<Vec<Value>>(),
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_non_pdf() {
+        assert!(matches!(
+            extract_text(b"<html></html>"),
+            Err(PdfError::NotPdf)
+        ));
+        assert!(extract_text(b"%PDF-1.4 truncated").is_err());
+    }
+
+    #[test]
+    fn test_extracts_text_of_every_page() {
+        let pdf = include_bytes!("../../tests/fixtures/report.pdf");
+        let pages = extract_pages(pdf).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].trim(), "Quarterly report");
+        assert_eq!(pages[1].trim(), "Widget sales rose");
+
+        let url = url::Url::parse("https://example.com/report.pdf").unwrap();
+        let response = HttpResponse::for_test(&url, pdf);
+        assert!(is_pdf(&response));
+        let data = PdfExtractor::new()
+            .with_output(PdfOutput::Pages)
+            .extract(&response)
+            .unwrap();
+        assert!(matches!(
+            data,
+            ParsedData::Items(items) if items[1] == json!({ "page": 2, "text": "Widget sales rose" })
+        ));
+    }
+
+    #[test]
+    fn test_table_rows() {
+        let rows = extract_table_rows("Report title\nName  Price\tQty\nWidget   9.99  3\n");
+        assert_eq!(
+            rows,
+            vec![vec!["Name", "Price", "Qty"], vec!["Widget", "9.99", "3"],]
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use super::xml::{XmlDocument, XmlError};
+use crate::{HttpResponse, ScraperError, ScraperResult};
+use flate2::read::GzDecoder;
+use std::io::Read;
+use url::Url;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapEntry {
+    pub loc: Url,
+    pub lastmod: Option<String>,
+}
+
+/// A `sitemap.xml` file: either an index of other sitemaps or the URLs of
+/// a site.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sitemap {
+    Index(Vec<SitemapEntry>),
+    UrlSet(Vec<SitemapEntry>),
+}
+
+impl Sitemap {
+    /// Parses a sitemap or sitemap index, decompressing `.xml.gz` files
+    /// served without a `Content-Encoding`. Entries with an invalid `<loc>`
+    /// are skipped.
+    pub fn parse(body: &[u8]) -> Result<Self, XmlError> {
+        let text = if body.starts_with(&GZIP_MAGIC) {
+            let mut text = String::new();
+            GzDecoder::new(body)
+                .read_to_string(&mut text)
+                .map_err(|e| XmlError::Malformed(format!("Invalid gzip data: {}", e)))?;
+            text
+        } else {
+            String::from_utf8_lossy(body).into_owned()
+        };
+        let document = XmlDocument::parse(&text)?;
+        let root = document.root();
+        let entries = |name: &str| {
+            root.elements()
+                .filter(|e| e.name == name)
+                .filter_map(|e| {
+                    let loc = Url::parse(e.child_text("loc")?.trim()).ok()?;
+                    let lastmod = e.child_text("lastmod").map(|t| t.trim().to_string());
+                    Some(SitemapEntry { loc, lastmod })
+                })
+                .collect()
+        };
+        match root.name.as_str() {
+            "sitemapindex" => Ok(Sitemap::Index(entries("sitemap"))),
+            "urlset" => Ok(Sitemap::UrlSet(entries("url"))),
+            other => Err(XmlError::Malformed(format!(
+                "Unexpected sitemap root element <{}>",
+                other
+            ))),
+        }
+    }
+
+    pub fn from_response(response: &HttpResponse) -> ScraperResult<Self> {
+        Self::parse(&response.raw_body).map_err(|e| ScraperError::ParsingError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    #[test]
+    fn test_parses_gzipped_urlset_and_index() {
+        let urlset = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <url><loc> https://shop.com/p/1 </loc><lastmod>2024-05-01</lastmod></url>
+              <url><loc>not a url</loc></url>
+              <url><loc>https://shop.com/p/2</loc></url>
+            </urlset>"#;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(urlset.as_bytes()).unwrap();
+        let Sitemap::UrlSet(urls) = Sitemap::parse(&encoder.finish().unwrap()).unwrap() else {
+            panic!("expected a urlset");
+        };
+        assert_eq!(urls.len(), 2);
+        assert_eq!(urls[0].loc.as_str(), "https://shop.com/p/1");
+        assert_eq!(urls[0].lastmod.as_deref(), Some("2024-05-01"));
+
+        let index = r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <sitemap><loc>https://shop.com/sitemap-products.xml.gz</loc></sitemap>
+            </sitemapindex>"#;
+        assert!(matches!(
+            Sitemap::parse(index.as_bytes()).unwrap(),
+            Sitemap::Index(entries) if entries.len() == 1
+        ));
+        assert!(Sitemap::parse(b"<html></html>").is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use crate::core::spider::ParsedData;
+use crate::{HttpResponse, ScraperError, ScraperResult};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SpreadsheetError {
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[cfg(feature = "xlsx")]
+    #[error("XLSX error: {0}")]
+    Xlsx(#[from] calamine::XlsxError),
+    #[error("Sheet not found: {0}")]
+    SheetNotFound(String),
+    #[error("Unsupported spreadsheet format")]
+    UnsupportedFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpreadsheetFormat {
+    Csv,
+    #[cfg(feature = "xlsx")]
+    Xlsx,
+}
+
+impl SpreadsheetFormat {
+    /// Guesses the format from the content type, falling back to the file
+    /// extension of the URL and finally to the ZIP magic bytes used by XLSX.
+    pub fn detect(response: &HttpResponse) -> Option<Self> {
+        let content_type = response
+            .headers
+            .get("content-type")
+            .map(|ct| ct.to_lowercase())
+            .unwrap_or_default();
+        let path = response.url.path().to_lowercase();
+
+        if content_type.contains("text/csv") || path.ends_with(".csv") {
+            return Some(SpreadsheetFormat::Csv);
+        }
+
+        #[cfg(feature = "xlsx")]
+        if content_type.contains("spreadsheetml")
+            || path.ends_with(".xlsx")
+            || response.raw_body.starts_with(b"PK\x03\x04")
+        {
+            return Some(SpreadsheetFormat::Xlsx);
+        }
+
+        None
+    }
+}
+
+/// Reads CSV data into JSON records keyed by the header row.
+pub fn parse_csv(bytes: &[u8], delimiter: u8) -> Result<Vec<Value>, SpreadsheetError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(bytes);
+    let headers: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+
+    reader
+        .records()
+        .map(|record| {
+            let record = record?;
+            Ok(rows_to_record(
+                &headers,
+                record.iter().map(|cell| Value::String(cell.to_string())),
+            ))
+        })
+        .collect()
+}
+
+/// Reads a worksheet (the first one when `sheet` is `None`) into JSON
+/// records keyed by its first row.
+#[cfg(feature = "xlsx")]
+pub fn parse_xlsx(bytes: &[u8], sheet: Option<&str>) -> Result<Vec<Value>, SpreadsheetError> {
+    use calamine::{open_workbook_from_rs, Data, Reader, Xlsx};
+    use std::io::Cursor;
+
+    let mut workbook: Xlsx<_> = open_workbook_from_rs(Cursor::new(bytes))?;
+    let sheet_name = match sheet {
+        Some(name) => name.to_string(),
+        None => workbook
+            .sheet_names()
+            .first()
+            .cloned()
+            .ok_or_else(|| SpreadsheetError::SheetNotFound("<first>".to_string()))?,
+    };
+    let range = workbook
+        .worksheet_range(&sheet_name)
+        .map_err(|_| SpreadsheetError::SheetNotFound(sheet_name.clone()))?;
+
+    let cell_to_value = |cell: &Data| match cell {
+        Data::Int(i) => Value::from(*i),
+        Data::Float(f) => Value::from(*f),
+        Data::Bool(b) => Value::Bool(*b),
+        Data::String(s) | Data::DateTimeIso(s) | Data::DurationIso(s) => Value::String(s.clone()),
+        Data::Empty => Value::Null,
+        other => Value::String(other.to_string()),
+    };
+
+    let mut rows = range.rows();
+    let headers: Vec<String> = match rows.next() {
+        Some(header_row) => header_row.iter().map(|cell| cell.to_string()).collect(),
+        None => return Ok(Vec::new()),
+    };
+
+    Ok(rows
+        .map(|row| rows_to_record(&headers, row.iter().map(cell_to_value)))
+        .collect())
+}
+
+fn rows_to_record(headers: &[String], cells: impl Iterator<Item = Value>) -> Value {
+    let mut record = Map::new();
+    for (index, cell) in cells.enumerate() {
+        let key = headers
+            .get(index)
+            .filter(|h| !h.is_empty())
+            .cloned()
+            .unwrap_or_else(|| format!("column_{}", index + 1));
+        record.insert(key, cell);
+    }
+    Value::Object(record)
+}
+
+/// Converts CSV/XLSX download responses into `ParsedData::Items`, one item
+/// per row.
+#[derive(Debug, Clone)]
+pub struct SpreadsheetParser {
+    delimiter: u8,
+    sheet: Option<String>,
+}
+
+impl Default for SpreadsheetParser {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            sheet: None,
+        }
+    }
+}
+
+impl SpreadsheetParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn with_sheet<S: Into<String>>(mut self, sheet: S) -> Self {
+        self.sheet = Some(sheet.into());
+        self
+    }
+
+    pub fn records(&self, response: &HttpResponse) -> Result<Vec<Value>, SpreadsheetError> {
+        match SpreadsheetFormat::detect(response) {
+            Some(SpreadsheetFormat::Csv) => parse_csv(&response.raw_body, self.delimiter),
+            #[cfg(feature = "xlsx")]
+            Some(SpreadsheetFormat::Xlsx) => parse_xlsx(&response.raw_body, self.sheet.as_deref()),
+            None => Err(SpreadsheetError::UnsupportedFormat),
+        }
+    }
+
+    pub fn extract(&self, response: &HttpResponse) -> ScraperResult<ParsedData> {
+        self.records(response)
+            .map(ParsedData::Items)
+            .map_err(|e| ScraperError::ParsingError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_csv_records() {
+        let csv = b"name,price,\nWidget,9.99,extra\n\"Gadget, large\",19.50\n";
+        let records = parse_csv(csv, b',').unwrap();
+        assert_eq!(
+            records,
+            vec![
+                json!({"name": "Widget", "price": "9.99", "column_3": "extra"}),
+                json!({"name": "Gadget, large", "price": "19.50"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_custom_delimiter() {
+        let records = parse_csv(b"a;b\n1;2\n", b';').unwrap();
+        assert_eq!(records, vec![json!({"a": "1", "b": "2"})]);
+    }
+
+    #[cfg(feature = "xlsx")]
+    #[test]
+    fn test_parse_xlsx_sheets() {
+        let xlsx = include_bytes!("../../tests/fixtures/products.xlsx");
+
+        // The first sheet is read by default; the empty cell becomes null.
+        let records = parse_xlsx(xlsx, None).unwrap();
+        assert_eq!(
+            records,
+            vec![
+                json!({"name": "Widget", "price": 9.99, "in_stock": true}),
+                json!({"name": "Gadget", "price": null, "in_stock": false}),
+            ]
+        );
+
+        let url = url::Url::parse("https://example.com/export.xlsx").unwrap();
+        let response = HttpResponse::for_test(&url, xlsx);
+        assert_eq!(
+            SpreadsheetFormat::detect(&response),
+            Some(SpreadsheetFormat::Xlsx)
+        );
+        let data = SpreadsheetParser::new()
+            .with_sheet("Stock")
+            .extract(&response)
+            .unwrap();
+        assert!(matches!(
+            data,
+            ParsedData::Items(items) if items == vec![json!({"sku": "W-1", "quantity": 3.0})]
+        ));
+
+        assert!(matches!(
+            parse_xlsx(xlsx, Some("Prices")),
+            Err(SpreadsheetError::SheetNotFound(name)) if name == "Prices"
+        ));
+    }
+}
@@ -0,0 +1,350 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::ResolveResult;
+use quick_xml::NsReader;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum XmlError {
+    #[error("Malformed XML: {0}")]
+    Malformed(String),
+    #[error("Unknown namespace prefix in selector: {0}")]
+    UnknownPrefix(String),
+    #[error("Document has no root element")]
+    Empty,
+}
+
+impl From<quick_xml::Error> for XmlError {
+    fn from(error: quick_xml::Error) -> Self {
+        XmlError::Malformed(error.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmlNode {
+    Element(XmlElement),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmlElement {
+    /// Local name, without any namespace prefix.
+    pub name: String,
+    /// Resolved namespace URI, if the element is bound to one.
+    pub namespace: Option<String>,
+    /// Attributes keyed by their qualified name as written in the document.
+    pub attributes: HashMap<String, String>,
+    pub children: Vec<XmlNode>,
+}
+
+impl XmlElement {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    /// Concatenated text of this element and all of its descendants.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        self.collect_text(&mut text);
+        text
+    }
+
+    fn collect_text(&self, out: &mut String) {
+        for child in &self.children {
+            match child {
+                XmlNode::Text(t) => out.push_str(t),
+                XmlNode::Element(e) => e.collect_text(out),
+            }
+        }
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &XmlElement> {
+        self.children.iter().filter_map(|child| match child {
+            XmlNode::Element(e) => Some(e),
+            XmlNode::Text(_) => None,
+        })
+    }
+
+    /// First direct child with the given local name.
+    pub fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.elements().find(|e| e.name == name)
+    }
+
+    /// Text of the first direct child with the given local name.
+    pub fn child_text(&self, name: &str) -> Option<String> {
+        self.child(name).map(|e| e.text())
+    }
+}
+
+/// A parsed XML document supporting a small XPath subset for selection.
+///
+/// Selectors are `/`-separated steps, where `//` matches descendants at any
+/// depth and `*` matches any element. Steps may carry a prefix registered
+/// with [`XmlDocument::with_namespace`] (e.g. `//sm:url/sm:loc`); unprefixed
+/// steps match on local name regardless of namespace.
+#[derive(Debug, Clone)]
+pub struct XmlDocument {
+    root: XmlElement,
+    namespaces: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+struct Step<'a> {
+    descendant: bool,
+    namespace: Option<&'a str>,
+    name: &'a str,
+}
+
+impl XmlDocument {
+    pub fn parse(input: &str) -> Result<Self, XmlError> {
+        let mut reader = NsReader::from_str(input);
+        reader.config_mut().trim_text(true);
+
+        let mut stack: Vec<XmlElement> = Vec::new();
+        let mut root = None;
+
+        loop {
+            match reader.read_resolved_event()? {
+                (ns, Event::Start(start)) => {
+                    stack.push(Self::build_element(ns, &start)?);
+                }
+                (ns, Event::Empty(start)) => {
+                    let element = Self::build_element(ns, &start)?;
+                    Self::attach(&mut stack, &mut root, element);
+                }
+                (_, Event::End(_)) => {
+                    let element = stack
+                        .pop()
+                        .ok_or_else(|| XmlError::Malformed("unexpected closing tag".into()))?;
+                    Self::attach(&mut stack, &mut root, element);
+                }
+                (_, Event::Text(text)) => {
+                    if let Some(parent) = stack.last_mut() {
+                        let text = text.unescape()?.into_owned();
+                        parent.children.push(XmlNode::Text(text));
+                    }
+                }
+                (_, Event::CData(data)) => {
+                    if let Some(parent) = stack.last_mut() {
+                        let text = String::from_utf8_lossy(&data.into_inner()).into_owned();
+                        parent.children.push(XmlNode::Text(text));
+                    }
+                }
+                (_, Event::Eof) => break,
+                _ => {}
+            }
+        }
+
+        if !stack.is_empty() {
+            return Err(XmlError::Malformed("unclosed element".into()));
+        }
+
+        Ok(Self {
+            root: root.ok_or(XmlError::Empty)?,
+            namespaces: HashMap::new(),
+        })
+    }
+
+    fn build_element(ns: ResolveResult, start: &BytesStart) -> Result<XmlElement, XmlError> {
+        let namespace = match ns {
+            ResolveResult::Bound(ns) => Some(String::from_utf8_lossy(ns.as_ref()).into_owned()),
+            _ => None,
+        };
+        let local = start.local_name();
+
+        let mut attributes = HashMap::new();
+        for attr in start.attributes() {
+            let attr = attr.map_err(|e| XmlError::Malformed(e.to_string()))?;
+            let key = String::from_utf8_lossy(attr.key.as_ref()).into_owned();
+            let value = attr.unescape_value()?.into_owned();
+            attributes.insert(key, value);
+        }
+
+        Ok(XmlElement {
+            name: String::from_utf8_lossy(local.as_ref()).into_owned(),
+            namespace,
+            attributes,
+            children: Vec::new(),
+        })
+    }
+
+    fn attach(stack: &mut [XmlElement], root: &mut Option<XmlElement>, element: XmlElement) {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(XmlNode::Element(element)),
+            None => *root = Some(element),
+        }
+    }
+
+    /// Registers a prefix usable in selectors for the given namespace URI.
+    pub fn with_namespace<P: Into<String>, U: Into<String>>(mut self, prefix: P, uri: U) -> Self {
+        self.namespaces.insert(prefix.into(), uri.into());
+        self
+    }
+
+    pub fn root(&self) -> &XmlElement {
+        &self.root
+    }
+
+    pub fn select(&self, selector: &str) -> Result<Vec<&XmlElement>, XmlError> {
+        let steps = self.parse_selector(selector)?;
+        if steps.is_empty() {
+            return Ok(vec![&self.root]);
+        }
+
+        // The root is matched by the first step itself, so start from a
+        // virtual parent holding only the root.
+        let mut current: Vec<&XmlElement> = Vec::new();
+        let first = &steps[0];
+        if first.descendant {
+            Self::collect_descendants(&self.root, first, &mut current, true);
+        } else if Self::matches(&self.root, first) {
+            current.push(&self.root);
+        }
+
+        for step in &steps[1..] {
+            let mut next = Vec::new();
+            for element in current {
+                if step.descendant {
+                    Self::collect_descendants(element, step, &mut next, false);
+                } else {
+                    next.extend(element.elements().filter(|e| Self::matches(e, step)));
+                }
+            }
+            current = next;
+        }
+
+        Ok(current)
+    }
+
+    /// Text content of every element matched by `selector`.
+    pub fn select_text(&self, selector: &str) -> Result<Vec<String>, XmlError> {
+        Ok(self
+            .select(selector)?
+            .into_iter()
+            .map(|e| e.text())
+            .collect())
+    }
+
+    fn parse_selector<'a>(&'a self, selector: &'a str) -> Result<Vec<Step<'a>>, XmlError> {
+        let mut steps = Vec::new();
+        let mut descendant = false;
+
+        for part in selector.trim().split('/') {
+            if part.is_empty() {
+                // An empty segment between two slashes marks `//`.
+                descendant = !steps.is_empty() || selector.trim().starts_with("//");
+                continue;
+            }
+
+            let (namespace, name) = match part.split_once(':') {
+                Some((prefix, name)) => {
+                    let uri = self
+                        .namespaces
+                        .get(prefix)
+                        .ok_or_else(|| XmlError::UnknownPrefix(prefix.to_string()))?;
+                    (Some(uri.as_str()), name)
+                }
+                None => (None, part),
+            };
+
+            steps.push(Step {
+                descendant,
+                namespace,
+                name,
+            });
+            descendant = false;
+        }
+
+        Ok(steps)
+    }
+
+    fn matches(element: &XmlElement, step: &Step) -> bool {
+        (step.name == "*" || element.name == step.name)
+            && step
+                .namespace
+                .is_none_or(|ns| element.namespace.as_deref() == Some(ns))
+    }
+
+    fn collect_descendants<'e>(
+        element: &'e XmlElement,
+        step: &Step,
+        out: &mut Vec<&'e XmlElement>,
+        include_self: bool,
+    ) {
+        if include_self && Self::matches(element, step) {
+            out.push(element);
+        }
+        for child in element.elements() {
+            Self::collect_descendants(child, step, out, true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SITEMAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+                xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">
+            <url>
+                <loc>https://example.com/a</loc>
+                <image:image><image:loc>https://example.com/a.png</image:loc></image:image>
+            </url>
+            <url><loc>https://example.com/b?x=1&amp;y=2</loc></url>
+        </urlset>"#;
+
+    #[test]
+    fn test_select_descendants_by_local_name() {
+        let doc = XmlDocument::parse(SITEMAP).unwrap();
+        let locs = doc.select_text("//url/loc").unwrap();
+        assert_eq!(
+            locs,
+            vec!["https://example.com/a", "https://example.com/b?x=1&y=2"]
+        );
+        assert_eq!(doc.select("//loc").unwrap().len(), 3);
+        assert_eq!(doc.root().name, "urlset");
+    }
+
+    #[test]
+    fn test_select_with_namespaces() {
+        let doc = XmlDocument::parse(SITEMAP)
+            .unwrap()
+            .with_namespace("sm", "http://www.sitemaps.org/schemas/sitemap/0.9")
+            .with_namespace("image", "http://www.google.com/schemas/sitemap-image/1.1");
+
+        assert_eq!(doc.select("/sm:urlset/sm:url/sm:loc").unwrap().len(), 2);
+        assert_eq!(
+            doc.select_text("//image:loc").unwrap(),
+            vec!["https://example.com/a.png"]
+        );
+        assert!(matches!(
+            doc.select("//missing:loc"),
+            Err(XmlError::UnknownPrefix(_))
+        ));
+    }
+
+    #[test]
+    fn test_attributes_and_cdata() {
+        let doc = XmlDocument::parse(
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+                <soap:Body><item id="7"><![CDATA[<b>raw</b>]]></item></soap:Body>
+            </soap:Envelope>"#,
+        )
+        .unwrap();
+
+        let item = doc.select("//item").unwrap()[0];
+        assert_eq!(item.attr("id"), Some("7"));
+        assert_eq!(item.text(), "<b>raw</b>");
+        assert_eq!(
+            doc.root().namespace.as_deref(),
+            Some("http://schemas.xmlsoap.org/soap/envelope/")
+        );
+    }
+
+    #[test]
+    fn test_malformed_xml() {
+        assert!(XmlDocument::parse("<a><b></a>").is_err());
+        assert!(matches!(XmlDocument::parse(""), Err(XmlError::Empty)));
+    }
+}
@@ -1,8 +1,8 @@
 use crate::core::spider::SpiderConfig;
 use crate::http::request::HttpRequest;
-use crate::{HttpResponse, ScraperError, ScraperResult, StatsTracker};
+use crate::{HttpResponse, ScraperError, ScraperResult, StatsTracker, WithRequest};
 use async_trait::async_trait;
-use log::{debug, info, warn};
+use log::{debug, info};
 use std::sync::Arc;
 use tokio::time::sleep;
 
@@ -19,55 +19,133 @@ pub trait Scraper: Send + Sync {
 
     async fn fetch(
         &self,
-        request: HttpRequest,
+        mut request: HttpRequest,
         config: &SpiderConfig,
     ) -> ScraperResult<HttpResponse> {
         let url = request.url.clone();
+        let host = url.host_str().unwrap_or_default().to_string();
+        // Retry state and policy matching follow the request as submitted,
+        // even if a retry transformer rewrites it.
+        let original = request.clone();
 
         loop {
+            if let Some((category, history)) = config.retry_config.exhausted_on_host(&original) {
+                let key = format!("Retries exhausted on host {}", host);
+                config.log_throttle.warn(&key, || {
+                    format!(
+                        "Giving up on URL: {} - host out of retries (category={:?})",
+                        url, category
+                    )
+                });
+                return Err(ScraperError::MaxRetriesReached {
+                    retry_count: history.counts.get(&category).copied().unwrap_or(0),
+                    category,
+                    url: Box::new(url.clone()),
+                    history: Box::new(history),
+                }
+                .with_request(request));
+            }
+
+            // Taken last: the trial request of a half-open circuit is only
+            // released by recording its outcome below.
+            if let Some(breaker) = &config.circuit_breaker {
+                if let Err(retry_after) = breaker.try_acquire(&host, self.stats()) {
+                    return Err(
+                        ScraperError::CircuitOpen { host, retry_after }.with_request(request)
+                    );
+                }
+            }
+
             info!("Fetching URL: {} [{}]", url, request.method);
-            let response = self.fetch_single(request.clone(), config).await?;
+            if let Some(budget) = &config.retry_config.retry_budget {
+                budget.record_request();
+            }
+            let result = self.fetch_single(request.clone(), config).await;
+            if let Some(breaker) = &config.circuit_breaker {
+                let success = matches!(&result, Ok(response) if response.status < 500);
+                breaker.record(&host, success, self.stats());
+            }
+            let response = result.with_request(&request)?;
             debug!(
                 "Received response: status={}, body_length={}",
                 response.status,
-                response.decoded_body.len()
+                response.raw_body.len()
             );
 
-            if let Some((category, delay)) = config.retry_config.should_retry_request(
-                &url,
-                response.status,
-                &response.decoded_body,
-            ) {
+            if let Some((category, mut delay)) =
+                config.retry_config.http_retry(&original, &response)
+            {
                 self.stats().record_retry(format!("{:?}", category));
-                let state = config.retry_config.get_retry_state(&url);
-                let attempt = state.counts.get(&category).unwrap();
-                let max_retries = config
-                    .retry_config
-                    .categories
-                    .get(&category)
-                    .map(|c| c.max_retries)
-                    .unwrap_or(0);
+                let counted = config.retry_config.retry_count(&url, &category);
+                let category_config = config.retry_config.categories.get(&category);
+                let max_retries = category_config.map(|c| c.max_retries).unwrap_or(0);
+
+                // Rate limited and unavailable servers may say when to come back.
+                if matches!(response.status, 429 | 503) {
+                    if let Some(retry_after) = response.retry_after() {
+                        let max_delay = category_config.map_or(retry_after, |c| c.max_delay);
+                        delay = retry_after.min(max_delay);
+                        debug!("Retry-After of {} is {:?}", url, retry_after);
+                    }
+                }
+
+                let deadline_exceeded = config.retry_config.deadline_exceeded(&url, delay);
+                if deadline_exceeded {
+                    let key = format!("Retry deadlines exceeded on host {}", host);
+                    config.log_throttle.warn(&key, || {
+                        format!(
+                            "Retry deadline exceeded for URL: {} (category={:?}, attempt={})",
+                            url,
+                            category,
+                            counted + 1
+                        )
+                    });
+                }
+
+                let budget_spent = counted + 1 < max_retries
+                    && !deadline_exceeded
+                    && !config.retry_config.spend_retry_budget();
+                if budget_spent {
+                    self.stats().record_denied_retry();
+                    config.log_throttle.warn("Retry budget spent", || {
+                        format!("Retry budget spent, giving up on URL: {}", url)
+                    });
+                }
 
-                if attempt >= &max_retries {
-                    return Err((
-                        ScraperError::MaxRetriesReached {
-                            category: category.clone(),
-                            retry_count: *attempt,
-                            url: Box::new(url.clone()),
-                        },
-                        Box::new(request),
-                    ));
+                // Counted only once the deadline and the budget allow it, with
+                // the delay actually waited so later backoffs build on it.
+                let recorded = !deadline_exceeded
+                    && !budget_spent
+                    && config
+                        .retry_config
+                        .record_http_retry(&original, &response, &category, delay);
+                let attempt = config.retry_config.retry_count(&url, &category);
+                if !recorded || attempt >= max_retries {
+                    return Err(ScraperError::MaxRetriesReached {
+                        category: category.clone(),
+                        retry_count: attempt,
+                        url: Box::new(url.clone()),
+                        history: Box::new(config.retry_config.get_retry_state(&url)),
+                    }
+                    .with_request(request));
                 }
 
-                warn!(
-                    "Retry triggered for URL: {} (category={:?}, attempt={}/{}, delay={:?})",
-                    url, category, attempt, max_retries, delay
-                );
+                let key = format!("{} retries on host {}", response.status, host);
+                config.log_throttle.warn(&key, || {
+                    format!(
+                        "Retry triggered for URL: {} (category={:?}, attempt={}/{}, delay={:?})",
+                        url, category, attempt, max_retries, delay
+                    )
+                });
 
                 sleep(delay).await;
+                if let Some(transformer) = &config.retry_config.request_transformer {
+                    transformer.transform(&mut request, &category, attempt);
+                }
                 continue;
             }
 
+            config.retry_config.record_success(&original);
             let state = config.retry_config.get_retry_state(&url);
             info!(
                 "Request completed for URL: {} (total_retries={}, status={})",
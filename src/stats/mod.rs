@@ -1,7 +1,16 @@
+mod report;
+mod window;
+
+pub use report::{CrawlReport, Regression, RegressionKind, RegressionThresholds};
+pub use window::WindowStats;
+
 use chrono::Duration;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
+use window::RollingWindow;
 
 #[derive(Debug, Default)]
 pub struct ScrapingStats {
@@ -12,43 +21,212 @@ pub struct ScrapingStats {
     pub retry_count: u64,
     pub data_downloaded: f64,
     pub total_response_time: u64,
+    /// Pages run through `Spider::parse`.
+    pub parsed_pages: u64,
+    /// Time spent parsing pages, in microseconds.
+    pub total_parse_time: u64,
+    /// Size and load of the spider's `ParsePool`, if it has one.
+    pub parse_pool: Option<ParsePoolStats>,
     pub status_codes: HashMap<u16, u64>,
     pub retry_reasons: HashMap<String, u64>,
+    /// Retryable failures given up on because the retry budget was spent.
+    pub denied_retries: u64,
     pub storage_errors: u64,
     pub parsing_errors: u64,
     pub unhandled_errors: u64,
+    pub items_scraped: u64,
+    /// Items discarded by item pipelines.
+    pub dropped_items: u64,
+    /// Items rejected by validation. Also counted as dropped.
+    pub invalid_items: u64,
+    pub filtered_urls: u64,
+    /// URLs over the length, query or path repetition limits of the url
+    /// filters. Also counted as filtered.
+    pub oversized_urls: u64,
+    /// Links with a scheme such as `javascript:` or `mailto:` dropped by
+    /// the link filter. Also counted as filtered.
+    pub unfollowable_schemes: u64,
+    /// Links to a fragment of their own page dropped by the link filter.
+    /// Also counted as filtered.
+    pub fragment_links: u64,
+    /// Pages skipped by incremental crawling because they had not changed.
+    pub unchanged_pages: u64,
+    /// Items that were still being stored when the crawl ended and did not
+    /// make it within the shutdown timeout.
+    pub unflushed_items: u64,
+    pub close_reason: Option<CloseReason>,
+    pub sources: HashMap<String, SourceStats>,
+    pub circuit_transitions: HashMap<String, u64>,
+    /// Requests queued at each crawl depth.
+    pub depths: HashMap<usize, u64>,
+    /// Items scraped per spider callback.
+    pub callbacks: HashMap<String, u64>,
+    pub domains: HashMap<String, DomainStats>,
+    /// Requests and bans per account of a `CredentialPool`.
+    pub accounts: HashMap<String, AccountStats>,
+    /// URL patterns suspected to be crawl traps, with the URLs dropped
+    /// because of them.
+    pub suspected_traps: HashMap<String, u64>,
+}
+
+/// Outcome of the requests to one host.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DomainStats {
+    pub requests: u64,
+    /// Requests with an error status or that failed to parse.
+    pub errors: u64,
+    pub items: u64,
+}
+
+impl DomainStats {
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.requests as f64
+        }
+    }
+}
+
+/// Use of one account of a `CredentialPool`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountStats {
+    pub requests: u64,
+    pub bans: u64,
+}
+
+/// Throughput of one seed source.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceStats {
+    pub requests: u64,
+    pub items: u64,
+}
+
+/// Size and load of a `ParsePool`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ParsePoolStats {
+    /// Parsers allowed to run at once.
+    pub size: usize,
+    pub max_size: usize,
+    /// Share of the pool's capacity spent parsing since it was created,
+    /// from 0 to 1. Close to 1 means parsing holds up the crawl.
+    pub utilization: f64,
+    /// Recent average time to parse a page.
+    pub parse_latency: std::time::Duration,
+    /// Recent average time to download a page.
+    pub fetch_latency: std::time::Duration,
+}
+
+/// Why a crawl ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CloseReason {
+    Finished,
+    SpiderStopped,
+    Cancelled,
+    MaxRequests(u64),
+    MaxItems(u64),
+    MaxDuration(std::time::Duration),
+    MaxErrors(u64),
+    LoginFailed,
+}
+
+impl fmt::Display for CloseReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloseReason::Finished => write!(f, "finished"),
+            CloseReason::SpiderStopped => write!(f, "stopped by spider"),
+            CloseReason::Cancelled => write!(f, "cancelled"),
+            CloseReason::MaxRequests(limit) => write!(f, "max requests reached ({})", limit),
+            CloseReason::MaxItems(limit) => write!(f, "max items reached ({})", limit),
+            CloseReason::MaxDuration(limit) => write!(f, "max duration reached ({:?})", limit),
+            CloseReason::MaxErrors(limit) => write!(f, "max errors reached ({})", limit),
+            CloseReason::LoginFailed => write!(f, "could not log in again"),
+        }
+    }
 }
 
 pub struct StatsTracker {
-    start_time: Instant,
+    start_time: parking_lot::RwLock<Instant>,
     total_requests: AtomicU64,
     successful_requests: AtomicU64,
     failed_requests: AtomicU64,
     retry_count: AtomicU64,
     data_downloaded: AtomicU64,
     total_response_time: AtomicU64,
+    parsed_pages: AtomicU64,
+    total_parse_time: AtomicU64,
+    parse_pool: parking_lot::RwLock<Option<ParsePoolStats>>,
     status_codes: parking_lot::RwLock<HashMap<u16, u64>>,
     retry_reasons: parking_lot::RwLock<HashMap<String, u64>>,
+    denied_retries: AtomicU64,
     storage_errors: AtomicU64,
     parsing_errors: AtomicU64,
     unhandled_errors: AtomicU64,
+    items_scraped: AtomicU64,
+    dropped_items: AtomicU64,
+    invalid_items: AtomicU64,
+    filtered_urls: AtomicU64,
+    oversized_urls: AtomicU64,
+    unfollowable_schemes: AtomicU64,
+    fragment_links: AtomicU64,
+    unchanged_pages: AtomicU64,
+    pending_items: AtomicU64,
+    unflushed_items: AtomicU64,
+    close_reason: parking_lot::RwLock<Option<CloseReason>>,
+    sources: parking_lot::RwLock<HashMap<String, SourceStats>>,
+    circuit_transitions: parking_lot::RwLock<HashMap<String, u64>>,
+    depths: parking_lot::RwLock<HashMap<usize, u64>>,
+    callbacks: parking_lot::RwLock<HashMap<String, u64>>,
+    domains: parking_lot::RwLock<HashMap<String, DomainStats>>,
+    accounts: parking_lot::RwLock<HashMap<String, AccountStats>>,
+    suspected_traps: parking_lot::RwLock<HashMap<String, u64>>,
+    recent: RollingWindow,
 }
 
 impl StatsTracker {
     pub fn new() -> Self {
+        Self::with_window(std::time::Duration::from_secs(300))
+    }
+
+    /// A tracker whose [`recent`](Self::recent) counters cover `window`
+    /// instead of the default 5 minutes.
+    pub fn with_window(window: std::time::Duration) -> Self {
         Self {
-            start_time: Instant::now(),
+            start_time: parking_lot::RwLock::new(Instant::now()),
             total_requests: AtomicU64::new(0),
             successful_requests: AtomicU64::new(0),
             failed_requests: AtomicU64::new(0),
             retry_count: AtomicU64::new(0),
             data_downloaded: AtomicU64::new(0),
             total_response_time: AtomicU64::new(0),
+            parsed_pages: AtomicU64::new(0),
+            total_parse_time: AtomicU64::new(0),
+            parse_pool: parking_lot::RwLock::new(None),
             status_codes: parking_lot::RwLock::new(HashMap::new()),
             retry_reasons: parking_lot::RwLock::new(HashMap::new()),
+            denied_retries: AtomicU64::new(0),
             storage_errors: AtomicU64::new(0),
             parsing_errors: AtomicU64::new(0),
             unhandled_errors: AtomicU64::new(0),
+            items_scraped: AtomicU64::new(0),
+            dropped_items: AtomicU64::new(0),
+            invalid_items: AtomicU64::new(0),
+            filtered_urls: AtomicU64::new(0),
+            oversized_urls: AtomicU64::new(0),
+            unfollowable_schemes: AtomicU64::new(0),
+            fragment_links: AtomicU64::new(0),
+            unchanged_pages: AtomicU64::new(0),
+            pending_items: AtomicU64::new(0),
+            unflushed_items: AtomicU64::new(0),
+            close_reason: parking_lot::RwLock::new(None),
+            sources: parking_lot::RwLock::new(HashMap::new()),
+            circuit_transitions: parking_lot::RwLock::new(HashMap::new()),
+            depths: parking_lot::RwLock::new(HashMap::new()),
+            callbacks: parking_lot::RwLock::new(HashMap::new()),
+            domains: parking_lot::RwLock::new(HashMap::new()),
+            accounts: parking_lot::RwLock::new(HashMap::new()),
+            suspected_traps: parking_lot::RwLock::new(HashMap::new()),
+            recent: RollingWindow::new(window),
         }
     }
 
@@ -58,6 +236,7 @@ impl StatsTracker {
             ErrorType::Parsing => self.parsing_errors.fetch_add(1, Ordering::SeqCst),
             ErrorType::Unhandled => self.unhandled_errors.fetch_add(1, Ordering::SeqCst),
         };
+        self.recent.record(|recent| recent.errors += 1);
     }
 
     pub fn record_request(
@@ -70,11 +249,18 @@ impl StatsTracker {
         self.total_requests.fetch_add(1, Ordering::SeqCst);
 
         // A request is only successful if both HTTP status is good AND parsing succeeded
-        if status < 400 && is_parsing_successful {
+        let successful = status < 400 && is_parsing_successful;
+        if successful {
             self.successful_requests.fetch_add(1, Ordering::SeqCst);
         } else {
             self.failed_requests.fetch_add(1, Ordering::SeqCst);
         }
+        self.recent.record(|recent| {
+            recent.requests += 1;
+            recent.failed_requests += u64::from(!successful);
+            recent.bytes += size as u64;
+            recent.total_response_time += duration.num_milliseconds().max(0) as u64;
+        });
 
         let mut status_codes = self.status_codes.write();
         *status_codes.entry(status).or_insert(0) += 1;
@@ -85,15 +271,242 @@ impl StatsTracker {
             .fetch_add(duration.num_milliseconds() as u64, Ordering::SeqCst);
     }
 
+    pub fn record_items(&self, count: u64) {
+        self.items_scraped.fetch_add(count, Ordering::SeqCst);
+        if count > 0 {
+            self.recent.record(|recent| recent.items += count);
+        }
+    }
+
+    pub fn record_dropped_items(&self, count: u64) {
+        self.dropped_items.fetch_add(count, Ordering::SeqCst);
+    }
+
+    pub fn record_invalid_items(&self, count: u64) {
+        self.invalid_items.fetch_add(count, Ordering::SeqCst);
+    }
+
+    pub fn record_filtered_url(&self) {
+        self.filtered_urls.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn record_oversized_url(&self) {
+        self.oversized_urls.fetch_add(1, Ordering::SeqCst);
+        self.record_filtered_url();
+    }
+
+    pub fn record_unfollowable_scheme(&self) {
+        self.unfollowable_schemes.fetch_add(1, Ordering::SeqCst);
+        self.record_filtered_url();
+    }
+
+    pub fn record_fragment_link(&self) {
+        self.fragment_links.fetch_add(1, Ordering::SeqCst);
+        self.record_filtered_url();
+    }
+
+    pub fn record_unchanged_page(&self) {
+        self.unchanged_pages.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Counts `count` items handed to storage until
+    /// [`finish_persisting`](Self::finish_persisting) is called for them.
+    pub fn record_parse(&self, elapsed: std::time::Duration) {
+        self.parsed_pages.fetch_add(1, Ordering::SeqCst);
+        self.total_parse_time
+            .fetch_add(elapsed.as_micros() as u64, Ordering::SeqCst);
+    }
+
+    pub fn set_parse_pool(&self, pool: ParsePoolStats) {
+        *self.parse_pool.write() = Some(pool);
+    }
+
+    pub fn start_persisting(&self, count: u64) {
+        self.pending_items.fetch_add(count, Ordering::SeqCst);
+    }
+
+    pub fn finish_persisting(&self, count: u64) {
+        self.pending_items.fetch_sub(count, Ordering::SeqCst);
+    }
+
+    /// Items handed to storage that have not been stored yet.
+    pub fn pending_items(&self) -> u64 {
+        self.pending_items.load(Ordering::SeqCst)
+    }
+
+    /// Counts the items still being stored as unflushed, once the tasks
+    /// storing them have been given up on.
+    pub fn record_unflushed_items(&self) {
+        let pending = self.pending_items.swap(0, Ordering::SeqCst);
+        self.unflushed_items.fetch_add(pending, Ordering::SeqCst);
+    }
+
+    pub fn record_source_request(&self, source: &str) {
+        let mut sources = self.sources.write();
+        sources.entry(source.to_string()).or_default().requests += 1;
+    }
+
+    pub fn record_source_items(&self, source: &str, count: u64) {
+        let mut sources = self.sources.write();
+        sources.entry(source.to_string()).or_default().items += count;
+    }
+
+    pub fn record_callback_items(&self, callback: &str, count: u64) {
+        *self
+            .callbacks
+            .write()
+            .entry(callback.to_string())
+            .or_insert(0) += count;
+    }
+
+    pub fn record_domain_request(&self, domain: &str, failed: bool) {
+        let mut domains = self.domains.write();
+        let entry = domains.entry(domain.to_string()).or_default();
+        entry.requests += 1;
+        if failed {
+            entry.errors += 1;
+        }
+    }
+
+    pub fn record_domain_items(&self, domain: &str, count: u64) {
+        let mut domains = self.domains.write();
+        domains.entry(domain.to_string()).or_default().items += count;
+    }
+
+    pub fn record_account_request(&self, account: &str) {
+        let mut accounts = self.accounts.write();
+        accounts.entry(account.to_string()).or_default().requests += 1;
+    }
+
+    pub fn record_account_ban(&self, account: &str) {
+        let mut accounts = self.accounts.write();
+        accounts.entry(account.to_string()).or_default().bans += 1;
+    }
+
+    pub fn record_suspected_trap(&self, pattern: &str) {
+        self.suspected_traps
+            .write()
+            .entry(pattern.to_string())
+            .or_insert(0);
+    }
+
+    pub fn record_trap_drop(&self, pattern: &str) {
+        *self
+            .suspected_traps
+            .write()
+            .entry(pattern.to_string())
+            .or_insert(0) += 1;
+        self.record_filtered_url();
+    }
+
+    pub fn record_depth_request(&self, depth: usize) {
+        *self.depths.write().entry(depth).or_insert(0) += 1;
+    }
+
+    pub fn depth_requests(&self, depth: usize) -> u64 {
+        self.depths.read().get(&depth).copied().unwrap_or(0)
+    }
+
+    /// Records why the crawl ended. The first reason recorded wins.
+    pub fn set_close_reason(&self, reason: CloseReason) {
+        let mut close_reason = self.close_reason.write();
+        if close_reason.is_none() {
+            *close_reason = Some(reason);
+        }
+    }
+
+    pub fn elapsed(&self) -> std::time::Duration {
+        self.start_time.read().elapsed()
+    }
+
+    /// Counters over the trailing window (5 minutes unless created with
+    /// [`with_window`](Self::with_window)), e.g. for health checks of a
+    /// long-lived worker. Not affected by [`snapshot_and_reset`](Self::snapshot_and_reset).
+    pub fn recent(&self) -> WindowStats {
+        self.recent.totals()
+    }
+
+    pub fn recent_window(&self) -> std::time::Duration {
+        self.recent.window()
+    }
+
+    /// Returns the stats collected so far and starts counting from zero, so
+    /// a worker that runs many jobs can report each one separately.
+    pub fn snapshot_and_reset(&self) -> ScrapingStats {
+        let started = std::mem::replace(&mut *self.start_time.write(), Instant::now());
+        let take = |counter: &AtomicU64| counter.swap(0, Ordering::SeqCst);
+        ScrapingStats {
+            duration: chrono::Duration::from_std(started.elapsed()).unwrap(),
+            total_requests: take(&self.total_requests),
+            successful_requests: take(&self.successful_requests),
+            failed_requests: take(&self.failed_requests),
+            retry_count: take(&self.retry_count),
+            data_downloaded: take(&self.data_downloaded) as f64 / (1024.0 * 1024.0),
+            total_response_time: take(&self.total_response_time),
+            parsed_pages: take(&self.parsed_pages),
+            total_parse_time: take(&self.total_parse_time),
+            parse_pool: *self.parse_pool.read(),
+            status_codes: std::mem::take(&mut *self.status_codes.write()),
+            retry_reasons: std::mem::take(&mut *self.retry_reasons.write()),
+            denied_retries: take(&self.denied_retries),
+            storage_errors: take(&self.storage_errors),
+            parsing_errors: take(&self.parsing_errors),
+            unhandled_errors: take(&self.unhandled_errors),
+            items_scraped: take(&self.items_scraped),
+            dropped_items: take(&self.dropped_items),
+            invalid_items: take(&self.invalid_items),
+            filtered_urls: take(&self.filtered_urls),
+            oversized_urls: take(&self.oversized_urls),
+            unfollowable_schemes: take(&self.unfollowable_schemes),
+            fragment_links: take(&self.fragment_links),
+            unchanged_pages: take(&self.unchanged_pages),
+            unflushed_items: take(&self.unflushed_items),
+            close_reason: self.close_reason.write().take(),
+            sources: std::mem::take(&mut *self.sources.write()),
+            circuit_transitions: std::mem::take(&mut *self.circuit_transitions.write()),
+            depths: std::mem::take(&mut *self.depths.write()),
+            callbacks: std::mem::take(&mut *self.callbacks.write()),
+            domains: std::mem::take(&mut *self.domains.write()),
+            accounts: std::mem::take(&mut *self.accounts.write()),
+            suspected_traps: std::mem::take(&mut *self.suspected_traps.write()),
+        }
+    }
+
+    pub fn total_requests(&self) -> u64 {
+        self.total_requests.load(Ordering::SeqCst)
+    }
+
+    pub fn items_scraped(&self) -> u64 {
+        self.items_scraped.load(Ordering::SeqCst)
+    }
+
+    /// Failed requests plus storage, parsing and unhandled errors.
+    pub fn total_errors(&self) -> u64 {
+        self.failed_requests.load(Ordering::SeqCst)
+            + self.storage_errors.load(Ordering::SeqCst)
+            + self.parsing_errors.load(Ordering::SeqCst)
+            + self.unhandled_errors.load(Ordering::SeqCst)
+    }
+
     pub fn record_retry(&self, category: String) {
         self.retry_count.fetch_add(1, Ordering::SeqCst);
         let mut retry_reasons = self.retry_reasons.write();
         *retry_reasons.entry(category).or_insert(0) += 1;
     }
 
+    pub fn record_denied_retry(&self) {
+        self.denied_retries.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Counts circuit breaker transitions such as `closed -> open`.
+    pub fn record_circuit_transition(&self, transition: String) {
+        let mut transitions = self.circuit_transitions.write();
+        *transitions.entry(transition).or_insert(0) += 1;
+    }
+
     pub fn get_stats(&self) -> ScrapingStats {
         ScrapingStats {
-            duration: chrono::Duration::from_std(self.start_time.elapsed()).unwrap(),
+            duration: chrono::Duration::from_std(self.elapsed()).unwrap(),
             total_requests: self.total_requests.load(Ordering::SeqCst),
             successful_requests: self.successful_requests.load(Ordering::SeqCst),
             failed_requests: self.failed_requests.load(Ordering::SeqCst),
@@ -101,11 +514,32 @@ impl StatsTracker {
             data_downloaded: (self.data_downloaded.load(Ordering::SeqCst) as f64)
                 / (1024.0 * 1024.0),
             total_response_time: self.total_response_time.load(Ordering::SeqCst),
+            parsed_pages: self.parsed_pages.load(Ordering::SeqCst),
+            total_parse_time: self.total_parse_time.load(Ordering::SeqCst),
+            parse_pool: *self.parse_pool.read(),
             status_codes: self.status_codes.read().clone(),
             retry_reasons: self.retry_reasons.read().clone(),
+            denied_retries: self.denied_retries.load(Ordering::SeqCst),
             storage_errors: self.storage_errors.load(Ordering::SeqCst),
             parsing_errors: self.parsing_errors.load(Ordering::SeqCst),
             unhandled_errors: self.unhandled_errors.load(Ordering::SeqCst),
+            items_scraped: self.items_scraped.load(Ordering::SeqCst),
+            dropped_items: self.dropped_items.load(Ordering::SeqCst),
+            invalid_items: self.invalid_items.load(Ordering::SeqCst),
+            filtered_urls: self.filtered_urls.load(Ordering::SeqCst),
+            oversized_urls: self.oversized_urls.load(Ordering::SeqCst),
+            unfollowable_schemes: self.unfollowable_schemes.load(Ordering::SeqCst),
+            fragment_links: self.fragment_links.load(Ordering::SeqCst),
+            unchanged_pages: self.unchanged_pages.load(Ordering::SeqCst),
+            unflushed_items: self.unflushed_items.load(Ordering::SeqCst),
+            close_reason: self.close_reason.read().clone(),
+            sources: self.sources.read().clone(),
+            circuit_transitions: self.circuit_transitions.read().clone(),
+            depths: self.depths.read().clone(),
+            callbacks: self.callbacks.read().clone(),
+            domains: self.domains.read().clone(),
+            accounts: self.accounts.read().clone(),
+            suspected_traps: self.suspected_traps.read().clone(),
         }
     }
 
@@ -114,19 +548,62 @@ impl StatsTracker {
         println!("\nScraping Statistics:");
         println!("===================");
         println!("Duration: {} seconds", stats.duration.num_seconds());
+        if let Some(reason) = &stats.close_reason {
+            println!("Close Reason: {}", reason);
+        }
         println!("Total Requests: {}", stats.total_requests);
         println!("Successful Requests: {}", stats.successful_requests);
         println!("Failed Requests: {}", stats.failed_requests);
         println!("Storage Errors: {}", stats.storage_errors);
         println!("Parsing Errors: {}", stats.parsing_errors);
         println!("Unhandled Errors: {}", stats.unhandled_errors);
+        println!("Items Scraped: {}", stats.items_scraped);
+        if stats.dropped_items > 0 {
+            println!("Dropped Items: {}", stats.dropped_items);
+        }
+        if stats.invalid_items > 0 {
+            println!("Invalid Items: {}", stats.invalid_items);
+        }
+        println!("Filtered URLs: {}", stats.filtered_urls);
+        if stats.oversized_urls > 0 {
+            println!("Oversized URLs: {}", stats.oversized_urls);
+        }
+        if stats.unfollowable_schemes > 0 {
+            println!("Non-Web Links: {}", stats.unfollowable_schemes);
+        }
+        if stats.fragment_links > 0 {
+            println!("Fragment Links: {}", stats.fragment_links);
+        }
+        if stats.unchanged_pages > 0 {
+            println!("Unchanged Pages: {}", stats.unchanged_pages);
+        }
+        if stats.unflushed_items > 0 {
+            println!("Unflushed Items: {}", stats.unflushed_items);
+        }
         println!("Retry Count: {}", stats.retry_count);
+        if stats.denied_retries > 0 {
+            println!("Retries Denied by Budget: {}", stats.denied_retries);
+        }
         println!("Data Downloaded: {:.2} MB", stats.data_downloaded);
 
         if stats.total_requests > 0 {
             let avg_response_time = stats.total_response_time as f64 / stats.total_requests as f64;
             println!("Average Response Time: {:.2}ms", avg_response_time);
         }
+        if stats.parsed_pages > 0 {
+            let avg_parse_time = stats.total_parse_time as f64 / stats.parsed_pages as f64;
+            println!("Average Parse Time: {:.2}ms", avg_parse_time / 1000.0);
+        }
+        if let Some(pool) = &stats.parse_pool {
+            println!(
+                "Parse Pool: {}/{} parsers, {:.0}% utilized (parse {:?}, fetch {:?})",
+                pool.size,
+                pool.max_size,
+                pool.utilization * 100.0,
+                pool.parse_latency,
+                pool.fetch_latency
+            );
+        }
 
         if !stats.status_codes.is_empty() {
             println!("\nStatus Codes:");
@@ -141,6 +618,53 @@ impl StatsTracker {
                 println!("  {}: {}", reason, count);
             }
         }
+
+        if !stats.depths.is_empty() {
+            println!("\nRequests per Depth:");
+            let mut depths: Vec<_> = stats.depths.iter().collect();
+            depths.sort();
+            for (depth, count) in depths {
+                println!("  {}: {}", depth, count);
+            }
+        }
+
+        if !stats.circuit_transitions.is_empty() {
+            println!("\nCircuit Transitions:");
+            for (transition, count) in stats.circuit_transitions.iter() {
+                println!("  {}: {}", transition, count);
+            }
+        }
+
+        if !stats.accounts.is_empty() {
+            println!("\nAccounts:");
+            for (account, account_stats) in stats.accounts.iter() {
+                println!(
+                    "  {}: {} requests, {} bans",
+                    account, account_stats.requests, account_stats.bans
+                );
+            }
+        }
+
+        if !stats.suspected_traps.is_empty() {
+            println!("\nSuspected Crawl Traps:");
+            for (pattern, dropped) in stats.suspected_traps.iter() {
+                println!("  {}: {} URLs dropped", pattern, dropped);
+            }
+        }
+
+        if !stats.sources.is_empty() {
+            let seconds = stats.duration.num_milliseconds().max(1) as f64 / 1000.0;
+            println!("\nSources:");
+            for (source, source_stats) in stats.sources.iter() {
+                println!(
+                    "  {}: {} requests ({:.2}/s), {} items",
+                    source,
+                    source_stats.requests,
+                    source_stats.requests as f64 / seconds,
+                    source_stats.items
+                );
+            }
+        }
     }
 }
 
@@ -156,3 +680,35 @@ pub enum ErrorType {
     Parsing,
     Unhandled,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_and_reset_keeps_recent_window() {
+        let stats = StatsTracker::new();
+        stats.record_request(200, 1024, Duration::milliseconds(20), true);
+        stats.record_request(500, 0, Duration::milliseconds(40), true);
+        stats.record_items(3);
+
+        let job = stats.snapshot_and_reset();
+        assert_eq!(job.total_requests, 2);
+        assert_eq!(job.failed_requests, 1);
+        assert_eq!(job.items_scraped, 3);
+        assert_eq!(job.status_codes.get(&500), Some(&1));
+
+        let fresh = stats.get_stats();
+        assert_eq!(fresh.total_requests, 0);
+        assert!(fresh.status_codes.is_empty());
+
+        let recent = stats.recent();
+        assert_eq!(recent.requests, 2);
+        assert_eq!(recent.items, 3);
+        assert_eq!(recent.error_rate(), 0.5);
+        assert_eq!(
+            recent.average_response_time(),
+            Some(std::time::Duration::from_millis(30))
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use super::{DomainStats, ScrapingStats};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Summary of a finished crawl, stored so the next run can be compared
+/// against it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrawlReport {
+    pub spider: String,
+    /// Version of the spider, unset in reports saved by older releases.
+    #[serde(default)]
+    pub spider_version: Option<String>,
+    pub finished_at: DateTime<Utc>,
+    pub total_requests: u64,
+    pub failed_requests: u64,
+    pub items_scraped: u64,
+    /// Items scraped per spider callback.
+    pub callbacks: HashMap<String, u64>,
+    pub domains: HashMap<String, DomainStats>,
+}
+
+impl CrawlReport {
+    pub fn new(spider: &str, stats: &ScrapingStats) -> Self {
+        Self {
+            spider: spider.to_string(),
+            spider_version: None,
+            finished_at: Utc::now(),
+            total_requests: stats.total_requests,
+            failed_requests: stats.failed_requests,
+            items_scraped: stats.items_scraped,
+            callbacks: stats.callbacks.clone(),
+            domains: stats.domains.clone(),
+        }
+    }
+
+    pub fn with_spider_version(mut self, version: &str) -> Self {
+        self.spider_version = Some(version.to_string());
+        self
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)
+    }
+
+    pub fn error_rate(&self) -> f64 {
+        if self.total_requests == 0 {
+            0.0
+        } else {
+            self.failed_requests as f64 / self.total_requests as f64
+        }
+    }
+
+    /// Regressions of this report compared to `previous`, largest item drops
+    /// first.
+    pub fn regressions(
+        &self,
+        previous: &CrawlReport,
+        thresholds: &RegressionThresholds,
+    ) -> Vec<Regression> {
+        let mut regressions = Vec::new();
+
+        thresholds.check_items(
+            "crawl".to_string(),
+            previous.items_scraped,
+            self.items_scraped,
+            &mut regressions,
+        );
+        for (callback, &items) in &previous.callbacks {
+            thresholds.check_items(
+                format!("callback {}", callback),
+                items,
+                self.callbacks.get(callback).copied().unwrap_or(0),
+                &mut regressions,
+            );
+        }
+
+        thresholds.check_error_rate(
+            "crawl".to_string(),
+            (previous.total_requests, previous.error_rate()),
+            (self.total_requests, self.error_rate()),
+            &mut regressions,
+        );
+        let empty = DomainStats::default();
+        for (domain, before) in &previous.domains {
+            let after = self.domains.get(domain).unwrap_or(&empty);
+            let scope = format!("domain {}", domain);
+            thresholds.check_items(scope.clone(), before.items, after.items, &mut regressions);
+            thresholds.check_error_rate(
+                scope,
+                (before.requests, before.error_rate()),
+                (after.requests, after.error_rate()),
+                &mut regressions,
+            );
+        }
+
+        regressions.sort_by(|a, b| {
+            (a.kind, b.magnitude())
+                .partial_cmp(&(b.kind, a.magnitude()))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        regressions
+    }
+}
+
+/// When a change between two runs counts as a regression.
+#[derive(Debug, Clone)]
+pub struct RegressionThresholds {
+    /// Relative item drop, e.g. `0.2` for 20% fewer items.
+    pub item_drop: f64,
+    /// Absolute error rate increase, e.g. `0.05` for 2% -> 7%.
+    pub error_rate_increase: f64,
+    /// Scopes with fewer items in the previous run are not compared.
+    pub min_items: u64,
+    /// Scopes with fewer requests in either run are not compared.
+    pub min_requests: u64,
+}
+
+impl Default for RegressionThresholds {
+    fn default() -> Self {
+        Self {
+            item_drop: 0.2,
+            error_rate_increase: 0.05,
+            min_items: 10,
+            min_requests: 10,
+        }
+    }
+}
+
+impl RegressionThresholds {
+    pub fn with_item_drop(mut self, item_drop: f64) -> Self {
+        self.item_drop = item_drop;
+        self
+    }
+
+    pub fn with_error_rate_increase(mut self, error_rate_increase: f64) -> Self {
+        self.error_rate_increase = error_rate_increase;
+        self
+    }
+
+    pub fn with_min_items(mut self, min_items: u64) -> Self {
+        self.min_items = min_items;
+        self
+    }
+
+    pub fn with_min_requests(mut self, min_requests: u64) -> Self {
+        self.min_requests = min_requests;
+        self
+    }
+
+    fn check_items(&self, scope: String, previous: u64, current: u64, out: &mut Vec<Regression>) {
+        if previous < self.min_items.max(1) || current >= previous {
+            return;
+        }
+        let drop = (previous - current) as f64 / previous as f64;
+        if drop >= self.item_drop {
+            out.push(Regression {
+                kind: RegressionKind::ItemsDropped,
+                scope,
+                previous: previous as f64,
+                current: current as f64,
+            });
+        }
+    }
+
+    fn check_error_rate(
+        &self,
+        scope: String,
+        (previous_requests, previous): (u64, f64),
+        (current_requests, current): (u64, f64),
+        out: &mut Vec<Regression>,
+    ) {
+        if previous_requests < self.min_requests || current_requests < self.min_requests {
+            return;
+        }
+        if current - previous >= self.error_rate_increase {
+            out.push(Regression {
+                kind: RegressionKind::ErrorRateIncreased,
+                scope,
+                previous,
+                current,
+            });
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RegressionKind {
+    ItemsDropped,
+    ErrorRateIncreased,
+}
+
+/// A metric that got worse between two runs. `scope` is `crawl`, or names
+/// the callback or domain, e.g. `domain example.com`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+    pub kind: RegressionKind,
+    pub scope: String,
+    pub previous: f64,
+    pub current: f64,
+}
+
+impl Regression {
+    /// Relative item drop, or absolute error rate increase.
+    pub fn magnitude(&self) -> f64 {
+        match self.kind {
+            RegressionKind::ItemsDropped => (self.previous - self.current) / self.previous,
+            RegressionKind::ErrorRateIncreased => self.current - self.previous,
+        }
+    }
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            RegressionKind::ItemsDropped => write!(
+                f,
+                "items dropped {:.0}% on {} ({} -> {})",
+                self.magnitude() * 100.0,
+                self.scope,
+                self.previous,
+                self.current
+            ),
+            RegressionKind::ErrorRateIncreased => write!(
+                f,
+                "error rate rose from {:.1}% to {:.1}% on {}",
+                self.previous * 100.0,
+                self.current * 100.0,
+                self.scope
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(
+        items: u64,
+        callbacks: &[(&str, u64)],
+        domains: &[(&str, DomainStats)],
+    ) -> CrawlReport {
+        CrawlReport {
+            spider: "books".to_string(),
+            spider_version: None,
+            finished_at: Utc::now(),
+            total_requests: domains.iter().map(|(_, d)| d.requests).sum(),
+            failed_requests: domains.iter().map(|(_, d)| d.errors).sum(),
+            items_scraped: items,
+            callbacks: callbacks
+                .iter()
+                .map(|(name, count)| (name.to_string(), *count))
+                .collect(),
+            domains: domains
+                .iter()
+                .map(|(name, stats)| (name.to_string(), stats.clone()))
+                .collect(),
+        }
+    }
+
+    fn domain(requests: u64, errors: u64, items: u64) -> DomainStats {
+        DomainStats {
+            requests,
+            errors,
+            items,
+        }
+    }
+
+    #[test]
+    fn test_reports_item_drops_and_error_rate_increases() {
+        let previous = report(
+            1100,
+            &[("ParseItem", 1000), ("ParsePagination", 100)],
+            &[
+                ("a.com", domain(500, 5, 1000)),
+                ("b.com", domain(100, 1, 100)),
+            ],
+        );
+        let current = report(
+            700,
+            &[("ParseItem", 600), ("ParsePagination", 100)],
+            &[
+                ("a.com", domain(500, 100, 600)),
+                ("b.com", domain(100, 2, 100)),
+            ],
+        );
+
+        let regressions = current.regressions(&previous, &RegressionThresholds::default());
+        let lines: Vec<String> = regressions.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            [
+                "items dropped 40% on callback ParseItem (1000 -> 600)",
+                "items dropped 40% on domain a.com (1000 -> 600)",
+                "items dropped 36% on crawl (1100 -> 700)",
+                "error rate rose from 1.0% to 20.0% on domain a.com",
+                "error rate rose from 1.0% to 17.0% on crawl",
+            ]
+        );
+        assert!(previous
+            .regressions(&previous, &RegressionThresholds::default())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_report_round_trips_through_disk() {
+        let path =
+            std::env::temp_dir().join(format!("turboscraper_report_{}.json", uuid::Uuid::now_v7()));
+        let saved = report(3, &[("ParseItem", 3)], &[("a.com", domain(4, 1, 3))]);
+        saved.save(&path).unwrap();
+        assert_eq!(CrawlReport::load(&path).unwrap(), saved);
+        let _ = fs::remove_file(path);
+    }
+}
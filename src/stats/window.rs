@@ -0,0 +1,119 @@
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Counters over the last few minutes of a [`StatsTracker`](super::StatsTracker).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WindowStats {
+    pub requests: u64,
+    pub failed_requests: u64,
+    /// Storage, parsing and unhandled errors.
+    pub errors: u64,
+    pub items: u64,
+    pub bytes: u64,
+    /// Sum of response times, in milliseconds.
+    pub total_response_time: u64,
+}
+
+impl WindowStats {
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.failed_requests as f64 / self.requests as f64
+        }
+    }
+
+    pub fn average_response_time(&self) -> Option<Duration> {
+        (self.requests > 0).then(|| Duration::from_millis(self.total_response_time / self.requests))
+    }
+
+    fn add(&mut self, other: &WindowStats) {
+        self.requests += other.requests;
+        self.failed_requests += other.failed_requests;
+        self.errors += other.errors;
+        self.items += other.items;
+        self.bytes += other.bytes;
+        self.total_response_time += other.total_response_time;
+    }
+}
+
+/// Counters bucketed by time, so totals over the trailing window are cheap
+/// and old buckets are dropped as they age out.
+#[derive(Debug)]
+pub(crate) struct RollingWindow {
+    window: Duration,
+    bucket: Duration,
+    buckets: Mutex<VecDeque<(Instant, WindowStats)>>,
+}
+
+impl RollingWindow {
+    const BUCKETS: u32 = 30;
+
+    pub(crate) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            bucket: (window / Self::BUCKETS).max(Duration::from_millis(1)),
+            buckets: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub(crate) fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub(crate) fn record(&self, update: impl FnOnce(&mut WindowStats)) {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock();
+        self.prune(&mut buckets, now);
+        match buckets.back_mut() {
+            Some((started, stats)) if now.duration_since(*started) < self.bucket => update(stats),
+            _ => {
+                let mut stats = WindowStats::default();
+                update(&mut stats);
+                buckets.push_back((now, stats));
+            }
+        }
+    }
+
+    pub(crate) fn totals(&self) -> WindowStats {
+        let mut buckets = self.buckets.lock();
+        self.prune(&mut buckets, Instant::now());
+        let mut totals = WindowStats::default();
+        for (_, stats) in buckets.iter() {
+            totals.add(stats);
+        }
+        totals
+    }
+
+    fn prune(&self, buckets: &mut VecDeque<(Instant, WindowStats)>, now: Instant) {
+        while buckets
+            .front()
+            .is_some_and(|(started, _)| now.duration_since(*started) >= self.window)
+        {
+            buckets.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_expire_after_window() {
+        let window = RollingWindow::new(Duration::from_millis(60));
+        window.record(|stats| stats.requests += 2);
+        std::thread::sleep(Duration::from_millis(30));
+        window.record(|stats| {
+            stats.requests += 1;
+            stats.failed_requests += 1;
+        });
+        assert_eq!(window.totals().requests, 3);
+
+        std::thread::sleep(Duration::from_millis(40));
+        let totals = window.totals();
+        assert_eq!(totals.requests, 1);
+        assert_eq!(totals.error_rate(), 1.0);
+    }
+}
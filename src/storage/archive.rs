@@ -0,0 +1,103 @@
+use super::base::StorageError;
+use crate::core::SpiderCallback;
+use crate::http::{Headers, HttpRequest, ResponseType};
+use crate::HttpResponse;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream, StreamExt};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use url::Url;
+
+/// Reads raw responses archived by [`DiskStorage`](super::DiskStorage), e.g.
+/// the `raw_response` parts of a [`WriteGroup`](super::WriteGroup).
+///
+/// Each document's `data` must hold the response `url` and `body`; `status`
+/// and `headers` are optional. A `body_encoding` of `base64` marks a body
+/// encoded as written by `WriteGroup::with_raw_response`.
+pub struct DiskArchive {
+    root: PathBuf,
+    callback: SpiderCallback,
+}
+
+impl DiskArchive {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            callback: SpiderCallback::ParseItem,
+        }
+    }
+
+    /// Callback set on the responses, so `Spider::parse` picks the right
+    /// branch. Defaults to `ParseItem`.
+    pub fn with_callback(mut self, callback: SpiderCallback) -> Self {
+        self.callback = callback;
+        self
+    }
+
+    /// Streams the archived responses in file name order. Files are read one
+    /// at a time as the stream is polled.
+    pub fn responses(
+        &self,
+    ) -> Result<impl Stream<Item = Result<HttpResponse, StorageError>>, StorageError> {
+        let mut paths = Vec::new();
+        collect_json_files(&self.root, &mut paths)?;
+        paths.sort();
+
+        let callback = self.callback.clone();
+        Ok(stream::iter(paths).map(move |path| read_response(&path, callback.clone())))
+    }
+}
+
+fn collect_json_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), StorageError> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_json_files(&path, paths)?;
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read_response(path: &Path, callback: SpiderCallback) -> Result<HttpResponse, StorageError> {
+    let document: Value = serde_json::from_slice(&fs::read(path)?)?;
+    let data = &document["data"];
+    let invalid = || {
+        StorageError::SerializationError(format!("{} is not an archived response", path.display()))
+    };
+
+    let url = data["url"]
+        .as_str()
+        .and_then(|url| Url::parse(url).ok())
+        .ok_or_else(invalid)?;
+    let body = data["body"].as_str().ok_or_else(invalid)?;
+    let raw_body = match data["body_encoding"].as_str() {
+        Some("base64") => BASE64_STANDARD.decode(body).map_err(|_| invalid())?,
+        _ => body.as_bytes().to_vec(),
+    };
+    let headers: Headers = serde_json::from_value(data["headers"].clone()).unwrap_or_default();
+    let timestamp = document["timestamp"]
+        .as_str()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now);
+
+    Ok(HttpResponse {
+        url: url.clone(),
+        status: data["status"].as_u64().unwrap_or(200) as u16,
+        response_type: ResponseType::detect(&headers, &raw_body),
+        headers,
+        raw_body,
+        decoded_body: OnceLock::new(),
+        timestamp,
+        retry_count: 0,
+        retry_history: HashMap::new(),
+        meta: None,
+        from_request: Box::new(HttpRequest::new(url, callback, 0)),
+    })
+}
@@ -15,6 +15,18 @@ pub struct StorageItem<T: Serialize> {
     pub id: String,
 }
 
+impl<T: Serialize + Send + Sync + 'static> StorageItem<T> {
+    pub fn into_serialized(self) -> StorageItem<Box<dyn ErasedSerialize + Send + Sync>> {
+        StorageItem {
+            url: self.url,
+            timestamp: self.timestamp,
+            data: self.data.into_storage_data(),
+            metadata: self.metadata,
+            id: self.id,
+        }
+    }
+}
+
 pub trait StorageConfig: Send + Sync {
     fn as_any(&self) -> &dyn std::any::Any;
     fn clone_box(&self) -> Box<dyn StorageConfig>;
@@ -37,6 +49,14 @@ pub enum StorageError {
     SerializationError(String),
 }
 
+impl StorageError {
+    /// Serialization errors fail the same way every time; connection and
+    /// operation errors may be transient.
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, StorageError::SerializationError(_))
+    }
+}
+
 #[async_trait]
 pub trait StorageBackend: Send + Sync {
     fn create_config(&self, collection_name: &str) -> Box<dyn StorageConfig>;
@@ -46,6 +66,24 @@ pub trait StorageBackend: Send + Sync {
         item: StorageItem<Box<dyn ErasedSerialize + Send + Sync>>,
         config: &dyn StorageConfig,
     ) -> Result<(), StorageError>;
+
+    /// Stores a batch and returns one result per item, in input order, so
+    /// callers can retry only the items that failed. The default stores the
+    /// items one by one.
+    async fn store_batch(
+        &self,
+        items: &[StorageItem<Value>],
+        config: &dyn StorageConfig,
+    ) -> Vec<Result<(), StorageError>> {
+        let mut results = Vec::with_capacity(items.len());
+        for item in items {
+            results.push(
+                self.store_serialized(item.clone().into_serialized(), config)
+                    .await,
+            );
+        }
+        results
+    }
 }
 
 pub trait IntoStorageData {
@@ -0,0 +1,258 @@
+use super::base::{StorageBackend, StorageConfig, StorageError, StorageItem};
+use log::{debug, warn};
+use serde_json::Value;
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// How failed items of a batched write are retried.
+#[derive(Debug, Clone)]
+pub struct BatchRetryConfig {
+    /// Attempts per item, including the first write.
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub backoff_factor: f64,
+}
+
+impl Default for BatchRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            backoff_factor: 2.0,
+        }
+    }
+}
+
+impl BatchRetryConfig {
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn with_initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    pub fn with_max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    pub fn with_backoff_factor(mut self, factor: f64) -> Self {
+        self.backoff_factor = factor;
+        self
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        self.initial_delay
+            .mul_f64(self.backoff_factor.powi(attempt.saturating_sub(1) as i32))
+            .min(self.max_delay)
+    }
+}
+
+/// Final outcome of one item of a batch.
+#[derive(Debug, Clone)]
+pub enum ItemOutcome {
+    Stored { attempts: u32 },
+    Failed { attempts: u32, error: StorageError },
+}
+
+impl ItemOutcome {
+    pub fn is_stored(&self) -> bool {
+        matches!(self, ItemOutcome::Stored { .. })
+    }
+}
+
+/// Per-item outcomes of a batched write, in the order the items were given.
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+    pub outcomes: Vec<ItemOutcome>,
+}
+
+impl BatchReport {
+    pub fn stored(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.is_stored()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.outcomes.len() - self.stored()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.failed() == 0
+    }
+
+    /// Positions and errors of the items that could not be stored.
+    pub fn failures(&self) -> impl Iterator<Item = (usize, &StorageError)> {
+        self.outcomes
+            .iter()
+            .enumerate()
+            .filter_map(|(position, outcome)| match outcome {
+                ItemOutcome::Failed { error, .. } => Some((position, error)),
+                ItemOutcome::Stored { .. } => None,
+            })
+    }
+}
+
+/// Writes `items` as a batch and re-sends only the items that failed with a
+/// retryable error, so stored items are never written twice.
+pub async fn store_batch_with_retry<B: StorageBackend + ?Sized>(
+    backend: &B,
+    items: &[StorageItem<Value>],
+    config: &dyn StorageConfig,
+    retry: &BatchRetryConfig,
+) -> BatchReport {
+    let mut outcomes: Vec<Option<ItemOutcome>> = vec![None; items.len()];
+    let mut pending: Vec<usize> = (0..items.len()).collect();
+    let mut attempt = 0;
+
+    while !pending.is_empty() {
+        attempt += 1;
+        let batch: Vec<StorageItem<Value>> = pending.iter().map(|&i| items[i].clone()).collect();
+        let results = backend.store_batch(&batch, config).await;
+
+        let mut failed = Vec::new();
+        for (&position, result) in pending.iter().zip(results) {
+            match result {
+                Ok(()) => outcomes[position] = Some(ItemOutcome::Stored { attempts: attempt }),
+                Err(error) if error.is_retryable() && attempt < retry.max_attempts => {
+                    failed.push(position);
+                }
+                Err(error) => {
+                    warn!(
+                        "Giving up storing {} after {} attempts: {}",
+                        items[position].url, attempt, error
+                    );
+                    outcomes[position] = Some(ItemOutcome::Failed {
+                        attempts: attempt,
+                        error,
+                    });
+                }
+            }
+        }
+
+        if !failed.is_empty() {
+            let delay = retry.delay(attempt);
+            debug!(
+                "Retrying {} of {} items to {} in {:?}",
+                failed.len(),
+                pending.len(),
+                config.destination(),
+                delay
+            );
+            sleep(delay).await;
+        }
+        pending = failed;
+    }
+
+    BatchReport {
+        outcomes: outcomes
+            .into_iter()
+            .map(|outcome| outcome.expect("every item has an outcome"))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use erased_serde::Serialize as ErasedSerialize;
+    use parking_lot::Mutex;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use url::Url;
+
+    /// Fails each id the number of times given, then stores it.
+    struct FlakyStorage {
+        failures: Mutex<HashMap<String, u32>>,
+        writes: Mutex<Vec<String>>,
+    }
+
+    struct NoConfig;
+
+    impl StorageConfig for NoConfig {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn clone_box(&self) -> Box<dyn StorageConfig> {
+            Box::new(NoConfig)
+        }
+
+        fn destination(&self) -> &str {
+            "test"
+        }
+    }
+
+    #[async_trait]
+    impl StorageBackend for FlakyStorage {
+        fn create_config(&self, _destination: &str) -> Box<dyn StorageConfig> {
+            Box::new(NoConfig)
+        }
+
+        async fn store_serialized(
+            &self,
+            item: StorageItem<Box<dyn ErasedSerialize + Send + Sync>>,
+            _config: &dyn StorageConfig,
+        ) -> Result<(), StorageError> {
+            if let Some(remaining) = self.failures.lock().get_mut(&item.id) {
+                if *remaining > 0 {
+                    *remaining -= 1;
+                    return Err(StorageError::OperationError("write failed".into()));
+                }
+            }
+            if item.id == "bad" {
+                return Err(StorageError::SerializationError("invalid".into()));
+            }
+            self.writes.lock().push(item.id);
+            Ok(())
+        }
+    }
+
+    fn item(id: &str) -> StorageItem<Value> {
+        StorageItem {
+            url: Url::parse("https://example.com/").unwrap(),
+            timestamp: Utc::now(),
+            data: json!({ "id": id }),
+            metadata: None,
+            id: id.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_only_failed_items() {
+        let storage = FlakyStorage {
+            failures: Mutex::new(HashMap::from([("b".to_string(), 1), ("c".to_string(), 5)])),
+            writes: Mutex::new(Vec::new()),
+        };
+        let retry = BatchRetryConfig::default().with_initial_delay(Duration::from_millis(1));
+        let items = vec![item("a"), item("b"), item("c"), item("bad")];
+
+        let report = store_batch_with_retry(&storage, &items, &NoConfig, &retry).await;
+
+        assert_eq!(*storage.writes.lock(), vec!["a", "b"]);
+        assert_eq!(report.stored(), 2);
+        assert!(matches!(
+            report.outcomes[1],
+            ItemOutcome::Stored { attempts: 2 }
+        ));
+        assert!(matches!(
+            report.outcomes[2],
+            ItemOutcome::Failed { attempts: 3, .. }
+        ));
+        // Serialization errors are not retried.
+        assert!(matches!(
+            report.outcomes[3],
+            ItemOutcome::Failed { attempts: 1, .. }
+        ));
+        assert_eq!(
+            report.failures().map(|(i, _)| i).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+    }
+}
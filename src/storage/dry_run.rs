@@ -0,0 +1,90 @@
+use super::base::{StorageBackend, StorageConfig, StorageError, StorageItem};
+use async_trait::async_trait;
+use erased_serde::Serialize as ErasedSerialize;
+use log::info;
+use serde::Serialize;
+
+/// How dry runs write items to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DryRunFormat {
+    /// Indented JSON.
+    Pretty,
+    /// One JSON object per line.
+    Jsonl,
+}
+
+impl DryRunFormat {
+    pub fn print<T: Serialize + ?Sized>(&self, value: &T) -> Result<(), serde_json::Error> {
+        let json = match self {
+            DryRunFormat::Pretty => serde_json::to_string_pretty(value)?,
+            DryRunFormat::Jsonl => serde_json::to_string(value)?,
+        };
+        println!("{}", json);
+        Ok(())
+    }
+}
+
+/// Logs or prints items instead of storing them, so spiders can be
+/// developed without a live database or broker.
+#[derive(Debug, Clone, Default)]
+pub struct DryRunStorage {
+    output: Option<DryRunFormat>,
+}
+
+impl DryRunStorage {
+    /// Logs every item at info level.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prints every item to stdout in `format`.
+    pub fn printing(format: DryRunFormat) -> Self {
+        Self {
+            output: Some(format),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DryRunConfig {
+    pub destination: String,
+}
+
+impl StorageConfig for DryRunConfig {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn StorageConfig> {
+        Box::new(self.clone())
+    }
+
+    fn destination(&self) -> &str {
+        &self.destination
+    }
+}
+
+#[async_trait]
+impl StorageBackend for DryRunStorage {
+    fn create_config(&self, destination: &str) -> Box<dyn StorageConfig> {
+        Box::new(DryRunConfig {
+            destination: destination.to_string(),
+        })
+    }
+
+    async fn store_serialized(
+        &self,
+        item: StorageItem<Box<dyn ErasedSerialize + Send + Sync>>,
+        config: &dyn StorageConfig,
+    ) -> Result<(), StorageError> {
+        match self.output {
+            Some(format) => format.print(&item)?,
+            None => info!(
+                "Dry run, not storing item in {}: {}",
+                config.destination(),
+                serde_json::to_string(&item)?
+            ),
+        }
+        Ok(())
+    }
+}
@@ -2,10 +2,16 @@
 use super::KafkaStorage;
 #[cfg(feature = "mongodb")]
 use super::MongoStorage;
-use super::{base::StorageError, DiskStorage, StorageBackend, StorageConfig, StorageItem};
-use anyhow::Error;
+use super::{
+    base::StorageError, DiskStorage, DryRunStorage, StorageBackend, StorageConfig, StorageItem,
+};
+use anyhow::{Context, Error};
 use async_trait::async_trait;
 use erased_serde::Serialize as ErasedSerialize;
+use log::warn;
+use serde_json::Value;
+#[cfg(feature = "kafka")]
+use std::time::Duration;
 
 pub enum StorageType {
     Disk {
@@ -21,6 +27,8 @@ pub enum StorageType {
         brokers: String,
         client_id: String,
     },
+    /// Logs items instead of storing them, see [`DryRunStorage`].
+    DryRun,
 }
 
 #[derive(Clone)]
@@ -30,6 +38,7 @@ pub enum Storage {
     Mongo(Box<MongoStorage>),
     #[cfg(feature = "kafka")]
     Kafka(Box<KafkaStorage>),
+    DryRun(DryRunStorage),
 }
 
 #[async_trait]
@@ -41,6 +50,7 @@ impl StorageBackend for Storage {
             Storage::Mongo(storage) => storage.create_config(destination),
             #[cfg(feature = "kafka")]
             Storage::Kafka(storage) => storage.create_config(destination),
+            Storage::DryRun(storage) => storage.create_config(destination),
         }
     }
 
@@ -55,25 +65,150 @@ impl StorageBackend for Storage {
             Storage::Mongo(storage) => storage.store_serialized(item, config).await,
             #[cfg(feature = "kafka")]
             Storage::Kafka(storage) => storage.store_serialized(item, config).await,
+            Storage::DryRun(storage) => storage.store_serialized(item, config).await,
+        }
+    }
+
+    async fn store_batch(
+        &self,
+        items: &[StorageItem<Value>],
+        config: &dyn StorageConfig,
+    ) -> Vec<Result<(), StorageError>> {
+        match self {
+            Storage::Disk(storage) => storage.store_batch(items, config).await,
+            #[cfg(feature = "mongodb")]
+            Storage::Mongo(storage) => storage.store_batch(items, config).await,
+            #[cfg(feature = "kafka")]
+            Storage::Kafka(storage) => storage.store_batch(items, config).await,
+            Storage::DryRun(storage) => storage.store_batch(items, config).await,
         }
     }
 }
 
+/// How [`create_storage_with`] treats backends that can't be reached at
+/// startup.
+#[derive(Debug, Clone, Default)]
+pub struct StorageOptions {
+    lazy_connection: bool,
+    dry_run_fallback: bool,
+}
+
+impl StorageOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Skips the connection check of MongoDB and Kafka, which then connect on
+    /// the first write.
+    pub fn with_lazy_connection(mut self, lazy: bool) -> Self {
+        self.lazy_connection = lazy;
+        self
+    }
+
+    /// Falls back to a [`DryRunStorage`] when the backend can't be opened,
+    /// instead of failing.
+    pub fn with_dry_run_fallback(mut self, fallback: bool) -> Self {
+        self.dry_run_fallback = fallback;
+        self
+    }
+
+    async fn open(&self, storage_type: StorageType) -> Result<Storage, Error> {
+        match storage_type {
+            StorageType::Disk { path } => {
+                let storage = DiskStorage::new(&path)
+                    .with_context(|| format!("Cannot use {} for disk storage", path))?;
+                Ok(Storage::Disk(Box::new(storage)))
+            }
+            #[cfg(feature = "mongodb")]
+            StorageType::Mongo {
+                connection_string,
+                database,
+            } => {
+                let storage = MongoStorage::new(&connection_string, &database)
+                    .await
+                    .with_context(|| {
+                        format!("Invalid MongoDB connection string for {}", database)
+                    })?;
+                if !self.lazy_connection {
+                    storage
+                        .ping()
+                        .await
+                        .with_context(|| format!("MongoDB database {} is unreachable", database))?;
+                }
+                Ok(Storage::Mongo(Box::new(storage)))
+            }
+            #[cfg(feature = "kafka")]
+            StorageType::Kafka { brokers, client_id } => {
+                let storage = KafkaStorage::new(&brokers, &client_id)
+                    .with_context(|| format!("Invalid Kafka configuration for {}", brokers))?;
+                if !self.lazy_connection {
+                    storage
+                        .check_connection(Duration::from_secs(10))
+                        .await
+                        .with_context(|| format!("Kafka brokers {} are unreachable", brokers))?;
+                }
+                Ok(Storage::Kafka(Box::new(storage)))
+            }
+            StorageType::DryRun => Ok(Storage::DryRun(DryRunStorage::new())),
+        }
+    }
+}
+
+/// Opens the storage and checks that it is usable: the directory of disk
+/// storage is created, MongoDB is pinged and Kafka metadata is fetched.
 pub async fn create_storage(storage_type: StorageType) -> Result<Storage, Error> {
-    match storage_type {
-        StorageType::Disk { path } => Ok(Storage::Disk(Box::new(DiskStorage::new(path).unwrap()))),
-        #[cfg(feature = "mongodb")]
-        StorageType::Mongo {
-            connection_string,
-            database,
-        } => Ok(Storage::Mongo(Box::new(
-            MongoStorage::new(&connection_string, &database)
-                .await
-                .unwrap(),
-        ))),
-        #[cfg(feature = "kafka")]
-        StorageType::Kafka { brokers, client_id } => Ok(Storage::Kafka(Box::new(
-            KafkaStorage::new(&brokers, &client_id).unwrap(),
-        ))),
+    create_storage_with(storage_type, &StorageOptions::default()).await
+}
+
+pub async fn create_storage_with(
+    storage_type: StorageType,
+    options: &StorageOptions,
+) -> Result<Storage, Error> {
+    match options.open(storage_type).await {
+        Err(e) if options.dry_run_fallback => {
+            warn!("{:#}, logging items instead of storing them", e);
+            Ok(Storage::DryRun(DryRunStorage::new()))
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unusable_storage_errors_or_falls_back_to_dry_run() {
+        let file = std::env::temp_dir().join(format!("storage_file_{}", uuid::Uuid::now_v7()));
+        std::fs::write(&file, "not a directory").unwrap();
+        let path = file.join("output").to_string_lossy().to_string();
+
+        let error = create_storage(StorageType::Disk { path: path.clone() })
+            .await
+            .err()
+            .unwrap();
+        assert!(error.to_string().starts_with("Cannot use"));
+
+        let storage = create_storage_with(
+            StorageType::Disk { path },
+            &StorageOptions::new().with_dry_run_fallback(true),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(storage, Storage::DryRun(_)));
+
+        let config = storage.create_config("data");
+        let item = StorageItem {
+            url: url::Url::parse("https://example.com/item/1").unwrap(),
+            timestamp: chrono::Utc::now(),
+            data: serde_json::json!({"title": "Item"}),
+            metadata: None,
+            id: "item".to_string(),
+        };
+        storage
+            .store_serialized(item.into_serialized(), &*config)
+            .await
+            .unwrap();
+        let _ = std::fs::remove_file(file);
     }
 }
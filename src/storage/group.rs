@@ -0,0 +1,240 @@
+use super::base::{StorageBackend, StorageError, StorageItem};
+use super::manager::StorageManager;
+use super::types::StorageCategory;
+use crate::HttpResponse;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chrono::Utc;
+use log::warn;
+use serde_json::{json, Value};
+use thiserror::Error;
+use url::Url;
+use uuid::Uuid;
+
+/// Role of the scraped item within its group.
+pub const ITEM_ROLE: &str = "item";
+pub const RAW_RESPONSE_ROLE: &str = "raw_response";
+
+/// A scraped item and the artifacts it came from (raw response, screenshot,
+/// ...), stored as one unit.
+///
+/// Every part is tagged with the same `group_id`, `group_role` and
+/// `group_parts` metadata. Attachments are written first and the item last,
+/// so an item in storage always has all of its attachments; a failed group
+/// can leave orphaned attachments but never an item without its page.
+#[derive(Debug, Clone)]
+pub struct WriteGroup {
+    pub group_id: String,
+    pub url: Url,
+    item: (StorageCategory, Value),
+    attachments: Vec<(String, StorageCategory, Value)>,
+}
+
+impl WriteGroup {
+    pub fn new(url: Url, category: StorageCategory, item: Value) -> Self {
+        Self {
+            group_id: Uuid::now_v7().to_string(),
+            url,
+            item: (category, item),
+            attachments: Vec::new(),
+        }
+    }
+
+    /// Reuses an id, e.g. to retry a group that failed part way.
+    pub fn with_group_id<S: Into<String>>(mut self, group_id: S) -> Self {
+        self.group_id = group_id.into();
+        self
+    }
+
+    pub fn with_attachment(mut self, role: &str, category: StorageCategory, data: Value) -> Self {
+        self.attachments.push((role.to_string(), category, data));
+        self
+    }
+
+    /// Attaches the response the item was extracted from, stored in the
+    /// `Raw` category. The body is base64-encoded, so binary and non-UTF-8
+    /// bodies are archived byte for byte.
+    pub fn with_raw_response(self, response: &HttpResponse) -> Self {
+        let data = json!({
+            "url": response.url.as_str(),
+            "status": response.status,
+            "headers": response.headers,
+            "body": BASE64_STANDARD.encode(&response.raw_body),
+            "body_encoding": "base64",
+        });
+        self.with_attachment(RAW_RESPONSE_ROLE, StorageCategory::Raw, data)
+    }
+
+    /// Roles of every part, attachments first.
+    pub fn roles(&self) -> Vec<&str> {
+        self.attachments
+            .iter()
+            .map(|(role, _, _)| role.as_str())
+            .chain([ITEM_ROLE])
+            .collect()
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("Failed to store {failed_role} of group {group_id}: {error}")]
+pub struct GroupWriteError {
+    pub group_id: String,
+    /// Roles of the parts written before the failure.
+    pub stored: Vec<String>,
+    pub failed_role: String,
+    pub error: StorageError,
+}
+
+impl StorageManager {
+    /// Stores every part of `group` and returns its id.
+    pub async fn store_group(&self, group: WriteGroup) -> Result<String, GroupWriteError> {
+        let roles: Vec<String> = group.roles().into_iter().map(str::to_string).collect();
+        let timestamp = Utc::now();
+        let (item_category, item) = group.item;
+        let parts =
+            group
+                .attachments
+                .into_iter()
+                .chain([(ITEM_ROLE.to_string(), item_category, item)]);
+
+        let mut stored = Vec::new();
+        for (role, category, data) in parts {
+            let (storage, config) = self.get_storage(&category);
+            let id = if role == ITEM_ROLE {
+                group.group_id.clone()
+            } else {
+                format!("{}-{}", group.group_id, role)
+            };
+            let item = StorageItem {
+                url: group.url.clone(),
+                timestamp,
+                data,
+                metadata: Some(json!({
+                    "group_id": group.group_id,
+                    "group_role": role,
+                    "group_parts": roles,
+                })),
+                id,
+            };
+
+            if let Err(error) = storage
+                .store_serialized(item.into_serialized(), &**config)
+                .await
+            {
+                if !stored.is_empty() {
+                    warn!(
+                        "Group {} left incomplete, stored parts: {:?}",
+                        group.group_id, stored
+                    );
+                }
+                return Err(GroupWriteError {
+                    group_id: group.group_id,
+                    stored,
+                    failed_role: role,
+                    error,
+                });
+            }
+            stored.push(role);
+        }
+        Ok(group.group_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{create_storage, StorageType};
+    use std::fs;
+    use std::path::Path;
+
+    fn stored_documents(dir: &Path) -> Vec<Value> {
+        let mut documents = Vec::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                documents.extend(stored_documents(&path));
+            } else {
+                documents.push(serde_json::from_slice(&fs::read(path).unwrap()).unwrap());
+            }
+        }
+        documents
+    }
+
+    #[tokio::test]
+    async fn test_group_parts_share_group_id() {
+        let dir = std::env::temp_dir().join(format!("turboscraper_group_{}", Uuid::now_v7()));
+        let storage = create_storage(StorageType::Disk {
+            path: dir.to_string_lossy().to_string(),
+        })
+        .await
+        .unwrap();
+        let manager = StorageManager::new()
+            .register_storage(StorageCategory::Data, storage.clone(), "items")
+            .register_storage(StorageCategory::Raw, storage, "raw");
+
+        let group = WriteGroup::new(
+            Url::parse("https://example.com/p/1").unwrap(),
+            StorageCategory::Data,
+            json!({"title": "Widget"}),
+        )
+        .with_attachment(
+            "screenshot",
+            StorageCategory::Raw,
+            json!({"png": "89504e47"}),
+        );
+        assert_eq!(group.roles(), vec!["screenshot", "item"]);
+
+        let group_id = manager.store_group(group).await.unwrap();
+        let documents = stored_documents(&dir);
+        assert_eq!(documents.len(), 2);
+        for document in &documents {
+            assert_eq!(document["metadata"]["group_id"], group_id.as_str());
+            assert_eq!(
+                document["metadata"]["group_parts"],
+                json!(["screenshot", "item"])
+            );
+        }
+        let item = documents
+            .iter()
+            .find(|d| d["metadata"]["group_role"] == ITEM_ROLE)
+            .unwrap();
+        assert_eq!(item["id"], group_id.as_str());
+        assert_eq!(item["data"]["title"], "Widget");
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_raw_response_round_trips_non_utf8_body() {
+        use crate::storage::DiskArchive;
+        use futures::StreamExt;
+
+        let dir = std::env::temp_dir().join(format!("turboscraper_group_{}", Uuid::now_v7()));
+        let storage = create_storage(StorageType::Disk {
+            path: dir.to_string_lossy().to_string(),
+        })
+        .await
+        .unwrap();
+        let manager = StorageManager::new()
+            .register_storage(StorageCategory::Data, storage.clone(), "items")
+            .register_storage(StorageCategory::Raw, storage, "raw");
+
+        // Latin-1 "café" followed by bytes that are invalid in UTF-8.
+        let body = [b'c', b'a', b'f', 0xe9, 0xff, 0xfe];
+        let response = HttpResponse::for_test("https://example.com/p/1", body);
+        let group = WriteGroup::new(
+            response.url.clone(),
+            StorageCategory::Data,
+            json!({"title": "Widget"}),
+        )
+        .with_raw_response(&response);
+        manager.store_group(group).await.unwrap();
+
+        let archived: Vec<_> = DiskArchive::new(dir.join("raw"))
+            .responses()
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].as_ref().unwrap().raw_body, body);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
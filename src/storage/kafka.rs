@@ -2,8 +2,10 @@ use super::base::{StorageBackend, StorageConfig, StorageError, StorageItem};
 use anyhow::Error;
 use async_trait::async_trait;
 use erased_serde::Serialize as ErasedSerialize;
-use rdkafka::producer::{FutureProducer, FutureRecord};
+use futures::future::join_all;
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
 use rdkafka::ClientConfig;
+use serde_json::Value;
 use std::error::Error as StdError;
 use std::fmt;
 use std::time::Duration;
@@ -51,6 +53,16 @@ impl KafkaStorage {
 
         Ok(Self { producer })
     }
+
+    /// Checks that the brokers are reachable by fetching the cluster
+    /// metadata. The producer itself only connects on first use.
+    pub async fn check_connection(&self, timeout: Duration) -> Result<(), Error> {
+        let producer = self.producer.clone();
+        tokio::task::spawn_blocking(move || producer.client().fetch_metadata(None, timeout))
+            .await?
+            .map_err(KafkaStorageError::Connection)?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -119,4 +131,19 @@ impl StorageBackend for KafkaStorage {
 
         Ok(())
     }
+
+    /// Produces every message of the batch concurrently and reports the
+    /// delivery result of each one.
+    async fn store_batch(
+        &self,
+        items: &[StorageItem<Value>],
+        config: &dyn StorageConfig,
+    ) -> Vec<Result<(), StorageError>> {
+        join_all(
+            items
+                .iter()
+                .map(|item| self.store_serialized(item.clone().into_serialized(), config)),
+        )
+        .await
+    }
 }
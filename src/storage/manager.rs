@@ -1,4 +1,7 @@
-use super::{base::StorageBackend, factory::Storage, StorageCategory, StorageConfig};
+use super::{
+    base::StorageBackend, factory::Storage, DryRunFormat, DryRunStorage, StorageCategory,
+    StorageConfig,
+};
 use crate::ScraperResult;
 use std::collections::HashMap;
 
@@ -6,6 +9,7 @@ use std::collections::HashMap;
 pub struct StorageManager {
     storages: HashMap<StorageCategory, (Storage, Box<dyn StorageConfig>)>,
     default_storage: StorageCategory,
+    dry_run: Option<DryRunFormat>,
 }
 
 impl Default for StorageManager {
@@ -19,6 +23,7 @@ impl StorageManager {
         Self {
             storages: HashMap::new(),
             default_storage: StorageCategory::default(),
+            dry_run: None,
         }
     }
 
@@ -28,12 +33,28 @@ impl StorageManager {
         storage: Storage,
         destination: &str,
     ) -> Self {
+        let storage = match self.dry_run {
+            Some(format) => Storage::DryRun(DryRunStorage::printing(format)),
+            None => storage,
+        };
         let config = storage.create_config(destination);
         self.storages.insert(category.clone(), (storage, config));
 
         self
     }
 
+    /// Prints items to stdout instead of writing them to the registered
+    /// storages, including ones registered afterwards.
+    pub fn with_dry_run(mut self, format: DryRunFormat) -> Self {
+        self.dry_run = Some(format);
+        let storage = Storage::DryRun(DryRunStorage::printing(format));
+        for (current, config) in self.storages.values_mut() {
+            *config = storage.create_config(config.destination());
+            *current = storage.clone();
+        }
+        self
+    }
+
     pub fn set_default_storage(mut self, category: StorageCategory) -> ScraperResult<Self> {
         self.default_storage = category;
         Ok(self)
@@ -49,3 +70,47 @@ impl StorageManager {
         self.storages.get(&self.default_storage).unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{create_storage, StorageItem, StorageType};
+
+    #[tokio::test]
+    async fn test_dry_run_skips_backend_writes() {
+        let output = std::env::temp_dir().join(format!("dry_run_{}", uuid::Uuid::now_v7()));
+        let disk = || async {
+            create_storage(StorageType::Disk {
+                path: output.to_string_lossy().to_string(),
+            })
+            .await
+            .unwrap()
+        };
+        let manager = StorageManager::new()
+            .register_storage(StorageCategory::Data, disk().await, "data")
+            .with_dry_run(DryRunFormat::Jsonl)
+            .register_storage(StorageCategory::Error, disk().await, "errors");
+
+        for category in [StorageCategory::Data, StorageCategory::Error] {
+            let (storage, config) = manager.get_storage(&category);
+            assert!(matches!(storage, Storage::DryRun(_)));
+            let item = StorageItem {
+                url: url::Url::parse("https://example.com/item/1").unwrap(),
+                timestamp: chrono::Utc::now(),
+                data: serde_json::json!({"title": "Item"}),
+                metadata: None,
+                id: "item".to_string(),
+            };
+            storage
+                .store_serialized(item.into_serialized(), &**config)
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            manager.get_storage(&StorageCategory::Error).1.destination(),
+            "errors"
+        );
+        assert_eq!(std::fs::read_dir(&output).unwrap().count(), 0);
+        let _ = std::fs::remove_dir_all(output);
+    }
+}
@@ -1,6 +1,10 @@
+pub mod archive;
 pub mod base;
+pub mod batch;
 pub mod disk;
+pub mod dry_run;
 pub mod factory;
+pub mod group;
 pub mod manager;
 
 #[cfg(feature = "kafka")]
@@ -9,9 +13,13 @@ pub mod kafka;
 pub mod mongo;
 pub mod types;
 
+pub use archive::DiskArchive;
 pub use base::{IntoStorageData, StorageBackend, StorageConfig, StorageItem};
+pub use batch::{BatchReport, BatchRetryConfig, ItemOutcome};
 pub use disk::DiskStorage;
-pub use factory::{create_storage, Storage, StorageType};
+pub use dry_run::{DryRunFormat, DryRunStorage};
+pub use factory::{create_storage, create_storage_with, Storage, StorageOptions, StorageType};
+pub use group::{GroupWriteError, WriteGroup};
 #[cfg(feature = "kafka")]
 pub use kafka::KafkaStorage;
 pub use manager::StorageManager;
@@ -3,7 +3,14 @@ use crate::ScraperError;
 use anyhow::Error;
 use async_trait::async_trait;
 use erased_serde::Serialize as ErasedSerialize;
+use log::warn;
+use mongodb::error::{ErrorKind, InsertManyError, WriteFailure};
 use mongodb::{bson::doc, error::Error as MongoError, Client};
+use serde_json::Value;
+
+/// Server code for a write that hit an existing `_id`, i.e. an item a
+/// previous attempt already stored.
+const DUPLICATE_KEY: i32 = 11000;
 
 // Unified error type for MongoDB operations
 #[derive(Debug)]
@@ -23,8 +30,7 @@ impl MongoStorage {
     pub async fn new(connection_string: &str, database_name: &str) -> Result<Self, Error> {
         let client = Client::with_uri_str(connection_string)
             .await
-            .map_err(MongoStorageError::Connection)
-            .unwrap();
+            .map_err(|e| StorageError::from(MongoStorageError::Connection(e)))?;
 
         Ok(Self {
             database_name: database_name.to_string(),
@@ -32,11 +38,23 @@ impl MongoStorage {
         })
     }
 
+    /// Checks that the server is reachable. The client itself only connects
+    /// on first use.
+    pub async fn ping(&self) -> Result<(), Error> {
+        self.client
+            .database(&self.database_name)
+            .run_command(doc! { "ping": 1 })
+            .await
+            .map_err(|e| StorageError::from(MongoStorageError::Connection(e)))?;
+        Ok(())
+    }
+
     async fn serialize_item(
         &self,
         item: StorageItem<Box<dyn ErasedSerialize + Send + Sync>>,
     ) -> Result<mongodb::bson::Document, MongoStorageError> {
         Ok(doc! {
+            "_id": item.id,
             "url": item.url.to_string(),
             "timestamp": item.timestamp.to_rfc3339(),
             "data": mongodb::bson::to_bson(&item.data)
@@ -69,6 +87,14 @@ impl StorageConfig for MongoConfig {
     }
 }
 
+fn is_duplicate_key(error: &MongoError) -> bool {
+    matches!(
+        &*error.kind,
+        ErrorKind::Write(WriteFailure::WriteError(write_error))
+            if write_error.code == DUPLICATE_KEY
+    )
+}
+
 impl From<MongoError> for StorageError {
     fn from(error: MongoError) -> Self {
         match *error.kind {
@@ -131,13 +157,114 @@ impl StorageBackend for MongoStorage {
             .await
             .map_err(StorageError::from)?;
 
-        self.client
+        let outcome = self
+            .client
             .database(&self.database_name)
             .collection(config.destination())
             .insert_one(doc)
+            .await;
+        match outcome {
+            Err(error) if !is_duplicate_key(&error) => Err(StorageError::from(error)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Inserts the batch unordered, so one bad document doesn't stop the
+    /// rest, and maps the bulk write errors back to the items they belong to.
+    async fn store_batch(
+        &self,
+        items: &[StorageItem<Value>],
+        config: &dyn StorageConfig,
+    ) -> Vec<Result<(), StorageError>> {
+        let mut results: Vec<Result<(), StorageError>> = Vec::with_capacity(items.len());
+        let mut docs = Vec::new();
+        // Position in `items` of each document sent to the server.
+        let mut positions = Vec::new();
+        for (position, item) in items.iter().enumerate() {
+            match self.serialize_item(item.clone().into_serialized()).await {
+                Ok(doc) => {
+                    docs.push(doc);
+                    positions.push(position);
+                    results.push(Ok(()));
+                }
+                Err(e) => results.push(Err(e.into())),
+            }
+        }
+        if docs.is_empty() {
+            return results;
+        }
+
+        let outcome = self
+            .client
+            .database(&self.database_name)
+            .collection(config.destination())
+            .insert_many(docs)
+            .ordered(false)
+            .await;
+        let error = match outcome {
+            Ok(_) => return results,
+            Err(error) => error,
+        };
+
+        match *error.kind {
+            ErrorKind::InsertMany(InsertManyError {
+                ref write_errors,
+                ref write_concern_error,
+                ..
+            }) => {
+                if let Some(concern) = write_concern_error {
+                    warn!(
+                        "Batch written to {} without write concern: {}",
+                        config.destination(),
+                        concern.message
+                    );
+                }
+                for write_error in write_errors.iter().flatten() {
+                    // A retried item that made it in before is stored.
+                    if write_error.code == DUPLICATE_KEY {
+                        continue;
+                    }
+                    if let Some(&position) = positions.get(write_error.index) {
+                        results[position] =
+                            Err(StorageError::OperationError(write_error.message.clone()));
+                    }
+                }
+            }
+            _ => {
+                let error = StorageError::from(error);
+                for &position in &positions {
+                    results[position] = Err(error.clone());
+                }
+            }
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use url::Url;
+
+    #[tokio::test]
+    async fn test_item_id_is_the_document_id() {
+        // The client connects lazily, so no server is needed.
+        let storage = MongoStorage::new("mongodb://localhost:27017", "test")
             .await
-            .map_err(StorageError::from)?;
+            .unwrap();
+        let item = StorageItem {
+            url: Url::parse("https://example.com/item").unwrap(),
+            timestamp: Utc::now(),
+            data: serde_json::json!({"name": "Widget"}),
+            metadata: None,
+            id: "item-1".to_string(),
+        };
 
-        Ok(())
+        let doc = storage
+            .serialize_item(item.into_serialized())
+            .await
+            .unwrap();
+        assert_eq!(doc.get_str("_id").unwrap(), "item-1");
     }
 }
@@ -0,0 +1,282 @@
+//! Crawls a small fake shop served by wiremock with a real `HttpScraper`,
+//! storing items on disk, to exercise the crate end to end.
+
+use async_trait::async_trait;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use scraper::{Html, Selector};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use turboscraper::core::retry::{
+    BackoffPolicy, CategoryConfig, RequestRetryCondition, RetryCategory, RetryCondition,
+    RetryConfig, RetryScope, RetryState,
+};
+use turboscraper::core::spider::{ParseResult, ParsedData, SpiderConfig, SpiderResponse};
+use turboscraper::core::SpiderCallback;
+use turboscraper::scrapers::http_scraper::HttpScraper;
+use turboscraper::storage::{create_storage, StorageCategory, StorageManager, StorageType};
+use turboscraper::{Crawler, HttpRequest, ScraperResult, Spider};
+use url::Url;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Listing pages link to products and to the next page; product pages hold
+/// the items.
+struct ShopSpider {
+    config: SpiderConfig,
+    start: Url,
+    storage_manager: StorageManager,
+}
+
+impl ShopSpider {
+    fn links(&self, response: &SpiderResponse, selector: &str) -> ScraperResult<Vec<Url>> {
+        let document = Html::parse_document(response.response.body_text()?);
+        let selector = Selector::parse(selector).unwrap();
+        Ok(document
+            .select(&selector)
+            .filter_map(|a| a.value().attr("href"))
+            .filter_map(|href| response.response.url.join(href).ok())
+            .collect())
+    }
+
+    fn text(document: &Html, selector: &str) -> String {
+        let selector = Selector::parse(selector).unwrap();
+        document
+            .select(&selector)
+            .next()
+            .map(|e| e.text().collect::<String>().trim().to_string())
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl Spider for ShopSpider {
+    fn name(&self) -> String {
+        "shop_spider".to_string()
+    }
+
+    fn storage_manager(&self) -> &StorageManager {
+        &self.storage_manager
+    }
+
+    fn start_requests(&self) -> Vec<HttpRequest> {
+        vec![HttpRequest::new(
+            self.start.clone(),
+            SpiderCallback::Bootstrap,
+            0,
+        )]
+    }
+
+    fn config(&self) -> &SpiderConfig {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: SpiderConfig) {
+        self.config = config;
+    }
+
+    fn parse(&self, response: &SpiderResponse) -> ScraperResult<(ParseResult, ParsedData)> {
+        let depth = response.response.from_request.depth;
+        match response.callback {
+            SpiderCallback::Bootstrap | SpiderCallback::ParsePagination => {
+                let mut requests: Vec<HttpRequest> = self
+                    .links(response, "a.product")?
+                    .into_iter()
+                    .map(|url| HttpRequest::new(url, SpiderCallback::ParseItem, depth + 1))
+                    .collect();
+                requests.extend(
+                    self.links(response, "a.next")?
+                        .into_iter()
+                        .map(|url| HttpRequest::new(url, SpiderCallback::ParsePagination, depth)),
+                );
+                Ok((ParseResult::Continue(requests), ParsedData::Empty))
+            }
+            _ => {
+                let document = Html::parse_document(response.response.body_text()?);
+                let item = json!({
+                    "name": Self::text(&document, "h1"),
+                    "price": Self::text(&document, ".price"),
+                });
+                Ok((ParseResult::Skip, ParsedData::Item(item)))
+            }
+        }
+    }
+
+    async fn handle_max_retries(
+        &self,
+        _category: RetryCategory,
+        _request: Box<HttpRequest>,
+        _history: RetryState,
+    ) -> ScraperResult<()> {
+        Ok(())
+    }
+}
+
+fn listing(products: &[u32], next: Option<&str>) -> String {
+    let mut html = String::from("<html><body><ul>");
+    for id in products {
+        html.push_str(&format!(
+            r#"<li><a class="product" href="/products/{}">Product {}</a></li>"#,
+            id, id
+        ));
+    }
+    html.push_str(r#"</ul><a class="product" href="javascript:void(0)">Compare</a>"#);
+    // The last page disables its next button.
+    let next = next.unwrap_or("#");
+    html.push_str(&format!(r#"<a class="next" href="{}">Next</a>"#, next));
+    html + "</body></html>"
+}
+
+fn product(id: u32) -> String {
+    format!(
+        r#"<html><body><h1>Product {}</h1><p class="price">{}.99</p></body></html>"#,
+        id,
+        id * 10
+    )
+}
+
+fn html(body: impl Into<Vec<u8>>) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(body.into(), "text/html; charset=utf-8")
+}
+
+/// A shop of four products over two listing pages, with `javascript:` and
+/// `#` links. The second page is reached through a redirect, product 2 is
+/// gzipped and product 3 is rate limited once.
+async fn fake_shop() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(path("/"))
+        .respond_with(html(listing(&[1, 2], Some("/page/2"))))
+        .mount(&server)
+        .await;
+    Mock::given(path("/page/2"))
+        .respond_with(ResponseTemplate::new(301).insert_header("location", "/listing?page=2"))
+        .mount(&server)
+        .await;
+    Mock::given(path("/listing"))
+        .and(query_param("page", "2"))
+        .respond_with(html(listing(&[3, 4], None)))
+        .mount(&server)
+        .await;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(product(2).as_bytes()).unwrap();
+    Mock::given(path("/products/2"))
+        .respond_with(html(encoder.finish().unwrap()).insert_header("content-encoding", "gzip"))
+        .mount(&server)
+        .await;
+    Mock::given(path("/products/3"))
+        .respond_with(ResponseTemplate::new(429))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    for id in [1, 3, 4] {
+        Mock::given(method("GET"))
+            .and(path(format!("/products/{}", id)))
+            .respond_with(html(product(id)))
+            .mount(&server)
+            .await;
+    }
+    server
+}
+
+fn rate_limit_retries() -> RetryConfig {
+    let mut retry_config = RetryConfig::default();
+    retry_config.categories.insert(
+        RetryCategory::RateLimit,
+        CategoryConfig {
+            max_retries: 2,
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(100),
+            backoff_policy: BackoffPolicy::Constant,
+            conditions: vec![RetryCondition::Request(RequestRetryCondition::StatusCode(
+                429,
+            ))],
+            matcher: None,
+            scope: RetryScope::Url,
+        },
+    );
+    retry_config
+}
+
+fn stored_items(dir: &Path) -> Vec<Value> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "json") {
+                files.push(path);
+            }
+        }
+    }
+    files
+        .iter()
+        .map(|file| serde_json::from_str(&std::fs::read_to_string(file).unwrap()).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_crawls_fake_shop_into_disk_storage() {
+    let server = fake_shop().await;
+    let dir = std::env::temp_dir().join(format!("turboscraper_e2e_{}", uuid::Uuid::now_v7()));
+    let storage = create_storage(StorageType::Disk {
+        path: dir.to_string_lossy().to_string(),
+    })
+    .await
+    .unwrap();
+    let spider = ShopSpider {
+        config: SpiderConfig {
+            retry_config: rate_limit_retries(),
+            ..Default::default()
+        },
+        start: Url::parse(&server.uri()).unwrap(),
+        storage_manager: StorageManager::new().register_storage(
+            StorageCategory::Data,
+            storage,
+            "products",
+        ),
+    };
+
+    let crawler = Crawler::new(Box::new(HttpScraper::new().unwrap()));
+    tokio::time::timeout(Duration::from_secs(10), crawler.run(spider))
+        .await
+        .expect("crawl should finish")
+        .unwrap();
+
+    let mut items: Vec<Value> = stored_items(&dir.join("products"))
+        .into_iter()
+        .map(|item| item["data"].clone())
+        .collect();
+    items.sort_by_key(|item| item["name"].as_str().unwrap().to_string());
+    assert_eq!(
+        items,
+        [
+            json!({"name": "Product 1", "price": "10.99"}),
+            json!({"name": "Product 2", "price": "20.99"}),
+            json!({"name": "Product 3", "price": "30.99"}),
+            json!({"name": "Product 4", "price": "40.99"}),
+        ]
+    );
+
+    let stats = crawler.stats().get_stats();
+    assert_eq!(stats.items_scraped, 4);
+    // Two listing pages and four products, the rate limited one retried.
+    assert_eq!(stats.total_requests, 6);
+    assert_eq!(stats.failed_requests, 0);
+    assert!(stats.retry_reasons.contains_key("RateLimit"));
+    assert_eq!(stats.unfollowable_schemes, 2);
+    assert_eq!(stats.fragment_links, 1);
+
+    let received = server.received_requests().await.unwrap();
+    let fetched = |route: &str| received.iter().filter(|r| r.url.path() == route).count();
+    assert_eq!(fetched("/products/3"), 2);
+    assert_eq!(fetched("/products/1"), 1);
+    assert_eq!(fetched("/page/2"), 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}